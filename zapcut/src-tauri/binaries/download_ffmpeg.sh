@@ -49,6 +49,12 @@ echo "https://www.gyan.dev/ffmpeg/builds/ffmpeg-release-essentials.zip"
 echo "Extract ffmpeg.exe and ffprobe.exe to windows-x86_64/"
 echo ""
 
+# RNNoise model for the arnndn noise reduction filter (shared by all platforms)
+echo ""
+echo "Downloading RNNoise model..."
+curl -sL https://raw.githubusercontent.com/GregorR/rnnoise-models/master/somnolent-hogwash-2018-09-01/sh.rnnn -o rnnoise.rnnn
+echo "✓ RNNoise model complete"
+
 echo "FFmpeg downloads complete!"
 echo ""
 echo "Verifying binaries..."
@@ -0,0 +1,458 @@
+use crate::commands::export::CropRect;
+use crate::commands::library::LibraryManager;
+use crate::utils::ffmpeg::{get_ffmpeg_path, get_video_info, measure_loudness, LoudnessStats};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use tauri::{command, State};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SceneCut {
+    pub time: f64,
+    /// Scene change score from FFmpeg (0-1)
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TimeRange {
+    pub start: f64,
+    pub end: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MediaAnalysis {
+    pub duration: f64,
+    /// Whether silence was analysed at all; a file without audio has no silent ranges
+    pub has_audio: bool,
+    pub black_ranges: Vec<TimeRange>,
+    pub silent_ranges: Vec<TimeRange>,
+    /// Suggested in/out points with leading and trailing dead air removed
+    pub suggested_trim: Option<TimeRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AnalysisOptions {
+    /// Minimum black duration in seconds (default 0.5)
+    pub black_min_duration: Option<f64>,
+    /// Pixel luminance threshold for "black" (0-1, default 0.10)
+    pub black_pixel_threshold: Option<f64>,
+    /// Noise floor in dB below which audio counts as silence (default -50)
+    pub silence_noise_db: Option<f64>,
+    /// Minimum silence duration in seconds (default 1.0)
+    pub silence_min_duration: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SilenceDetection {
+    pub duration: f64,
+    /// Silent ranges to cut, already shrunk by the padding
+    pub silences: Vec<TimeRange>,
+    /// What remains once the silences are cut, in order: the clip splits for a jump cut
+    pub keep_ranges: Vec<TimeRange>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CropDetection {
+    /// Suggested crop; equals the full frame when no bars were found
+    pub crop: CropRect,
+    pub source_width: u32,
+    pub source_height: u32,
+    pub has_bars: bool,
+}
+
+/// Run an FFmpeg analysis pass that decodes `path` into the null muxer and return its
+/// (stdout, stderr). Filters that report through metadata=print write to stdout; detect
+/// filters (blackdetect, silencedetect, cropdetect) log to stderr.
+fn run_analysis_pass(path: &str, pre_input: &[&str], filter_args: &[&str]) -> Result<(String, String), String> {
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
+
+    let mut args: Vec<&str> = vec!["-hide_banner", "-nostats"];
+    args.extend(pre_input);
+    args.extend(["-i", path]);
+    args.extend(filter_args);
+    args.extend(["-f", "null", "-"]);
+
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
+    if !output.status.success() {
+        return Err(format!("FFmpeg analysis failed: {}", stderr));
+    }
+
+    Ok((stdout, stderr))
+}
+
+/// Find scene changes using FFmpeg's scene score.
+/// `threshold` (0-1, default 0.3) is the minimum score for a cut; `min_scene_length` drops cuts
+/// that come less than that many seconds after the previous one.
+#[command]
+pub async fn detect_scenes(
+    path: String,
+    threshold: Option<f64>,
+    min_scene_length: Option<f64>,
+) -> Result<Vec<SceneCut>, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let threshold = threshold.unwrap_or(0.3).clamp(0.01, 1.0);
+    let min_scene_length = min_scene_length.unwrap_or(1.0).max(0.0);
+
+    // Scoring a downscaled copy is much faster and just as accurate for hard cuts
+    let filter = format!(
+        "scale=320:-2,select='gt(scene,{})',metadata=print:file=-",
+        threshold
+    );
+    let (stdout, _) = run_analysis_pass(&path, &[], &["-an", "-sn", "-vf", &filter])?;
+
+    // metadata=print emits "frame:N pts:N pts_time:T" followed by "lavfi.scene_score=S"
+    let mut cuts: Vec<SceneCut> = Vec::new();
+    let mut pending_time: Option<f64> = None;
+    for line in stdout.lines() {
+        if let Some(time) = line
+            .split_whitespace()
+            .find_map(|field| field.strip_prefix("pts_time:"))
+            .and_then(|t| t.parse::<f64>().ok())
+        {
+            pending_time = Some(time);
+        } else if let Some(score) = line
+            .trim()
+            .strip_prefix("lavfi.scene_score=")
+            .and_then(|s| s.parse::<f64>().ok())
+        {
+            let Some(time) = pending_time.take() else {
+                continue;
+            };
+            let too_close = cuts
+                .last()
+                .map(|last| time - last.time < min_scene_length)
+                .unwrap_or(time < min_scene_length);
+            if !too_close {
+                cuts.push(SceneCut { time, score });
+            }
+        }
+    }
+
+    println!("[Analysis] Found {} scene changes in {}", cuts.len(), path);
+
+    Ok(cuts)
+}
+
+/// Read `key:value` (or `key: value`) from a detect filter log line
+fn log_value(line: &str, key: &str) -> Option<f64> {
+    let rest = &line[line.find(key)? + key.len()..];
+    rest.trim_start_matches(':')
+        .split_whitespace()
+        .next()
+        .and_then(|value| value.parse::<f64>().ok())
+}
+
+fn parse_black_ranges(log: &str) -> Vec<TimeRange> {
+    log.lines()
+        .filter(|line| line.contains("blackdetect"))
+        .filter_map(|line| {
+            Some(TimeRange {
+                start: log_value(line, "black_start")?,
+                end: log_value(line, "black_end")?,
+            })
+        })
+        .collect()
+}
+
+fn parse_silent_ranges(log: &str, duration: f64) -> Vec<TimeRange> {
+    let mut ranges = Vec::new();
+    let mut open_start: Option<f64> = None;
+    for line in log.lines().filter(|line| line.contains("silencedetect")) {
+        if let Some(start) = log_value(line, "silence_start") {
+            open_start = Some(start.max(0.0));
+        } else if let Some(end) = log_value(line, "silence_end") {
+            if let Some(start) = open_start.take() {
+                ranges.push(TimeRange { start, end });
+            }
+        }
+    }
+    // Silence that runs to the end of the file never gets a silence_end line
+    if let Some(start) = open_start {
+        ranges.push(TimeRange { start, end: duration });
+    }
+    ranges
+}
+
+/// Where ranges of `a` overlap ranges of `b`, e.g. black frames that are also silent
+pub(crate) fn intersect_ranges(a: &[TimeRange], b: &[TimeRange]) -> Vec<TimeRange> {
+    a.iter()
+        .flat_map(|x| {
+            b.iter().filter_map(move |y| {
+                let (start, end) = (x.start.max(y.start), x.end.min(y.end));
+                (end > start).then_some(TimeRange { start, end })
+            })
+        })
+        .collect()
+}
+
+/// In/out points that skip dead air (black or silent) touching the start or end of the file
+pub(crate) fn suggest_trim(duration: f64, black: &[TimeRange], silent: &[TimeRange]) -> Option<TimeRange> {
+    const EDGE_TOLERANCE: f64 = 0.1;
+
+    let dead_air = black.iter().chain(silent.iter());
+    let start = dead_air
+        .clone()
+        .filter(|range| range.start <= EDGE_TOLERANCE)
+        .map(|range| range.end)
+        .fold(0.0, f64::max);
+    let end = dead_air
+        .filter(|range| range.end >= duration - EDGE_TOLERANCE)
+        .map(|range| range.start)
+        .fold(duration, f64::min);
+
+    let trims_anything = start > 0.0 || end < duration;
+    (trims_anything && end > start).then_some(TimeRange { start, end })
+}
+
+/// Find black-frame and silent ranges in a single decode pass
+pub fn analyze_dead_air(path: &str, options: &AnalysisOptions) -> Result<MediaAnalysis, String> {
+    let info = get_video_info(path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+
+    let black_filter = format!(
+        "blackdetect=d={}:pix_th={}",
+        options.black_min_duration.unwrap_or(0.5),
+        options.black_pixel_threshold.unwrap_or(0.10).clamp(0.0, 1.0)
+    );
+    let silence_filter = format!(
+        "silencedetect=n={}dB:d={}",
+        options.silence_noise_db.unwrap_or(-50.0),
+        options.silence_min_duration.unwrap_or(1.0)
+    );
+
+    // blackdetect doesn't need full resolution
+    let video_filter = format!("scale=320:-2,{}", black_filter);
+    let mut filter_args = vec!["-sn", "-vf", video_filter.as_str()];
+    if info.audio_codec.is_some() {
+        filter_args.extend(["-af", silence_filter.as_str()]);
+    }
+    let (_, log) = run_analysis_pass(path, &[], &filter_args)?;
+
+    let black_ranges = parse_black_ranges(&log);
+    let silent_ranges = if info.audio_codec.is_some() {
+        parse_silent_ranges(&log, info.duration)
+    } else {
+        Vec::new()
+    };
+    let suggested_trim = suggest_trim(info.duration, &black_ranges, &silent_ranges);
+
+    Ok(MediaAnalysis {
+        duration: info.duration,
+        has_audio: info.audio_codec.is_some(),
+        black_ranges,
+        silent_ranges,
+        suggested_trim,
+    })
+}
+
+/// Report black-frame and silent ranges for a source, plus suggested in/out points that
+/// trim dead air from the start and end
+#[command]
+pub async fn analyze_media(path: String, options: Option<AnalysisOptions>) -> Result<MediaAnalysis, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let analysis = analyze_dead_air(&path, &options.unwrap_or_default())?;
+
+    println!(
+        "[Analysis] {}: {} black ranges, {} silent ranges",
+        path,
+        analysis.black_ranges.len(),
+        analysis.silent_ranges.len()
+    );
+
+    Ok(analysis)
+}
+
+/// Shrink each silence by `padding` on both sides so speech isn't clipped, dropping any
+/// left empty, and return them with the ranges between them
+fn silence_cuts(silent: &[TimeRange], duration: f64, padding: f64) -> SilenceDetection {
+    let silences: Vec<TimeRange> = silent
+        .iter()
+        .filter_map(|range| {
+            // Silence touching either end of the file is cut right up to that end
+            let start = if range.start <= 0.0 { 0.0 } else { range.start + padding };
+            let end = if range.end >= duration { duration } else { range.end - padding };
+            (end > start).then_some(TimeRange { start, end })
+        })
+        .collect();
+
+    let mut keep_ranges = Vec::new();
+    let mut position = 0.0;
+    for silence in &silences {
+        if silence.start > position {
+            keep_ranges.push(TimeRange { start: position, end: silence.start });
+        }
+        position = silence.end;
+    }
+    if duration > position {
+        keep_ranges.push(TimeRange { start: position, end: duration });
+    }
+
+    SilenceDetection {
+        duration,
+        silences,
+        keep_ranges,
+    }
+}
+
+/// Find silences for a "remove silences" jump cut. Audio below `noise_floor_db` (default -40)
+/// for at least `min_duration` seconds (default 0.5) counts as silent; `padding` (default 0.1s)
+/// is left on either side of each silence.
+#[command]
+pub async fn detect_silences(
+    path: String,
+    noise_floor_db: Option<f64>,
+    min_duration: Option<f64>,
+    padding: Option<f64>,
+) -> Result<SilenceDetection, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let info = get_video_info(&path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    if info.audio_codec.is_none() {
+        return Err(format!("{} has no audio", path));
+    }
+
+    let filter = format!(
+        "silencedetect=n={}dB:d={}",
+        noise_floor_db.unwrap_or(-40.0).clamp(-90.0, 0.0),
+        min_duration.unwrap_or(0.5).max(0.01)
+    );
+    let (_, log) = tauri::async_runtime::spawn_blocking({
+        let path = path.clone();
+        move || run_analysis_pass(&path, &[], &["-vn", "-sn", "-af", &filter])
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let silent = parse_silent_ranges(&log, info.duration);
+    let detection = silence_cuts(&silent, info.duration, padding.unwrap_or(0.1).max(0.0));
+
+    println!(
+        "[Analysis] {}: {} silences to cut, {} ranges kept",
+        path,
+        detection.silences.len(),
+        detection.keep_ranges.len()
+    );
+
+    Ok(detection)
+}
+
+/// Parse the `crop=w:h:x:y` suggestion from a cropdetect log line
+fn parse_crop_line(line: &str) -> Option<CropRect> {
+    let values: Vec<u32> = line
+        .rsplit("crop=")
+        .next()?
+        .split_whitespace()
+        .next()?
+        .split(':')
+        .map(|v| v.parse::<u32>().ok())
+        .collect::<Option<_>>()?;
+    match values.as_slice() {
+        [width, height, x, y] => Some(CropRect {
+            x: *x,
+            y: *y,
+            width: *width,
+            height: *height,
+        }),
+        _ => None,
+    }
+}
+
+/// Suggest a crop rectangle that removes baked-in letterbox/pillarbox bars.
+/// Samples `sample_duration` seconds (default 10) starting at `sample_start` (default 10% into
+/// the file, so fades from black at the head don't skew the result).
+#[command]
+pub async fn detect_crop(
+    path: String,
+    sample_start: Option<f64>,
+    sample_duration: Option<f64>,
+) -> Result<CropDetection, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let info = get_video_info(&path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    let start = sample_start
+        .unwrap_or((info.duration * 0.1).min(60.0))
+        .clamp(0.0, (info.duration - 1.0).max(0.0));
+    let window = sample_duration.unwrap_or(10.0).max(1.0);
+
+    let start_arg = start.to_string();
+    let window_arg = window.to_string();
+    // reset=0 accumulates the detected area across the window, so the last line is the union
+    // of every sampled frame's content and never crops away moving picture
+    let (_, log) = run_analysis_pass(
+        &path,
+        &["-ss", &start_arg, "-t", &window_arg],
+        &["-an", "-sn", "-vf", "fps=2,cropdetect=limit=24:round=2:reset=0"],
+    )?;
+
+    let full_frame = CropRect {
+        x: 0,
+        y: 0,
+        width: info.width,
+        height: info.height,
+    };
+    let crop = log
+        .lines()
+        .rev()
+        .filter(|line| line.contains("cropdetect"))
+        .find_map(parse_crop_line)
+        .filter(|crop| crop.width > 0 && crop.height > 0)
+        .unwrap_or_else(|| full_frame.clone());
+
+    // Ignore a few pixels of encoder edge noise
+    let has_bars = info.width.saturating_sub(crop.width) > 4 || info.height.saturating_sub(crop.height) > 4;
+
+    Ok(CropDetection {
+        crop: if has_bars { crop } else { full_frame },
+        source_width: info.width,
+        source_height: info.height,
+        has_bars,
+    })
+}
+
+/// Measure EBU R128 loudness (integrated LUFS, true peak, loudness range) for a library item
+/// and store it on the item so the mixer can show how hot each source is
+#[command]
+pub async fn analyze_loudness(
+    library: State<'_, LibraryManager>,
+    media_id: String,
+) -> Result<LoudnessStats, String> {
+    let file_path = {
+        let library = library.library.lock().await;
+        library
+            .items
+            .iter()
+            .find(|item| item.id == media_id)
+            .map(|item| item.file_path.clone())
+            .ok_or_else(|| format!("Media item not found: {}", media_id))?
+    };
+
+    // Measuring decodes the whole file, so don't hold the library lock meanwhile
+    let stats = measure_loudness(&file_path).map_err(|e| format!("Failed to measure loudness: {}", e))?;
+    println!(
+        "[Analysis] {}: {:.1} LUFS, {:.1} dBTP, {:.1} LU",
+        file_path, stats.integrated_lufs, stats.true_peak_db, stats.loudness_range
+    );
+
+    let mut library = library.library.lock().await;
+    if let Some(item) = library.get_mut(&media_id) {
+        item.loudness = Some(stats.clone());
+        library.save()?;
+    }
+
+    Ok(stats)
+}
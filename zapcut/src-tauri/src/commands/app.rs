@@ -1,10 +1,152 @@
-use tauri::command;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tauri::{command, State};
+use crate::commands::library::LibraryManager;
 use crate::utils::app_init::initialize_app_directories;
+use crate::utils::cache::{
+    cleanup_caches, clear_cache, delete_orphaned_caches, dir_usage, get_cache_dir, get_cache_info,
+    migrate_legacy_caches, CacheInfo, CacheKind,
+};
+use crate::utils::settings::{get_settings, update_settings, ThumbnailSettings};
 
 #[command]
-pub async fn init_app() -> Result<String, String> {
+pub async fn init_app(library: State<'_, LibraryManager>) -> Result<String, String> {
     match initialize_app_directories() {
-        Ok(path) => Ok(path.to_string_lossy().to_string()),
+        Ok(path) => {
+            // Cache housekeeping must never block startup
+            match migrate_legacy_caches() {
+                Ok(moved) if moved.is_empty() => {}
+                Ok(moved) => {
+                    println!("[Cache] Migrated {} files from the temp directory", moved.len());
+                    // Library items still point at the old proxy and thumbnail locations
+                    if let Err(e) = library.rewrite_cache_paths(&moved).await {
+                        eprintln!("[Cache] Failed to update migrated library paths: {}", e);
+                    }
+                }
+                Err(e) => eprintln!("[Cache] Legacy cache migration failed: {}", e),
+            }
+            if let Err(e) = cleanup_caches() {
+                eprintln!("[Cache] Cache cleanup failed: {}", e);
+            }
+            Ok(path.to_string_lossy().to_string())
+        }
         Err(e) => Err(format!("Failed to initialize app directories: {}", e)),
     }
 }
+
+/// Disk usage for each managed cache (thumbnails, proxies, waveforms, ...)
+#[command]
+pub fn get_cache_stats() -> Result<Vec<CacheInfo>, String> {
+    CacheKind::ALL
+        .iter()
+        .map(|kind| get_cache_info(*kind).map_err(|e| format!("Failed to read cache info: {}", e)))
+        .collect()
+}
+
+/// Delete all files of one cache category
+#[command]
+pub fn clear_media_cache(kind: CacheKind) -> Result<(), String> {
+    clear_cache(kind).map_err(|e| format!("Failed to clear cache: {}", e))
+}
+
+/// Delete cached proxies, thumbnails and other generated files whose source media is gone.
+/// Sources on a drive that isn't connected count as gone, so this is only run on request.
+#[command]
+pub fn delete_orphaned_media_caches() -> Result<u64, String> {
+    delete_orphaned_caches().map_err(|e| format!("Failed to delete orphaned caches: {}", e))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageCategory {
+    /// recordings, exports, media, projects, thumbnails, proxies, prerender_cache or other_caches
+    pub category: String,
+    pub paths: Vec<String>,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageStats {
+    pub categories: Vec<StorageCategory>,
+    pub total_bytes: u64,
+}
+
+fn storage_category(category: &str, paths: Vec<PathBuf>) -> StorageCategory {
+    let (file_count, total_bytes) = paths
+        .iter()
+        .map(|path| dir_usage(path))
+        .fold((0, 0), |(count, bytes), (c, b)| (count + c, bytes + b));
+    StorageCategory {
+        category: category.to_string(),
+        paths: paths.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+        file_count,
+        total_bytes,
+    }
+}
+
+/// Disk usage per storage category so the user can decide what to clear
+#[command]
+pub fn get_storage_stats() -> Result<StorageStats, String> {
+    let root = initialize_app_directories()
+        .map_err(|e| format!("Failed to initialize app directories: {}", e))?;
+    let cache_dir = |kind: CacheKind| {
+        get_cache_dir(kind).map_err(|e| format!("Failed to locate {} cache: {}", kind.dir_name(), e))
+    };
+
+    let other_caches = [
+        CacheKind::Waveforms,
+        CacheKind::Filmstrips,
+        CacheKind::Frames,
+        CacheKind::ContactSheets,
+    ]
+    .into_iter()
+    .map(cache_dir)
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let categories = vec![
+        storage_category("recordings", vec![root.join("recordings")]),
+        storage_category("exports", vec![root.join("exports")]),
+        // Downloads, card ingests and transcodes
+        storage_category("media", vec![root.join("media")]),
+        storage_category("projects", vec![root.join("projects")]),
+        // The top-level thumbnails folder predates the managed cache
+        storage_category(
+            "thumbnails",
+            vec![cache_dir(CacheKind::Thumbnails)?, root.join("thumbnails")],
+        ),
+        storage_category("proxies", vec![cache_dir(CacheKind::Proxies)?]),
+        storage_category("prerender_cache", vec![cache_dir(CacheKind::Prerender)?]),
+        storage_category("other_caches", other_caches),
+    ];
+
+    let total_bytes = categories.iter().map(|c| c.total_bytes).sum();
+    Ok(StorageStats {
+        categories,
+        total_bytes,
+    })
+}
+
+#[command]
+pub fn get_thumbnail_settings() -> ThumbnailSettings {
+    get_settings().thumbnails
+}
+
+/// Update thumbnail settings. Returns true when they changed, in which case the
+/// frontend should call regenerate_thumbnails for existing library items.
+#[command]
+pub fn set_thumbnail_settings(settings: ThumbnailSettings) -> Result<bool, String> {
+    if settings.max_dimension < 32 {
+        return Err("Thumbnail max dimension must be at least 32px".to_string());
+    }
+    if settings.quality == 0 || settings.quality > 100 {
+        return Err("Thumbnail quality must be between 1 and 100".to_string());
+    }
+
+    let changed = get_settings().thumbnails != settings;
+    if changed {
+        update_settings(|s| s.thumbnails = settings)
+            .map_err(|e| format!("Failed to save thumbnail settings: {}", e))?;
+    }
+
+    Ok(changed)
+}
@@ -0,0 +1,362 @@
+use crate::commands::prerender::{
+    clips_from, segment_duration, segment_graph, SegmentClip, SegmentFormat,
+};
+use crate::utils::ffmpeg::get_ffmpeg_path;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+/// Seconds of decoded audio buffered ahead of the output device
+const BUFFER_AHEAD_SECONDS: usize = 2;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AudioClockStatus {
+    pub loaded: bool,
+    pub playing: bool,
+    /// Seconds from the start of the loaded clips, counted in samples actually played
+    pub position: f64,
+    pub duration: f64,
+    pub sample_rate: u32,
+}
+
+/// Playback position measured by the output device: frames handed to the device since
+/// `start`, so underruns don't advance it
+struct AudioClock {
+    start: f64,
+    sample_rate: u32,
+    frames: AtomicU64,
+}
+
+impl AudioClock {
+    fn position(&self) -> f64 {
+        self.start + self.frames.load(Ordering::SeqCst) as f64 / self.sample_rate as f64
+    }
+}
+
+struct AudioSession {
+    stop: Arc<AtomicBool>,
+    clock: Arc<AudioClock>,
+}
+
+#[derive(Default)]
+struct AudioState {
+    timeline: Option<(Vec<SegmentClip>, SegmentFormat, f64)>,
+    playing: bool,
+    position: f64,
+    session: Option<AudioSession>,
+}
+
+/// Plays the timeline's mixed audio on the default output device. Its clock counts the samples
+/// the device has consumed, so the preview video can follow it instead of the browser clock.
+pub struct AudioEngine {
+    state: Mutex<AudioState>,
+}
+
+impl AudioEngine {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(AudioState::default()),
+        }
+    }
+
+    /// Stop the running session, keeping the position it reached
+    fn stop_session(state: &mut AudioState) {
+        if let Some(session) = state.session.take() {
+            session.stop.store(true, Ordering::SeqCst);
+            state.position = session.clock.position();
+        }
+    }
+
+    fn start_session(&self, app: &AppHandle) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        Self::stop_session(&mut state);
+        let (clips, format, _) = state.timeline.clone().ok_or("No timeline loaded for playback")?;
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No audio output device available")?;
+        let config = device
+            .default_output_config()
+            .map_err(|e| format!("Failed to get output config: {}", e))?;
+        let sample_rate = config.sample_rate().0;
+        let channels = config.channels() as usize;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let clock = Arc::new(AudioClock {
+            start: state.position,
+            sample_rate,
+            frames: AtomicU64::new(0),
+        });
+        let buffer = Arc::new(Mutex::new(VecDeque::new()));
+        let finished = Arc::new(AtomicBool::new(false));
+
+        let args = decoder_args(&clips, &format, state.position, sample_rate, channels)?;
+        let decoder = DecoderShared {
+            buffer: buffer.clone(),
+            stop: stop.clone(),
+            finished: finished.clone(),
+            capacity: sample_rate as usize * channels * BUFFER_AHEAD_SECONDS,
+        };
+        std::thread::spawn(move || {
+            if let Err(e) = decode_audio(&args, &decoder) {
+                eprintln!("[Audio] Decoding failed: {}", e);
+            }
+            decoder.finished.store(true, Ordering::SeqCst);
+        });
+
+        let output = OutputShared {
+            buffer: buffer.clone(),
+            clock: clock.clone(),
+            channels,
+        };
+        let (app, thread_stop) = (app.clone(), stop.clone());
+        std::thread::spawn(move || {
+            // Streams aren't Send on every platform, so this thread owns it until stopped
+            let stream = match build_stream(&device, &config, output) {
+                Ok(stream) => stream,
+                Err(e) => {
+                    eprintln!("[Audio] {}", e);
+                    return;
+                }
+            };
+            if let Err(e) = stream.play() {
+                eprintln!("[Audio] Failed to start output: {}", e);
+                return;
+            }
+            while !thread_stop.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(20));
+                // Decoded to the end and all of it played
+                if finished.load(Ordering::SeqCst) && buffer.lock().unwrap().is_empty() {
+                    finish_playback(&app, &thread_stop);
+                    break;
+                }
+            }
+        });
+
+        state.session = Some(AudioSession { stop, clock });
+        Ok(())
+    }
+
+    fn status(&self) -> AudioClockStatus {
+        let state = self.state.lock().unwrap();
+        let (position, sample_rate) = match &state.session {
+            Some(session) => (session.clock.position(), session.clock.sample_rate),
+            None => (state.position, 0),
+        };
+        AudioClockStatus {
+            loaded: state.timeline.is_some(),
+            playing: state.playing,
+            position,
+            duration: state.timeline.as_ref().map(|(_, _, duration)| *duration).unwrap_or(0.0),
+            sample_rate,
+        }
+    }
+}
+
+struct DecoderShared {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    stop: Arc<AtomicBool>,
+    finished: Arc<AtomicBool>,
+    /// Samples to buffer before waiting for the device to catch up
+    capacity: usize,
+}
+
+struct OutputShared {
+    buffer: Arc<Mutex<VecDeque<f32>>>,
+    clock: Arc<AudioClock>,
+    channels: usize,
+}
+
+/// FFmpeg arguments mixing the clips' audio from `position` as interleaved f32 at the device's
+/// rate and channel count. Every input is seeked, so starting late costs no extra decoding.
+fn decoder_args(
+    clips: &[SegmentClip],
+    format: &SegmentFormat,
+    position: f64,
+    sample_rate: u32,
+    channels: usize,
+) -> Result<Vec<String>, String> {
+    let (clips, format) = clips_from(clips, format, position);
+    let (mut args, _, out_audio) = segment_graph(&clips, &format, false)?;
+    args.extend([
+        "-map".to_string(),
+        format!("[{}]", out_audio),
+        "-ac".to_string(),
+        channels.to_string(),
+        "-ar".to_string(),
+        sample_rate.to_string(),
+        "-f".to_string(),
+        "f32le".to_string(),
+        "-".to_string(),
+    ]);
+    Ok(args)
+}
+
+fn decode_audio(args: &[String], shared: &DecoderShared) -> Result<(), String> {
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| e.to_string())?;
+    let mut child = Command::new(ffmpeg_path)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+    let mut stdout = child.stdout.take().ok_or("FFmpeg has no output")?;
+
+    let mut chunk = vec![0u8; 64 * 1024];
+    let mut pending = Vec::new();
+    while !shared.stop.load(Ordering::SeqCst) {
+        if shared.buffer.lock().unwrap().len() >= shared.capacity {
+            std::thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+        let read = stdout.read(&mut chunk).map_err(|e| format!("Failed to read audio: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        pending.extend_from_slice(&chunk[..read]);
+        let whole = pending.len() / 4 * 4;
+        let samples = pending[..whole]
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
+        shared.buffer.lock().unwrap().extend(samples);
+        pending.drain(..whole);
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+    Ok(())
+}
+
+fn build_stream(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    output: OutputShared,
+) -> Result<cpal::Stream, String> {
+    match config.sample_format() {
+        SampleFormat::F32 => build_typed_stream::<f32>(device, config, output),
+        SampleFormat::I16 => build_typed_stream::<i16>(device, config, output),
+        SampleFormat::U16 => build_typed_stream::<u16>(device, config, output),
+        SampleFormat::I32 => build_typed_stream::<i32>(device, config, output),
+        other => Err(format!("Unsupported output sample format: {:?}", other)),
+    }
+}
+
+fn build_typed_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::SupportedStreamConfig,
+    output: OutputShared,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample + FromSample<f32>,
+{
+    device
+        .build_output_stream(
+            &config.config(),
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                // Never block the realtime thread: if the decoder holds the buffer right now,
+                // this callback plays silence like an underrun
+                let mut available = 0;
+                if let Ok(mut buffer) = output.buffer.try_lock() {
+                    available = buffer.len().min(data.len()) / output.channels * output.channels;
+                    for (slot, sample) in data.iter_mut().zip(buffer.drain(..available)) {
+                        *slot = T::from_sample(sample);
+                    }
+                }
+                // Underruns play silence without advancing the clock
+                for slot in &mut data[available..] {
+                    *slot = T::from_sample(0.0f32);
+                }
+                let frames = (available / output.channels) as u64;
+                output.clock.frames.fetch_add(frames, Ordering::SeqCst);
+            },
+            |e| eprintln!("[Audio] Output error: {}", e),
+            None,
+        )
+        .map_err(|e| format!("Failed to open audio output: {}", e))
+}
+
+/// The clips played out: stop at the end and tell the frontend
+fn finish_playback(app: &AppHandle, stop: &Arc<AtomicBool>) {
+    let engine = app.state::<AudioEngine>();
+    let mut state = engine.state.lock().unwrap();
+    if state.session.as_ref().is_some_and(|session| Arc::ptr_eq(&session.stop, stop)) {
+        AudioEngine::stop_session(&mut state);
+        state.playing = false;
+        drop(state);
+        let _ = app.emit("audio://ended", ());
+    }
+}
+
+/// Load the clips whose mixed audio the engine plays, paused at the start
+#[command]
+pub fn load_audio_playback(
+    engine: State<'_, AudioEngine>,
+    clips: Vec<SegmentClip>,
+    format: Option<SegmentFormat>,
+) -> Result<AudioClockStatus, String> {
+    if clips.is_empty() {
+        return Err("No clips to play".to_string());
+    }
+    let format = format.unwrap_or_default();
+    {
+        let mut state = engine.state.lock().unwrap();
+        AudioEngine::stop_session(&mut state);
+        let duration = segment_duration(&clips, &format);
+        state.timeline = Some((clips, format, duration));
+        state.playing = false;
+        state.position = 0.0;
+    }
+    Ok(engine.status())
+}
+
+#[command]
+pub fn play_audio_playback(
+    app: AppHandle,
+    engine: State<'_, AudioEngine>,
+) -> Result<AudioClockStatus, String> {
+    engine.start_session(&app)?;
+    engine.state.lock().unwrap().playing = true;
+    Ok(engine.status())
+}
+
+#[command]
+pub fn pause_audio_playback(engine: State<'_, AudioEngine>) -> AudioClockStatus {
+    {
+        let mut state = engine.state.lock().unwrap();
+        AudioEngine::stop_session(&mut state);
+        state.playing = false;
+    }
+    engine.status()
+}
+
+/// Jump to `time` seconds, carrying on playing from there when playing
+#[command]
+pub fn seek_audio_playback(
+    app: AppHandle,
+    engine: State<'_, AudioEngine>,
+    time: f64,
+) -> Result<AudioClockStatus, String> {
+    let playing = {
+        let mut state = engine.state.lock().unwrap();
+        AudioEngine::stop_session(&mut state);
+        let duration = state.timeline.as_ref().map(|(_, _, duration)| *duration).unwrap_or(0.0);
+        state.position = time.clamp(0.0, duration);
+        state.playing
+    };
+    if playing {
+        engine.start_session(&app)?;
+    }
+    Ok(engine.status())
+}
+
+/// The audio clock, for syncing preview video to what is actually being heard
+#[command]
+pub fn get_audio_clock(engine: State<'_, AudioEngine>) -> AudioClockStatus {
+    engine.status()
+}
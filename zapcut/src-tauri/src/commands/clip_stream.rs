@@ -0,0 +1,177 @@
+use crate::commands::stream::{clip_stream_url, is_in_scope};
+use crate::utils::ffmpeg::spawn_concat_ranges_fmp4;
+use crate::utils::prerender_cache::prerender_cache_dir;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{command, AppHandle, State};
+
+/// Clip streams kept open; opening another closes the oldest
+const MAX_CLIP_STREAMS: usize = 4;
+
+/// One range of a source file, in source seconds
+#[derive(Debug, Deserialize, Clone)]
+pub struct ClipRange {
+    pub path: String,
+    pub in_point: f64,
+    pub out_point: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct ClipStreamInfo {
+    pub id: String,
+    /// stream:// URL of the joined fMP4, playable while it is still being written
+    pub url: String,
+    /// Seconds; approximate since each range starts on a keyframe
+    pub duration: f64,
+}
+
+/// A joined fMP4 being written by a background FFmpeg, readable as it grows
+pub(crate) struct ClipStream {
+    pub path: PathBuf,
+    /// Bytes written to `path` so far
+    pub written: AtomicU64,
+    pub finished: AtomicBool,
+    cancel: AtomicBool,
+}
+
+/// Open gapless streams of consecutive clips, served at `/__clips/<id>`
+pub struct ClipStreams {
+    streams: Mutex<VecDeque<(String, Arc<ClipStream>)>>,
+}
+
+impl ClipStreams {
+    pub fn new() -> Self {
+        Self {
+            streams: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, id: &str) -> Option<Arc<ClipStream>> {
+        let streams = self.streams.lock().unwrap();
+        streams.iter().find(|(stream_id, _)| stream_id == id).map(|(_, stream)| stream.clone())
+    }
+}
+
+/// Copy FFmpeg's output to the stream's file as it arrives, until done or cancelled
+fn write_clip_stream(ranges: &[(String, f64, f64)], stream: &ClipStream) -> Result<(), String> {
+    let list_path = stream.path.with_extension("txt");
+    let result = (|| {
+        let mut child = spawn_concat_ranges_fmp4(ranges, &list_path).map_err(|e| e.to_string())?;
+        let mut stdout = child.stdout.take().ok_or("FFmpeg has no output")?;
+        let mut file = File::create(&stream.path)
+            .map_err(|e| format!("Failed to create clip stream file: {}", e))?;
+
+        let mut chunk = vec![0u8; 256 * 1024];
+        while !stream.cancel.load(Ordering::SeqCst) {
+            let read = stdout
+                .read(&mut chunk)
+                .map_err(|e| format!("Failed to read FFmpeg output: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&chunk[..read])
+                .and_then(|_| file.flush())
+                .map_err(|e| format!("Failed to write clip stream: {}", e))?;
+            stream.written.fetch_add(read as u64, Ordering::SeqCst);
+        }
+        let _ = child.kill();
+        let _ = child.wait();
+        Ok(())
+    })();
+    let _ = std::fs::remove_file(&list_path);
+    result
+}
+
+/// Stop the writer; whichever of this and the writer sees the other's flag last deletes the file
+fn close(stream: &ClipStream) {
+    stream.cancel.store(true, Ordering::SeqCst);
+    if stream.finished.load(Ordering::SeqCst) {
+        let _ = std::fs::remove_file(&stream.path);
+    }
+}
+
+/// Start joining consecutive clip ranges into one fMP4 for gapless preview without
+/// prerendering. The sources must share codec settings since streams are copied, not
+/// re-encoded. The returned URL can be played right away; it grows as FFmpeg works.
+#[command]
+pub async fn open_clip_stream(
+    app: AppHandle,
+    streams: State<'_, ClipStreams>,
+    clips: Vec<ClipRange>,
+) -> Result<ClipStreamInfo, String> {
+    if clips.is_empty() {
+        return Err("No clips to stream".to_string());
+    }
+    for clip in &clips {
+        if !is_in_scope(&app, &clip.path).await {
+            return Err(format!("{} is not in the media library", clip.path));
+        }
+        if clip.out_point <= clip.in_point {
+            return Err(format!(
+                "Invalid range {}-{} of {}",
+                clip.in_point, clip.out_point, clip.path
+            ));
+        }
+    }
+
+    let dir = prerender_cache_dir()
+        .map_err(|e| format!("Failed to get prerender cache directory: {}", e))?
+        .join("clip_streams");
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("Failed to create clip stream directory: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().simple().to_string();
+    let stream = Arc::new(ClipStream {
+        path: dir.join(format!("{}.mp4", id)),
+        written: AtomicU64::new(0),
+        finished: AtomicBool::new(false),
+        cancel: AtomicBool::new(false),
+    });
+    {
+        let mut open = streams.streams.lock().unwrap();
+        while open.len() >= MAX_CLIP_STREAMS {
+            if let Some((_, oldest)) = open.pop_front() {
+                close(&oldest);
+            }
+        }
+        open.push_back((id.clone(), stream.clone()));
+    }
+
+    let ranges: Vec<(String, f64, f64)> = clips
+        .iter()
+        .map(|clip| (clip.path.clone(), clip.in_point, clip.out_point))
+        .collect();
+    let worker = stream.clone();
+    std::thread::spawn(move || {
+        if let Err(e) = write_clip_stream(&ranges, &worker) {
+            eprintln!("[Stream] Clip stream failed: {}", e);
+        }
+        worker.finished.store(true, Ordering::SeqCst);
+        if worker.cancel.load(Ordering::SeqCst) {
+            let _ = std::fs::remove_file(&worker.path);
+        }
+    });
+
+    Ok(ClipStreamInfo {
+        url: clip_stream_url(&id),
+        duration: clips.iter().map(|clip| clip.out_point - clip.in_point).sum(),
+        id,
+    })
+}
+
+/// Stop a clip stream and delete its file
+#[command]
+pub fn close_clip_stream(streams: State<'_, ClipStreams>, id: String) -> Result<(), String> {
+    let mut open = streams.streams.lock().unwrap();
+    if let Some(index) = open.iter().position(|(stream_id, _)| *stream_id == id) {
+        if let Some((_, stream)) = open.remove(index) {
+            close(&stream);
+        }
+    }
+    Ok(())
+}
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::process::{Command, Stdio};
 use std::sync::{Arc, Mutex};
-use tauri::command;
-use crate::utils::ffmpeg::{get_ffmpeg_path, get_video_info};
+use tauri::{command, State};
+use crate::commands::library::LibraryManager;
+use crate::utils::ffmpeg::{
+    check_audio_track, escape_filter_path, get_ffmpeg_path, get_rnnoise_model_path, get_video_info,
+    measure_loudness, measure_loudness_range, LoudnessStats,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ExportConfig {
@@ -13,6 +18,124 @@ pub struct ExportConfig {
     pub quality: String,
     pub fps: Option<f64>,
     pub include_audio: bool,
+    /// Render from camera originals even when clips were cut against library transcodes
+    #[serde(default = "default_use_original_media")]
+    pub use_original_media: bool,
+    /// Background music mixed under the clips' audio
+    #[serde(default)]
+    pub music: Option<MusicTrack>,
+}
+
+fn default_use_original_media() -> bool {
+    true
+}
+
+/// An audio file played under the whole timeline, separate from the clips
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MusicTrack {
+    pub path: String,
+    /// Where the music starts on the timeline, in seconds
+    #[serde(default)]
+    pub start_time: f64,
+    /// Seconds into the music file to start from
+    #[serde(default)]
+    pub trim_start: f64,
+    /// Volume change in decibels
+    #[serde(default)]
+    pub gain_db: f64,
+    /// Repeat the music until the timeline ends
+    #[serde(default)]
+    pub looped: bool,
+    /// Dip the music whenever the clips' audio (usually narration) is loud; none when None
+    #[serde(default)]
+    pub ducking: Option<Ducking>,
+}
+
+/// Sidechain compression of the music, keyed on the clips' audio
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Ducking {
+    /// Level of the clips' audio above which the music is turned down, in dBFS
+    pub threshold_db: f64,
+    /// How hard the music is turned down above the threshold
+    pub ratio: f64,
+    /// Milliseconds for the music to dip once narration starts
+    pub attack_ms: f64,
+    /// Milliseconds for the music to come back once narration stops
+    pub release_ms: f64,
+}
+
+impl Default for Ducking {
+    fn default() -> Self {
+        Self {
+            threshold_db: -30.0,
+            ratio: 8.0,
+            attack_ms: 20.0,
+            release_ms: 400.0,
+        }
+    }
+}
+
+impl Ducking {
+    /// sidechaincompress with the settings clamped to the ranges FFmpeg accepts
+    pub fn to_filter(&self) -> String {
+        let threshold = 10f64.powf(self.threshold_db / 20.0).clamp(0.001, 1.0);
+        format!(
+            "sidechaincompress=threshold={:.5}:ratio={:.2}:attack={:.2}:release={:.2}",
+            threshold,
+            self.ratio.clamp(1.0, 20.0),
+            self.attack_ms.clamp(0.01, 2000.0),
+            self.release_ms.clamp(0.01, 9000.0)
+        )
+    }
+}
+
+/// Check the music file exists before any work is done
+fn validate_music(config: &ExportConfig) -> Result<(), String> {
+    match &config.music {
+        Some(music) if config.include_audio && !std::path::Path::new(&music.path).exists() => {
+            Err(format!("Music file not found: {}", music.path))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// FFmpeg input arguments for the music, looped when asked so it outlasts the timeline
+fn music_input_args(music: &MusicTrack) -> Vec<String> {
+    let mut args = vec![];
+    if music.looped {
+        args.extend(["-stream_loop".to_string(), "-1".to_string()]);
+    }
+    if music.trim_start > 0.0 {
+        args.extend(["-ss".to_string(), format!("{:.3}", music.trim_start)]);
+    }
+    args.extend(["-i".to_string(), music.path.clone()]);
+    args
+}
+
+/// Filter graph mixing `music`, FFmpeg input `input`, under the `voice` pad into `output`.
+/// The mix lasts as long as the voice. With ducking, the voice also keys a compressor on
+/// the music.
+fn music_mix_filters(music: &MusicTrack, input: usize, voice: &str, output: &str) -> Vec<String> {
+    let format = "aresample=48000,aformat=sample_fmts=fltp:channel_layouts=stereo";
+    let mut music_filters = vec![format.to_string()];
+    music_filters.extend(audio_mix_filters(music.gain_db, 0.0));
+    if music.start_time > 0.001 {
+        let delay_ms = (music.start_time * 1000.0).round() as u64;
+        music_filters.push(format!("adelay={}:all=1", delay_ms));
+    }
+
+    let mut parts = vec![format!("[{}:a]{}[music]", input, music_filters.join(","))];
+    let mix = "amix=inputs=2:duration=first:normalize=0";
+    match &music.ducking {
+        Some(ducking) => {
+            parts.push(format!("{}{},asplit=2[voice][voicekey]", voice, format));
+            parts.push(format!("[music][voicekey]{}[ducked]", ducking.to_filter()));
+            parts.push(format!("[voice][ducked]{}{}", mix, output));
+        }
+        None => parts.push(format!("{}[music]{}{}", voice, mix, output)),
+    }
+    parts
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -25,6 +148,334 @@ pub struct Clip {
     pub duration: f64,
     pub speed: f64,
     pub track_index: Option<u32>,
+    /// Region of the source frame to keep, applied before scaling
+    #[serde(default)]
+    pub crop: Option<CropRect>,
+    /// Which of the source's audio streams to use (0-based); the first when None
+    #[serde(default)]
+    pub audio_track: Option<u32>,
+    /// Volume change in decibels
+    #[serde(default)]
+    pub gain_db: f64,
+    /// Stereo balance from -1 (left only) through 0 (centre) to 1 (right only)
+    #[serde(default)]
+    pub pan: f64,
+    /// Seconds of audio fade-in at the start of the clip
+    #[serde(default)]
+    pub fade_in_sec: f64,
+    /// Seconds of audio fade-out at the end of the clip
+    #[serde(default)]
+    pub fade_out_sec: f64,
+    /// Also fade the picture from and to black over the same lengths
+    #[serde(default)]
+    pub fade_video: bool,
+    /// Noise reduction for hiss and hum; none when None
+    #[serde(default)]
+    pub denoise_audio: Option<DenoiseAudio>,
+    /// Effects applied to the clip's audio in order, before gain and pan
+    #[serde(default)]
+    pub audio_effects: Vec<AudioEffect>,
+    /// Integrated loudness to bring the clip's audio to, in LUFS (e.g. -16), measured over the
+    /// exported track and trim range; added to `gain_db` at export
+    #[serde(default)]
+    pub normalize_lufs: Option<f64>,
+    /// External audio (e.g. from a separate recorder) used instead of the clip's own
+    #[serde(default)]
+    pub audio_override: Option<AudioOverride>,
+}
+
+/// Replacement audio for a clip, lined up with the clip's source
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudioOverride {
+    pub path: String,
+    /// Time in the override file matching the start of the clip's source file, in seconds;
+    /// negative when the override started recording later
+    #[serde(default)]
+    pub offset: f64,
+}
+
+impl AudioOverride {
+    /// Where in the override the clip starts, for a clip trimmed to `trim_start` in its source
+    fn seek(&self, trim_start: f64) -> f64 {
+        trim_start + self.offset
+    }
+
+    /// FFmpeg input arguments reading the override from the clip's start onwards
+    pub fn input_args(&self, trim_start: f64, source_duration: f64) -> Vec<String> {
+        let seek = self.seek(trim_start);
+        let mut args = vec![];
+        if seek > 0.0 {
+            args.extend(["-ss".to_string(), format!("{:.3}", seek)]);
+        }
+        if seek >= 0.0 {
+            args.extend(["-t".to_string(), format!("{:.3}", source_duration)]);
+        }
+        args.extend(["-i".to_string(), self.path.clone()]);
+        args
+    }
+
+    /// Filters fitting the override to the clip: silence where the override hadn't started
+    /// yet, then padded with silence or truncated to `source_duration`
+    pub fn align_filters(&self, trim_start: f64, source_duration: f64) -> Vec<String> {
+        let seek = self.seek(trim_start);
+        let mut filters = vec![];
+        if seek < 0.0 {
+            let delay_ms = (-seek * 1000.0).round() as u64;
+            filters.push(format!("adelay={}:all=1", delay_ms));
+        }
+        filters.push("apad".to_string());
+        filters.push(format!("atrim=duration={:.3}", source_duration));
+        filters.push("asetpts=PTS-STARTPTS".to_string());
+        filters
+    }
+}
+
+/// Check every clip's audio override exists before any work is done
+fn validate_audio_overrides(clips: &[Clip], config: &ExportConfig) -> Result<(), String> {
+    if !config.include_audio {
+        return Ok(());
+    }
+    for clip in clips {
+        if let Some(audio_override) = &clip.audio_override {
+            if !std::path::Path::new(&audio_override.path).exists() {
+                return Err(format!(
+                    "Audio override for clip {} not found: {}",
+                    clip.id, audio_override.path
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check every clip's chosen audio track exists in its source. Clips whose audio is replaced
+/// by an override don't read their own tracks.
+fn validate_audio_tracks(clips: &[Clip], config: &ExportConfig) -> Result<(), String> {
+    if !config.include_audio {
+        return Ok(());
+    }
+    for clip in clips.iter().filter(|clip| clip.audio_override.is_none()) {
+        if let Some(track) = clip.audio_track {
+            check_audio_track(&clip.file_path, track)
+                .map_err(|e| format!("Clip {}: {}", clip.id, e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Per-clip mix filters: `gain_db` as a volume change, then `pan` as a stereo balance. Empty
+/// when both are neutral.
+pub fn audio_mix_filters(gain_db: f64, pan: f64) -> Vec<String> {
+    let mut filters = vec![];
+    if gain_db.abs() > 0.001 {
+        filters.push(format!("volume={:.3}dB", gain_db));
+    }
+    let pan = pan.clamp(-1.0, 1.0);
+    if pan.abs() > 0.001 {
+        // Balance: the far side is attenuated, the near side stays at full level
+        let (left, right) = ((1.0 - pan).min(1.0), (1.0 + pan).min(1.0));
+        filters.push("aformat=channel_layouts=stereo".to_string());
+        filters.push(format!("pan=stereo|c0={:.4}*c0|c1={:.4}*c1", left, right));
+    }
+    filters
+}
+
+/// Fade-in and fade-out filters for a clip lasting `duration` output seconds, using `filter`
+/// (`afade` for audio, `fade` for video). Fades longer than the clip together are shortened
+/// in proportion so they don't overlap.
+pub fn fade_filters(filter: &str, duration: f64, fade_in: f64, fade_out: f64) -> Vec<String> {
+    let (fade_in, fade_out) = (fade_in.max(0.0), fade_out.max(0.0));
+    let scale = if fade_in + fade_out > duration && duration > 0.0 {
+        duration / (fade_in + fade_out)
+    } else {
+        1.0
+    };
+    let (fade_in, fade_out) = (fade_in * scale, fade_out * scale);
+
+    let mut filters = vec![];
+    if fade_in > 0.001 {
+        filters.push(format!("{}=t=in:st=0:d={:.3}", filter, fade_in));
+    }
+    if fade_out > 0.001 {
+        filters.push(format!(
+            "{}=t=out:st={:.3}:d={:.3}",
+            filter,
+            (duration - fade_out).max(0.0),
+            fade_out
+        ));
+    }
+    filters
+}
+
+/// For serde: leave neutral mix settings out of serialized clips
+pub(crate) fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+/// Crop rectangle in source pixels
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CropRect {
+    pub fn to_filter(&self) -> String {
+        format!("crop={}:{}:{}:{}", self.width, self.height, self.x, self.y)
+    }
+}
+
+/// Noise reduction applied to a clip's audio before anything else
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DenoiseAudio {
+    #[serde(default)]
+    pub method: DenoiseMethod,
+    /// 0 (barely) to 1 (as much as possible)
+    #[serde(default = "default_denoise_strength")]
+    pub strength: f64,
+}
+
+fn default_denoise_strength() -> f64 {
+    0.5
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DenoiseMethod {
+    /// FFT noise reduction (afftdn), best for steady noise such as fan hiss
+    #[default]
+    Fft,
+    /// RNNoise (arnndn), better with changing background noise; needs the bundled model
+    Rnn,
+}
+
+impl DenoiseAudio {
+    /// Fails when RNNoise is asked for but its model isn't installed
+    pub fn to_filter(&self) -> Result<String, String> {
+        let strength = self.strength.clamp(0.0, 1.0);
+        match self.method {
+            DenoiseMethod::Rnn => {
+                let model = get_rnnoise_model_path()
+                    .map_err(|e| format!("RNNoise noise reduction unavailable: {}", e))?;
+                Ok(format!("arnndn=m='{}':mix={:.2}", escape_filter_path(&model), strength))
+            }
+            // 6dB of reduction at the lowest strength up to 30dB at the highest
+            DenoiseMethod::Fft => Ok(format!("afftdn=nr={:.1}:nf=-40:tn=1", 6.0 + strength * 24.0)),
+        }
+    }
+}
+
+/// One step of a clip's audio effect chain
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AudioEffect {
+    /// Parametric EQ
+    Eq { bands: Vec<EqBand> },
+    Compressor(Compressor),
+    /// Tames harsh "s" sounds in speech
+    DeEsser(DeEsser),
+}
+
+/// A peaking EQ band
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EqBand {
+    /// Centre frequency in Hz
+    pub frequency: f64,
+    /// Boost (positive) or cut (negative) in decibels
+    pub gain_db: f64,
+    /// Bandwidth as a Q factor; higher is narrower
+    #[serde(default = "default_eq_q")]
+    pub q: f64,
+}
+
+fn default_eq_q() -> f64 {
+    1.0
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Compressor {
+    /// Level above which the audio is compressed, in dBFS
+    pub threshold_db: f64,
+    pub ratio: f64,
+    pub attack_ms: f64,
+    pub release_ms: f64,
+    /// Gain added after compression, in decibels
+    pub makeup_db: f64,
+}
+
+impl Default for Compressor {
+    fn default() -> Self {
+        Self {
+            threshold_db: -18.0,
+            ratio: 3.0,
+            attack_ms: 20.0,
+            release_ms: 250.0,
+            makeup_db: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DeEsser {
+    /// 0 (off) to 1 (strongest)
+    pub intensity: f64,
+    /// Most the sibilance is turned down, 0 to 1
+    pub max_reduction: f64,
+    /// Which sibilance to target, 0 (lower, duller) to 1 (higher, brighter)
+    pub frequency: f64,
+}
+
+impl Default for DeEsser {
+    fn default() -> Self {
+        Self {
+            intensity: 0.5,
+            max_reduction: 0.5,
+            frequency: 0.5,
+        }
+    }
+}
+
+impl AudioEffect {
+    /// FFmpeg filters for this effect, clamped to the ranges FFmpeg accepts; empty for an EQ
+    /// without bands
+    pub fn to_filters(&self) -> Vec<String> {
+        match self {
+            AudioEffect::Eq { bands } => bands
+                .iter()
+                .map(|band| {
+                    format!(
+                        "equalizer=f={:.1}:t=q:w={:.3}:g={:.2}",
+                        band.frequency.clamp(20.0, 20000.0),
+                        band.q.clamp(0.1, 20.0),
+                        band.gain_db.clamp(-24.0, 24.0)
+                    )
+                })
+                .collect(),
+            AudioEffect::Compressor(compressor) => vec![format!(
+                "acompressor=threshold={:.5}:ratio={:.2}:attack={:.2}:release={:.2}:makeup={:.3}",
+                10f64.powf(compressor.threshold_db / 20.0).clamp(0.001, 1.0),
+                compressor.ratio.clamp(1.0, 20.0),
+                compressor.attack_ms.clamp(0.01, 2000.0),
+                compressor.release_ms.clamp(0.01, 9000.0),
+                10f64.powf(compressor.makeup_db / 20.0).clamp(1.0, 64.0)
+            )],
+            AudioEffect::DeEsser(de_esser) => vec![format!(
+                "deesser=i={:.2}:m={:.2}:f={:.2}",
+                de_esser.intensity.clamp(0.0, 1.0),
+                de_esser.max_reduction.clamp(0.0, 1.0),
+                de_esser.frequency.clamp(0.0, 1.0)
+            )],
+        }
+    }
+}
+
+/// Filters for a clip's whole effect chain, in order
+pub fn audio_effect_filters(effects: &[AudioEffect]) -> Vec<String> {
+    effects.iter().flat_map(AudioEffect::to_filters).collect()
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -57,6 +508,127 @@ lazy_static::lazy_static! {
     }));
 }
 
+/// Point clips that reference a library transcode back at the original media
+async fn swap_to_original_media(library: &LibraryManager, clips: Vec<Clip>) -> Vec<Clip> {
+    let library = library.library.lock().await;
+    clips
+        .into_iter()
+        .map(|mut clip| {
+            if let Some(item) = library.find_by_transcode_path(&clip.file_path) {
+                if std::path::Path::new(&item.file_path).exists() {
+                    println!("[Export] Using original {} for clip {}", item.file_path, clip.id);
+                    clip.file_path = item.file_path.clone();
+                } else {
+                    eprintln!("[Export] Original missing for clip {}, keeping transcode", clip.id);
+                }
+            }
+            clip
+        })
+        .collect()
+}
+
+/// Highest true peak normalization may push a clip to, in dBTP
+const NORMALIZE_PEAK_CEILING_DB: f64 = -1.0;
+
+/// Fold each clip's `normalize_lufs` into its `gain_db`, from the loudness of the audio the
+/// clip actually exports: its audio track (or override) over its trimmed range. An untrimmed
+/// clip on the first track uses the source's loudness in the library, measured now and stored
+/// there when it hasn't been analyzed. The gain is held back so the true peak stays under
+/// -1 dBTP, and silent clips are left alone.
+async fn apply_loudness_normalization(
+    library: &LibraryManager,
+    mut clips: Vec<Clip>,
+) -> Result<Vec<Clip>, String> {
+    let mut measured: HashMap<String, LoudnessStats> = HashMap::new();
+    for clip in clips.iter_mut() {
+        let Some(target) = clip.normalize_lufs else {
+            continue;
+        };
+        // The override replaces the clip's audio, so it's what gets measured
+        let (audio_path, track, start) = match &clip.audio_override {
+            Some(audio_override) => {
+                (audio_override.path.clone(), 0, audio_override.seek(clip.trim_start))
+            }
+            None => (clip.file_path.clone(), clip.audio_track.unwrap_or(0), clip.trim_start),
+        };
+        let whole_source = track == 0 && start == 0.0 && clip.trim_end == 0.0;
+        let length = (!whole_source).then_some(clip.duration * clip.speed);
+        let key = format!("{}#{}@{:.3}+{:?}", audio_path, track, start, length);
+        let stats = match measured.get(&key) {
+            Some(stats) => stats.clone(),
+            None => {
+                let stats = match length {
+                    None => source_loudness(library, &audio_path).await?,
+                    Some(length) => segment_loudness(&audio_path, track, start, length).await?,
+                };
+                measured.insert(key, stats.clone());
+                stats
+            }
+        };
+        if stats.integrated_lufs <= -70.0 {
+            continue;
+        }
+
+        let gain = (target - stats.integrated_lufs)
+            .min(NORMALIZE_PEAK_CEILING_DB - stats.true_peak_db)
+            .clamp(-30.0, 30.0);
+        println!(
+            "[Export] Normalizing clip {} from {:.1} to {:.1} LUFS: {:+.1}dB",
+            clip.id, stats.integrated_lufs, target, gain
+        );
+        clip.gain_db += gain;
+    }
+    Ok(clips)
+}
+
+/// Loudness of part of a source. An override that starts after the clip is measured from its
+/// own start, over what the clip hears of it.
+async fn segment_loudness(
+    file_path: &str,
+    track: u32,
+    start: f64,
+    length: f64,
+) -> Result<LoudnessStats, String> {
+    let path = file_path.to_string();
+    let length = (length + start.min(0.0)).max(0.0);
+    tauri::async_runtime::spawn_blocking(move || {
+        measure_loudness_range(&path, track, start.max(0.0), Some(length))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(|e| format!("Failed to measure loudness of {}: {}", file_path, e))
+}
+
+/// Loudness of a source file, from its library item when analyzed before
+async fn source_loudness(
+    library: &LibraryManager,
+    file_path: &str,
+) -> Result<LoudnessStats, String> {
+    let media_id = {
+        let library = library.library.lock().await;
+        let item = library.items.iter().find(|item| item.file_path == file_path);
+        if let Some(stats) = item.and_then(|item| item.loudness.clone()) {
+            return Ok(stats);
+        }
+        item.map(|item| item.id.clone())
+    };
+
+    let path = file_path.to_string();
+    let stats = tauri::async_runtime::spawn_blocking(move || measure_loudness(&path))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to measure loudness of {}: {}", file_path, e))?;
+
+    if let Some(media_id) = media_id {
+        let mut library = library.library.lock().await;
+        if let Some(item) = library.get_mut(&media_id) {
+            item.loudness = Some(stats.clone());
+            library.save()?;
+        }
+    }
+    Ok(stats)
+}
+
 /// Validates a single clip before export
 fn validate_clip(clip: &Clip) -> Result<ClipValidationResult, String> {
     // Check if file exists
@@ -222,7 +794,18 @@ fn validate_output(output_path: &str, expected_duration: f64) -> Result<(), Stri
 }
 
 #[command]
-pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<String, String> {
+pub async fn export_timeline(
+    library: State<'_, LibraryManager>,
+    clips: Vec<Clip>,
+    config: ExportConfig,
+) -> Result<String, String> {
+    let clips = if config.use_original_media {
+        swap_to_original_media(&library, clips).await
+    } else {
+        clips
+    };
+    let clips = apply_loudness_normalization(&library, clips).await?;
+
     // Update progress
     {
         let mut progress = EXPORT_PROGRESS.lock().unwrap();
@@ -250,6 +833,10 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
 
     // Phase 1: Validate all clips before starting
     println!("[Export] Phase 1: Validating clips...");
+    validate_music(&config)?;
+    validate_audio_overrides(&clips, &config)?;
+    validate_audio_tracks(&clips, &config)?;
+
     let validation_results = match validate_all_clips(&clips) {
         Ok(results) => {
             println!("[Export] ✓ All {} clips validated successfully", clips.len());
@@ -360,12 +947,28 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
             "-i".to_string(),
             clip.file_path.clone(),
         ];
+
+        // Replacement audio is input 1
+        let audio_override = clip.audio_override.as_ref().filter(|_| config.include_audio);
+        if let Some(audio_override) = audio_override {
+            println!(
+                "  - Audio from: {} (offset {:.3}s)",
+                audio_override.path, audio_override.offset
+            );
+            ffmpeg_args.extend(audio_override.input_args(clip.trim_start, source_duration));
+        }
         
         let validation = &validation_results[index];
-        let has_audio = validation.has_audio && config.include_audio;
+        let has_audio =
+            (validation.has_audio || audio_override.is_some()) && config.include_audio;
         
         // Phase 3b: Build comprehensive video filter chain
         let mut video_filters = Vec::new();
+
+        // Crop first so scaling works on the kept region only
+        if let Some(crop) = &clip.crop {
+            video_filters.push(crop.to_filter());
+        }
         
         // Speed adjustment (if not 1.0x)
         if (clip.speed - 1.0).abs() > 0.001 {
@@ -381,6 +984,15 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
         
         // Force constant frame rate for VFR videos
         video_filters.push(format!("fps={}", target_fps));
+
+        if clip.fade_video {
+            video_filters.extend(fade_filters(
+                "fade",
+                clip.duration,
+                clip.fade_in_sec,
+                clip.fade_out_sec,
+            ));
+        }
         
         // Apply all video filters
         ffmpeg_args.extend(vec![
@@ -391,6 +1003,13 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
         // Phase 3c: Handle audio with speed adjustment
         if has_audio {
             let mut audio_filters = Vec::new();
+            if let Some(audio_override) = audio_override {
+                audio_filters
+                    .extend(audio_override.align_filters(clip.trim_start, source_duration));
+            }
+            if let Some(denoise) = &clip.denoise_audio {
+                audio_filters.push(denoise.to_filter()?);
+            }
             
             if (clip.speed - 1.0).abs() > 0.001 {
                 // Chain atempo filters for speed (each can only handle 0.5-2.0 range)
@@ -412,7 +1031,32 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
             // Normalize audio: stereo, 48kHz sample rate
             audio_filters.push("aresample=48000".to_string());
             audio_filters.push("aformat=sample_fmts=fltp:channel_layouts=stereo".to_string());
+            audio_filters.extend(audio_effect_filters(&clip.audio_effects));
+            audio_filters.extend(audio_mix_filters(clip.gain_db, clip.pan));
+            audio_filters.extend(fade_filters(
+                "afade",
+                clip.duration,
+                clip.fade_in_sec,
+                clip.fade_out_sec,
+            ));
             
+            // Pick a specific audio track (e.g. narration vs. system audio), or the override
+            if audio_override.is_some() {
+                ffmpeg_args.extend(vec![
+                    "-map".to_string(),
+                    "0:v:0".to_string(),
+                    "-map".to_string(),
+                    "1:a:0".to_string(),
+                ]);
+            } else if let Some(track) = clip.audio_track {
+                ffmpeg_args.extend(vec![
+                    "-map".to_string(),
+                    "0:v:0".to_string(),
+                    "-map".to_string(),
+                    format!("0:a:{}", track),
+                ]);
+            }
+
             ffmpeg_args.extend(vec![
                 "-af".to_string(),
                 audio_filters.join(","),
@@ -593,20 +1237,45 @@ pub async fn export_timeline(clips: Vec<Clip>, config: ExportConfig) -> Result<S
 
     // Phase 5: Concatenate with copy mode (safe since all clips are now normalized)
     println!("[Export] Phase 5: Concatenating normalized clips...");
-    let concat_args = vec![
+    let mut concat_args = vec![
         "-f".to_string(),
         "concat".to_string(),
         "-safe".to_string(),
         "0".to_string(),
         "-i".to_string(),
         concat_file.to_str().unwrap().to_string(),
-        "-c".to_string(),
-        "copy".to_string(), // Safe to use copy now since all clips match
+    ];
+    match config.music.as_ref().filter(|_| config.include_audio) {
+        Some(music) => {
+            // Video is still copied; only the audio is re-encoded with the music mixed in
+            println!("[Export] Mixing music from {}", music.path);
+            concat_args.extend(music_input_args(music));
+            concat_args.extend(vec![
+                "-filter_complex".to_string(),
+                music_mix_filters(music, 1, "[0:a]", "[mixa]").join(";"),
+                "-map".to_string(),
+                "0:v".to_string(),
+                "-map".to_string(),
+                "[mixa]".to_string(),
+                "-c:v".to_string(),
+                "copy".to_string(),
+                "-c:a".to_string(),
+                "aac".to_string(),
+                "-b:a".to_string(),
+                "192k".to_string(),
+            ]);
+        }
+        None => concat_args.extend(vec![
+            "-c".to_string(),
+            "copy".to_string(), // Safe to use copy now since all clips match
+        ]),
+    }
+    concat_args.extend(vec![
         "-movflags".to_string(),
         "+faststart".to_string(),
         "-y".to_string(),
         config.output_path.clone(),
-    ];
+    ]);
     
     println!("[Export] Running final concatenation...");
     let output = Command::new(&ffmpeg_path)
@@ -689,7 +1358,18 @@ pub fn get_export_progress() -> ExportProgress {
 /// Optimized export using filter_complex for single-pass rendering
 /// This eliminates intermediate files and is 2-3x faster
 #[command]
-pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -> Result<String, String> {
+pub async fn export_timeline_optimized(
+    library: State<'_, LibraryManager>,
+    clips: Vec<Clip>,
+    config: ExportConfig,
+) -> Result<String, String> {
+    let clips = if config.use_original_media {
+        swap_to_original_media(&library, clips).await
+    } else {
+        clips
+    };
+    let clips = apply_loudness_normalization(&library, clips).await?;
+
     // Update progress
     {
         let mut progress = EXPORT_PROGRESS.lock().unwrap();
@@ -705,6 +1385,9 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
     if sorted_clips.is_empty() {
         return Err("No clips to export".to_string());
     }
+    validate_music(&config)?;
+    validate_audio_overrides(&sorted_clips, &config)?;
+    validate_audio_tracks(&sorted_clips, &config)?;
 
     // Get FFmpeg binary path
     let ffmpeg_path = match get_ffmpeg_path() {
@@ -735,6 +1418,22 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
         args.push("-i".to_string());
         args.push(clip.file_path.clone());
     }
+    // Music goes in after the clips, as input sorted_clips.len()
+    let music = config.music.as_ref().filter(|_| config.include_audio);
+    if let Some(music) = music {
+        args.extend(music_input_args(music));
+    }
+    // Then any audio overrides, by clip
+    let mut override_inputs: HashMap<usize, usize> = HashMap::new();
+    let mut next_input = sorted_clips.len() + music.is_some() as usize;
+    for (i, clip) in sorted_clips.iter().enumerate() {
+        let audio_override = clip.audio_override.as_ref().filter(|_| config.include_audio);
+        if let Some(audio_override) = audio_override {
+            args.extend(audio_override.input_args(clip.trim_start, clip.duration));
+            override_inputs.insert(i, next_input);
+            next_input += 1;
+        }
+    }
 
     {
         let mut progress = EXPORT_PROGRESS.lock().unwrap();
@@ -750,6 +1449,18 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
     for (i, clip) in sorted_clips.iter().enumerate() {
         let mut video_filters = vec![];
         let mut audio_filters = vec![];
+
+        if let Some(audio_override) = &clip.audio_override {
+            if override_inputs.contains_key(&i) {
+                audio_filters.extend(audio_override.align_filters(clip.trim_start, clip.duration));
+            }
+        }
+        if let Some(denoise) = &clip.denoise_audio {
+            audio_filters.push(denoise.to_filter()?);
+        }
+        if let Some(crop) = &clip.crop {
+            video_filters.push(crop.to_filter());
+        }
         
         // Speed adjustment
         if (clip.speed - 1.0).abs() > 0.001 {
@@ -769,6 +1480,14 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
                 audio_filters.push(format!("atempo={:.3}", speed));
             }
         }
+        audio_filters.extend(audio_effect_filters(&clip.audio_effects));
+        audio_filters.extend(audio_mix_filters(clip.gain_db, clip.pan));
+        audio_filters.extend(fade_filters(
+            "afade",
+            clip.duration,
+            clip.fade_in_sec,
+            clip.fade_out_sec,
+        ));
         
         // Resolution scaling
         if config.resolution != "source" {
@@ -781,6 +1500,14 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
             };
             video_filters.push(format!("scale={}:force_original_aspect_ratio=decrease,pad={}:(ow-iw)/2:(oh-ih)/2", scale, scale));
         }
+        if clip.fade_video {
+            video_filters.extend(fade_filters(
+                "fade",
+                clip.duration,
+                clip.fade_in_sec,
+                clip.fade_out_sec,
+            ));
+        }
         
         // Apply filters
         if !video_filters.is_empty() {
@@ -790,10 +1517,14 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
         }
         
         if config.include_audio {
+            let source = match override_inputs.get(&i) {
+                Some(input) => format!("[{}:a:0]", input),
+                None => format!("[{}:a:{}]", i, clip.audio_track.unwrap_or(0)),
+            };
             if !audio_filters.is_empty() {
-                filter_parts.push(format!("[{}:a]{}[a{}]", i, audio_filters.join(","), i));
+                filter_parts.push(format!("{}{}[a{}]", source, audio_filters.join(","), i));
             } else {
-                filter_parts.push(format!("[{}:a]anull[a{}]", i, i));
+                filter_parts.push(format!("{}anull[a{}]", source, i));
             }
         }
     }
@@ -813,6 +1544,9 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
     } else {
         filter_parts.push(concat_v);
     }
+    if let Some(music) = music {
+        filter_parts.extend(music_mix_filters(music, sorted_clips.len(), "[outa]", "[mixa]"));
+    }
     
     let filter_complex = filter_parts.join(";");
     
@@ -826,7 +1560,7 @@ pub async fn export_timeline_optimized(clips: Vec<Clip>, config: ExportConfig) -
     if config.include_audio {
         args.extend(vec![
             "-map".to_string(),
-            "[outa]".to_string(),
+            if music.is_some() { "[mixa]" } else { "[outa]" }.to_string(),
         ]);
     }
     
@@ -0,0 +1,801 @@
+use crate::commands::library::LibraryManager;
+use crate::commands::media::{import_media_file, MediaItem};
+use crate::utils::app_init::get_media_dir;
+use crate::utils::hash::full_file_hash;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, State};
+use tokio::io::AsyncWriteExt;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DownloadProgress {
+    pub url: String,
+    pub downloaded_bytes: u64,
+    pub total_bytes: Option<u64>,
+    pub percentage: Option<f64>,
+    pub status: String,
+}
+
+/// Pick a file name for a download from the URL path, falling back to a timestamped name
+fn file_name_from_url(url: &reqwest::Url) -> String {
+    let candidate = url
+        .path_segments()
+        .and_then(|mut segments| segments.next_back())
+        .map(|segment| urlencoding::decode(segment).unwrap_or_default().to_string())
+        .unwrap_or_default();
+
+    // Strip characters that are invalid in file names on any platform
+    let sanitized: String = candidate
+        .chars()
+        .filter(|c| !matches!(c, '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|'))
+        .collect();
+
+    if sanitized.is_empty() || !sanitized.contains('.') {
+        format!("download_{}.mp4", chrono::Utc::now().format("%Y%m%d_%H%M%S"))
+    } else {
+        sanitized
+    }
+}
+
+/// Find a destination path that doesn't overwrite an existing file
+fn unique_destination(dir: &Path, file_name: &str) -> PathBuf {
+    let candidate = dir.join(file_name);
+    if !candidate.exists() {
+        return candidate;
+    }
+
+    let path = Path::new(file_name);
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("download");
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+    (1..)
+        .map(|i| dir.join(format!("{}_{}.{}", stem, i, extension)))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// Where a download of `url` is staged until it completes. Keyed on the whole URL so two
+/// downloads that share a file name never resume each other's bytes.
+fn download_part_path(media_dir: &Path, url: &str) -> PathBuf {
+    let mut hasher = Sha256::new();
+    hasher.update(url.as_bytes());
+    media_dir.join(format!("download_{:x}.part", hasher.finalize()))
+}
+
+/// The ETag (or Last-Modified date) a response's body is tied to, for `If-Range` on resume
+fn response_validator(response: &reqwest::Response) -> Option<String> {
+    let headers = response.headers();
+    headers
+        .get(reqwest::header::ETAG)
+        .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+        .or_else(|| headers.get(reqwest::header::LAST_MODIFIED))
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string)
+}
+
+/// The full length from a `Content-Range: bytes */<length>` header of a 416 response
+fn unsatisfied_range_length(response: &reqwest::Response) -> Option<u64> {
+    response
+        .headers()
+        .get(reqwest::header::CONTENT_RANGE)?
+        .to_str()
+        .ok()?
+        .strip_prefix("bytes */")?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn emit_download_progress(app: &AppHandle, progress: DownloadProgress) {
+    if let Err(e) = app.emit("import://download-progress", progress) {
+        eprintln!("[Import] Failed to emit download progress: {}", e);
+    }
+}
+
+/// Download a remote video into the Zapcut media folder and import it.
+/// Interrupted downloads leave a `.part` file (named after a hash of the URL) that is resumed
+/// with a Range request when the server supports it. The resume carries `If-Range` with the
+/// first response's ETag or Last-Modified date, so a file that changed on the server is
+/// downloaded again from the start rather than spliced onto stale bytes.
+/// Emits `import://download-progress` events while downloading.
+#[tauri::command]
+pub async fn import_from_url(
+    app: AppHandle,
+    library: State<'_, LibraryManager>,
+    url: String,
+) -> Result<MediaItem, String> {
+    let parsed = reqwest::Url::parse(&url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err("Only http and https URLs can be imported".to_string());
+    }
+
+    let media_dir = get_media_dir().map_err(|e| format!("Failed to get media directory: {}", e))?;
+    let file_name = file_name_from_url(&parsed);
+    let part_path = download_part_path(&media_dir, &url);
+    let validator_path = part_path.with_extension("part.validator");
+
+    // Resume from a previous partial download only when we know which version of the file
+    // it holds; without a validator the bytes can't be matched to the server's copy
+    let validator = tokio::fs::read_to_string(&validator_path).await.ok();
+    let existing_bytes = match validator {
+        Some(_) => tokio::fs::metadata(&part_path).await.map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+
+    let client = reqwest::Client::new();
+    let mut request = client.get(parsed.clone());
+    if let (true, Some(validator)) = (existing_bytes > 0, &validator) {
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", existing_bytes))
+            .header(reqwest::header::IF_RANGE, validator.trim());
+    }
+
+    let mut response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to start download: {}", e))?;
+
+    // A 416 for a `.part` that already holds the whole file means the last attempt finished
+    // downloading but not finalizing; any other 416 means the `.part` is unusable
+    let mut complete = false;
+    if existing_bytes > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        if unsatisfied_range_length(&response) == Some(existing_bytes) {
+            complete = true;
+        } else {
+            response = client
+                .get(parsed.clone())
+                .send()
+                .await
+                .map_err(|e| format!("Failed to start download: {}", e))?;
+        }
+    }
+
+    let status = response.status();
+    if !complete && !status.is_success() {
+        return Err(format!("Download failed with HTTP status {}", status));
+    }
+
+    // 206 means the server honoured the Range header (and If-Range matched); anything else
+    // restarts from zero
+    let resuming =
+        complete || (existing_bytes > 0 && status == reqwest::StatusCode::PARTIAL_CONTENT);
+    let mut downloaded = if resuming { existing_bytes } else { 0 };
+    let total_bytes = if complete {
+        Some(existing_bytes)
+    } else {
+        response.content_length().map(|len| len + downloaded)
+    };
+
+    if !resuming {
+        match response_validator(&response) {
+            Some(validator) => tokio::fs::write(&validator_path, validator)
+                .await
+                .map_err(|e| format!("Failed to write download state: {}", e))?,
+            None => {
+                let _ = tokio::fs::remove_file(&validator_path).await;
+            }
+        }
+    }
+
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(resuming)
+        .truncate(!resuming)
+        .open(&part_path)
+        .await
+        .map_err(|e| format!("Failed to open download file: {}", e))?;
+
+    println!(
+        "[Import] Downloading {} ({})",
+        url,
+        if complete {
+            "already downloaded"
+        } else if resuming {
+            "resuming"
+        } else {
+            "new download"
+        }
+    );
+
+    let mut stream = response.bytes_stream();
+    let mut last_emit = Instant::now();
+
+    while let Some(chunk) = if complete { None } else { stream.next().await } {
+        let chunk = chunk.map_err(|e| format!("Download interrupted: {}", e))?;
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write download: {}", e))?;
+        downloaded += chunk.len() as u64;
+
+        if last_emit.elapsed() >= Duration::from_millis(250) {
+            last_emit = Instant::now();
+            emit_download_progress(&app, DownloadProgress {
+                url: url.clone(),
+                downloaded_bytes: downloaded,
+                total_bytes,
+                percentage: total_bytes.map(|total| downloaded as f64 / total.max(1) as f64 * 100.0),
+                status: "downloading".to_string(),
+            });
+        }
+    }
+
+    file.flush().await.map_err(|e| format!("Failed to flush download: {}", e))?;
+    drop(file);
+
+    if let Some(total) = total_bytes {
+        if downloaded < total {
+            return Err(format!(
+                "Download incomplete ({} of {} bytes); retry to resume",
+                downloaded, total
+            ));
+        }
+    }
+
+    let destination = unique_destination(&media_dir, &file_name);
+    tokio::fs::rename(&part_path, &destination)
+        .await
+        .map_err(|e| format!("Failed to finalize download: {}", e))?;
+    let _ = tokio::fs::remove_file(&validator_path).await;
+
+    emit_download_progress(&app, DownloadProgress {
+        url: url.clone(),
+        downloaded_bytes: downloaded,
+        total_bytes: Some(downloaded),
+        percentage: Some(100.0),
+        status: "importing".to_string(),
+    });
+
+    let destination_str = destination.to_string_lossy().to_string();
+    let item = match import_media_file(&destination_str) {
+        Ok(item) => item,
+        Err(e) => {
+            // Not a usable video; don't leave junk in the media folder
+            let _ = tokio::fs::remove_file(&destination).await;
+            return Err(format!("Downloaded file could not be imported: {}", e));
+        }
+    };
+
+    library.add_items(vec![item.clone()]).await?;
+
+    emit_download_progress(&app, DownloadProgress {
+        url,
+        downloaded_bytes: downloaded,
+        total_bytes: Some(downloaded),
+        percentage: Some(100.0),
+        status: "complete".to_string(),
+    });
+
+    Ok(item)
+}
+
+/// Extensions recognised as camera video files on a memory card. Low-res previews (.lrv)
+/// and sidecars (.thm, .xml, .cpi) are deliberately left out.
+const CARD_VIDEO_EXTENSIONS: &[&str] = &["mp4", "mov", "mts", "m2ts", "mxf", "avi", "insv"];
+
+/// AVCHD cameras split recordings at the FAT32 limit (or at 2 GiB on older models)
+const SPAN_LIMITS: &[u64] = &[2 * 1024 * 1024 * 1024, 4 * 1024 * 1024 * 1024];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IngestedFile {
+    pub source: String,
+    pub destination: String,
+    pub size: u64,
+    pub verified: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IngestFailure {
+    pub path: String,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IngestReport {
+    pub destination_dir: String,
+    pub copied: Vec<IngestedFile>,
+    /// Spanned recordings that were joined into a single file before import. The copied
+    /// segments listed here are deleted once the join succeeds; the card keeps the originals.
+    pub joined_groups: Vec<Vec<String>>,
+    pub imported: Vec<MediaItem>,
+    pub failed: Vec<IngestFailure>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct IngestProgress {
+    pub current_file: String,
+    pub files_done: usize,
+    pub total_files: usize,
+    pub bytes_copied: u64,
+    pub total_bytes: u64,
+    pub status: String,
+}
+
+/// Directories on a card that hold camera clips (DCIM plus AVCHD/XAVC/XDCAM private trees)
+fn card_clip_roots(card_path: &Path) -> Vec<PathBuf> {
+    [
+        "DCIM",
+        "PRIVATE/AVCHD/BDMV/STREAM",
+        "AVCHD/BDMV/STREAM",
+        "PRIVATE/M4ROOT/CLIP",
+        "M4ROOT/CLIP",
+        "PRIVATE/XDROOT/Clip",
+        "XDROOT/Clip",
+        "CLIP",
+    ]
+    .iter()
+    .map(|relative| card_path.join(relative))
+    .filter(|path| path.is_dir())
+    .collect()
+}
+
+fn collect_card_clips(dir: &Path, found: &mut Vec<(PathBuf, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_card_clips(&path, found);
+            continue;
+        }
+
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase())
+            .unwrap_or_default();
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && CARD_VIDEO_EXTENSIONS.contains(&extension.as_str()) {
+            found.push((path, metadata.len()));
+        }
+    }
+}
+
+/// GoPro chapter naming: GH/GX/GP<chapter:2><clip:4> and GOPR<clip:4> for the first chapter
+/// of a GP recording. The key keeps the encoding prefix, since an AVC (GH) and an HEVC (GX)
+/// recording can share a clip number.
+fn gopro_chapter_key(path: &Path) -> Option<(String, u32)> {
+    let stem = path.file_stem()?.to_str()?.to_uppercase();
+    if stem.len() != 8 || !stem.is_ascii() {
+        return None;
+    }
+    let (prefix, digits) = stem.split_at(4);
+    if prefix == "GOPR" && digits.chars().all(|c| c.is_ascii_digit()) {
+        return Some((format!("GP{}", digits), 0));
+    }
+    let (kind, rest) = stem.split_at(2);
+    if matches!(kind, "GH" | "GX" | "GP") && rest.chars().all(|c| c.is_ascii_digit()) {
+        let chapter = rest[..2].parse().ok()?;
+        return Some((format!("{}{}", kind, &rest[2..]), chapter));
+    }
+    None
+}
+
+fn is_span_boundary(size: u64) -> bool {
+    SPAN_LIMITS
+        .iter()
+        .any(|limit| size as f64 >= *limit as f64 * 0.95 && size <= *limit)
+}
+
+/// Group clips that belong to the same recording: GoPro chapters by clip number, and
+/// consecutive AVCHD/XAVC files where the previous one stopped at a FAT32 span limit.
+fn group_spanned_clips(mut clips: Vec<(PathBuf, u64)>) -> Vec<Vec<(PathBuf, u64)>> {
+    clips.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let mut gopro_groups: std::collections::BTreeMap<String, Vec<(u32, PathBuf, u64)>> =
+        std::collections::BTreeMap::new();
+    let mut groups: Vec<Vec<(PathBuf, u64)>> = Vec::new();
+
+    for (path, size) in clips {
+        if let Some((key, chapter)) = gopro_chapter_key(&path) {
+            gopro_groups.entry(key).or_default().push((chapter, path, size));
+            continue;
+        }
+
+        let continues_previous = groups
+            .last()
+            .and_then(|group| group.last())
+            .map(|(prev_path, prev_size)| {
+                prev_path.parent() == path.parent()
+                    && prev_path.extension() == path.extension()
+                    && is_span_boundary(*prev_size)
+            })
+            .unwrap_or(false);
+
+        if continues_previous {
+            groups.last_mut().unwrap().push((path, size));
+        } else {
+            groups.push(vec![(path, size)]);
+        }
+    }
+
+    for (_, mut chapters) in gopro_groups {
+        chapters.sort_by_key(|(chapter, _, _)| *chapter);
+        groups.push(chapters.into_iter().map(|(_, path, size)| (path, size)).collect());
+    }
+
+    groups
+}
+
+fn emit_ingest_progress(app: &AppHandle, progress: IngestProgress) {
+    if let Err(e) = app.emit("ingest://progress", progress) {
+        eprintln!("[Ingest] Failed to emit progress: {}", e);
+    }
+}
+
+/// Offload a mounted camera card: find clips under DCIM and the AVCHD/XAVC private folders,
+/// copy them to `destination_dir` (default: Zapcut/media/ingest_<timestamp>) with size
+/// verification (plus SHA-256 when `verify_hash` is set), join spanned segments (dropping the
+/// copied segments) and import the results into the library. Emits `ingest://progress` events.
+#[tauri::command]
+pub async fn ingest_card(
+    app: AppHandle,
+    library: State<'_, LibraryManager>,
+    card_path: String,
+    destination_dir: Option<String>,
+    verify_hash: Option<bool>,
+) -> Result<IngestReport, String> {
+    let card = PathBuf::from(&card_path);
+    if !card.is_dir() {
+        return Err(format!("Card path is not a directory: {}", card_path));
+    }
+
+    let roots = card_clip_roots(&card);
+    if roots.is_empty() {
+        return Err("No DCIM, AVCHD or XAVC folders found on the card".to_string());
+    }
+
+    let mut clips = Vec::new();
+    for root in &roots {
+        collect_card_clips(root, &mut clips);
+    }
+    clips.sort();
+    clips.dedup();
+
+    if clips.is_empty() {
+        return Err("No video clips found on the card".to_string());
+    }
+
+    let destination = match destination_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => get_media_dir()
+            .map_err(|e| format!("Failed to get media directory: {}", e))?
+            .join(format!("ingest_{}", chrono::Local::now().format("%Y%m%d_%H%M%S"))),
+    };
+    tokio::fs::create_dir_all(&destination)
+        .await
+        .map_err(|e| format!("Failed to create ingest folder: {}", e))?;
+
+    let verify_hash = verify_hash.unwrap_or(false);
+    let total_files = clips.len();
+    let total_bytes: u64 = clips.iter().map(|(_, size)| size).sum();
+    let groups = group_spanned_clips(clips);
+
+    let mut report = IngestReport {
+        destination_dir: destination.to_string_lossy().to_string(),
+        copied: Vec::new(),
+        joined_groups: Vec::new(),
+        imported: Vec::new(),
+        failed: Vec::new(),
+    };
+    let mut bytes_copied = 0;
+    let mut files_done = 0;
+
+    for group in groups {
+        let mut group_destinations = Vec::new();
+
+        for (source, size) in &group {
+            let source_str = source.to_string_lossy().to_string();
+            emit_ingest_progress(&app, IngestProgress {
+                current_file: source_str.clone(),
+                files_done,
+                total_files,
+                bytes_copied,
+                total_bytes,
+                status: "copying".to_string(),
+            });
+
+            let file_name = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let target = unique_destination(&destination, &file_name);
+
+            match copy_and_verify(source, &target, *size, verify_hash).await {
+                Ok(verified) => {
+                    report.copied.push(IngestedFile {
+                        source: source_str,
+                        destination: target.to_string_lossy().to_string(),
+                        size: *size,
+                        verified,
+                    });
+                    group_destinations.push(target);
+                }
+                Err(e) => {
+                    eprintln!("[Ingest] ✗ {}: {}", source_str, e);
+                    report.failed.push(IngestFailure { path: source_str, error: e });
+                }
+            }
+
+            files_done += 1;
+            bytes_copied += size;
+        }
+
+        // Only join a spanned recording when every segment arrived intact
+        if group_destinations.is_empty() {
+            continue;
+        }
+        let import_path = if group_destinations.len() > 1 && group_destinations.len() == group.len() {
+            let first = &group_destinations[0];
+            let stem = first.file_stem().and_then(|s| s.to_str()).unwrap_or("clip");
+            let extension = first.extension().and_then(|e| e.to_str()).unwrap_or("mp4");
+            let joined_name = format!("{}_joined.{}", stem, extension);
+            let joined = unique_destination(&destination, &joined_name);
+            match crate::utils::ffmpeg::concat_files_copy(&group_destinations, joined.to_str().unwrap()) {
+                Ok(()) => {
+                    for segment in &group_destinations {
+                        if let Err(e) = tokio::fs::remove_file(segment).await {
+                            eprintln!(
+                                "[Ingest] Failed to remove joined segment {:?}: {}",
+                                segment, e
+                            );
+                        }
+                    }
+                    report.joined_groups.push(
+                        group_destinations.iter().map(|p| p.to_string_lossy().to_string()).collect(),
+                    );
+                    vec![joined]
+                }
+                Err(e) => {
+                    eprintln!("[Ingest] Could not join spanned clips, importing separately: {}", e);
+                    group_destinations
+                }
+            }
+        } else {
+            group_destinations
+        };
+
+        for path in import_path {
+            let path_str = path.to_string_lossy().to_string();
+            emit_ingest_progress(&app, IngestProgress {
+                current_file: path_str.clone(),
+                files_done,
+                total_files,
+                bytes_copied,
+                total_bytes,
+                status: "importing".to_string(),
+            });
+            match import_media_file(&path_str) {
+                Ok(item) => report.imported.push(item),
+                Err(e) => report.failed.push(IngestFailure { path: path_str, error: e }),
+            }
+        }
+    }
+
+    library.add_items(report.imported.clone()).await?;
+
+    emit_ingest_progress(&app, IngestProgress {
+        current_file: String::new(),
+        files_done,
+        total_files,
+        bytes_copied,
+        total_bytes,
+        status: "complete".to_string(),
+    });
+
+    Ok(report)
+}
+
+/// Copy a file and verify the copy by size (and optionally SHA-256). Returns whether a hash
+/// comparison was performed.
+async fn copy_and_verify(source: &Path, target: &Path, expected_size: u64, verify_hash: bool) -> Result<bool, String> {
+    tokio::fs::copy(source, target)
+        .await
+        .map_err(|e| format!("Copy failed: {}", e))?;
+
+    let copied_size = tokio::fs::metadata(target)
+        .await
+        .map(|m| m.len())
+        .map_err(|e| format!("Cannot read copied file: {}", e))?;
+    if copied_size != expected_size {
+        let _ = tokio::fs::remove_file(target).await;
+        return Err(format!("Size mismatch after copy ({} vs {} bytes)", copied_size, expected_size));
+    }
+
+    if !verify_hash {
+        return Ok(false);
+    }
+
+    let source = source.to_path_buf();
+    let target_owned = target.to_path_buf();
+    let (source_hash, target_hash) = tauri::async_runtime::spawn_blocking(move || {
+        (full_file_hash(&source), full_file_hash(&target_owned))
+    })
+    .await
+    .map_err(|e| format!("Hash task failed: {}", e))?;
+
+    let source_hash = source_hash.map_err(|e| format!("Failed to hash source: {}", e))?;
+    let target_hash = target_hash.map_err(|e| format!("Failed to hash copy: {}", e))?;
+    if source_hash != target_hash {
+        let _ = tokio::fs::remove_file(target).await;
+        return Err("Checksum mismatch after copy".to_string());
+    }
+
+    Ok(true)
+}
+
+/// Default video extensions accepted by folder import (matches validate_video_file)
+const DEFAULT_IMPORT_EXTENSIONS: &[&str] = &["mp4", "mov", "webm", "avi", "mkv"];
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FolderImportProgress {
+    pub file_index: usize,
+    pub total_files: usize,
+    pub current_file_name: String,
+    pub status: String,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FolderImportResult {
+    pub imported: Vec<MediaItem>,
+    /// Files skipped by the duration filter
+    pub skipped: Vec<String>,
+    pub failed: Vec<IngestFailure>,
+}
+
+/// Files that are never worth importing: hidden files (including macOS "._" resource forks)
+fn is_junk_file(name: &str) -> bool {
+    name.starts_with('.') || name.eq_ignore_ascii_case("thumbs.db")
+}
+
+fn collect_folder_files(dir: &Path, recursive: bool, extensions: &[String], found: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if is_junk_file(&name) {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+
+        if file_type.is_dir() {
+            if recursive {
+                collect_folder_files(&path, recursive, extensions, found);
+            }
+        } else if file_type.is_file() {
+            let extension = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+            if extensions.contains(&extension) {
+                found.push(path);
+            }
+        }
+    }
+}
+
+fn emit_folder_progress(app: &AppHandle, progress: FolderImportProgress) {
+    if let Err(e) = app.emit("import://progress", progress) {
+        eprintln!("[Import] Failed to emit import progress: {}", e);
+    }
+}
+
+/// Import every video in a folder, optionally recursing into subfolders.
+/// Hidden files are ignored, `extensions` defaults to the supported video formats and clips
+/// shorter than `min_duration` seconds (default 1s) are skipped. Emits `import://progress`.
+#[tauri::command]
+pub async fn import_folder(
+    app: AppHandle,
+    library: State<'_, LibraryManager>,
+    path: String,
+    recursive: Option<bool>,
+    extensions: Option<Vec<String>>,
+    min_duration: Option<f64>,
+) -> Result<FolderImportResult, String> {
+    let root = PathBuf::from(&path);
+    if !root.is_dir() {
+        return Err(format!("Not a directory: {}", path));
+    }
+
+    let extensions: Vec<String> = extensions
+        .map(|list| {
+            list.into_iter()
+                .map(|e| e.trim_start_matches('.').to_lowercase())
+                .collect()
+        })
+        .unwrap_or_else(|| DEFAULT_IMPORT_EXTENSIONS.iter().map(|e| e.to_string()).collect());
+    let min_duration = min_duration.unwrap_or(1.0);
+
+    let mut files = Vec::new();
+    collect_folder_files(&root, recursive.unwrap_or(true), &extensions, &mut files);
+    files.sort();
+
+    let total_files = files.len();
+    let mut result = FolderImportResult {
+        imported: Vec::new(),
+        skipped: Vec::new(),
+        failed: Vec::new(),
+    };
+
+    for (file_index, file) in files.iter().enumerate() {
+        let file_str = file.to_string_lossy().to_string();
+        let current_file_name = file
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        emit_folder_progress(&app, FolderImportProgress {
+            file_index,
+            total_files,
+            current_file_name: current_file_name.clone(),
+            status: "analyzing".to_string(),
+            error: None,
+        });
+
+        // Probe first so tiny clips are dropped before spending time on thumbnails and proxies
+        match crate::utils::ffmpeg::get_video_info(&file_str) {
+            Ok(info) if info.duration < min_duration => {
+                result.skipped.push(file_str);
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                let error = format!("Failed to analyze video: {}", e);
+                emit_folder_progress(&app, FolderImportProgress {
+                    file_index,
+                    total_files,
+                    current_file_name,
+                    status: "error".to_string(),
+                    error: Some(error.clone()),
+                });
+                result.failed.push(IngestFailure { path: file_str, error });
+                continue;
+            }
+        }
+
+        emit_folder_progress(&app, FolderImportProgress {
+            file_index,
+            total_files,
+            current_file_name: current_file_name.clone(),
+            status: "generating-thumbnail".to_string(),
+            error: None,
+        });
+
+        match import_media_file(&file_str) {
+            Ok(item) => {
+                library.add_items(vec![item.clone()]).await?;
+                result.imported.push(item);
+                emit_folder_progress(&app, FolderImportProgress {
+                    file_index,
+                    total_files,
+                    current_file_name,
+                    status: "complete".to_string(),
+                    error: None,
+                });
+            }
+            Err(error) => {
+                emit_folder_progress(&app, FolderImportProgress {
+                    file_index,
+                    total_files,
+                    current_file_name,
+                    status: "error".to_string(),
+                    error: Some(error.clone()),
+                });
+                result.failed.push(IngestFailure { path: file_str, error });
+            }
+        }
+    }
+
+    Ok(result)
+}
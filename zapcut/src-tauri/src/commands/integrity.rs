@@ -0,0 +1,269 @@
+use crate::utils::ffmpeg::{get_ffmpeg_path, get_video_info, run_ffmpeg_with_progress};
+use crate::utils::jobs::JobManager;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::Command;
+use tauri::{command, AppHandle, Manager, State};
+
+/// Cap on individual decode errors kept in a report; the count stays exact
+const MAX_REPORTED_DECODE_ERRORS: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepairAttempt {
+    pub strategy: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RepairReport {
+    /// Playable copy, if any strategy worked
+    pub output_path: Option<String>,
+    /// Strategy that produced the output
+    pub strategy: Option<String>,
+    /// Duration of the damaged source as far as FFprobe can tell
+    pub original_duration: Option<f64>,
+    pub repaired_duration: Option<f64>,
+    pub attempts: Vec<RepairAttempt>,
+    pub notes: Vec<String>,
+}
+
+/// Recovery strategies, cheapest first: (name, input flags, output codec flags)
+const REPAIR_STRATEGIES: &[(&str, &[&str], &[&str])] = &[
+    // Rewriting the container fixes missing WebM cues/duration and bad index tables
+    ("remux", &[], &["-c", "copy"]),
+    // Regenerate timestamps for streams that lost them mid-write
+    ("regenerate_timestamps", &["-fflags", "+genpts"], &["-c", "copy"]),
+    // Drop packets the demuxer flags as corrupt, still without re-encoding
+    (
+        "discard_corrupt",
+        &["-err_detect", "ignore_err", "-fflags", "+genpts+discardcorrupt"],
+        &["-c", "copy"],
+    ),
+    // Last resort: decode whatever is readable and encode a fresh file
+    (
+        "reencode",
+        &["-err_detect", "ignore_err", "-fflags", "+genpts+discardcorrupt"],
+        &[
+            "-c:v", "libx264", "-preset", "fast", "-crf", "18", "-pix_fmt", "yuv420p",
+            "-c:a", "aac", "-b:a", "192k",
+        ],
+    ),
+];
+
+fn default_repair_output(path: &Path) -> String {
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    // Matroska holds VP8/VP9/Opus from WebM captures when stream-copying
+    let extension = match path.extension().and_then(|e| e.to_str()).map(|e| e.to_lowercase()) {
+        Some(ext) if ext == "webm" || ext == "mkv" => "mkv",
+        _ => "mp4",
+    };
+    path.with_file_name(format!("{}_repaired.{}", stem, extension))
+        .to_string_lossy()
+        .to_string()
+}
+
+fn run_repair_strategy(input: &str, output: &str, input_flags: &[&str], codec_flags: &[&str]) -> Result<f64, String> {
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
+
+    let mut args: Vec<&str> = input_flags.to_vec();
+    args.extend(["-i", input, "-map", "0:v?", "-map", "0:a?"]);
+    args.extend(codec_flags);
+    if output.ends_with(".mp4") || output.ends_with(".mov") {
+        args.extend(["-movflags", "+faststart"]);
+    }
+    args.extend(["-y", output]);
+
+    let result = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let last_line = stderr.lines().last().unwrap_or("unknown error").to_string();
+        return Err(last_line);
+    }
+
+    // A written file is not enough; it must probe with a real duration
+    let info = get_video_info(output).map_err(|e| format!("Output is not readable: {}", e))?;
+    if info.duration <= 0.0 {
+        return Err("Output has no playable duration".to_string());
+    }
+    Ok(info.duration)
+}
+
+/// Try increasingly aggressive FFmpeg recovery strategies on a damaged recording and write a
+/// playable copy. The source file is never modified.
+#[command]
+pub async fn repair_media(path: String, output_path: Option<String>) -> Result<RepairReport, String> {
+    let source = Path::new(&path);
+    if !source.exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let output = output_path.unwrap_or_else(|| default_repair_output(source));
+    if Path::new(&output) == source {
+        return Err("Output path must differ from the input path".to_string());
+    }
+
+    let mut notes = Vec::new();
+    let original_duration = match get_video_info(&path) {
+        Ok(info) => Some(info.duration),
+        Err(_) => {
+            let is_mp4 = source
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| matches!(e.to_lowercase().as_str(), "mp4" | "mov" | "m4v"))
+                .unwrap_or(false);
+            if is_mp4 {
+                notes.push(
+                    "The MP4 index (moov atom) may be missing; FFmpeg can only recover it if some \
+                     of the index was written"
+                        .to_string(),
+                );
+            }
+            None
+        }
+    };
+
+    let mut attempts = Vec::new();
+    for (strategy, input_flags, codec_flags) in REPAIR_STRATEGIES {
+        println!("[Repair] Trying {} on {}", strategy, path);
+        match run_repair_strategy(&path, &output, input_flags, codec_flags) {
+            Ok(duration) => {
+                attempts.push(RepairAttempt {
+                    strategy: strategy.to_string(),
+                    success: true,
+                    error: None,
+                });
+                if let Some(original) = original_duration {
+                    if duration + 0.5 < original {
+                        notes.push(format!(
+                            "Recovered {:.1}s of {:.1}s; the rest could not be read",
+                            duration, original
+                        ));
+                    }
+                }
+                return Ok(RepairReport {
+                    output_path: Some(output),
+                    strategy: Some(strategy.to_string()),
+                    original_duration,
+                    repaired_duration: Some(duration),
+                    attempts,
+                    notes,
+                });
+            }
+            Err(e) => {
+                eprintln!("[Repair] {} failed: {}", strategy, e);
+                attempts.push(RepairAttempt {
+                    strategy: strategy.to_string(),
+                    success: false,
+                    error: Some(e),
+                });
+            }
+        }
+    }
+
+    let _ = std::fs::remove_file(&output);
+    notes.push("No strategy produced a playable file".to_string());
+
+    Ok(RepairReport {
+        output_path: None,
+        strategy: None,
+        original_duration,
+        repaired_duration: None,
+        attempts,
+        notes,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DecodeIssue {
+    /// Approximate position in seconds (FFmpeg reports progress about twice a second)
+    pub time: f64,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct VerifyReport {
+    pub path: String,
+    pub duration: f64,
+    pub passed: bool,
+    pub error_count: usize,
+    pub errors: Vec<DecodeIssue>,
+}
+
+fn verify_file(
+    path: &str,
+    cancel: &std::sync::atomic::AtomicBool,
+    on_progress: impl FnMut(f64),
+) -> Result<VerifyReport, String> {
+    let info = get_video_info(path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+
+    let args: Vec<String> = ["-v", "error", "-i", path, "-f", "null", "-"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let log = run_ffmpeg_with_progress(&args, info.duration, Some(cancel), on_progress)
+        .map_err(|e| format!("Decode check failed: {}", e))?;
+
+    let error_count = log.len();
+    let errors = log
+        .into_iter()
+        .take(MAX_REPORTED_DECODE_ERRORS)
+        .map(|line| DecodeIssue {
+            time: line.time,
+            message: line.message,
+        })
+        .collect();
+
+    Ok(VerifyReport {
+        path: path.to_string(),
+        duration: info.duration,
+        passed: error_count == 0,
+        error_count,
+        errors,
+    })
+}
+
+/// Fully decode a file in the background to find corrupt frames before they break an export.
+/// Returns the job id; the VerifyReport is delivered as the job result via `job://update`.
+#[command]
+pub async fn verify_media(
+    app: AppHandle,
+    jobs: State<'_, JobManager>,
+    path: String,
+) -> Result<String, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let name = Path::new(&path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.clone());
+    let (job_id, cancel) = jobs.create(&app, "verify", &format!("Verify {}", name));
+
+    let worker_app = app.clone();
+    let worker_job = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = worker_app.state::<JobManager>();
+        let result = verify_file(&path, &cancel, |percentage| {
+            jobs.set_progress(&worker_app, &worker_job, percentage)
+        });
+
+        match result {
+            Ok(report) => {
+                println!("[Verify] {}: {} decode errors", report.path, report.error_count);
+                jobs.complete(&worker_app, &worker_job, serde_json::to_value(&report).ok());
+            }
+            Err(e) => {
+                eprintln!("[Verify] {}: {}", path, e);
+                jobs.fail(&worker_app, &worker_job, e);
+            }
+        }
+    });
+
+    Ok(job_id)
+}
@@ -0,0 +1,25 @@
+use crate::utils::jobs::{JobInfo, JobManager};
+use tauri::{AppHandle, State};
+
+/// List every tracked background job, oldest first
+#[tauri::command]
+pub fn list_jobs(jobs: State<'_, JobManager>) -> Vec<JobInfo> {
+    jobs.list()
+}
+
+#[tauri::command]
+pub fn get_job(jobs: State<'_, JobManager>, job_id: String) -> Result<JobInfo, String> {
+    jobs.get(&job_id).ok_or_else(|| format!("Job not found: {}", job_id))
+}
+
+/// Cancel a queued or running job. Returns false if the job already finished.
+#[tauri::command]
+pub fn cancel_job(app: AppHandle, jobs: State<'_, JobManager>, job_id: String) -> bool {
+    jobs.cancel(&app, &job_id)
+}
+
+/// Remove completed, failed and cancelled jobs from the list
+#[tauri::command]
+pub fn clear_finished_jobs(jobs: State<'_, JobManager>) {
+    jobs.clear_finished();
+}
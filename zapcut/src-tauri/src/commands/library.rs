@@ -0,0 +1,659 @@
+use crate::commands::media::MediaItem;
+use crate::utils::app_init::initialize_app_directories;
+use crate::utils::ffmpeg::get_video_info;
+use crate::utils::hash::quick_file_hash;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tauri::State;
+use tokio::sync::Mutex;
+
+/// Persistent media library, stored as library.json in the Zapcut directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MediaLibrary {
+    pub items: Vec<MediaItem>,
+    #[serde(default)]
+    pub collections: Vec<Collection>,
+}
+
+/// A named bin of library items; items can belong to any number of collections
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    pub media_ids: Vec<String>,
+    pub created_at: String,
+}
+
+impl MediaLibrary {
+    fn library_path() -> anyhow::Result<PathBuf> {
+        Ok(initialize_app_directories()?.join("library.json"))
+    }
+
+    pub fn load() -> Self {
+        Self::library_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = Self::library_path()
+            .map_err(|e| format!("Failed to locate library file: {}", e))?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("Failed to serialize library: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write library: {}", e))
+    }
+
+    pub fn get_mut(&mut self, media_id: &str) -> Option<&mut MediaItem> {
+        self.items.iter_mut().find(|item| item.id == media_id)
+    }
+
+    /// Find the item whose transcode lives at `path`
+    pub fn find_by_transcode_path(&self, path: &str) -> Option<&MediaItem> {
+        self.items.iter().find(|item| {
+            item.transcode
+                .as_ref()
+                .map(|transcode| transcode.path == path)
+                .unwrap_or(false)
+        })
+    }
+
+    fn get_collection_mut(&mut self, collection_id: &str) -> Result<&mut Collection, String> {
+        self.collections
+            .iter_mut()
+            .find(|collection| collection.id == collection_id)
+            .ok_or_else(|| format!("Collection not found: {}", collection_id))
+    }
+
+    /// Insert or replace an item by id
+    pub fn upsert(&mut self, item: MediaItem) {
+        match self.get_mut(&item.id) {
+            Some(existing) => *existing = item,
+            None => self.items.push(item),
+        }
+    }
+}
+
+pub struct LibraryManager {
+    pub library: Mutex<MediaLibrary>,
+}
+
+impl LibraryManager {
+    pub fn new() -> Self {
+        Self {
+            library: Mutex::new(MediaLibrary::load()),
+        }
+    }
+
+    /// Add (or replace) items and persist the library
+    pub async fn add_items(&self, items: Vec<MediaItem>) -> Result<(), String> {
+        let mut library = self.library.lock().await;
+        for item in items {
+            library.upsert(item);
+        }
+        library.save()
+    }
+
+    /// Point proxy and thumbnail paths at the new location of moved cache files, given as
+    /// new path by old path
+    pub async fn rewrite_cache_paths(
+        &self,
+        moved: &std::collections::HashMap<String, String>,
+    ) -> Result<(), String> {
+        let mut library = self.library.lock().await;
+        let mut changed = false;
+        for item in library.items.iter_mut() {
+            for path in [&mut item.proxy_path, &mut item.thumbnail_path].into_iter().flatten() {
+                if let Some(new_path) = moved.get(path.as_str()) {
+                    *path = new_path.clone();
+                    changed = true;
+                }
+            }
+        }
+        if changed {
+            library.save()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RelinkResult {
+    pub media_id: String,
+    pub old_path: String,
+    pub new_path: String,
+    /// "hash" when the content fingerprint matched, "name_and_size" otherwise
+    pub matched_by: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MissingMediaReport {
+    pub relinked: Vec<RelinkResult>,
+    /// Media ids that are still missing, either because nothing matched or matches were ambiguous
+    pub unresolved: Vec<String>,
+}
+
+/// Get every item in the persistent library
+#[tauri::command]
+pub async fn get_library_items(library: State<'_, LibraryManager>) -> Result<Vec<MediaItem>, String> {
+    let library = library.library.lock().await;
+    Ok(library.items.clone())
+}
+
+/// Remove an item from the library (the source file is left untouched)
+#[tauri::command]
+pub async fn remove_library_item(
+    library: State<'_, LibraryManager>,
+    media_id: String,
+) -> Result<(), String> {
+    let mut library = library.library.lock().await;
+    library.items.retain(|item| item.id != media_id);
+    for collection in &mut library.collections {
+        collection.media_ids.retain(|id| id != &media_id);
+    }
+    library.save()
+}
+
+/// Point a library item at a new file location after it was moved or renamed
+#[tauri::command]
+pub async fn relink_media(
+    library: State<'_, LibraryManager>,
+    media_id: String,
+    new_path: String,
+) -> Result<MediaItem, String> {
+    if !Path::new(&new_path).exists() {
+        return Err(format!("File does not exist at path: {}", new_path));
+    }
+
+    let info = get_video_info(&new_path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+
+    let mut library = library.library.lock().await;
+    let item = library
+        .get_mut(&media_id)
+        .ok_or_else(|| format!("Media item not found: {}", media_id))?;
+
+    // A different duration almost certainly means a different file
+    if item.duration > 0.0 && (info.duration - item.duration).abs() > 0.5 {
+        return Err(format!(
+            "Duration mismatch: library item is {:.2}s but {} is {:.2}s",
+            item.duration, new_path, info.duration
+        ));
+    }
+
+    item.file_path = new_path.clone();
+    item.name = Path::new(&new_path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("Unknown")
+        .to_string();
+    item.file_size = info.file_size;
+    item.content_hash = quick_file_hash(Path::new(&new_path)).ok();
+
+    let updated = item.clone();
+    library.save()?;
+
+    Ok(updated)
+}
+
+/// Recursively collect files under `dir` whose name or size matches a missing item
+fn collect_candidates(dir: &Path, names: &[String], sizes: &[u64], found: &mut Vec<(PathBuf, u64)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_candidates(&path, names, sizes, found);
+        } else if file_type.is_file() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+            if names.contains(&name) || sizes.contains(&size) {
+                found.push((path, size));
+            }
+        }
+    }
+}
+
+/// Scan `search_dirs` for library items whose source file is missing.
+/// Candidates must match the original size; renamed files are accepted when the stored content
+/// hash matches, otherwise the file name must match too. Unambiguous matches are relinked in the
+/// library and returned.
+#[tauri::command]
+pub async fn find_missing_media(
+    library: State<'_, LibraryManager>,
+    search_dirs: Vec<String>,
+) -> Result<MissingMediaReport, String> {
+    let mut library = library.library.lock().await;
+
+    let missing: Vec<MediaItem> = library
+        .items
+        .iter()
+        .filter(|item| !Path::new(&item.file_path).exists())
+        .cloned()
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(MissingMediaReport {
+            relinked: Vec::new(),
+            unresolved: Vec::new(),
+        });
+    }
+
+    let names: Vec<String> = missing.iter().map(|item| item.name.clone()).collect();
+    let sizes: Vec<u64> = missing.iter().map(|item| item.file_size).collect();
+    let mut candidates = Vec::new();
+    for dir in &search_dirs {
+        collect_candidates(Path::new(dir), &names, &sizes, &mut candidates);
+    }
+
+    let mut relinked = Vec::new();
+    let mut unresolved = Vec::new();
+
+    for item in missing {
+        let same_size = candidates
+            .iter()
+            .filter(|(_, size)| *size == item.file_size)
+            .map(|(path, _)| path);
+
+        let (matched, matched_by): (Vec<&PathBuf>, &str) = match &item.content_hash {
+            Some(hash) => (
+                same_size
+                    .filter(|path| quick_file_hash(path).map(|h| &h == hash).unwrap_or(false))
+                    .collect(),
+                "hash",
+            ),
+            None => (
+                same_size
+                    .filter(|path| {
+                        path.file_name()
+                            .map(|n| n.to_string_lossy() == item.name.as_str())
+                            .unwrap_or(false)
+                    })
+                    .collect(),
+                "name_and_size",
+            ),
+        };
+
+        if matched.len() != 1 {
+            unresolved.push(item.id.clone());
+            continue;
+        }
+
+        let new_path = matched[0].to_string_lossy().to_string();
+        if let Some(entry) = library.get_mut(&item.id) {
+            entry.file_path = new_path.clone();
+            if let Some(name) = matched[0].file_name() {
+                entry.name = name.to_string_lossy().to_string();
+            }
+        }
+        relinked.push(RelinkResult {
+            media_id: item.id,
+            old_path: item.file_path,
+            new_path,
+            matched_by: matched_by.to_string(),
+        });
+    }
+
+    if !relinked.is_empty() {
+        library.save()?;
+    }
+
+    Ok(MissingMediaReport { relinked, unresolved })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TagCount {
+    pub tag: String,
+    pub count: usize,
+}
+
+/// Trim tags and drop empty or case-insensitive duplicates, keeping the first spelling
+fn normalize_tags(tags: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_string();
+        if !tag.is_empty() && !normalized.iter().any(|t| t.eq_ignore_ascii_case(&tag)) {
+            normalized.push(tag);
+        }
+    }
+    normalized
+}
+
+/// Add and/or remove tags on one or more items
+#[tauri::command]
+pub async fn update_media_tags(
+    library: State<'_, LibraryManager>,
+    media_ids: Vec<String>,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Vec<MediaItem>, String> {
+    let mut library = library.library.lock().await;
+    let mut updated = Vec::new();
+
+    for media_id in &media_ids {
+        let item = library
+            .get_mut(media_id)
+            .ok_or_else(|| format!("Media item not found: {}", media_id))?;
+        let tags = item
+            .tags
+            .drain(..)
+            .filter(|tag| !remove.iter().any(|r| r.trim().eq_ignore_ascii_case(tag)))
+            .chain(add.iter().cloned());
+        item.tags = normalize_tags(tags.collect::<Vec<_>>());
+        updated.push(item.clone());
+    }
+
+    library.save()?;
+    Ok(updated)
+}
+
+/// Set (1-5) or clear (None) the star rating of one or more items
+#[tauri::command]
+pub async fn set_media_rating(
+    library: State<'_, LibraryManager>,
+    media_ids: Vec<String>,
+    rating: Option<u8>,
+) -> Result<(), String> {
+    if let Some(rating) = rating {
+        if !(1..=5).contains(&rating) {
+            return Err(format!("Rating must be between 1 and 5, got {}", rating));
+        }
+    }
+
+    let mut library = library.library.lock().await;
+    for media_id in &media_ids {
+        library
+            .get_mut(media_id)
+            .ok_or_else(|| format!("Media item not found: {}", media_id))?
+            .rating = rating;
+    }
+    library.save()
+}
+
+/// Every tag in use with the number of items carrying it, most used first
+#[tauri::command]
+pub async fn get_library_tags(library: State<'_, LibraryManager>) -> Result<Vec<TagCount>, String> {
+    let library = library.library.lock().await;
+    let mut counts: Vec<TagCount> = Vec::new();
+
+    for tag in library.items.iter().flat_map(|item| item.tags.iter()) {
+        match counts.iter_mut().find(|c| c.tag.eq_ignore_ascii_case(tag)) {
+            Some(count) => count.count += 1,
+            None => counts.push(TagCount {
+                tag: tag.clone(),
+                count: 1,
+            }),
+        }
+    }
+
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+    Ok(counts)
+}
+
+#[tauri::command]
+pub async fn get_collections(library: State<'_, LibraryManager>) -> Result<Vec<Collection>, String> {
+    let library = library.library.lock().await;
+    Ok(library.collections.clone())
+}
+
+#[tauri::command]
+pub async fn create_collection(
+    library: State<'_, LibraryManager>,
+    name: String,
+) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+
+    let collection = Collection {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        media_ids: Vec::new(),
+        created_at: chrono::Utc::now().to_rfc3339(),
+    };
+
+    let mut library = library.library.lock().await;
+    library.collections.push(collection.clone());
+    library.save()?;
+    Ok(collection)
+}
+
+#[tauri::command]
+pub async fn rename_collection(
+    library: State<'_, LibraryManager>,
+    collection_id: String,
+    name: String,
+) -> Result<Collection, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Collection name cannot be empty".to_string());
+    }
+
+    let mut library = library.library.lock().await;
+    let collection = library.get_collection_mut(&collection_id)?;
+    collection.name = name;
+    let updated = collection.clone();
+    library.save()?;
+    Ok(updated)
+}
+
+/// Delete a collection; its items stay in the library
+#[tauri::command]
+pub async fn delete_collection(
+    library: State<'_, LibraryManager>,
+    collection_id: String,
+) -> Result<(), String> {
+    let mut library = library.library.lock().await;
+    library.collections.retain(|collection| collection.id != collection_id);
+    library.save()
+}
+
+/// Add items to and/or remove items from a collection
+#[tauri::command]
+pub async fn update_collection_items(
+    library: State<'_, LibraryManager>,
+    collection_id: String,
+    add: Vec<String>,
+    remove: Vec<String>,
+) -> Result<Collection, String> {
+    let mut library = library.library.lock().await;
+
+    if let Some(missing) = add.iter().find(|id| !library.items.iter().any(|item| &item.id == *id)) {
+        return Err(format!("Media item not found: {}", missing));
+    }
+
+    let collection = library.get_collection_mut(&collection_id)?;
+    collection.media_ids.retain(|id| !remove.contains(id));
+    for media_id in add {
+        if !collection.media_ids.contains(&media_id) {
+            collection.media_ids.push(media_id);
+        }
+    }
+
+    let updated = collection.clone();
+    library.save()?;
+    Ok(updated)
+}
+
+/// Items in a collection, in the collection's order
+#[tauri::command]
+pub async fn get_collection_items(
+    library: State<'_, LibraryManager>,
+    collection_id: String,
+) -> Result<Vec<MediaItem>, String> {
+    let library = library.library.lock().await;
+    let collection = library
+        .collections
+        .iter()
+        .find(|collection| collection.id == collection_id)
+        .ok_or_else(|| format!("Collection not found: {}", collection_id))?;
+
+    Ok(collection
+        .media_ids
+        .iter()
+        .filter_map(|id| library.items.iter().find(|item| &item.id == id).cloned())
+        .collect())
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchSort {
+    #[default]
+    ImportedAt,
+    CapturedAt,
+    Name,
+    Duration,
+    FileSize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Items must carry every one of these tags (case-insensitive)
+    pub tags: Vec<String>,
+    pub codec: Option<String>,
+    pub min_width: Option<u32>,
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub min_duration: Option<f64>,
+    pub max_duration: Option<f64>,
+    /// RFC 3339 bounds, matched against the capture date (or import date if unknown)
+    pub date_from: Option<String>,
+    pub date_to: Option<String>,
+    pub min_rating: Option<u8>,
+    pub collection_id: Option<String>,
+    pub sort: SearchSort,
+    pub descending: bool,
+    /// Zero-based page index
+    pub page: usize,
+    /// Defaults to 50
+    pub page_size: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SearchResults {
+    pub items: Vec<MediaItem>,
+    /// Number of matches across all pages
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+}
+
+fn parse_date(value: &str) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    chrono::DateTime::parse_from_rfc3339(value.trim()).ok()
+}
+
+/// Capture date when known, import date otherwise
+fn item_date(item: &MediaItem) -> Option<chrono::DateTime<chrono::FixedOffset>> {
+    item.captured_at
+        .as_deref()
+        .and_then(parse_date)
+        .or_else(|| parse_date(&item.imported_at))
+}
+
+fn matches_filters(item: &MediaItem, filters: &SearchFilters, collection: Option<&Collection>) -> bool {
+    let has_tags = filters
+        .tags
+        .iter()
+        .all(|wanted| item.tags.iter().any(|tag| tag.eq_ignore_ascii_case(wanted.trim())));
+    let codec_ok = filters
+        .codec
+        .as_ref()
+        .map(|codec| item.codec.eq_ignore_ascii_case(codec))
+        .unwrap_or(true);
+    let size_ok = filters.min_width.map(|w| item.width >= w).unwrap_or(true)
+        && filters.min_height.map(|h| item.height >= h).unwrap_or(true)
+        && filters.max_height.map(|h| item.height <= h).unwrap_or(true);
+    let duration_ok = filters.min_duration.map(|d| item.duration >= d).unwrap_or(true)
+        && filters.max_duration.map(|d| item.duration <= d).unwrap_or(true);
+    let rating_ok = filters
+        .min_rating
+        .map(|min| item.rating.map(|r| r >= min).unwrap_or(false))
+        .unwrap_or(true);
+    let collection_ok = collection
+        .map(|c| c.media_ids.contains(&item.id))
+        .unwrap_or(true);
+
+    let date = item_date(item);
+    let from_ok = match filters.date_from.as_deref().and_then(parse_date) {
+        Some(from) => date.map(|d| d >= from).unwrap_or(false),
+        None => true,
+    };
+    let to_ok = match filters.date_to.as_deref().and_then(parse_date) {
+        Some(to) => date.map(|d| d <= to).unwrap_or(false),
+        None => true,
+    };
+
+    has_tags && codec_ok && size_ok && duration_ok && rating_ok && collection_ok && from_ok && to_ok
+}
+
+/// Filter, sort and page the library. `query` matches a substring of the name or any tag.
+#[tauri::command]
+pub async fn search_media(
+    library: State<'_, LibraryManager>,
+    query: Option<String>,
+    filters: Option<SearchFilters>,
+) -> Result<SearchResults, String> {
+    let filters = filters.unwrap_or_default();
+    let query = query.unwrap_or_default().trim().to_lowercase();
+    let page_size = filters.page_size.unwrap_or(50).clamp(1, 500);
+
+    let library = library.library.lock().await;
+    let collection = match &filters.collection_id {
+        Some(id) => Some(
+            library
+                .collections
+                .iter()
+                .find(|c| &c.id == id)
+                .ok_or_else(|| format!("Collection not found: {}", id))?,
+        ),
+        None => None,
+    };
+
+    let mut matches: Vec<&MediaItem> = library
+        .items
+        .iter()
+        .filter(|item| {
+            query.is_empty()
+                || item.name.to_lowercase().contains(&query)
+                || item.tags.iter().any(|tag| tag.to_lowercase().contains(&query))
+        })
+        .filter(|item| matches_filters(item, &filters, collection))
+        .collect();
+
+    matches.sort_by(|a, b| {
+        let ordering = match filters.sort {
+            SearchSort::ImportedAt => a.imported_at.cmp(&b.imported_at),
+            SearchSort::CapturedAt => item_date(a).cmp(&item_date(b)),
+            SearchSort::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SearchSort::Duration => a.duration.total_cmp(&b.duration),
+            SearchSort::FileSize => a.file_size.cmp(&b.file_size),
+        };
+        if filters.descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+
+    let total = matches.len();
+    let items = matches
+        .into_iter()
+        .skip(filters.page * page_size)
+        .take(page_size)
+        .cloned()
+        .collect();
+
+    Ok(SearchResults {
+        items,
+        total,
+        page: filters.page,
+        page_size,
+    })
+}
@@ -1,8 +1,24 @@
-use crate::utils::ffmpeg::{create_proxy, generate_thumbnail, get_video_info, VideoInfo};
+use crate::commands::analysis::TimeRange;
+use crate::commands::library::LibraryManager;
+use crate::commands::stream::read_file_range;
+use crate::commands::transcode::TranscodeInfo;
+use crate::utils::app_init::get_projects_dir;
+use crate::utils::cache::{get_cache_dir, register_cache_entry, CacheKind};
+use crate::utils::hash::quick_file_hash;
+use crate::utils::recording_metadata::RecordingMarker;
+use crate::utils::settings::{get_settings, ThumbnailFormat};
+use crate::utils::ffmpeg::{
+    check_remux_compatibility, conform_frame_rate, create_proxy, detect_variable_frame_rate,
+    audio_extract_codec, audio_peaks, extract_audio_range, extract_frame, probe_format,
+    extract_subtitle_stream, generate_sprite_sheet, generate_thumbnail,
+    get_subtitle_streams, get_video_info, remux_copy, render_contact_sheet, render_waveform_png, AudioStreamInfo, GpsLocation, LoudnessStats, SubtitleStreamInfo, VideoInfo,
+};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::Path;
-use tauri::command;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use tauri::{command, State};
 use base64::{engine::general_purpose, Engine as _};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -19,89 +35,233 @@ pub struct MediaItem {
     pub file_size: u64,
     pub codec: String,
     pub imported_at: String,
+    /// Capture date from container metadata (falls back to None, never the file mtime)
+    pub captured_at: Option<String>,
+    pub device_make: Option<String>,
+    pub device_model: Option<String>,
+    pub location: Option<GpsLocation>,
+    /// Quick content fingerprint used for relinking moved files
+    pub content_hash: Option<String>,
+    /// Editing-friendly mezzanine created by transcode_media; file_path always stays the original
+    pub transcode: Option<TranscodeInfo>,
+    /// Whether the editor should play and cut the transcode instead of the original
+    #[serde(default)]
+    pub use_transcode: bool,
+    /// Variable frame rate source (typical of screen recordings); prone to audio drift
+    #[serde(default)]
+    pub is_vfr: bool,
+    /// User-facing recommendation when the source needs attention before editing
+    pub frame_rate_warning: Option<String>,
+    /// HDR source (PQ or HLG); its proxy is tone-mapped to SDR
+    #[serde(default)]
+    pub is_hdr: bool,
+    /// Audio streams with their labels, so the editor can expose each recorded track
+    #[serde(default)]
+    pub audio_tracks: Vec<AudioStreamInfo>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Star rating, 1-5
+    pub rating: Option<u8>,
+    /// Set by analyze_loudness
+    pub loudness: Option<LoudnessStats>,
+    /// Items recorded alongside this one with the same start time (screen and webcam files)
+    #[serde(default)]
+    pub linked_items: Vec<String>,
+    /// Moments flagged while recording, in seconds from the start of this file
+    #[serde(default)]
+    pub markers: Vec<RecordingMarker>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FilmstripFrame {
+    pub index: u32,
+    pub time: f64,
+    pub x: u32,
+    pub y: u32,
+}
+
+/// Index describing where each frame lives inside a filmstrip sprite sheet
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct FilmstripIndex {
+    pub media_id: String,
+    pub sprite_path: String,
+    pub frame_width: u32,
+    pub frame_height: u32,
+    pub columns: u32,
+    pub rows: u32,
+    pub frames: Vec<FilmstripFrame>,
+}
+
+/// Cached audio waveform of a library item
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WaveformData {
+    pub media_id: String,
+    pub peaks_per_second: u32,
+    /// Peak amplitude (0..1) of each 1/peaks_per_second of a second
+    pub peaks: Vec<f32>,
+}
+
+/// Waveform resolution; fine enough for the timeline at full zoom
+pub(crate) const WAVEFORM_PEAKS_PER_SECOND: u32 = 100;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThumbnailSource {
+    pub media_id: String,
+    pub file_path: String,
+    pub duration: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RegeneratedThumbnail {
+    pub media_id: String,
+    pub thumbnail_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Maximum number of on-demand scrub frames kept on disk
+const FRAME_CACHE_MAX_ENTRIES: usize = 300;
+
+#[command]
+pub async fn import_video(
+    library: State<'_, LibraryManager>,
+    file_path: String,
+) -> Result<MediaItem, String> {
+    let item = import_media_file(&file_path)?;
+    library.add_items(vec![item.clone()]).await?;
+    Ok(item)
 }
 
 #[command]
-pub async fn import_video(file_path: String) -> Result<MediaItem, String> {
+pub async fn import_videos(
+    library: State<'_, LibraryManager>,
+    file_paths: Vec<String>,
+) -> Result<Vec<MediaItem>, String> {
+    let mut items = Vec::new();
+
+    for path in file_paths {
+        match import_media_file(&path) {
+            Ok(item) => items.push(item),
+            Err(e) => eprintln!("Failed to import: {}", e),
+        }
+    }
+
+    if items.is_empty() {
+        return Err("No videos imported successfully".to_string());
+    }
+
+    library.add_items(items.clone()).await?;
+
+    Ok(items)
+}
+
+/// Probe a file and generate its thumbnail and proxy, producing a new MediaItem.
+/// Shared by every import path; callers are responsible for adding it to the library.
+pub fn import_media_file(file_path: &str) -> Result<MediaItem, String> {
     // Validate file exists
-    if !Path::new(&file_path).exists() {
+    if !Path::new(file_path).exists() {
         return Err("File does not exist".to_string());
     }
 
     // Get video info via FFprobe
-    let info = get_video_info(&file_path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    let info = get_video_info(file_path).map_err(|e| format!("Failed to analyze video: {}", e))?;
 
     // Generate unique ID
     let id = uuid::Uuid::new_v4().to_string();
 
     // Get file name
-    let name = Path::new(&file_path)
+    let name = Path::new(file_path)
         .file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("Unknown")
         .to_string();
 
+    // Fingerprint used to find the file again if it is moved or renamed
+    let content_hash = quick_file_hash(Path::new(file_path)).ok();
+
     // Generate thumbnail
-    let thumbnail_path = generate_thumbnail_for_import(&file_path, &id, &info).ok();
+    let thumbnail_path = generate_thumbnail_for_import(file_path, &id, info.duration).ok();
+
+    // Variable frame rate sources get a CFR proxy and a nominal rate snapped to a standard one
+    let is_vfr = detect_variable_frame_rate(file_path, &info).unwrap_or_else(|e| {
+        eprintln!("[Import] Frame rate check failed for {}: {}", file_path, e);
+        false
+    });
+    let fps = if is_vfr { conform_frame_rate(info.avg_fps) } else { info.fps };
+    let frame_rate_warning = is_vfr.then(|| {
+        format!(
+            "Variable frame rate source. The preview proxy was conformed to {} fps; transcode it \
+             before editing to keep audio in sync on export.",
+            fps
+        )
+    });
 
     // Generate proxy video for fast preview
-    let proxy_path = generate_proxy_for_import(&file_path, &id, &info).ok();
+    let proxy_path = generate_proxy_for_import(file_path, &id, &info, is_vfr).ok();
 
     let item = MediaItem {
         id,
         name,
-        file_path: file_path.clone(),
+        file_path: file_path.to_string(),
         proxy_path,
         duration: info.duration,
         width: info.width,
         height: info.height,
-        fps: info.fps,
+        fps,
         thumbnail_path,
         file_size: info.file_size,
         codec: info.codec,
         imported_at: chrono::Utc::now().to_rfc3339(),
+        captured_at: info.capture.creation_time,
+        device_make: info.capture.device_make,
+        device_model: info.capture.device_model,
+        location: info.capture.location,
+        content_hash,
+        transcode: None,
+        use_transcode: false,
+        is_vfr,
+        frame_rate_warning,
+        audio_tracks: info.audio_streams.clone(),
+        tags: Vec::new(),
+        rating: None,
+        loudness: None,
+        is_hdr: info.hdr_format.is_some(),
+        linked_items: Vec::new(),
+        markers: Vec::new(),
     };
 
     Ok(item)
 }
 
-#[command]
-pub async fn import_videos(file_paths: Vec<String>) -> Result<Vec<MediaItem>, String> {
-    let mut items = Vec::new();
-
-    for path in file_paths {
-        match import_video(path).await {
-            Ok(item) => items.push(item),
-            Err(e) => eprintln!("Failed to import: {}", e),
-        }
-    }
-
-    if items.is_empty() {
-        return Err("No videos imported successfully".to_string());
-    }
-
-    Ok(items)
-}
-
 fn generate_thumbnail_for_import(
     video_path: &str,
     id: &str,
-    info: &VideoInfo,
+    duration: f64,
 ) -> Result<String, String> {
-    // Create thumbnails directory in temp
-    let app_data = std::env::temp_dir().join("zapcut").join("thumbnails");
-    fs::create_dir_all(&app_data)
+    let app_data = get_cache_dir(CacheKind::Thumbnails)
         .map_err(|e| format!("Failed to create thumbnails directory: {}", e))?;
 
-    let thumbnail_name = format!("{}.jpg", id);
+    let settings = get_settings().thumbnails;
+
+    // Remove thumbnails written with a previous format so only the current one remains
+    for format in [ThumbnailFormat::Jpeg, ThumbnailFormat::Webp] {
+        if format != settings.format {
+            let _ = fs::remove_file(app_data.join(format!("{}.{}", id, format.extension())));
+        }
+    }
+
+    let thumbnail_name = format!("{}.{}", id, settings.format.extension());
     let thumbnail_path = app_data.join(&thumbnail_name);
 
     // Generate thumbnail at 1 second (or 10% of duration)
-    let timestamp = (info.duration * 0.1).min(1.0);
+    let timestamp = (duration * 0.1).min(1.0);
 
-    generate_thumbnail(video_path, thumbnail_path.to_str().unwrap(), timestamp)
+    generate_thumbnail(video_path, thumbnail_path.to_str().unwrap(), timestamp, &settings)
         .map_err(|e| format!("Failed to generate thumbnail: {}", e))?;
 
+    if let Err(e) = register_cache_entry(CacheKind::Thumbnails, &thumbnail_path, Some(id), Some(video_path)) {
+        eprintln!("[Cache] Failed to register thumbnail: {}", e);
+    }
+
     Ok(thumbnail_path.to_string_lossy().to_string())
 }
 
@@ -109,10 +269,9 @@ fn generate_proxy_for_import(
     video_path: &str,
     id: &str,
     info: &VideoInfo,
+    is_vfr: bool,
 ) -> Result<String, String> {
-    // Create proxies directory in temp
-    let app_data = std::env::temp_dir().join("zapcut").join("proxies");
-    fs::create_dir_all(&app_data)
+    let app_data = get_cache_dir(CacheKind::Proxies)
         .map_err(|e| format!("Failed to create proxies directory: {}", e))?;
 
     let proxy_name = format!("{}_proxy.mp4", id);
@@ -121,57 +280,75 @@ fn generate_proxy_for_import(
     // Cap FPS at 30 for high-fps sources (saves processing time and file size)
     let target_fps = if info.fps > 60.0 {
         Some(30.0)
+    } else if is_vfr {
+        Some(conform_frame_rate(info.avg_fps))
     } else {
         None
     };
 
-    create_proxy(video_path, proxy_path.to_str().unwrap(), target_fps)
-        .map_err(|e| format!("Failed to generate proxy: {}", e))?;
+    // HDR sources get a tone-mapped proxy; fall back to a plain one if this FFmpeg lacks zscale
+    let tone_map = info.hdr_format.is_some();
+    let proxy_result = create_proxy(video_path, proxy_path.to_str().unwrap(), target_fps, tone_map)
+        .or_else(|e| {
+            if !tone_map {
+                return Err(e);
+            }
+            eprintln!("[Import] Tone-mapped proxy failed, falling back to a plain proxy: {}", e);
+            create_proxy(video_path, proxy_path.to_str().unwrap(), target_fps, false)
+        });
+    proxy_result.map_err(|e| format!("Failed to generate proxy: {}", e))?;
+
+    if let Err(e) = register_cache_entry(CacheKind::Proxies, &proxy_path, Some(id), Some(video_path)) {
+        eprintln!("[Cache] Failed to register proxy: {}", e);
+    }
 
     Ok(proxy_path.to_string_lossy().to_string())
 }
 
-#[command]
-pub async fn get_thumbnail_base64(thumbnail_path: String) -> Result<String, String> {
-    use std::fs;
-    
-    // Read the thumbnail file
-    let file_data = fs::read(&thumbnail_path)
-        .map_err(|e| format!("Failed to read thumbnail file: {}", e))?;
-    
-    // Convert to base64
-    let base64 = general_purpose::STANDARD.encode(&file_data);
-    
-    Ok(format!("data:image/jpeg;base64,{}", base64))
+/// Largest read the file commands return over IPC; everything passes through JSON, so large
+/// media must be played through stream:// URLs instead
+const MAX_IPC_READ_BYTES: u64 = 256 * 1024 * 1024;
+
+/// Read `length` bytes from `offset` (the rest of the file by default) for the IPC read commands
+fn read_file_for_ipc(
+    path: &str,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<Vec<u8>, String> {
+    let metadata = fs::metadata(path)
+        .map_err(|e| format!("Failed to read file metadata: {} - Path: {}", e, path))?;
+    let offset = offset.unwrap_or(0).min(metadata.len());
+    let remaining = metadata.len() - offset;
+    let length = match length {
+        Some(length) => length.min(remaining).min(MAX_IPC_READ_BYTES),
+        None if remaining > MAX_IPC_READ_BYTES => {
+            return Err(format!(
+                "File is too large to read over IPC ({} bytes); use a stream:// URL or read it in \
+                 chunks with offset and length - Path: {}",
+                remaining, path
+            ));
+        }
+        None => remaining,
+    };
+    read_file_range(Path::new(path), offset, length)
+        .map_err(|e| format!("Failed to read file: {} - Path: {}", e, path))
 }
 
+/// Deprecated for playback: give the video element a stream:// URL instead, which is read in
+/// ranges. Reads `length` bytes from `offset` when given, and refuses whole files over 256 MB.
 #[command]
-pub async fn read_video_file(file_path: String) -> Result<Vec<u8>, String> {
-    use std::fs;
-    
+pub async fn read_video_file(
+    file_path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<Vec<u8>, String> {
     // Check if file exists
     if !Path::new(&file_path).exists() {
         let error = format!("File does not exist at path: {}", file_path);
         return Err(error);
     }
     
-    // Get file metadata
-    let metadata = fs::metadata(&file_path)
-        .map_err(|e| {
-            let error = format!("Failed to read file metadata: {} - Path: {}", e, file_path);
-            error
-        })?;
-    
-    let _file_size = metadata.len();
-    
-    // Read the video file
-    let file_data = fs::read(&file_path)
-        .map_err(|e| {
-            let error = format!("Failed to read video file: {} - Path: {}", e, file_path);
-            error
-        })?;
-    
-    Ok(file_data)
+    read_file_for_ipc(&file_path, offset, length)
 }
 
 #[command]
@@ -195,23 +372,557 @@ pub async fn validate_video_file(file_path: String) -> Result<bool, String> {
 }
 
 // Read binary file and return as Vec<u8>
+// Deprecated for media: use stream:// URLs, which are read in ranges. Reads `length` bytes from
+// `offset` when given; whole files over 256 MB are refused.
 #[tauri::command]
-pub async fn read_binary_file(path: String) -> Result<Vec<u8>, String> {
-    use std::path::Path;
-    
+pub async fn read_binary_file(
+    path: String,
+    offset: Option<u64>,
+    length: Option<u64>,
+) -> Result<Vec<u8>, String> {
     // First check if file exists
     if !Path::new(&path).exists() {
         return Err(format!("File does not exist at path: {}", path));
     }
     
-    // Get file metadata for debugging
-    let _metadata = fs::metadata(&path)
-        .map_err(|e| {
-            let error = format!("Failed to read file metadata: {} - Path: {}", e, path);
-            error
-        })?;
-    
-    std::fs::read(&path)
-        .map_err(|e| format!("Failed to read file: {} - Path: {}", e, path))
+    read_file_for_ipc(&path, offset, length)
 }
 
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RemuxResult {
+    pub output_path: String,
+    pub container: String,
+    pub video_codec: String,
+    pub audio_codec: Option<String>,
+    pub file_size: u64,
+}
+
+/// Change a file's container (e.g. MKV screen recording to MP4) with a stream copy.
+/// Fails up front if the codecs aren't allowed in the target container.
+#[command]
+pub async fn remux_file(input: String, output: String, container: Option<String>) -> Result<RemuxResult, String> {
+    if !Path::new(&input).exists() {
+        return Err(format!("File does not exist at path: {}", input));
+    }
+    if Path::new(&input) == Path::new(&output) {
+        return Err("Output path must differ from the input path".to_string());
+    }
+
+    // Default to the output file's extension
+    let container = container
+        .or_else(|| {
+            Path::new(&output)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_string())
+        })
+        .ok_or_else(|| "Could not determine target container".to_string())?
+        .to_lowercase();
+
+    let info = get_video_info(&input).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    check_remux_compatibility(&container, &info.codec, info.audio_codec.as_deref())
+        .map_err(|e| e.to_string())?;
+
+    println!("[Remux] {} -> {} ({})", input, output, container);
+    remux_copy(&input, &output, &container, &info.codec)
+        .map_err(|e| format!("Failed to remux file: {}", e))?;
+
+    let file_size = fs::metadata(&output)
+        .map(|m| m.len())
+        .map_err(|e| format!("Remuxed file not found: {}", e))?;
+
+    Ok(RemuxResult {
+        output_path: output,
+        container,
+        video_codec: info.codec,
+        audio_codec: info.audio_codec,
+        file_size,
+    })
+}
+
+/// Where an embedded subtitle track of `path` is extracted to:
+/// <projects>/subtitles/<file stem>_<stream index>_<language>.srt
+fn subtitle_srt_path(path: &str, stream: &SubtitleStreamInfo) -> Result<PathBuf, String> {
+    let subtitles_dir = get_projects_dir()
+        .map_err(|e| format!("Failed to get projects directory: {}", e))?
+        .join("subtitles");
+    fs::create_dir_all(&subtitles_dir)
+        .map_err(|e| format!("Failed to create subtitles directory: {}", e))?;
+
+    let stem = Path::new(path)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("subtitles");
+    let suffix = stream.language.as_deref().unwrap_or("und");
+    Ok(subtitles_dir.join(format!("{}_{}_{}.srt", stem, stream.index, suffix)))
+}
+
+/// The extracted SRT of subtitle track `stream_index` of `path`, extracting it first when it
+/// hasn't been yet
+pub(crate) fn ensure_subtitle_srt(path: &str, stream_index: u32) -> Result<PathBuf, String> {
+    let stream = get_subtitle_streams(path)
+        .map_err(|e| format!("Failed to read subtitle streams: {}", e))?
+        .into_iter()
+        .find(|s| s.index == stream_index)
+        .ok_or_else(|| format!("Stream {} is not a text subtitle track", stream_index))?;
+    let srt_path = subtitle_srt_path(path, &stream)?;
+    if !srt_path.exists() {
+        extract_subtitle_stream(path, stream.index, srt_path.to_str().unwrap())
+            .map_err(|e| format!("Failed to extract subtitle stream {}: {}", stream.index, e))?;
+    }
+    Ok(srt_path)
+}
+
+/// Extract embedded text subtitle tracks (mov_text/subrip/...) to .srt files in the projects folder.
+/// If `stream_index` is None, every text subtitle track in the file is extracted.
+#[command]
+pub async fn extract_subtitles(path: String, stream_index: Option<u32>) -> Result<Vec<String>, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let streams = get_subtitle_streams(&path)
+        .map_err(|e| format!("Failed to read subtitle streams: {}", e))?;
+
+    let selected: Vec<_> = match stream_index {
+        Some(index) => {
+            let stream = streams
+                .into_iter()
+                .find(|s| s.index == index)
+                .ok_or_else(|| format!("Stream {} is not a text subtitle track", index))?;
+            vec![stream]
+        }
+        None => streams,
+    };
+
+    if selected.is_empty() {
+        return Err("No text subtitle tracks found".to_string());
+    }
+
+    let mut outputs = Vec::new();
+    for stream in selected {
+        let srt_path = subtitle_srt_path(&path, &stream)?;
+
+        extract_subtitle_stream(&path, stream.index, srt_path.to_str().unwrap())
+            .map_err(|e| format!("Failed to extract subtitle stream {}: {}", stream.index, e))?;
+
+        outputs.push(srt_path.to_string_lossy().to_string());
+    }
+
+    Ok(outputs)
+}
+
+/// Detach the audio of `path` (audio track `audio_track`, the first when None) to a WAV, MP3
+/// or FLAC file in the projects folder, optionally only `range` of it in source seconds, and
+/// add that file to the library as an audio-only item
+#[command]
+pub async fn extract_audio(
+    library: State<'_, LibraryManager>,
+    path: String,
+    format: Option<String>,
+    range: Option<TimeRange>,
+    audio_track: Option<u32>,
+) -> Result<MediaItem, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+    let format = format.unwrap_or_else(|| "wav".to_string()).to_lowercase();
+    if audio_extract_codec(&format).is_none() {
+        return Err(format!("Unsupported audio format: {}", format));
+    }
+    if let Some(range) = &range {
+        if range.end <= range.start || range.start < 0.0 {
+            return Err(format!("Invalid range {}-{}", range.start, range.end));
+        }
+    }
+
+    let audio_dir = get_projects_dir()
+        .map_err(|e| format!("Failed to get projects directory: {}", e))?
+        .join("audio");
+    fs::create_dir_all(&audio_dir)
+        .map_err(|e| format!("Failed to create audio directory: {}", e))?;
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let stem = Path::new(&path)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("audio");
+    let output = audio_dir.join(format!("{}_audio_{}.{}", stem, &id[..8], format));
+    let output_str = output.to_string_lossy().to_string();
+
+    let item = tauri::async_runtime::spawn_blocking({
+        let (path, format, output_str) = (path.clone(), format.clone(), output_str.clone());
+        move || -> Result<MediaItem, String> {
+            let (start, end) = range.map_or((0.0, None), |range| (range.start, Some(range.end)));
+            extract_audio_range(&path, &output_str, &format, audio_track.unwrap_or(0), start, end)
+                .map_err(|e| format!("Failed to extract audio: {}", e))?;
+            audio_media_item(id, &output_str, &format)
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    println!("[Audio] Extracted audio of {} to {}", path, output_str);
+    library.add_items(vec![item.clone()]).await?;
+    Ok(item)
+}
+
+/// Library item for an audio-only file written by extract_audio
+fn audio_media_item(id: String, file_path: &str, format: &str) -> Result<MediaItem, String> {
+    let probed = probe_format(file_path)
+        .map_err(|e| format!("Failed to analyze extracted audio: {}", e))?;
+    let codec = match format {
+        "wav" => "pcm_s16le",
+        other => other,
+    };
+    let file_size = fs::metadata(file_path).map(|m| m.len()).unwrap_or(0);
+
+    Ok(MediaItem {
+        id,
+        name: Path::new(file_path)
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("Audio")
+            .to_string(),
+        file_path: file_path.to_string(),
+        proxy_path: None,
+        duration: probed.duration,
+        width: 0,
+        height: 0,
+        fps: 0.0,
+        thumbnail_path: None,
+        file_size,
+        codec: codec.to_string(),
+        imported_at: chrono::Utc::now().to_rfc3339(),
+        captured_at: None,
+        device_make: None,
+        device_model: None,
+        location: None,
+        content_hash: quick_file_hash(Path::new(file_path)).ok(),
+        transcode: None,
+        use_transcode: false,
+        is_vfr: false,
+        frame_rate_warning: None,
+        audio_tracks: vec![AudioStreamInfo {
+            track: 0,
+            codec: Some(codec.to_string()),
+            channels: None,
+            language: None,
+            title: None,
+        }],
+        tags: Vec::new(),
+        rating: None,
+        loudness: None,
+        is_hdr: false,
+        linked_items: Vec::new(),
+        markers: Vec::new(),
+    })
+}
+
+/// Regenerate library thumbnails with the current thumbnail settings.
+/// Called by the frontend after the thumbnail settings change.
+#[command]
+pub async fn regenerate_thumbnails(items: Vec<ThumbnailSource>) -> Result<Vec<RegeneratedThumbnail>, String> {
+    let results = items
+        .into_iter()
+        .map(|item| {
+            match generate_thumbnail_for_import(&item.file_path, &item.media_id, item.duration) {
+                Ok(path) => RegeneratedThumbnail {
+                    media_id: item.media_id,
+                    thumbnail_path: Some(path),
+                    error: None,
+                },
+                Err(e) => RegeneratedThumbnail {
+                    media_id: item.media_id,
+                    thumbnail_path: None,
+                    error: Some(e),
+                },
+            }
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Generate a sprite sheet of `count` evenly spaced frames for timeline clip rendering.
+/// Results are cached per media id, frame count and width.
+#[command]
+pub async fn generate_filmstrip(
+    media_id: String,
+    path: String,
+    count: u32,
+    width: u32,
+) -> Result<FilmstripIndex, String> {
+    if count == 0 || width == 0 {
+        return Err("Filmstrip count and width must be greater than zero".to_string());
+    }
+
+    let filmstrip_dir = get_cache_dir(CacheKind::Filmstrips)
+        .map_err(|e| format!("Failed to create filmstrips directory: {}", e))?;
+
+    let base_name = format!("{}_{}x{}", media_id, count, width);
+    let sprite_path = filmstrip_dir.join(format!("{}.jpg", base_name));
+    let index_path = filmstrip_dir.join(format!("{}.json", base_name));
+
+    // Reuse the cached sprite sheet if both files are present
+    if sprite_path.exists() {
+        if let Ok(contents) = fs::read_to_string(&index_path) {
+            if let Ok(index) = serde_json::from_str::<FilmstripIndex>(&contents) {
+                return Ok(index);
+            }
+        }
+    }
+
+    let info = get_video_info(&path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    if info.duration <= 0.0 {
+        return Err("Cannot build a filmstrip for a video without duration".to_string());
+    }
+
+    // Keep frame height even and aspect-correct for the requested width
+    let aspect = info.height as f64 / info.width.max(1) as f64;
+    let frame_height = (((width as f64 * aspect) / 2.0).round() as u32).max(1) * 2;
+    let columns = count.min(10);
+    let rows = count.div_ceil(columns);
+
+    generate_sprite_sheet(
+        &path,
+        sprite_path.to_str().unwrap(),
+        info.duration,
+        count,
+        columns,
+        width,
+        frame_height,
+    )
+    .map_err(|e| format!("Failed to generate filmstrip: {}", e))?;
+
+    let interval = info.duration / count as f64;
+    let frames = (0..count)
+        .map(|i| FilmstripFrame {
+            index: i,
+            time: i as f64 * interval,
+            x: (i % columns) * width,
+            y: (i / columns) * frame_height,
+        })
+        .collect();
+
+    let index = FilmstripIndex {
+        media_id,
+        sprite_path: sprite_path.to_string_lossy().to_string(),
+        frame_width: width,
+        frame_height,
+        columns,
+        rows,
+        frames,
+    };
+
+    let json = serde_json::to_string_pretty(&index)
+        .map_err(|e| format!("Failed to serialize filmstrip index: {}", e))?;
+    fs::write(&index_path, json)
+        .map_err(|e| format!("Failed to write filmstrip index: {}", e))?;
+
+    if let Err(e) = register_cache_entry(CacheKind::Filmstrips, &sprite_path, Some(&index.media_id), Some(&path)) {
+        eprintln!("[Cache] Failed to register filmstrip: {}", e);
+    }
+
+    Ok(index)
+}
+
+/// Cached waveform JSON for a library item, computing it from `path` the first time
+pub(crate) fn ensure_waveform(media_id: &str, path: &str) -> Result<PathBuf, String> {
+    let waveform_dir = get_cache_dir(CacheKind::Waveforms)
+        .map_err(|e| format!("Failed to create waveforms directory: {}", e))?;
+    let waveform_path =
+        waveform_dir.join(format!("{}_{}.json", media_id, WAVEFORM_PEAKS_PER_SECOND));
+    if waveform_path.exists() {
+        return Ok(waveform_path);
+    }
+
+    let peaks = audio_peaks(path, WAVEFORM_PEAKS_PER_SECOND)
+        .map_err(|e| format!("Failed to compute waveform: {}", e))?;
+    let waveform = WaveformData {
+        media_id: media_id.to_string(),
+        peaks_per_second: WAVEFORM_PEAKS_PER_SECOND,
+        peaks,
+    };
+    let json = serde_json::to_string(&waveform)
+        .map_err(|e| format!("Failed to serialize waveform: {}", e))?;
+    fs::write(&waveform_path, json).map_err(|e| format!("Failed to write waveform: {}", e))?;
+
+    if let Err(e) = register_cache_entry(CacheKind::Waveforms, &waveform_path, Some(media_id), Some(path)) {
+        eprintln!("[Cache] Failed to register waveform: {}", e);
+    }
+
+    Ok(waveform_path)
+}
+
+/// Render the audio of `path` as a pre-drawn waveform strip (PNG, transparent background) for
+/// audio-only and detached audio clips. `color` is a hex color such as "#4ade80" or an FFmpeg
+/// color name. Images are cached by source, size and color; returns the image path.
+#[command]
+pub async fn render_waveform_image(
+    path: String,
+    width: u32,
+    height: u32,
+    color: Option<String>,
+) -> Result<String, String> {
+    if !Path::new(&path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+    let width = width.clamp(16, 16384);
+    let height = height.clamp(8, 2048);
+    let color = color.unwrap_or_else(|| "#4ade80".to_string());
+    // Goes into a filter graph, so only hex digits, names and an @alpha suffix are allowed
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '#' | '@' | '.');
+    if color.is_empty() || !color.chars().all(allowed) {
+        return Err(format!("Invalid waveform color: {}", color));
+    }
+    let ffmpeg_color = color.replacen('#', "0x", 1);
+
+    let waveform_dir = get_cache_dir(CacheKind::Waveforms)
+        .map_err(|e| format!("Failed to create waveforms directory: {}", e))?;
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    hasher.update(format!("\0{}x{}\0{}", width, height, color).as_bytes());
+    let image_path = waveform_dir.join(format!("{:x}.png", hasher.finalize()));
+
+    if !image_path.exists() {
+        render_waveform_png(&path, image_path.to_str().unwrap(), width, height, &ffmpeg_color)
+            .map_err(|e| format!("Failed to render waveform image: {}", e))?;
+        if let Err(e) = register_cache_entry(CacheKind::Waveforms, &image_path, None, Some(&path)) {
+            eprintln!("[Cache] Failed to register waveform image: {}", e);
+        }
+    }
+
+    Ok(image_path.to_string_lossy().to_string())
+}
+
+/// Frame thumbnails are cached at timestamps rounded to this many milliseconds
+pub(crate) const FRAME_THUMBNAIL_STEP_MS: u64 = 100;
+
+/// Cached frame thumbnail for `path` at `timestamp`, extracting it first when it isn't cached.
+/// Returns the file and the rounded timestamp in milliseconds it shows.
+pub(crate) fn ensure_frame_thumbnail(
+    path: &str,
+    timestamp: f64,
+    width: u32,
+) -> Result<(PathBuf, u64), String> {
+    if !Path::new(path).exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+
+    let cache_dir = get_cache_dir(CacheKind::Frames)
+        .map_err(|e| format!("Failed to create frame cache directory: {}", e))?;
+
+    // Round to 100ms so hovering produces cache hits instead of a new extraction per pixel
+    let step = FRAME_THUMBNAIL_STEP_MS as f64 / 1000.0;
+    let rounded_ms = ((timestamp.max(0.0) / step).round() as u64) * FRAME_THUMBNAIL_STEP_MS;
+    // A stable digest rather than DefaultHasher, whose output may change between Rust
+    // releases and would orphan every cached file
+    let mut hasher = Sha256::new();
+    hasher.update(path.as_bytes());
+    hasher.update(format!("\0{}\0{}", rounded_ms, width).as_bytes());
+    let frame_path = cache_dir.join(format!("{:x}.jpg", hasher.finalize()));
+
+    if frame_path.exists() {
+        touch_cache_entry(&frame_path);
+    } else {
+        extract_frame(path, frame_path.to_str().unwrap(), rounded_ms as f64 / 1000.0, Some(width))
+            .map_err(|e| format!("Failed to extract frame: {}", e))?;
+        evict_frame_cache(&cache_dir, FRAME_CACHE_MAX_ENTRIES);
+    }
+
+    Ok((frame_path, rounded_ms))
+}
+
+/// Read a cached frame thumbnail as a base64 data URL
+pub(crate) fn frame_thumbnail_data_url(frame_path: &Path) -> Result<String, String> {
+    let file_data = fs::read(frame_path)
+        .map_err(|e| format!("Failed to read frame thumbnail: {}", e))?;
+
+    Ok(format!(
+        "data:image/jpeg;base64,{}",
+        general_purpose::STANDARD.encode(&file_data)
+    ))
+}
+
+/// Extract a frame on demand for scrub previews, returned as a base64 data URL.
+/// Frames are cached on disk (keyed by path, timestamp rounded to 0.1s and width) with LRU eviction.
+#[command]
+pub async fn get_frame_thumbnail(path: String, timestamp: f64, width: u32) -> Result<String, String> {
+    let (frame_path, _) = ensure_frame_thumbnail(&path, timestamp, width)?;
+    frame_thumbnail_data_url(&frame_path)
+}
+
+/// Mark a cache entry as recently used by bumping its modification time
+fn touch_cache_entry(path: &Path) {
+    if let Ok(file) = fs::File::options().append(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
+
+/// Delete the least recently used files once the cache exceeds `max_entries`
+fn evict_frame_cache(cache_dir: &Path, max_entries: usize) {
+    let Ok(entries) = fs::read_dir(cache_dir) else {
+        return;
+    };
+
+    let mut files: Vec<(PathBuf, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((entry.path(), modified))
+        })
+        .collect();
+
+    if files.len() <= max_entries {
+        return;
+    }
+
+    files.sort_by_key(|(_, modified)| *modified);
+    let excess = files.len() - max_entries;
+    for (path, _) in files.into_iter().take(excess) {
+        let _ = fs::remove_file(path);
+    }
+}
+
+/// Generate a contact sheet (grid of frames with timestamps) for quickly reviewing long recordings.
+/// Defaults to a 5x6 grid of 320px-wide tiles.
+#[command]
+pub async fn generate_contact_sheet(
+    path: String,
+    columns: Option<u32>,
+    rows: Option<u32>,
+    tile_width: Option<u32>,
+) -> Result<String, String> {
+    let columns = columns.unwrap_or(5).clamp(1, 20);
+    let rows = rows.unwrap_or(6).clamp(1, 20);
+    let tile_width = tile_width.unwrap_or(320).clamp(64, 1920);
+
+    let info = get_video_info(&path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    if info.duration <= 0.0 {
+        return Err("Cannot build a contact sheet for a video without duration".to_string());
+    }
+
+    let sheets_dir = get_cache_dir(CacheKind::ContactSheets)
+        .map_err(|e| format!("Failed to create contact sheets directory: {}", e))?;
+
+    let stem = Path::new(&path)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or("video")
+        .to_string();
+    let sheet_path = sheets_dir.join(format!("{}_{}x{}_contact.jpg", stem, columns, rows));
+
+    render_contact_sheet(
+        &path,
+        sheet_path.to_str().unwrap(),
+        info.duration,
+        columns,
+        rows,
+        tile_width,
+    )
+    .map_err(|e| format!("Failed to generate contact sheet: {}", e))?;
+
+    Ok(sheet_path.to_string_lossy().to_string())
+}
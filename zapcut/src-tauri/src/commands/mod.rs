@@ -4,3 +4,18 @@ pub mod recording;
 pub mod app;
 pub mod prerender;
 
+pub mod library;
+pub mod ingest;
+pub mod jobs;
+pub mod transcode;
+pub mod integrity;
+pub mod analysis;
+pub mod schedule;
+pub mod presets;
+pub mod preview;
+pub mod playback;
+pub mod audio_engine;
+pub mod thumbnail_prefetch;
+pub mod stream;
+pub mod stream_metrics;
+pub mod clip_stream;
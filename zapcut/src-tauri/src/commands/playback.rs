@@ -0,0 +1,286 @@
+use crate::commands::prerender::{
+    clips_from, segment_duration, segment_graph, SegmentClip, SegmentFormat,
+};
+use crate::utils::ffmpeg::get_ffmpeg_path;
+use crate::utils::settings::{get_settings, update_settings};
+use serde::Serialize;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+/// Path under the `stream` protocol serving the engine's latest frame
+pub const PLAYBACK_FRAME_PATH: &str = "/__playback/frame";
+
+/// Payload of `playback://frame`, sent whenever a new frame is ready at PLAYBACK_FRAME_PATH
+#[derive(Debug, Serialize, Clone)]
+pub struct PlaybackFrame {
+    /// Seconds from the start of the loaded clips
+    pub position: f64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PlaybackStatus {
+    pub loaded: bool,
+    pub playing: bool,
+    pub position: f64,
+    pub duration: f64,
+}
+
+struct Timeline {
+    clips: Vec<SegmentClip>,
+    format: SegmentFormat,
+    duration: f64,
+}
+
+#[derive(Default)]
+struct PlaybackState {
+    timeline: Option<Timeline>,
+    playing: bool,
+    position: f64,
+    /// Stop flag of the running decoder
+    decoder: Option<Arc<AtomicBool>>,
+    frame: Option<Arc<Vec<u8>>>,
+}
+
+/// Opt-in preview engine: composites the timeline on the CPU with the same FFmpeg graph
+/// prerender uses and pushes JPEG frames to the webview through the `stream` protocol, paced
+/// to real time. Audio is played separately by AudioEngine.
+///
+/// This is a fallback for timelines the browser can't composite smoothly, not a GPU
+/// renderer: there is no wgpu compositing or shared texture, and each frame is JPEG-encoded
+/// and fetched by the webview, so it is limited to preview resolutions.
+pub struct PlaybackEngine {
+    state: Mutex<PlaybackState>,
+}
+
+impl PlaybackEngine {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PlaybackState::default()),
+        }
+    }
+
+    /// The most recent frame, for the `stream` protocol
+    pub fn latest_frame(&self) -> Option<Arc<Vec<u8>>> {
+        self.state.lock().unwrap().frame.clone()
+    }
+
+    fn stop_decoder(state: &mut PlaybackState) {
+        if let Some(stop) = state.decoder.take() {
+            stop.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Start decoding from the current position; a single frame when `still`
+    fn start_decoder(&self, app: &AppHandle, still: bool) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        Self::stop_decoder(&mut state);
+        let timeline = state.timeline.as_ref().ok_or("No timeline loaded for playback")?;
+        let (args, fps) = decoder_args(timeline, state.position, still)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        state.decoder = Some(stop.clone());
+
+        let (app, start) = (app.clone(), state.position);
+        std::thread::spawn(move || {
+            if let Err(e) = decode_frames(&app, &args, start, fps, still, &stop) {
+                eprintln!("[Playback] Decoding failed: {}", e);
+            }
+        });
+        Ok(())
+    }
+
+    fn status(&self) -> PlaybackStatus {
+        let state = self.state.lock().unwrap();
+        PlaybackStatus {
+            loaded: state.timeline.is_some(),
+            playing: state.playing,
+            position: state.position,
+            duration: state.timeline.as_ref().map(|timeline| timeline.duration).unwrap_or(0.0),
+        }
+    }
+}
+
+/// FFmpeg arguments compositing `timeline` from `position` into an MJPEG pipe. The audio
+/// branch of the graph is discarded. Every input is seeked to `position`, so a seek only
+/// decodes from the nearest keyframe of each clip.
+fn decoder_args(
+    timeline: &Timeline,
+    position: f64,
+    still: bool,
+) -> Result<(Vec<String>, f64), String> {
+    let (clips, format) = clips_from(&timeline.clips, &timeline.format, position);
+    let (mut args, out_video, out_audio) = segment_graph(&clips, &format, true)?;
+    if let Some(graph) = args.last_mut() {
+        graph.push_str(&format!(";[{}]anullsink", out_audio));
+    }
+    args.extend(["-map".to_string(), format!("[{}]", out_video)]);
+    if still {
+        args.extend(["-frames:v".to_string(), "1".to_string()]);
+    }
+    args.extend(["-c:v", "mjpeg", "-q:v", "5", "-f", "image2pipe", "-"].map(String::from));
+    Ok((args, timeline.format.fps.max(1.0)))
+}
+
+/// Read JPEG frames from the decoder and publish them on schedule until the clips end or
+/// `stop` is set
+fn decode_frames(
+    app: &AppHandle,
+    args: &[String],
+    start: f64,
+    fps: f64,
+    still: bool,
+    stop: &AtomicBool,
+) -> Result<(), String> {
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| e.to_string())?;
+    let mut child = Command::new(ffmpeg_path)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to start FFmpeg: {}", e))?;
+    let mut stdout = child.stdout.take().ok_or("FFmpeg has no output")?;
+
+    let engine = app.state::<PlaybackEngine>();
+    let started = Instant::now();
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0u8; 256 * 1024];
+    let mut frames = 0u64;
+    loop {
+        if stop.load(Ordering::SeqCst) {
+            let _ = child.kill();
+            break;
+        }
+        let read = stdout.read(&mut chunk).map_err(|e| format!("Failed to read frames: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+
+        // Byte stuffing means FF D9 only ever marks the end of a JPEG
+        while let Some(end) = buffer.windows(2).position(|pair| pair == [0xFF, 0xD9]) {
+            let frame: Vec<u8> = buffer.drain(..end + 2).collect();
+            let position = start + frames as f64 / fps;
+            if !still {
+                let due = started + Duration::from_secs_f64(frames as f64 / fps);
+                if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                    std::thread::sleep(wait);
+                }
+            }
+            frames += 1;
+
+            let mut state = engine.state.lock().unwrap();
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            state.frame = Some(Arc::new(frame));
+            state.position = position;
+            drop(state);
+            if let Err(e) = app.emit("playback://frame", PlaybackFrame { position }) {
+                eprintln!("[Playback] Failed to emit frame: {}", e);
+            }
+        }
+    }
+    let _ = child.wait();
+
+    if !still && !stop.load(Ordering::SeqCst) {
+        let mut state = engine.state.lock().unwrap();
+        state.playing = false;
+        state.decoder = None;
+        drop(state);
+        let _ = app.emit("playback://ended", ());
+    }
+    Ok(())
+}
+
+fn require_native_engine() -> Result<(), String> {
+    if get_settings().preview.native_engine {
+        Ok(())
+    } else {
+        Err("The native preview engine is disabled".to_string())
+    }
+}
+
+/// Turn the native preview engine on or off. Turning it off stops any playback.
+#[command]
+pub fn set_native_preview(engine: State<'_, PlaybackEngine>, enabled: bool) -> Result<(), String> {
+    update_settings(|settings| settings.preview.native_engine = enabled)
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+    if !enabled {
+        let mut state = engine.state.lock().unwrap();
+        PlaybackEngine::stop_decoder(&mut state);
+        *state = PlaybackState::default();
+    }
+    Ok(())
+}
+
+/// Load clips into the engine, paused at the start, and show the first frame
+#[command]
+pub fn load_playback(
+    app: AppHandle,
+    engine: State<'_, PlaybackEngine>,
+    clips: Vec<SegmentClip>,
+    format: Option<SegmentFormat>,
+) -> Result<PlaybackStatus, String> {
+    require_native_engine()?;
+    if clips.is_empty() {
+        return Err("No clips to play".to_string());
+    }
+    let format = format.unwrap_or_default();
+    {
+        let mut state = engine.state.lock().unwrap();
+        PlaybackEngine::stop_decoder(&mut state);
+        state.timeline = Some(Timeline {
+            duration: segment_duration(&clips, &format),
+            clips,
+            format,
+        });
+        state.playing = false;
+        state.position = 0.0;
+    }
+    engine.start_decoder(&app, true)?;
+    Ok(engine.status())
+}
+
+#[command]
+pub fn play_playback(app: AppHandle, engine: State<'_, PlaybackEngine>) -> Result<PlaybackStatus, String> {
+    require_native_engine()?;
+    engine.state.lock().unwrap().playing = true;
+    engine.start_decoder(&app, false)?;
+    Ok(engine.status())
+}
+
+#[command]
+pub fn pause_playback(engine: State<'_, PlaybackEngine>) -> PlaybackStatus {
+    {
+        let mut state = engine.state.lock().unwrap();
+        PlaybackEngine::stop_decoder(&mut state);
+        state.playing = false;
+    }
+    engine.status()
+}
+
+/// Jump to `time` seconds, continuing playback from there or showing that frame when paused
+#[command]
+pub fn seek_playback(
+    app: AppHandle,
+    engine: State<'_, PlaybackEngine>,
+    time: f64,
+) -> Result<PlaybackStatus, String> {
+    require_native_engine()?;
+    let playing = {
+        let mut state = engine.state.lock().unwrap();
+        let duration = state.timeline.as_ref().map(|timeline| timeline.duration).unwrap_or(0.0);
+        state.position = time.clamp(0.0, duration);
+        state.playing
+    };
+    engine.start_decoder(&app, !playing)?;
+    Ok(engine.status())
+}
+
+#[command]
+pub fn get_playback_status(engine: State<'_, PlaybackEngine>) -> PlaybackStatus {
+    engine.status()
+}
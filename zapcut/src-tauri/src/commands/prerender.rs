@@ -1,8 +1,25 @@
-use crate::utils::ffmpeg::get_ffmpeg_path;
+use crate::commands::export::{
+    audio_effect_filters, audio_mix_filters, fade_filters, is_zero, AudioEffect, DenoiseAudio,
+};
+use crate::utils::capture::{HardwareEncoding, RecordingQuality};
+use crate::utils::ffmpeg::{concat_files_copy, run_ffmpeg_with_progress};
+use crate::utils::jobs::{JobManager, JobStatus};
+use crate::utils::mp4::{codec_strings, read_init_segment};
+use crate::utils::prerender_cache::{
+    cache_stats, clear_cache, evict_to_limit, prerender_cache_dir, remove_segment,
+    remove_segments_for_clips, segment_path, touch_segment,
+    PrerenderCacheStats,
+};
+use crate::utils::settings::{get_settings, update_settings};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use std::process::{Command, Stdio};
-use tauri::command;
+use sha2::{Digest, Sha256};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+use tauri::{command, AppHandle, Emitter, Manager, State};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[allow(dead_code)]
@@ -15,104 +32,900 @@ pub struct PrerenderSegment {
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct SegmentClip {
+    /// Timeline clip this came from, so its cached segments can be invalidated when it changes
+    #[serde(default)]
+    pub clip_id: Option<String>,
     pub file_path: String,
+    /// 720p proxy of `file_path`, read instead of the original for segments no larger than it.
+    /// Export always reads originals.
+    #[serde(default)]
+    pub proxy_path: Option<String>,
     pub trim_start: f64,
     pub trim_end: f64,
     pub duration: f64,
     pub speed: f64,
+    /// Where the clip starts, in seconds from the start of the segment. Time not covered by an
+    /// earlier clip renders as black and silence, so a gap on the timeline stays a gap. None
+    /// places the clip right after the previous one.
+    #[serde(default)]
+    pub start_offset: Option<f64>,
+    /// Transition into this clip from whatever precedes it in the segment
+    #[serde(default)]
+    pub transition: Option<SegmentTransition>,
+    /// Timeline track; the lowest track present is the base layer and higher tracks are
+    /// composited over it, higher on top
+    #[serde(default)]
+    pub track_index: Option<u32>,
+    /// Placement of a clip on an overlay track; full frame when None
+    #[serde(default)]
+    pub transform: Option<ClipTransform>,
+    /// Volume change in decibels
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub gain_db: f64,
+    /// Stereo balance from -1 (left only) to 1 (right only)
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub pan: f64,
+    /// Seconds of audio fade-in at the start of the clip
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fade_in_sec: f64,
+    /// Seconds of audio fade-out at the end of the clip
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub fade_out_sec: f64,
+    /// Also fade the picture from and to black
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub fade_video: bool,
+    /// Noise reduction for the clip's audio
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub denoise_audio: Option<DenoiseAudio>,
+    /// Effects applied to the clip's audio in order
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub audio_effects: Vec<AudioEffect>,
+}
+
+/// Where an overlay clip sits in the frame, as fractions of the segment size
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ClipTransform {
+    /// Left edge, 0 at the left of the frame
+    pub x: f64,
+    /// Top edge, 0 at the top of the frame
+    pub y: f64,
+    /// Clip width as a fraction of the frame width
+    pub scale: f64,
+}
+
+/// A crossfade-style transition rendered with xfade (video) and acrossfade (audio)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SegmentTransition {
+    /// xfade transition name: fade, dissolve, wipeleft, slideup, circleopen, ...
+    #[serde(default = "default_transition_kind")]
+    pub kind: String,
+    pub duration: f64,
+}
+
+fn default_transition_kind() -> String {
+    "fade".to_string()
+}
+
+impl SegmentTransition {
+    /// Transition names are plain words; anything else would break the filter graph
+    fn xfade_name(&self) -> &str {
+        if !self.kind.is_empty() && self.kind.chars().all(|c| c.is_ascii_lowercase()) {
+            &self.kind
+        } else {
+            "fade"
+        }
+    }
+}
+
+/// Where a clip lands in the rendered segment
+struct ClipTiming {
+    /// Black and silence inserted before the clip
+    gap: f64,
+    /// Seconds the clip's transition overlaps what precedes it
+    overlap: f64,
+    /// Output time the clip starts at
+    start: f64,
+}
+
+/// Height proxies are generated at on import
+const PROXY_HEIGHT: u32 = 720;
+
+impl SegmentClip {
+    /// File to decode for a segment of `format`: the proxy when there is one and the segment
+    /// wouldn't need more detail than it has
+    fn source_path(&self, format: &SegmentFormat) -> &str {
+        match &self.proxy_path {
+            Some(proxy) if format.height <= PROXY_HEIGHT && Path::new(proxy).exists() => proxy,
+            _ => &self.file_path,
+        }
+    }
+
+    /// Seconds the clip lasts once its speed is applied
+    fn output_duration(&self) -> f64 {
+        self.duration / self.speed.max(0.01)
+    }
+}
+
+/// Timing of each clip in the segment, and the segment's total length. A transition can't
+/// outlast either side of it.
+fn segment_timing<'a>(
+    clips: impl IntoIterator<Item = &'a SegmentClip>,
+    format: &SegmentFormat,
+) -> (Vec<ClipTiming>, f64) {
+    // Anything shorter than a frame is rounding, not a gap
+    let frame = 1.0 / format.fps.max(1.0);
+    let mut position = 0.0;
+    let mut previous = 0.0;
+    let timings = clips
+        .into_iter()
+        .map(|clip| {
+            let gap = clip
+                .start_offset
+                .map(|offset| offset - position)
+                .filter(|gap| *gap >= frame)
+                .unwrap_or(0.0);
+            let length = clip.output_duration();
+            let before = if gap > 0.0 { gap } else { previous };
+            let overlap = clip
+                .transition
+                .as_ref()
+                .map(|transition| transition.duration.min(before).min(length))
+                .filter(|overlap| *overlap >= frame)
+                .unwrap_or(0.0);
+
+            let start = position + gap - overlap;
+            position = start + length;
+            previous = length;
+            ClipTiming { gap, overlap, start }
+        })
+        .collect();
+    (timings, position)
+}
+
+/// Length of the segment `clips` make up once normalized to `format`, in seconds
+pub(crate) fn segment_duration(clips: &[SegmentClip], format: &SegmentFormat) -> f64 {
+    segment_layers(clips, format).duration
+}
+
+/// Output of prerender_audio_segment
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioContainer {
+    /// AAC in M4A: small, fine for scrubbing
+    #[default]
+    Aac,
+    /// 16-bit PCM: no decode cost, exact samples
+    Wav,
+}
+
+impl AudioContainer {
+    fn extension(&self) -> &'static str {
+        match self {
+            AudioContainer::Aac => "m4a",
+            AudioContainer::Wav => "wav",
+        }
+    }
+
+    fn codec_args(&self) -> Vec<String> {
+        let args: &[&str] = match self {
+            AudioContainer::Aac => &["-c:a", "aac", "-b:a", "128k"],
+            AudioContainer::Wav => &["-c:a", "pcm_s16le"],
+        };
+        args.iter().map(|s| s.to_string()).collect()
+    }
+}
+
+/// File layout of a prerendered video segment
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SegmentContainer {
+    /// Regular MP4 for a <video> src
+    #[default]
+    Mp4,
+    /// Fragmented MP4 for Media Source Extensions. Always encoded in software with fixed
+    /// H.264 settings, so every segment of a format shares the same init segment and they can
+    /// be appended to one SourceBuffer.
+    Fmp4,
+}
+
+/// Resolution and frame rate every clip in a segment is normalized to
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SegmentFormat {
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    /// Pad the segment with black and silence to this many seconds when its clips end sooner
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub length: Option<f64>,
+}
+
+impl Default for SegmentFormat {
+    fn default() -> Self {
+        Self {
+            width: 1280,
+            height: 720,
+            fps: 30.0,
+            length: None,
+        }
+    }
+}
+
+impl SegmentFormat {
+    /// yuv420p needs even dimensions
+    fn even_width(&self) -> u32 {
+        self.width.max(2) / 2 * 2
+    }
+
+    fn even_height(&self) -> u32 {
+        self.height.max(2) / 2 * 2
+    }
+
+    /// The same aspect ratio and frame rate at a quality tier's height
+    fn at_quality(&self, quality: PrerenderQuality) -> SegmentFormat {
+        let height = quality.height();
+        let aspect = self.width.max(1) as f64 / self.height.max(1) as f64;
+        SegmentFormat {
+            width: ((height as f64 * aspect / 2.0).round() as u32 * 2).max(2),
+            height,
+            fps: self.fps,
+            length: self.length,
+        }
+    }
+
+    /// Letterbox into the target size at a constant frame rate
+    fn video_filter(&self) -> String {
+        format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:(ow-iw)/2:(oh-ih)/2:black,setsar=1,fps={fps}",
+            w = self.even_width(),
+            h = self.even_height(),
+            fps = self.fps
+        )
+    }
+}
+
+/// Resolution tiers for prerendered segments. The queue renders a fast draft first and
+/// upgrades it once nothing else is waiting.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum PrerenderQuality {
+    /// 480p
+    Draft,
+    /// 720p
+    Normal,
+    /// 1080p
+    High,
+}
+
+impl PrerenderQuality {
+    fn height(&self) -> u32 {
+        match self {
+            PrerenderQuality::Draft => 480,
+            PrerenderQuality::Normal => 720,
+            PrerenderQuality::High => 1080,
+        }
+    }
+}
+
+/// Encoder arguments for fragmented segments. A keyframe every two seconds keeps fragments
+/// short enough to seek within.
+fn fragmented_video_args(format: &SegmentFormat) -> Vec<String> {
+    let keyframe_interval = ((format.fps * 2.0).round() as u32).max(1).to_string();
+    [
+        "-c:v", "libx264", "-preset", "veryfast", "-crf", "23",
+        "-profile:v", "high", "-level:v", "4.1", "-pix_fmt", "yuv420p",
+        "-g", &keyframe_interval, "-keyint_min", &keyframe_interval, "-sc_threshold", "0",
+        "-movflags", "+frag_keyframe+empty_moov+default_base_moof",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// Audio every clip is resampled to before concatenation
+const SEGMENT_AUDIO_FORMAT: &str = "aformat=sample_fmts=fltp:sample_rates=48000:channel_layouts=stereo";
+
+/// Payload of `prerender://progress`
+#[derive(Debug, Serialize, Clone)]
+pub struct PrerenderProgress {
+    pub segment_id: String,
+    /// 0-100
+    pub percentage: f64,
+}
+
+/// Payload of `prerender://done`, sent once per render whether it succeeded, failed or was
+/// cancelled
+#[derive(Debug, Serialize, Clone)]
+pub struct PrerenderDone {
+    pub segment_id: String,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+    pub cancelled: bool,
+}
+
+/// Payload of `prerender://upgraded`, sent when a draft segment has been replaced by its
+/// requested quality
+#[derive(Debug, Serialize, Clone)]
+pub struct PrerenderUpgraded {
+    pub segment_id: String,
+    pub quality: PrerenderQuality,
+    pub output_path: String,
+}
+
+fn emit_progress(app: &AppHandle, segment_id: &str, percentage: f64) {
+    let progress = PrerenderProgress {
+        segment_id: segment_id.to_string(),
+        percentage,
+    };
+    if let Err(e) = app.emit("prerender://progress", progress) {
+        eprintln!("[Prerender] Failed to emit progress: {}", e);
+    }
+}
+
+fn emit_done(app: &AppHandle, segment_id: &str, result: &Result<PathBuf, String>, cancelled: bool) {
+    let done = PrerenderDone {
+        segment_id: segment_id.to_string(),
+        output_path: result.as_ref().ok().map(|path| path.to_string_lossy().to_string()),
+        error: result.as_ref().err().filter(|_| !cancelled).cloned(),
+        cancelled,
+    };
+    if let Err(e) = app.emit("prerender://done", done) {
+        eprintln!("[Prerender] Failed to emit completion: {}", e);
+    }
+}
+
+/// Cache key for a segment: everything that affects the render (the clip list with its trims
+/// and speeds, and the output format) plus the size and modification time of each file read
+/// (the proxy when one is used), so a replaced file re-renders
+fn prerender_cache_key(clips: &[SegmentClip], format: &SegmentFormat) -> Result<String, String> {
+    let mut hasher = Sha256::new();
+    let description = serde_json::to_string(format)
+        .map_err(|e| format!("Failed to describe segment format: {}", e))?;
+    hasher.update(description.as_bytes());
+    for clip in clips {
+        // The id doesn't change the picture, so identical clips share a cached segment
+        let anonymous = SegmentClip {
+            clip_id: None,
+            ..clip.clone()
+        };
+        let description = serde_json::to_string(&anonymous)
+            .map_err(|e| format!("Failed to describe clip: {}", e))?;
+        hasher.update(description.as_bytes());
+
+        let source = clip.source_path(format);
+        let metadata = std::fs::metadata(source)
+            .map_err(|e| format!("Failed to read {}: {}", source, e))?;
+        hasher.update(metadata.len().to_le_bytes());
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|since| since.as_nanos())
+            .unwrap_or(0);
+        hasher.update(modified.to_le_bytes());
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Cache key for a segment rendered into `container`; fragmented renders are cached apart
+/// from plain ones of the same clips
+fn container_cache_key(
+    clips: &[SegmentClip],
+    format: &SegmentFormat,
+    container: SegmentContainer,
+) -> Result<String, String> {
+    let key = prerender_cache_key(clips, format)?;
+    if container == SegmentContainer::Mp4 {
+        return Ok(key);
+    }
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update(b"fmp4");
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 /// Render a timeline segment (10 seconds) into a single cached video file
 /// This allows seamless playback of complex timelines without real-time compositing
+///
+/// Segments are cached by content (see prerender_cache_key): an identical clip list returns
+/// the existing file without encoding. The returned path is the cached file, or `output_path`
+/// when one is given, in which case the cached file is copied there. Progress and completion
+/// are emitted as `prerender://progress` and `prerender://done` events keyed by `segment_id`.
+/// Clips are normalized to `format` (1280x720 at 30 fps by default), resized to the height of
+/// `quality` when one is given. With the `fmp4` container the segment is fragmented for
+/// Media Source Extensions; see get_init_segment. Its timestamps start at zero, so set the
+/// SourceBuffer's timestampOffset to the segment's start before appending.
 #[command]
 pub async fn prerender_segment(
-    _segment_id: String,
+    app: AppHandle,
+    segment_id: String,
     clips: Vec<SegmentClip>,
-    output_path: String,
+    format: Option<SegmentFormat>,
+    quality: Option<PrerenderQuality>,
+    container: Option<SegmentContainer>,
+    output_path: Option<String>,
 ) -> Result<String, String> {
     
     if clips.is_empty() {
         return Err("No clips to render".to_string());
     }
     
-    let ffmpeg_path = get_ffmpeg_path()
-        .map_err(|e| format!("FFmpeg not found: {}", e))?;
+    let format = format.unwrap_or_default();
+    let format = match quality {
+        Some(quality) => format.at_quality(quality),
+        None => format,
+    };
+    let container = container.unwrap_or_default();
+    let key = container_cache_key(&clips, &format, container)?;
+    let result = render_cached(&key, &clips, &format, container, None, |percentage| {
+        emit_progress(&app, &segment_id, percentage)
+    });
+    emit_done(&app, &segment_id, &result, false);
+    let cached_path = result?;
     
-    // Create temp directory for intermediate files
-    let temp_dir = std::env::temp_dir().join("zapcut").join("prerender");
-    std::fs::create_dir_all(&temp_dir)
-        .map_err(|e| format!("Failed to create temp dir: {}", e))?;
+    match output_path {
+        Some(output_path) if Path::new(&output_path) != cached_path => {
+            std::fs::copy(&cached_path, &output_path)
+                .map_err(|e| format!("Failed to copy cached segment: {}", e))?;
+            Ok(output_path)
+        }
+        _ => Ok(cached_path.to_string_lossy().to_string()),
+    }
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct InitSegment {
+    /// MIME type with codecs, for MediaSource.addSourceBuffer
+    pub mime_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Initialization segment of a fragmented segment rendered with the `fmp4` container. Every
+/// fragmented segment of the same format shares it, so it only needs to be appended once.
+/// Only files in the prerender cache are read, and only their ftyp and moov boxes.
+#[command]
+pub fn get_init_segment(path: String) -> Result<InitSegment, String> {
+    let cache_dir = prerender_cache_dir()
+        .and_then(|dir| Ok(dir.canonicalize()?))
+        .map_err(|e| format!("Failed to locate prerender cache: {}", e))?;
+    let file = Path::new(&path)
+        .canonicalize()
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    if !file.starts_with(&cache_dir) {
+        return Err(format!("{} is not a prerendered segment", path));
+    }
+
+    let init = read_init_segment(&file)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?
+        .ok_or_else(|| format!("{} is not a fragmented MP4", path))?;
+    Ok(InitSegment {
+        mime_type: format!("video/mp4; codecs=\"{}\"", codec_strings(&init).join(", ")),
+        data: init,
+    })
+}
+
+/// Timeline seconds per cached piece of a preview range. Pieces sit on a fixed grid so
+/// overlapping ranges share them.
+const PREVIEW_PIECE_SECONDS: f64 = 10.0;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PreviewRange {
+    pub path: String,
+    /// Timeline time the file starts at, at or before the requested start since pieces are
+    /// aligned to a grid; seek to `requested start - start` to begin playback there
+    pub start: f64,
+    pub end: f64,
+}
+
+/// Where each clip starts on the timeline: its start_offset, or straight after the previous
+/// clip on its track
+fn timeline_starts(clips: &[SegmentClip]) -> Vec<f64> {
+    let mut track_ends: HashMap<u32, f64> = HashMap::new();
+    clips
+        .iter()
+        .map(|clip| {
+            let track_end = track_ends.entry(clip.track_index.unwrap_or(0)).or_insert(0.0);
+            let start = clip.start_offset.unwrap_or(*track_end);
+            *track_end = start + clip.output_duration();
+            start
+        })
+        .collect()
+}
+
+/// The parts of `clips` inside timeline `window_start..window_end`, trimmed and placed
+/// relative to the window. A transition is kept only when the clip's head is in the window.
+fn window_clips(
+    clips: &[SegmentClip],
+    starts: &[f64],
+    window_start: f64,
+    window_end: f64,
+) -> Vec<SegmentClip> {
+    clips
+        .iter()
+        .zip(starts)
+        .filter_map(|(clip, &start)| {
+            let end = start + clip.output_duration();
+            let (cut_start, cut_end) = (start.max(window_start), end.min(window_end));
+            if cut_end - cut_start <= 0.0 {
+                return None;
+            }
+            let speed = clip.speed.max(0.01);
+            let trim_start = clip.trim_start + (cut_start - start) * speed;
+            let duration = (cut_end - cut_start) * speed;
+            Some(SegmentClip {
+                trim_start,
+                trim_end: trim_start + duration,
+                duration,
+                start_offset: Some(cut_start - window_start),
+                transition: clip.transition.clone().filter(|_| cut_start == start),
+                // Like transitions, a fade is kept only where the window holds that end
+                fade_in_sec: if cut_start == start { clip.fade_in_sec } else { 0.0 },
+                fade_out_sec: if cut_end == end { clip.fade_out_sec } else { 0.0 },
+                ..clip.clone()
+            })
+        })
+        .collect()
+}
+
+/// `clips` and `format` from timeline time `position` on, placed relative to it. Each clip's
+/// trim start is moved up to where playback begins, so its input is seeked there rather than
+/// decoded from its head.
+pub(crate) fn clips_from(
+    clips: &[SegmentClip],
+    format: &SegmentFormat,
+    position: f64,
+) -> (Vec<SegmentClip>, SegmentFormat) {
+    let starts = timeline_starts(clips);
+    let format = SegmentFormat {
+        length: format.length.map(|length| (length - position).max(0.0)),
+        ..format.clone()
+    };
+    (window_clips(clips, &starts, position, f64::INFINITY), format)
+}
+
+/// Joined preview ranges kept on disk; their pieces stay in the segment cache regardless
+const MAX_PREVIEW_RANGES: usize = 4;
+
+/// Delete all but the newest joined preview ranges
+fn prune_preview_ranges(dir: &Path) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut ranges: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    ranges.sort_by_key(|(modified, _)| Reverse(*modified));
+    for (_, path) in ranges.into_iter().skip(MAX_PREVIEW_RANGES) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Render timeline `start..end` of `clips` into one file for immediate playback, like an NLE's
+/// "render in to out". Clips are placed on the timeline by start_offset. The range is cut into
+/// fixed pieces that are cached like segments, so only pieces not rendered before are encoded,
+/// and the pieces are then joined without re-encoding. `draft_quality` renders at 480p.
+#[command]
+pub async fn render_preview_range(
+    clips: Vec<SegmentClip>,
+    start: f64,
+    end: f64,
+    draft_quality: Option<bool>,
+    format: Option<SegmentFormat>,
+) -> Result<PreviewRange, String> {
+    let starts = timeline_starts(&clips);
+    let timeline_end = clips
+        .iter()
+        .zip(&starts)
+        .map(|(clip, start)| start + clip.output_duration())
+        .fold(0.0, f64::max);
+    let end = end.min(timeline_end);
+    let start = start.max(0.0);
+    if end <= start {
+        return Err("Preview range is empty".to_string());
+    }
+
+    let mut format = format.unwrap_or_default();
+    if draft_quality.unwrap_or(false) {
+        format = format.at_quality(PrerenderQuality::Draft);
+    }
+    let first_piece = (start / PREVIEW_PIECE_SECONDS).floor() as u64;
+    let last_piece = ((end / PREVIEW_PIECE_SECONDS).ceil() as u64).max(first_piece + 1);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut pieces = Vec::new();
+        let mut hasher = Sha256::new();
+        for piece in first_piece..last_piece {
+            let window_start = piece as f64 * PREVIEW_PIECE_SECONDS;
+            let window_end = (window_start + PREVIEW_PIECE_SECONDS).min(timeline_end);
+            let piece_clips = window_clips(&clips, &starts, window_start, window_end);
+            // Every piece lasts exactly its window, even where the timeline is empty
+            let piece_format = SegmentFormat {
+                length: Some(window_end - window_start),
+                ..format.clone()
+            };
+            let key = prerender_cache_key(&piece_clips, &piece_format)?;
+            hasher.update(key.as_bytes());
+            pieces.push(render_cached(
+                &key,
+                &piece_clips,
+                &piece_format,
+                SegmentContainer::Mp4,
+                None,
+                |_| {},
+            )?);
+        }
+
+        let stitched = prerender_cache_dir()
+            .map_err(|e| e.to_string())?
+            .join("ranges")
+            .join(format!("{:x}.mp4", hasher.finalize()));
+        if !stitched.exists() {
+            std::fs::create_dir_all(stitched.parent().unwrap())
+                .map_err(|e| format!("Failed to create preview range directory: {}", e))?;
+            let partial = stitched.with_extension("partial.mp4");
+            concat_files_copy(&pieces, &partial.to_string_lossy())
+                .map_err(|e| format!("Failed to join preview pieces: {}", e))?;
+            std::fs::rename(&partial, &stitched)
+                .map_err(|e| format!("Failed to store preview range: {}", e))?;
+            prune_preview_ranges(stitched.parent().unwrap());
+        }
+
+        println!("[Prerender] Preview range {:.2}-{:.2}s ready: {:?}", start, end, stitched);
+        Ok(PreviewRange {
+            path: stitched.to_string_lossy().to_string(),
+            start: first_piece as f64 * PREVIEW_PIECE_SECONDS,
+            end,
+        })
+    })
+    .await
+    .map_err(|e| format!("Preview range task failed: {}", e))?
+}
+
+/// Return the cached file for `key`, running `render` into a temporary path first when it
+/// isn't cached. The temporary name means an interrupted render is never mistaken for a
+/// cached one.
+fn cached_render<R>(
+    key: &str,
+    extension: &str,
+    clips: &[SegmentClip],
+    render: R,
+) -> Result<PathBuf, String>
+where
+    R: FnOnce(&str) -> Result<(), String>,
+{
+    let cached_path = segment_path(key, extension).map_err(|e| e.to_string())?;
     
-    // For a single clip, just trim it directly
-    if clips.len() == 1 {
-        return render_single_clip(&clips[0], &output_path, &ffmpeg_path);
+    if cached_path.exists() {
+        println!("[Prerender] Reusing cached segment {}", key);
+    } else {
+        let partial_path = cached_path.with_extension(format!("partial.{}", extension));
+        if let Err(e) = render(&partial_path.to_string_lossy()) {
+            let _ = std::fs::remove_file(&partial_path);
+            return Err(e);
+        }
+        std::fs::rename(&partial_path, &cached_path)
+            .map_err(|e| format!("Failed to store cached segment: {}", e))?;
     }
     
-    // For multiple clips, build a filter_complex command
-    render_multiple_clips(&clips, &output_path, &ffmpeg_path, &temp_dir)
+    let clip_ids: Vec<String> = clips.iter().filter_map(|clip| clip.clip_id.clone()).collect();
+    if let Err(e) = touch_segment(key, &clip_ids) {
+        eprintln!("[Prerender] {}", e);
+    }
+    enforce_cache_limit();
+    Ok(cached_path)
 }
 
-/// Render a single clip segment
-fn render_single_clip(
-    clip: &SegmentClip,
-    output_path: &str,
-    ffmpeg_path: &PathBuf,
+/// Return the cached render of `clips` under `key`, encoding it first when it isn't cached
+fn render_cached<F>(
+    key: &str,
+    clips: &[SegmentClip],
+    format: &SegmentFormat,
+    container: SegmentContainer,
+    cancel: Option<&AtomicBool>,
+    mut on_progress: F,
+) -> Result<PathBuf, String>
+where
+    F: FnMut(f64),
+{
+    cached_render(key, "mp4", clips, |partial| {
+        let duration = segment_layers(clips, format).duration;
+        let mut encode = |quality: &RecordingQuality| {
+            let video_args = match container {
+                SegmentContainer::Mp4 => quality
+                    .video_codec_args("ultrafast", 23)
+                    .map_err(|e| format!("Failed to choose an encoder: {}", e))?,
+                SegmentContainer::Fmp4 => fragmented_video_args(format),
+            };
+            let args = segment_args(clips, format, &video_args, partial)?;
+            run_ffmpeg_with_progress(&args, duration, cancel, &mut on_progress)
+                .map(|_| ())
+                .map_err(|e| format!("FFmpeg failed: {}", e))
+        };
+        
+        let quality = prerender_quality();
+        let result = encode(&quality);
+        let cancelled = cancel.map(|flag| flag.load(Ordering::SeqCst)).unwrap_or(false);
+        let retry = container == SegmentContainer::Mp4
+            && quality.hardware_encoding == HardwareEncoding::Auto;
+        if result.is_err() && !cancelled && retry {
+            // Hardware encoders can reject some sizes or pixel formats; preview must still render
+            eprintln!("[Prerender] Hardware encode failed, retrying in software");
+            return encode(&quality.software());
+        }
+        result
+    })
+}
+
+/// Render only the mixed audio of `clips`. No video is decoded or encoded, so this is much
+/// faster than a full segment when only audio changed or for audio scrubbing.
+/// Progress and completion are emitted like prerender_segment's, keyed by `segment_id`.
+#[command]
+pub async fn prerender_audio_segment(
+    app: AppHandle,
+    segment_id: String,
+    clips: Vec<SegmentClip>,
+    container: Option<AudioContainer>,
 ) -> Result<String, String> {
-    
-    let mut args = vec![
-        "-ss".to_string(),
-        format!("{:.3}", clip.trim_start),
-        "-i".to_string(),
-        clip.file_path.clone(),
-        "-t".to_string(),
-        format!("{:.3}", clip.duration),
-    ];
-    
-    // Apply speed if needed
-    if (clip.speed - 1.0).abs() > 0.001 {
-        args.extend(vec![
-            "-vf".to_string(),
-            format!("setpts={}*PTS", 1.0 / clip.speed),
-            "-af".to_string(),
-            format!("atempo={}", clip.speed),
-        ]);
+    if clips.is_empty() {
+        return Err("No clips to render".to_string());
     }
     
-    args.extend(vec![
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "ultrafast".to_string(),
-        "-crf".to_string(),
-        "23".to_string(),
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-y".to_string(),
-        output_path.to_string(),
-    ]);
+    let container = container.unwrap_or_default();
+    let format = SegmentFormat::default();
+    let mut hasher = Sha256::new();
+    hasher.update(prerender_cache_key(&clips, &format)?.as_bytes());
+    hasher.update(format!("audio:{}", container.extension()).as_bytes());
+    let key = format!("{:x}", hasher.finalize());
     
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+    let duration = segment_layers(&clips, &format).duration;
+    let (worker_app, worker_segment) = (app.clone(), segment_id.clone());
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        cached_render(&key, container.extension(), &clips, |partial| {
+            let args = audio_segment_args(&clips, container, partial)?;
+            run_ffmpeg_with_progress(&args, duration, None, |percentage| {
+                emit_progress(&worker_app, &worker_segment, percentage)
+            })
+            .map(|_| ())
+            .map_err(|e| format!("FFmpeg failed: {}", e))
+        })
+    })
+    .await
+    .map_err(|e| format!("Audio prerender task failed: {}", e))?;
     
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg failed: {}", error));
+    emit_done(&app, &segment_id, &result, false);
+    result.map(|path| path.to_string_lossy().to_string())
+}
+
+/// atempo filters for `speed`; each atempo only accepts 0.5-2.0, so larger changes are chained
+fn atempo_chain(speed: f64) -> Vec<String> {
+    let mut speed = speed;
+    let mut audio_filters = vec![];
+    while speed > 2.0 {
+        audio_filters.push("atempo=2.0".to_string());
+        speed /= 2.0;
+    }
+    while speed < 0.5 {
+        audio_filters.push("atempo=0.5".to_string());
+        speed /= 0.5;
+    }
+    if (speed - 1.0).abs() > 0.001 {
+        audio_filters.push(format!("atempo={:.3}", speed));
+    }
+    audio_filters
+}
+
+/// A segment split into its base track and the clips composited over it
+struct SegmentLayers {
+    /// Indices into the clip list, in timeline order
+    base: Vec<usize>,
+    base_timings: Vec<ClipTiming>,
+    /// Length of the base track alone
+    base_duration: f64,
+    /// Indices of clips on higher tracks, bottom track first
+    overlays: Vec<usize>,
+    /// Length of the whole segment, overlays included
+    duration: f64,
+}
+
+fn segment_layers(clips: &[SegmentClip], format: &SegmentFormat) -> SegmentLayers {
+    let track = |clip: &SegmentClip| clip.track_index.unwrap_or(0);
+    let base_track = clips.iter().map(track).min().unwrap_or(0);
+
+    let base: Vec<usize> = (0..clips.len()).filter(|i| track(&clips[*i]) == base_track).collect();
+    let mut overlays: Vec<usize> = (0..clips.len()).filter(|i| track(&clips[*i]) != base_track).collect();
+    // Stable, so clips on one track keep their order
+    overlays.sort_by_key(|i| track(&clips[*i]));
+
+    let (base_timings, base_duration) = segment_timing(base.iter().map(|i| &clips[*i]), format);
+    let duration = overlays
+        .iter()
+        .map(|i| clips[*i].start_offset.unwrap_or(0.0) + clips[*i].output_duration())
+        .fold(base_duration, f64::max)
+        .max(format.length.unwrap_or(0.0));
+
+    SegmentLayers {
+        base,
+        base_timings,
+        base_duration,
+        overlays,
+        duration,
     }
-    
-    Ok(output_path.to_string())
 }
 
-/// Render multiple clips using filter_complex for optimal performance
-fn render_multiple_clips(
+/// Video and audio filters fitting clip `i` into the segment: noise reduction, speed, then
+/// `video_fit`
+/// (scaling), then the shared audio format, the clip's effects, gain and pan, and its fades.
+/// Audio only unless `video`.
+fn clip_filters(
+    filter_parts: &mut Vec<String>,
+    i: usize,
+    clip: &SegmentClip,
+    video_fit: &str,
+    video: bool,
+) -> Result<(), String> {
+    let mut video_filters = vec![];
+    let mut audio_filters = vec![];
+    if let Some(denoise) = &clip.denoise_audio {
+        audio_filters.push(denoise.to_filter()?);
+    }
+    if (clip.speed - 1.0).abs() > 0.001 {
+        video_filters.push(format!("setpts={}*PTS", 1.0 / clip.speed));
+        audio_filters.extend(atempo_chain(clip.speed));
+    }
+    video_filters.push(video_fit.to_string());
+    audio_filters.push(SEGMENT_AUDIO_FORMAT.to_string());
+    audio_filters.extend(audio_effect_filters(&clip.audio_effects));
+    audio_filters.extend(audio_mix_filters(clip.gain_db, clip.pan));
+
+    let duration = clip.output_duration();
+    audio_filters.extend(fade_filters("afade", duration, clip.fade_in_sec, clip.fade_out_sec));
+    if clip.fade_video {
+        video_filters.extend(fade_filters("fade", duration, clip.fade_in_sec, clip.fade_out_sec));
+    }
+
+    if video {
+        filter_parts.push(format!("[{}:v]{}[v{}]", i, video_filters.join(","), i));
+    }
+    filter_parts.push(format!("[{}:a]{}[a{}]", i, audio_filters.join(","), i));
+    Ok(())
+}
+
+/// Black and silence of `duration` seconds as pads [gv<id>] and [ga<id>]; silence only
+/// unless `video`
+fn gap_filters(
+    filter_parts: &mut Vec<String>,
+    id: &str,
+    duration: f64,
+    format: &SegmentFormat,
+    video: bool,
+) {
+    if video {
+        filter_parts.push(format!(
+            "color=c=black:s={}x{}:r={}:d={:.3},setsar=1[gv{}]",
+            format.even_width(),
+            format.even_height(),
+            format.fps,
+            duration,
+            id
+        ));
+    }
+    filter_parts.push(format!(
+        "anullsrc=r=48000:cl=stereo,atrim=duration={:.3},{}[ga{}]",
+        duration, SEGMENT_AUDIO_FORMAT, id
+    ));
+}
+
+/// FFmpeg inputs and filter_complex for a segment, returning the arguments with the output
+/// video and audio pads. Clips on the base track play back to back; every clip is scaled,
+/// padded and resampled to `format` first so sources with different resolutions, frame rates
+/// or audio layouts concatenate cleanly. Clips on higher tracks are overlaid at their
+/// transform and their audio mixed in. Without `video` only the audio is decoded and mixed,
+/// and the video pad is empty. Fails when a clip asks for a filter that isn't available.
+pub(crate) fn segment_graph(
     clips: &[SegmentClip],
-    output_path: &str,
-    ffmpeg_path: &PathBuf,
-    _temp_dir: &PathBuf,
-) -> Result<String, String> {
+    format: &SegmentFormat,
+    video: bool,
+) -> Result<(Vec<String>, String, String), String> {
     
     // Build FFmpeg command with multiple inputs and filter_complex
     let mut args = vec![];
@@ -123,122 +936,694 @@ fn render_multiple_clips(
         args.push(format!("{:.3}", clip.trim_start));
         args.push("-t".to_string());
         args.push(format!("{:.3}", clip.duration));
+        if !video {
+            args.push("-vn".to_string());
+        }
         args.push("-i".to_string());
-        args.push(clip.file_path.clone());
+        args.push(clip.source_path(format).to_string());
     }
     
     // Build filter_complex for concatenation
     let mut filter_parts = vec![];
+    // Pads waiting to be concatenated, and the number of concat/xfade joins so far
+    let mut run: Vec<(String, String)> = vec![];
+    let mut joins = 0;
+    let layers = segment_layers(clips, format);
     
-    for (i, clip) in clips.iter().enumerate() {
-        if (clip.speed - 1.0).abs() > 0.001 {
-            // Apply speed adjustment
-            let video_filter = format!("[{}:v]setpts={}*PTS[v{}]", i, 1.0 / clip.speed, i);
-            filter_parts.push(video_filter);
-            
-            // Audio speed (limit to valid atempo range)
-            let mut speed = clip.speed;
-            let mut audio_filters = vec![];
-            while speed > 2.0 {
-                audio_filters.push("atempo=2.0".to_string());
-                speed /= 2.0;
-            }
-            while speed < 0.5 {
-                audio_filters.push("atempo=0.5".to_string());
-                speed /= 0.5;
-            }
-            if (speed - 1.0).abs() > 0.001 {
-                audio_filters.push(format!("atempo={:.3}", speed));
-            }
-            
-            if !audio_filters.is_empty() {
-                let audio_filter = format!("[{}:a]{}[a{}]", i, audio_filters.join(","), i);
-                filter_parts.push(audio_filter);
-            } else {
-                filter_parts.push(format!("[{}:a]anull[a{}]", i, i));
+    for (position, &i) in layers.base.iter().enumerate() {
+        let clip = &clips[i];
+        let timing = &layers.base_timings[position];
+        if timing.gap > 0.0 {
+            gap_filters(&mut filter_parts, &i.to_string(), timing.gap, format, video);
+            run.push((format!("gv{}", i), format!("ga{}", i)));
+        }
+        
+        clip_filters(&mut filter_parts, i, clip, &format.video_filter(), video)?;
+        
+        let (clip_video, audio) = (format!("v{}", i), format!("a{}", i));
+        match clip.transition.as_ref().filter(|_| timing.overlap > 0.0) {
+            Some(transition) => {
+                // Everything so far becomes one stream that the clip fades in over
+                let (before_video, before_audio) =
+                    join_run(&mut filter_parts, &mut run, &mut joins, video);
+                if video {
+                    filter_parts.push(format!(
+                        "[{}][{}]xfade=transition={}:duration={:.3}:offset={:.3}[xv{}]",
+                        before_video, clip_video, transition.xfade_name(), timing.overlap, timing.start, joins
+                    ));
+                }
+                filter_parts.push(format!(
+                    "[{}][{}]acrossfade=d={:.3}[xa{}]",
+                    before_audio, audio, timing.overlap, joins
+                ));
+                run.push((format!("xv{}", joins), format!("xa{}", joins)));
+                joins += 1;
             }
-        } else {
-            // No speed adjustment
-            filter_parts.push(format!("[{}:v]null[v{}]", i, i));
-            filter_parts.push(format!("[{}:a]anull[a{}]", i, i));
+            None => run.push((clip_video, audio)),
         }
     }
     
-    // Concatenate all streams
-    let v_inputs: Vec<String> = (0..clips.len()).map(|i| format!("[v{}]", i)).collect();
-    let _a_inputs: Vec<String> = (0..clips.len()).map(|i| format!("[a{}]", i)).collect();
+    // Overlays (or a fixed segment length) can run past the base track, which is then held on
+    // black
+    let tail = layers.duration - layers.base_duration;
+    if tail >= 1.0 / format.fps.max(1.0) {
+        gap_filters(&mut filter_parts, "end", tail, format, video);
+        run.push(("gvend".to_string(), "gaend".to_string()));
+    }
     
-    filter_parts.push(format!(
-        "{}concat=n={}:v=1:a=1[outv][outa]",
-        v_inputs.join(""),
-        clips.len()
-    ));
+    let (mut out_video, mut out_audio) = join_run(&mut filter_parts, &mut run, &mut joins, video);
     
-    let filter_complex = filter_parts.join(";");
+    let mut mixed_audio = vec![];
+    for &i in &layers.overlays {
+        let clip = &clips[i];
+        let start = clip.start_offset.unwrap_or(0.0).max(0.0);
+        let end = start + clip.output_duration();
+        let (x, y, width) = match &clip.transform {
+            Some(transform) => (
+                transform.x * format.width as f64,
+                transform.y * format.height as f64,
+                (transform.scale.clamp(0.01, 4.0) * format.width as f64) as u32 / 2 * 2,
+            ),
+            None => (0.0, 0.0, format.even_width()),
+        };
+        
+        // Shift the clip to its place in the segment and only show it there
+        let fit = format!("scale={}:-2,setsar=1,setpts=PTS-STARTPTS+{:.3}/TB", width.max(2), start);
+        clip_filters(&mut filter_parts, i, clip, &fit, video)?;
+        if video {
+            filter_parts.push(format!(
+                "[{}][v{}]overlay=x={:.0}:y={:.0}:eof_action=pass:enable='between(t,{:.3},{:.3})'[ov{}]",
+                out_video, i, x, y, start, end, i
+            ));
+            out_video = format!("ov{}", i);
+        }
+        
+        filter_parts.push(format!("[a{}]adelay={:.0}:all=1[da{}]", i, start * 1000.0, i));
+        mixed_audio.push(format!("[da{}]", i));
+    }
     
+    if !mixed_audio.is_empty() {
+        filter_parts.push(format!(
+            "[{}]{}amix=inputs={}:duration=first:normalize=0[mixa]",
+            out_audio,
+            mixed_audio.join(""),
+            mixed_audio.len() + 1
+        ));
+        out_audio = "mixa".to_string();
+    }
+    
+    args.push("-filter_complex".to_string());
+    args.push(filter_parts.join(";"));
+    Ok((args, out_video, out_audio))
+}
+
+/// FFmpeg arguments rendering a segment's video and audio
+fn segment_args(
+    clips: &[SegmentClip],
+    format: &SegmentFormat,
+    video_args: &[String],
+    output_path: &str,
+) -> Result<Vec<String>, String> {
+    let (mut args, out_video, out_audio) = segment_graph(clips, format, true)?;
     args.extend(vec![
-        "-filter_complex".to_string(),
-        filter_complex,
         "-map".to_string(),
-        "[outv]".to_string(),
+        format!("[{}]", out_video),
         "-map".to_string(),
-        "[outa]".to_string(),
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "ultrafast".to_string(),
-        "-crf".to_string(),
-        "23".to_string(),
+        format!("[{}]", out_audio),
         "-c:a".to_string(),
         "aac".to_string(),
-        "-y".to_string(),
-        output_path.to_string(),
     ]);
+    args.extend_from_slice(video_args);
+    args.extend(vec!["-y".to_string(), output_path.to_string()]);
     
-    let output = Command::new(ffmpeg_path)
-        .args(&args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
-    
-    if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("FFmpeg failed: {}", error));
+    Ok(args)
+}
+
+/// FFmpeg arguments rendering only a segment's mixed audio
+fn audio_segment_args(
+    clips: &[SegmentClip],
+    container: AudioContainer,
+    output_path: &str,
+) -> Result<Vec<String>, String> {
+    let (mut args, _, out_audio) = segment_graph(clips, &SegmentFormat::default(), false)?;
+    args.extend(vec!["-map".to_string(), format!("[{}]", out_audio)]);
+    args.extend(container.codec_args());
+    args.extend(vec!["-y".to_string(), output_path.to_string()]);
+    Ok(args)
+}
+
+/// Concatenate the pads in `run` into one video/audio pair, leaving `run` empty. A single
+/// pair is passed through as-is.
+fn join_run(
+    filter_parts: &mut Vec<String>,
+    run: &mut Vec<(String, String)>,
+    joins: &mut usize,
+    video: bool,
+) -> (String, String) {
+    if run.len() == 1 {
+        return run.remove(0);
+    }
+    if video {
+        // concat takes each piece's video and audio pads in turn
+        let inputs: String = run.iter().map(|(video, audio)| format!("[{}][{}]", video, audio)).collect();
+        filter_parts.push(format!(
+            "{}concat=n={}:v=1:a=1[cv{}][ca{}]",
+            inputs,
+            run.len(),
+            joins,
+            joins
+        ));
+    } else {
+        let inputs: String = run.iter().map(|(_, audio)| format!("[{}]", audio)).collect();
+        filter_parts.push(format!("{}concat=n={}:v=0:a=1[ca{}]", inputs, run.len(), joins));
+    }
+    run.clear();
+    let joined = (format!("cv{}", joins), format!("ca{}", joins));
+    *joins += 1;
+    joined
+}
+
+/// Preview encodes favour speed over fidelity: the GPU encoder when one works (VideoToolbox,
+/// NVENC, QSV), otherwise x264 ultrafast
+fn prerender_quality() -> RecordingQuality {
+    RecordingQuality {
+        hardware_encoding: get_settings().prerender.hardware_encoding,
+        ..RecordingQuality::default()
+    }
+}
+
+/// Evict least recently used segments past the configured cache size
+fn enforce_cache_limit() {
+    match evict_to_limit(get_settings().prerender.max_cache_bytes) {
+        Ok((0, _)) => {}
+        Ok((count, bytes)) => println!("[Prerender] Evicted {} segments ({} bytes)", count, bytes),
+        Err(e) => eprintln!("[Prerender] Cache eviction failed: {}", e),
     }
-    
-    Ok(output_path.to_string())
 }
 
 /// Get the cache directory for prerendered segments
 #[command]
 pub fn get_prerender_cache_dir() -> Result<String, String> {
-    let cache_dir = std::env::temp_dir()
-        .join("zapcut")
-        .join("prerender_cache");
-    
-    std::fs::create_dir_all(&cache_dir)
-        .map_err(|e| format!("Failed to create cache dir: {}", e))?;
-    
-    Ok(cache_dir.to_string_lossy().to_string())
+    prerender_cache_dir()
+        .map(|dir| dir.to_string_lossy().to_string())
+        .map_err(|e| format!("Failed to create cache dir: {}", e))
+}
+
+/// Segment count and size of the prerender cache, with its limit
+#[command]
+pub fn get_prerender_cache_stats() -> Result<PrerenderCacheStats, String> {
+    cache_stats(get_settings().prerender.max_cache_bytes)
+        .map_err(|e| format!("Failed to read prerender cache: {}", e))
+}
+
+/// Set how many segments the background queue renders at once (1-8)
+#[command]
+pub fn set_prerender_concurrency(
+    queue: State<'_, PrerenderQueue>,
+    max_concurrent: usize,
+) -> Result<(), String> {
+    if !(1..=MAX_PRERENDER_WORKERS).contains(&max_concurrent) {
+        return Err(format!(
+            "Concurrent prerenders must be between 1 and {}",
+            MAX_PRERENDER_WORKERS
+        ));
+    }
+    update_settings(|s| s.prerender.max_concurrent = max_concurrent)
+        .map_err(|e| format!("Failed to save prerender settings: {}", e))?;
+    queue.wake.notify_all();
+    Ok(())
+}
+
+/// Change the prerender cache size limit, evicting immediately if the cache is over it
+#[command]
+pub fn set_prerender_cache_limit(max_bytes: u64) -> Result<PrerenderCacheStats, String> {
+    if max_bytes < 100 * 1024 * 1024 {
+        return Err("Prerender cache limit must be at least 100 MB".to_string());
+    }
+    update_settings(|s| s.prerender.max_cache_bytes = max_bytes)
+        .map_err(|e| format!("Failed to save prerender settings: {}", e))?;
+    enforce_cache_limit();
+    get_prerender_cache_stats()
 }
 
 /// Clear prerender cache
 #[command]
 pub fn clear_prerender_cache() -> Result<(), String> {
-    let cache_dir = std::env::temp_dir()
-        .join("zapcut")
-        .join("prerender_cache");
-    
-    if cache_dir.exists() {
-        std::fs::remove_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to clear cache: {}", e))?;
-        
-        // Recreate the directory
-        std::fs::create_dir_all(&cache_dir)
-            .map_err(|e| format!("Failed to recreate cache dir: {}", e))?;
+    clear_cache().map_err(|e| format!("Failed to clear cache: {}", e))
+}
+
+/// Worker threads of the background queue, the ceiling for the max_concurrent setting
+const MAX_PRERENDER_WORKERS: usize = 8;
+
+/// How many segments may render right now. Other background jobs (exports, transcodes, ...)
+/// compete for the same CPU, so while any is running the queue drops to one render.
+fn prerender_concurrency(app: &AppHandle) -> usize {
+    let busy = app
+        .state::<JobManager>()
+        .list()
+        .iter()
+        .any(|job| job.status == JobStatus::Running);
+    if busy {
+        1
+    } else {
+        get_settings().prerender.max_concurrent.clamp(1, MAX_PRERENDER_WORKERS)
     }
-    
-    Ok(())
 }
 
+/// What a queued render is for when the segment asked for a quality tier
+#[derive(Clone, PartialEq)]
+enum RenderStage {
+    /// The only render the segment needs
+    Final,
+    /// A draft standing in until the requested quality is rendered
+    Draft,
+    /// The requested quality, replacing the draft cached under `draft_key`. Only starts when
+    /// nothing else is queued.
+    Upgrade { draft_key: String },
+}
+
+struct QueuedPrerender {
+    segment_id: String,
+    key: String,
+    clips: Vec<SegmentClip>,
+    format: SegmentFormat,
+    quality: Option<PrerenderQuality>,
+    container: SegmentContainer,
+    stage: RenderStage,
+    priority: i32,
+    /// Enqueue order, so equal priorities render first come first served
+    sequence: u64,
+}
+
+struct RunningPrerender {
+    segment_id: String,
+    key: String,
+    quality: Option<PrerenderQuality>,
+    priority: i32,
+    cancel: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct QueueState {
+    pending: Vec<QueuedPrerender>,
+    running: Vec<RunningPrerender>,
+    next_sequence: u64,
+    /// The latest request for every segment id, to requeue after invalidation
+    requested: HashMap<String, SegmentRequest>,
+}
+
+#[derive(Clone)]
+struct SegmentRequest {
+    clips: Vec<SegmentClip>,
+    format: SegmentFormat,
+    quality: Option<PrerenderQuality>,
+    container: SegmentContainer,
+    priority: i32,
+}
+
+impl SegmentRequest {
+    fn format_at(&self, quality: Option<PrerenderQuality>) -> SegmentFormat {
+        match quality {
+            Some(quality) => self.format.at_quality(quality),
+            None => self.format.clone(),
+        }
+    }
+
+    fn key_at(&self, quality: Option<PrerenderQuality>) -> Result<String, String> {
+        container_cache_key(&self.clips, &self.format_at(quality), self.container)
+    }
+}
+
+fn is_cached(key: &str) -> bool {
+    segment_path(key, "mp4").map(|path| path.exists()).unwrap_or(false)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrerenderQueueItem {
+    pub segment_id: String,
+    pub priority: i32,
+    pub quality: Option<PrerenderQuality>,
+    /// "queued" or "running"
+    pub status: String,
+}
+
+/// Segments waiting to be prerendered in the background. Higher priorities render first, so
+/// the frontend can rank segments by distance from the playhead and drop stale ones when the
+/// timeline changes.
+pub struct PrerenderQueue {
+    state: Mutex<QueueState>,
+    wake: Condvar,
+}
+
+impl PrerenderQueue {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(QueueState::default()),
+            wake: Condvar::new(),
+        }
+    }
+
+    /// Queue a segment, replacing a queued request with the same id and cancelling a running
+    /// render of an outdated version. Returns false when it is already cached or rendering.
+    ///
+    /// A segment asking for more than draft quality is rendered as a draft first when nothing
+    /// is cached for it yet; the worker queues the upgrade once the draft is done.
+    fn push(&self, segment_id: String, request: SegmentRequest) -> Result<bool, String> {
+        let format = request.format_at(request.quality);
+        let key = request.key_at(request.quality)?;
+        let cached = is_cached(&key);
+        let draft = match request.quality {
+            Some(quality) if quality > PrerenderQuality::Draft && !cached => {
+                let draft_format = request.format_at(Some(PrerenderQuality::Draft));
+                let draft_key = request.key_at(Some(PrerenderQuality::Draft))?;
+                Some((draft_key, draft_format))
+            }
+            _ => None,
+        };
+
+        let mut state = self.state.lock().unwrap();
+        state.requested.insert(segment_id.clone(), request.clone());
+        state.pending.retain(|queued| queued.segment_id != segment_id);
+        let mut already_rendering = false;
+        // Renders already being cancelled don't count
+        let current = state.running.iter().filter(|running| {
+            running.segment_id == segment_id && !running.cancel.load(Ordering::SeqCst)
+        });
+        for running in current {
+            let is_draft = draft.as_ref().is_some_and(|(draft_key, _)| running.key == *draft_key);
+            if running.key == key || is_draft {
+                already_rendering = true;
+            } else {
+                running.cancel.store(true, Ordering::SeqCst);
+            }
+        }
+        if cached || already_rendering {
+            return Ok(false);
+        }
+
+        let (key, format, quality, stage) = match draft {
+            Some((draft_key, draft_format)) if !is_cached(&draft_key) => (
+                draft_key,
+                draft_format,
+                Some(PrerenderQuality::Draft),
+                RenderStage::Draft,
+            ),
+            Some((draft_key, _)) => (key, format, request.quality, RenderStage::Upgrade { draft_key }),
+            None => (key, format, request.quality, RenderStage::Final),
+        };
+        let sequence = state.next_sequence;
+        state.next_sequence += 1;
+        state.pending.push(QueuedPrerender {
+            segment_id,
+            key,
+            clips: request.clips,
+            format,
+            quality,
+            container: request.container,
+            stage,
+            priority: request.priority,
+            sequence,
+        });
+        self.wake.notify_all();
+        Ok(true)
+    }
+
+    /// Block until a segment can start within `limit` concurrent renders, then move it to
+    /// running. The limit is re-checked every second since jobs elsewhere can change it.
+    /// Upgrades only start once no other segment is waiting.
+    fn next(&self, limit: impl Fn() -> usize) -> (QueuedPrerender, Arc<AtomicBool>) {
+        loop {
+            // Evaluated before locking: it reads settings and the job list, which must not be
+            // waited on while every other queue call is blocked
+            let limit = limit();
+            let mut state = self.state.lock().unwrap();
+            if state.running.len() < limit {
+                let best = state
+                    .pending
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, request)| {
+                        let idle_only = matches!(request.stage, RenderStage::Upgrade { .. });
+                        (!idle_only, request.priority, Reverse(request.sequence))
+                    })
+                    .map(|(index, _)| index);
+                if let Some(index) = best {
+                    let request = state.pending.remove(index);
+                    let cancel = Arc::new(AtomicBool::new(false));
+                    state.running.push(RunningPrerender {
+                        segment_id: request.segment_id.clone(),
+                        key: request.key.clone(),
+                        quality: request.quality,
+                        priority: request.priority,
+                        cancel: cancel.clone(),
+                    });
+                    return (request, cancel);
+                }
+            }
+            // Woken by queue changes and concurrency settings; the timeout picks up jobs
+            // starting or finishing
+            let _ = self.wake.wait_timeout(state, Duration::from_secs(1)).unwrap();
+        }
+    }
+
+    fn finish(&self, cancel: &Arc<AtomicBool>) {
+        self.state
+            .lock()
+            .unwrap()
+            .running
+            .retain(|running| !Arc::ptr_eq(&running.cancel, cancel));
+        self.wake.notify_all();
+    }
+
+    /// Queue the requested quality of a segment whose draft just finished
+    fn queue_upgrade(&self, segment_id: &str) {
+        let request = self.state.lock().unwrap().requested.get(segment_id).cloned();
+        if let Some(request) = request {
+            if let Err(e) = self.push(segment_id.to_string(), request) {
+                eprintln!("[Prerender] Couldn't queue upgrade of segment {}: {}", segment_id, e);
+            }
+        }
+    }
+}
+
+/// Cached file of the latest request for `segment_id`: its requested quality, or the draft
+/// standing in for it
+pub(crate) fn cached_segment_file(queue: &PrerenderQueue, segment_id: &str) -> Option<PathBuf> {
+    let request = queue.state.lock().unwrap().requested.get(segment_id).cloned()?;
+    let mut qualities = vec![request.quality];
+    if request.quality.is_some() {
+        qualities.push(Some(PrerenderQuality::Draft));
+    }
+    qualities.into_iter().find_map(|quality| {
+        let key = request.key_at(quality).ok()?;
+        segment_path(&key, "mp4").ok().filter(|path| path.exists())
+    })
+}
+
+/// Background workers for the prerender queue; run for the life of the app
+pub fn run_prerender_queue(app: AppHandle) {
+    for _ in 1..MAX_PRERENDER_WORKERS {
+        let worker_app = app.clone();
+        std::thread::spawn(move || prerender_worker(worker_app));
+    }
+    prerender_worker(app);
+}
+
+fn prerender_worker(app: AppHandle) {
+    let queue = app.state::<PrerenderQueue>();
+    loop {
+        let (request, cancel) = queue.next(|| prerender_concurrency(&app));
+        let segment_id = &request.segment_id;
+        let result = render_cached(
+            &request.key,
+            &request.clips,
+            &request.format,
+            request.container,
+            Some(&cancel),
+            |percentage| emit_progress(&app, segment_id, percentage),
+        );
+        queue.finish(&cancel);
+
+        let cancelled = result.is_err() && cancel.load(Ordering::SeqCst);
+        match &result {
+            Ok(path) => println!("[Prerender] Segment {} ready: {:?}", segment_id, path),
+            Err(_) if cancelled => println!("[Prerender] Segment {} cancelled", segment_id),
+            Err(e) => eprintln!("[Prerender] Segment {} failed: {}", segment_id, e),
+        }
+        emit_done(&app, segment_id, &result, cancelled);
+
+        let Ok(path) = &result else {
+            continue;
+        };
+        match &request.stage {
+            RenderStage::Final => {}
+            RenderStage::Draft => queue.queue_upgrade(segment_id),
+            RenderStage::Upgrade { draft_key } => {
+                // The draft has been superseded; drop it rather than waiting for eviction
+                if let Err(e) = remove_segment(draft_key) {
+                    eprintln!("[Prerender] Failed to remove draft of {}: {}", segment_id, e);
+                }
+                emit_upgraded(&app, segment_id, request.quality, path);
+            }
+        }
+    }
+}
+
+fn emit_upgraded(app: &AppHandle, segment_id: &str, quality: Option<PrerenderQuality>, path: &Path) {
+    let Some(quality) = quality else {
+        return;
+    };
+    let upgraded = PrerenderUpgraded {
+        segment_id: segment_id.to_string(),
+        quality,
+        output_path: path.to_string_lossy().to_string(),
+    };
+    if let Err(e) = app.emit("prerender://upgraded", upgraded) {
+        eprintln!("[Prerender] Failed to emit upgrade: {}", e);
+    }
+}
+
+/// Queue a segment for background prerendering. A segment already queued under the same id is
+/// replaced, and a running render of an outdated version of it is cancelled. Returns false when
+/// the segment is already cached or rendering, so there is nothing to do.
+///
+/// With a `quality` above draft, a draft is rendered first and upgraded when the queue is
+/// otherwise idle; `prerender://upgraded` is emitted once the better file replaces it. The
+/// `container` is honored as in prerender_segment.
+#[command]
+pub fn enqueue_prerender(
+    queue: State<'_, PrerenderQueue>,
+    segment_id: String,
+    clips: Vec<SegmentClip>,
+    format: Option<SegmentFormat>,
+    quality: Option<PrerenderQuality>,
+    container: Option<SegmentContainer>,
+    priority: Option<i32>,
+) -> Result<bool, String> {
+    if clips.is_empty() {
+        return Err("No clips to render".to_string());
+    }
+    let request = SegmentRequest {
+        clips,
+        format: format.unwrap_or_default(),
+        quality,
+        container: container.unwrap_or_default(),
+        priority: priority.unwrap_or(0),
+    };
+    queue.push(segment_id, request)
+}
+
+/// Delete cached segments containing any of `clip_ids` (e.g. after their source media was
+/// replaced or relinked) and queue those segments again from their latest request. Segments
+/// whose clip list itself changed should simply be enqueued again with the new clips.
+/// Returns the ids of the segments requeued.
+#[command]
+pub fn invalidate_prerender_for_clips(
+    queue: State<'_, PrerenderQueue>,
+    clip_ids: Vec<String>,
+) -> Result<Vec<String>, String> {
+    let removed = remove_segments_for_clips(&clip_ids)
+        .map_err(|e| format!("Failed to invalidate prerender cache: {}", e))?;
+    println!("[Prerender] Invalidated {} cached segments for {} clips", removed, clip_ids.len());
+
+    let uses_clips = |request: &SegmentRequest| {
+        request
+            .clips
+            .iter()
+            .any(|clip| clip.clip_id.as_ref().is_some_and(|id| clip_ids.contains(id)))
+    };
+    let affected: Vec<(String, SegmentRequest)> = {
+        let state = queue.state.lock().unwrap();
+        // A render in progress would only store the stale version again
+        for running in &state.running {
+            if state.requested.get(&running.segment_id).is_some_and(uses_clips) {
+                running.cancel.store(true, Ordering::SeqCst);
+            }
+        }
+        state
+            .requested
+            .iter()
+            .filter(|(_, request)| uses_clips(request))
+            .map(|(segment_id, request)| (segment_id.clone(), request.clone()))
+            .collect()
+    };
+
+    let mut requeued = Vec::new();
+    for (segment_id, request) in affected {
+        match queue.push(segment_id.clone(), request) {
+            Ok(_) => requeued.push(segment_id),
+            // A clip's source may be gone; the frontend will enqueue a fixed segment
+            Err(e) => eprintln!("[Prerender] Couldn't requeue segment {}: {}", segment_id, e),
+        }
+    }
+    Ok(requeued)
+}
+
+/// Drop queued segments and cancel running ones. Every segment when `segment_ids` is None.
+/// Returns how many were removed or cancelled.
+#[command]
+pub fn cancel_prerender(
+    queue: State<'_, PrerenderQueue>,
+    segment_ids: Option<Vec<String>>,
+) -> usize {
+    let matches = |id: &String| segment_ids.as_ref().map(|ids| ids.contains(id)).unwrap_or(true);
+
+    let mut state = queue.state.lock().unwrap();
+    let before = state.pending.len();
+    state.pending.retain(|request| !matches(&request.segment_id));
+    let mut cancelled = before - state.pending.len();
+
+    for running in state.running.iter().filter(|running| matches(&running.segment_id)) {
+        running.cancel.store(true, Ordering::SeqCst);
+        cancelled += 1;
+    }
+    cancelled
+}
+
+/// Change the priority of queued segments, e.g. as the playhead moves. Returns how many were
+/// found in the queue.
+#[command]
+pub fn reprioritize_prerender(
+    queue: State<'_, PrerenderQueue>,
+    priorities: HashMap<String, i32>,
+) -> usize {
+    let mut state = queue.state.lock().unwrap();
+    let mut updated = 0;
+    for request in state.pending.iter_mut() {
+        if let Some(priority) = priorities.get(&request.segment_id) {
+            request.priority = *priority;
+            updated += 1;
+        }
+    }
+    for running in state.running.iter_mut() {
+        if let Some(priority) = priorities.get(&running.segment_id) {
+            running.priority = *priority;
+        }
+    }
+    updated
+}
+
+/// Running segments followed by queued ones in the order they will render
+#[command]
+pub fn get_prerender_queue(queue: State<'_, PrerenderQueue>) -> Vec<PrerenderQueueItem> {
+    let state = queue.state.lock().unwrap();
+    let mut pending: Vec<&QueuedPrerender> = state.pending.iter().collect();
+    pending.sort_by_key(|request| (Reverse(request.priority), request.sequence));
+
+    state
+        .running
+        .iter()
+        .map(|running| PrerenderQueueItem {
+            segment_id: running.segment_id.clone(),
+            priority: running.priority,
+            quality: running.quality,
+            status: "running".to_string(),
+        })
+        .chain(pending.into_iter().map(|request| PrerenderQueueItem {
+            segment_id: request.segment_id.clone(),
+            priority: request.priority,
+            quality: request.quality,
+            status: "queued".to_string(),
+        }))
+        .collect()
+}
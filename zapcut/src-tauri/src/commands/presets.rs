@@ -0,0 +1,162 @@
+use crate::commands::recording::{RecordingManager, RecordingSettings, RecordingState};
+use crate::utils::app_init::initialize_app_directories;
+use crate::utils::capture::RecordingQuality;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::State;
+
+/// Named recording settings: devices, capture, quality and processing in one bundle
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingPreset {
+    pub name: String,
+    pub settings: RecordingSettings,
+    /// Shipped with the app; can't be overwritten or deleted
+    #[serde(default)]
+    pub builtin: bool,
+    pub updated_at: String,
+}
+
+/// User presets, persisted as recording_presets.json in the Zapcut directory
+pub struct RecordingPresets {
+    presets: Mutex<Vec<RecordingPreset>>,
+}
+
+impl RecordingPresets {
+    pub fn new() -> Self {
+        let presets = Self::presets_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            presets: Mutex::new(presets),
+        }
+    }
+
+    fn presets_path() -> anyhow::Result<PathBuf> {
+        Ok(initialize_app_directories()?.join("recording_presets.json"))
+    }
+
+    fn save(presets: &[RecordingPreset]) -> Result<(), String> {
+        let path = Self::presets_path()
+            .map_err(|e| format!("Failed to locate presets file: {}", e))?;
+        let json = serde_json::to_string_pretty(presets)
+            .map_err(|e| format!("Failed to serialize presets: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write presets: {}", e))
+    }
+
+    fn find(&self, name: &str) -> Option<RecordingPreset> {
+        builtin_presets()
+            .into_iter()
+            .chain(self.presets.lock().unwrap().iter().cloned())
+            .find(|preset| preset.name == name)
+    }
+}
+
+fn builtin_presets() -> Vec<RecordingPreset> {
+    let preset = |name: &str, fps: u32, max_height: u32, screen: bool, microphone: bool| {
+        RecordingPreset {
+            name: name.to_string(),
+            settings: RecordingSettings {
+                fps: Some(fps),
+                quality: RecordingQuality {
+                    max_height: Some(max_height),
+                    ..RecordingQuality::default()
+                },
+                screen_recording_enabled: screen,
+                webcam_enabled: !screen,
+                microphone_enabled: microphone,
+                ..RecordingSettings::default()
+            },
+            builtin: true,
+            updated_at: String::new(),
+        }
+    };
+    vec![
+        preset("1080p60 screen + mic", 60, 1080, true, true),
+        preset("720p30 webcam only", 30, 720, false, false),
+    ]
+}
+
+/// Built-in presets followed by the user's, in the order they were saved
+#[tauri::command]
+pub fn list_recording_presets(presets: State<'_, RecordingPresets>) -> Vec<RecordingPreset> {
+    let mut all = builtin_presets();
+    all.extend(presets.presets.lock().unwrap().iter().cloned());
+    all
+}
+
+/// Save `settings` (the current recording settings when None) under `name`, replacing any user
+/// preset with that name
+#[tauri::command]
+pub async fn save_recording_preset(
+    presets: State<'_, RecordingPresets>,
+    manager: State<'_, RecordingManager>,
+    name: String,
+    settings: Option<RecordingSettings>,
+) -> Result<RecordingPreset, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Preset name cannot be empty".to_string());
+    }
+    if builtin_presets().iter().any(|preset| preset.name == name) {
+        return Err(format!("\"{}\" is a built-in preset", name));
+    }
+    let settings = match settings {
+        Some(settings) => settings,
+        None => manager.state.lock().await.current_settings.clone(),
+    };
+
+    let preset = RecordingPreset {
+        name,
+        settings,
+        builtin: false,
+        updated_at: Utc::now().to_rfc3339(),
+    };
+
+    let mut saved = presets.presets.lock().unwrap();
+    match saved.iter_mut().find(|existing| existing.name == preset.name) {
+        Some(existing) => *existing = preset.clone(),
+        None => saved.push(preset.clone()),
+    }
+    RecordingPresets::save(&saved)?;
+    Ok(preset)
+}
+
+/// Remove a user preset; returns false if there was none with that name
+#[tauri::command]
+pub fn delete_recording_preset(presets: State<'_, RecordingPresets>, name: String) -> Result<bool, String> {
+    if builtin_presets().iter().any(|preset| preset.name == name) {
+        return Err(format!("\"{}\" is a built-in preset", name));
+    }
+    let mut saved = presets.presets.lock().unwrap();
+    let before = saved.len();
+    saved.retain(|preset| preset.name != name);
+    if saved.len() == before {
+        return Ok(false);
+    }
+    RecordingPresets::save(&saved)?;
+    Ok(true)
+}
+
+/// Make a preset's settings the current recording settings
+#[tauri::command]
+pub async fn apply_recording_preset(
+    presets: State<'_, RecordingPresets>,
+    manager: State<'_, RecordingManager>,
+    name: String,
+) -> Result<RecordingState, String> {
+    let preset = presets
+        .find(&name)
+        .ok_or_else(|| format!("Unknown recording preset: {}", name))?;
+
+    let mut state = manager.state.lock().await;
+    if state.is_recording {
+        return Err("Can't change settings while recording".to_string());
+    }
+    state.current_settings = preset.settings;
+    println!("[Recording] Applied preset \"{}\"", name);
+    Ok(state.clone())
+}
@@ -0,0 +1,401 @@
+use crate::commands::prerender::{cached_segment_file, PrerenderQueue};
+use crate::commands::stream::hls_url;
+use crate::utils::ffmpeg::{decode_frame, decode_frame_rgb, remux_to_hls, video_frame_times};
+use crate::utils::prerender_cache::hls_dir;
+use crate::utils::scopes::{compute_scopes, FrameScopes};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tauri::{command, State};
+
+/// Image encoding for decoded frames
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameFormat {
+    Png,
+    #[default]
+    Jpeg,
+}
+
+/// Decoded frames kept in memory, enough to step back and forth around the playhead
+const FRAME_CACHE_CAPACITY: usize = 48;
+
+lazy_static::lazy_static! {
+    /// Most recently used last
+    static ref FRAME_CACHE: Mutex<VecDeque<(String, Arc<Vec<u8>>)>> = Mutex::new(VecDeque::new());
+}
+
+fn cached_frame(key: &str) -> Option<Arc<Vec<u8>>> {
+    let mut cache = FRAME_CACHE.lock().unwrap();
+    let index = cache.iter().position(|(cached, _)| cached == key)?;
+    let entry = cache.remove(index)?;
+    let frame = entry.1.clone();
+    cache.push_back(entry);
+    Some(frame)
+}
+
+fn store_frame(key: String, frame: Arc<Vec<u8>>) {
+    let mut cache = FRAME_CACHE.lock().unwrap();
+    cache.retain(|(cached, _)| *cached != key);
+    cache.push_back((key, frame));
+    while cache.len() > FRAME_CACHE_CAPACITY {
+        cache.pop_front();
+    }
+}
+
+/// A media file, or the cached render of a prerender segment id
+pub(crate) fn resolve_frame_source(queue: &PrerenderQueue, source: &str) -> Result<PathBuf, String> {
+    if Path::new(source).exists() {
+        return Ok(PathBuf::from(source));
+    }
+    cached_segment_file(queue, source)
+        .ok_or_else(|| format!("No file or prerendered segment found for: {}", source))
+}
+
+/// Cache key for a frame. The modification time makes a replaced file decode again.
+fn frame_key(path: &Path, time: f64, width: Option<u32>, format: FrameFormat) -> String {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|time| time.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since| since.as_nanos())
+        .unwrap_or(0);
+    format!("{}|{}|{:.6}|{:?}|{:?}", path.display(), modified, time, width, format)
+}
+
+/// Encoded frame at `time`, from the in-memory cache when it was decoded recently
+async fn frame_image(
+    path: PathBuf,
+    time: f64,
+    width: Option<u32>,
+    format: FrameFormat,
+) -> Result<Vec<u8>, String> {
+    let key = frame_key(&path, time, width, format);
+    if let Some(frame) = cached_frame(&key) {
+        return Ok(frame.as_ref().clone());
+    }
+
+    let frame = tauri::async_runtime::spawn_blocking(move || {
+        decode_frame(&path.to_string_lossy(), time, None, width, format == FrameFormat::Png)
+    })
+    .await
+    .map_err(|e| format!("Frame decoding task failed: {}", e))?
+    .map_err(|e| format!("Failed to decode frame: {}", e))?;
+
+    let frame = Arc::new(frame);
+    store_frame(key, frame.clone());
+    Ok(frame.as_ref().clone())
+}
+
+/// Decode the exact frame at `time` seconds of a media file or prerendered segment (`source` is
+/// either a path or a segment id) and return the encoded image bytes. Recently decoded frames
+/// are kept in memory so stepping back and forth doesn't decode them again.
+#[command]
+pub async fn get_frame_at(
+    queue: State<'_, PrerenderQueue>,
+    source: String,
+    time: f64,
+    width: Option<u32>,
+    format: Option<FrameFormat>,
+) -> Result<Vec<u8>, String> {
+    let path = resolve_frame_source(&queue, &source)?;
+    frame_image(path, time, width, format.unwrap_or_default()).await
+}
+
+/// Per-frame video filters the effect controls produce, and so the only ones a filter preview
+/// may use. None of them read files or other inputs.
+const PREVIEW_FILTERS: &[&str] = &[
+    "eq", "hue", "colorbalance", "colortemperature", "colorchannelmixer", "vibrance",
+    "negate", "crop", "scale", "pad", "setsar", "format", "hflip", "vflip", "transpose",
+    "rotate", "boxblur", "gblur", "unsharp", "vignette", "fade",
+];
+
+/// Option keys that name a file to read. None of PREVIEW_FILTERS take one, but they are refused
+/// by name too in case a newer FFmpeg adds one.
+const FILE_OPTION_KEYS: &[&str] = &["textfile", "filename", "file", "fontfile"];
+
+/// Split a filter chain at its commas. None when the description is a graph rather than one
+/// linear chain (pad labels or `;`), or when it quotes or escapes anything: FFmpeg's quoting
+/// rules differ per level, and the effect controls never need either.
+fn split_filter_chain(chain: &str) -> Option<Vec<&str>> {
+    if chain.contains(['\\', '\'', ';', '[', ']']) {
+        return None;
+    }
+    Some(chain.split(',').collect())
+}
+
+/// Reject a filter chain unless it is one linear chain of PREVIEW_FILTERS with no file options
+fn check_preview_filters(chain: &str) -> Result<(), String> {
+    let filters = split_filter_chain(chain)
+        .ok_or("Filter chain may only contain per-frame video filters")?;
+    for filter in filters {
+        let (name, options) = filter.split_once('=').unwrap_or((filter, ""));
+        let name = name.trim();
+        if !PREVIEW_FILTERS.contains(&name) {
+            return Err(format!("Filter '{}' can't be previewed on a frame", name));
+        }
+        for option in options.split(':') {
+            let key = option.split('=').next().unwrap_or_default().trim();
+            if FILE_OPTION_KEYS.contains(&key) {
+                return Err(format!("Filter option '{}' can't be previewed on a frame", key));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Apply a clip's filter chain (an FFmpeg -vf description such as
+/// "eq=brightness=0.1:saturation=1.3,hue=h=15") to the frame at `time` and return the processed
+/// image, so effect parameters can be previewed without rendering a segment. The result is
+/// scaled to `width` after filtering. Filtered frames aren't cached since parameters change on
+/// every call.
+#[command]
+pub async fn preview_filter_on_frame(
+    queue: State<'_, PrerenderQueue>,
+    path: String,
+    time: f64,
+    filter_description: String,
+    width: Option<u32>,
+    format: Option<FrameFormat>,
+) -> Result<Vec<u8>, String> {
+    let path = resolve_frame_source(&queue, &path)?;
+    let filter = filter_description.trim().to_string();
+    // Source filters could read arbitrary files; a frame preview only needs per-frame filters
+    if !filter.is_empty() {
+        check_preview_filters(&filter)?;
+    }
+    let png = format.unwrap_or_default() == FrameFormat::Png;
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let filter = Some(filter.as_str()).filter(|filter| !filter.is_empty());
+        decode_frame(&path.to_string_lossy(), time, filter, width, png)
+    })
+    .await
+    .map_err(|e| format!("Filter preview task failed: {}", e))?
+    .map_err(|e| format!("Failed to apply filter: {}", e))
+}
+
+/// Width frames are scaled to for scopes; plenty of resolution for the plots and quick to
+/// decode while scrubbing
+const SCOPE_WIDTH: u32 = 320;
+
+/// Decode the frame at `time` of a media file or prerendered segment and compute its luma
+/// waveform, RGB histogram and vectorscope
+#[command]
+pub async fn get_frame_scopes(
+    queue: State<'_, PrerenderQueue>,
+    source: String,
+    time: f64,
+    width: Option<u32>,
+) -> Result<FrameScopes, String> {
+    let path = resolve_frame_source(&queue, &source)?;
+    let width = width.unwrap_or(SCOPE_WIDTH).clamp(16, 1920);
+    tauri::async_runtime::spawn_blocking(move || {
+        let (width, height, pixels) = decode_frame_rgb(&path.to_string_lossy(), time, width)
+            .map_err(|e| format!("Failed to decode frame: {}", e))?;
+        Ok(compute_scopes(width, height, &pixels))
+    })
+    .await
+    .map_err(|e| format!("Scopes task failed: {}", e))?
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum StepDirection {
+    Next,
+    Previous,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct FrameStep {
+    /// Presentation time of the frame stepped to, in seconds
+    pub time: f64,
+    pub image: Vec<u8>,
+}
+
+/// How far around the current time to look for the neighbouring frame; the wider window covers
+/// frames held for a long time in variable frame rate recordings
+const STEP_WINDOWS: [f64; 2] = [2.0, 10.0];
+
+/// Timestamps within this of the current time count as the current frame
+const STEP_EPSILON: f64 = 0.0005;
+
+/// Step one frame forwards or backwards from `current_pts` using the source's real frame
+/// timestamps, so stepping stays frame accurate on variable frame rate sources. Returns the time
+/// of the frame stepped to along with its image.
+#[command]
+pub async fn step_frame(
+    queue: State<'_, PrerenderQueue>,
+    path: String,
+    current_pts: f64,
+    direction: StepDirection,
+    width: Option<u32>,
+    format: Option<FrameFormat>,
+) -> Result<FrameStep, String> {
+    let path = resolve_frame_source(&queue, &path)?;
+    let probe_path = path.to_string_lossy().to_string();
+    let time = tauri::async_runtime::spawn_blocking(move || {
+        for window in STEP_WINDOWS {
+            let (start, end) = match direction {
+                StepDirection::Next => (current_pts, current_pts + window),
+                StepDirection::Previous => ((current_pts - window).max(0.0), current_pts),
+            };
+            let times = video_frame_times(&probe_path, start, end)
+                .map_err(|e| format!("Failed to read frame timestamps: {}", e))?;
+            let found = match direction {
+                StepDirection::Next => times.into_iter().find(|t| *t > current_pts + STEP_EPSILON),
+                StepDirection::Previous => {
+                    times.into_iter().rev().find(|t| *t < current_pts - STEP_EPSILON)
+                }
+            };
+            if let Some(time) = found {
+                return Ok(time.max(0.0));
+            }
+        }
+        Err(match direction {
+            StepDirection::Next => "Already at the last frame".to_string(),
+            StepDirection::Previous => "Already at the first frame".to_string(),
+        })
+    })
+    .await
+    .map_err(|e| format!("Frame stepping task failed: {}", e))??;
+
+    // Seek just before the timestamp so rounding can't skip past the frame
+    let seek = (time - STEP_EPSILON).max(0.0);
+    let image = frame_image(path, seek, width, format.unwrap_or_default()).await?;
+    Ok(FrameStep { time, image })
+}
+
+/// Chunk length prerendered segments are re-segmented to for HLS playback
+const HLS_CHUNK_DURATION: f64 = 4.0;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PreviewPlaylist {
+    /// stream:// URL of the m3u8, for a <video> element
+    pub url: String,
+    pub path: String,
+    /// Seconds of timeline covered
+    pub duration: f64,
+    /// How many of the requested segments are in the playlist
+    pub segment_count: usize,
+    /// False when a segment wasn't prerendered yet, in which case the playlist stops before it
+    /// and can be rebuilt once the segment is ready
+    pub complete: bool,
+}
+
+struct HlsChunk {
+    duration: f64,
+    path: PathBuf,
+}
+
+/// HLS chunks of a cached segment, remuxing it the first time it is asked for
+fn segment_chunks(segment: &Path, chunk_duration: f64) -> Result<Vec<HlsChunk>, String> {
+    let key = segment
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(|| format!("Invalid segment path: {}", segment.display()))?;
+    let hls_root = hls_dir().map_err(|e| format!("Failed to get HLS directory: {}", e))?;
+    let dir = hls_root.join(key);
+
+    if !dir.join("index.m3u8").exists() {
+        // Remux beside the final directory so a half-written one is never used
+        let partial = hls_root.join(format!("{}.partial", key));
+        let _ = std::fs::remove_dir_all(&partial);
+        remux_to_hls(&segment.to_string_lossy(), &partial, chunk_duration)
+            .map_err(|e| format!("Failed to remux segment for HLS: {}", e))?;
+        if std::fs::rename(&partial, &dir).is_err() {
+            // Another request remuxed it first
+            let _ = std::fs::remove_dir_all(&partial);
+        }
+    }
+
+    let index = std::fs::read_to_string(dir.join("index.m3u8"))
+        .map_err(|e| format!("Failed to read segment playlist: {}", e))?;
+    let mut chunks = Vec::new();
+    let mut duration = None;
+    for line in index.lines().map(str::trim) {
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            duration = info.split(',').next().and_then(|value| value.parse::<f64>().ok());
+        } else if !line.is_empty() && !line.starts_with('#') {
+            chunks.push(HlsChunk {
+                duration: duration.take().unwrap_or(chunk_duration),
+                path: dir.join(line),
+            });
+        }
+    }
+    Ok(chunks)
+}
+
+/// Assemble the cached renders of `segment_ids`, in timeline order, into one HLS playlist so a
+/// <video> element can play the whole timeline with native buffering. Segments are re-segmented
+/// into short MPEG-TS chunks once and reused. The playlist stops at the first segment that
+/// isn't prerendered yet; build it again once more segments are ready.
+#[command]
+pub async fn build_preview_playlist(
+    queue: State<'_, PrerenderQueue>,
+    segment_ids: Vec<String>,
+    chunk_duration: Option<f64>,
+) -> Result<PreviewPlaylist, String> {
+    if segment_ids.is_empty() {
+        return Err("No segments to play".to_string());
+    }
+    let segments: Vec<PathBuf> = segment_ids
+        .iter()
+        .map_while(|segment_id| cached_segment_file(&queue, segment_id))
+        .collect();
+    if segments.is_empty() {
+        return Err(format!("Segment {} has not been prerendered yet", segment_ids[0]));
+    }
+    let complete = segments.len() == segment_ids.len();
+    let chunk_duration = chunk_duration.unwrap_or(HLS_CHUNK_DURATION).max(1.0);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        // Segment files are named by their cache key, so a digest of the list is stable too
+        let mut hasher = Sha256::new();
+        for segment in &segments {
+            hasher.update(segment.to_string_lossy().as_bytes());
+            hasher.update(b"\0");
+        }
+        let hls_root = hls_dir().map_err(|e| format!("Failed to get HLS directory: {}", e))?;
+        let playlist_name = format!("{:x}.m3u8", hasher.finalize());
+        let playlist_path = hls_root.join(&playlist_name);
+
+        let mut body = String::new();
+        let mut duration = 0.0;
+        let mut longest: f64 = 0.0;
+        for (index, segment) in segments.iter().enumerate() {
+            // Every segment restarts its timestamps
+            if index > 0 {
+                body.push_str("#EXT-X-DISCONTINUITY\n");
+            }
+            for chunk in segment_chunks(segment, chunk_duration)? {
+                let relative = chunk.path.strip_prefix(&hls_root).unwrap_or(&chunk.path);
+                body.push_str(&format!("#EXTINF:{:.6},\n{}\n", chunk.duration, hls_url(relative)));
+                duration += chunk.duration;
+                longest = longest.max(chunk.duration);
+            }
+        }
+
+        let playlist = format!(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:{}\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-PLAYLIST-TYPE:{}\n{}{}",
+            longest.ceil() as u64,
+            if complete { "VOD" } else { "EVENT" },
+            body,
+            if complete { "#EXT-X-ENDLIST\n" } else { "" }
+        );
+        std::fs::write(&playlist_path, playlist)
+            .map_err(|e| format!("Failed to write preview playlist: {}", e))?;
+
+        Ok(PreviewPlaylist {
+            url: hls_url(Path::new(&playlist_name)),
+            path: playlist_path.to_string_lossy().to_string(),
+            duration,
+            segment_count: segments.len(),
+            complete,
+        })
+    })
+    .await
+    .map_err(|e| format!("Playlist task failed: {}", e))?
+}
@@ -1,20 +1,204 @@
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::sync::Mutex;
-use std::process::{Command, Stdio};
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::{Duration, Instant};
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use anyhow::Result;
 use crate::utils::app_init::get_recordings_dir;
-use crate::utils::ffmpeg::get_ffmpeg_path;
+use crate::utils::ffmpeg::{
+    check_remux_compatibility, generate_thumbnail, get_ffmpeg_path, get_video_info, remux_copy_range,
+    run_ffmpeg_with_progress,
+};
+use crate::utils::disk::available_space;
+use crate::utils::jobs::JobManager;
+use crate::utils::permissions::{check_permission, request_permission, PermissionKind, PermissionState};
+use crate::utils::cache::{get_cache_dir, CacheKind};
+use crate::utils::settings::get_settings;
+use crate::utils::capture::{
+    audio_input_args, build_capture_args, build_replay_args, display_input, enumerate_displays, pip_filter, CaptureConfig, CaptureTarget,
+    DisplayBounds, DisplayInfo, HardwareEncoding, PipLayout, RecordingQuality, SystemAudioMode, REPLAY_SEGMENT_SECS,
+};
+use crate::utils::ffmpeg::concat_files_copy;
+use crate::commands::export::Clip;
+use crate::commands::library::LibraryManager;
+use crate::commands::media::{import_media_file, MediaItem};
+use crate::commands::analysis::{
+    analyze_dead_air, intersect_ranges, suggest_trim, AnalysisOptions, TimeRange,
+};
+use crate::utils::recording_metadata::{RecordingMarker, RecordingMetadata};
+use crate::utils::keystrokes::{keystroke_overlay_filters, KeystrokeLog, KeystrokeRecorder};
+use crate::utils::cursor::{cursor_effects_filter, CursorEffects, CursorRecorder, CursorTrack};
+use crate::utils::devices::{
+    list_capture_devices, list_system_audio_sources, CaptureDevice, DeviceKind,
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RecordingSettings {
+    /// Capture the screen; when false the webcam is recorded on its own
+    #[serde(default = "default_screen_recording_enabled")]
+    pub screen_recording_enabled: bool,
     pub microphone: Option<String>,
     pub microphone_enabled: bool,
     pub webcam_enabled: bool,
     pub webcam_device: Option<String>,
     pub output_path: Option<PathBuf>,
+    /// Display id from list_displays (a raw FFmpeg screen input also works); platform default when None
+    #[serde(default)]
+    pub display: Option<String>,
+    /// Whole display, a window or a region of it
+    #[serde(default)]
+    pub capture_target: CaptureTarget,
+    /// Capture frame rate for native recordings (default 30); also caps processed browser recordings
+    #[serde(default)]
+    pub fps: Option<u32>,
+    /// Resolution cap, CRF/bitrate and encoder for native capture and processing
+    #[serde(default)]
+    pub quality: RecordingQuality,
+    #[serde(default = "default_capture_cursor")]
+    pub capture_cursor: bool,
+    /// Capture desktop audio in native recordings
+    #[serde(default)]
+    pub system_audio_enabled: bool,
+    /// Loopback device id; the first of get_system_audio_sources when None
+    #[serde(default)]
+    pub system_audio_device: Option<String>,
+    #[serde(default)]
+    pub system_audio_mode: SystemAudioMode,
+    /// What process_recording does with the browser's WebM
+    #[serde(default)]
+    pub processing_mode: ProcessingMode,
+    /// Roll native recordings over to a new file after this many seconds
+    #[serde(default)]
+    pub max_segment_duration: Option<u64>,
+    /// Roll native recordings over to a new file once it reaches this many megabytes
+    #[serde(default)]
+    pub max_segment_size_mb: Option<u64>,
+    /// Join rolled-over segments into one file on stop; otherwise keep them as numbered parts
+    #[serde(default = "default_stitch_segments")]
+    pub stitch_segments: bool,
+    /// Emit `recording://low-disk-space` when free space on the recordings volume drops below
+    /// this many megabytes (0 disables)
+    #[serde(default = "default_low_space_warning_mb")]
+    pub low_space_warning_mb: u64,
+    /// Stop and finalize the recording when free space drops below this many megabytes
+    /// (0 disables)
+    #[serde(default = "default_min_free_space_mb")]
+    pub min_free_space_mb: u64,
+    /// Track cursor movement and clicks during native recordings so render_cursor_effects can
+    /// add highlights afterwards
+    #[serde(default = "default_record_cursor_events")]
+    pub record_cursor_events: bool,
+    /// Log key presses during browser recordings (skipping password fields) so
+    /// process_recording can show them on screen. The log is only kept in memory.
+    #[serde(default)]
+    pub record_keystrokes: bool,
+    /// Cut silent or black stretches from the start and end when processing a browser recording
+    #[serde(default)]
+    pub auto_trim: bool,
+    /// Thresholds for what auto_trim counts as dead air
+    #[serde(default)]
+    pub auto_trim_options: AnalysisOptions,
+    /// Save the webcam of a native recording as its own `<name>_webcam.mp4` instead of a
+    /// second stream in the screen file
+    #[serde(default)]
+    pub separate_webcam_file: bool,
+}
+
+fn default_record_cursor_events() -> bool {
+    true
+}
+
+fn default_low_space_warning_mb() -> u64 {
+    2048
+}
+
+fn default_min_free_space_mb() -> u64 {
+    500
+}
+
+/// Payload of `recording://low-disk-space`
+#[derive(Debug, Serialize, Clone)]
+pub struct LowDiskSpace {
+    pub free_bytes: u64,
+    pub threshold_bytes: u64,
+}
+
+/// Payload of `recording://auto-stopped`
+#[derive(Debug, Serialize, Clone)]
+pub struct AutoStopped {
+    pub reason: String,
+    pub output_path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// How a browser recording is turned into the saved file
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProcessingMode {
+    /// Re-encode to H.264/AAC MP4
+    #[default]
+    Reencode,
+    /// Copy the streams into MP4 when the codecs allow it, re-encoding otherwise
+    Remux,
+    /// Keep the WebM as recorded
+    KeepOriginal,
+}
+
+/// Choices from the review screen for processing one browser recording
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ProcessOptions {
+    /// Burn the keys logged during the recording into the lower third
+    #[serde(default)]
+    pub keystroke_overlay: bool,
+    /// Keep the recording from this point (seconds); overrides auto_trim
+    #[serde(default)]
+    pub trim_start: Option<f64>,
+    /// Keep the recording up to this point (seconds); overrides auto_trim
+    #[serde(default)]
+    pub trim_end: Option<f64>,
+}
+
+impl ProcessOptions {
+    /// The requested in/out points; the end is infinite when only a start was given
+    fn trim(&self) -> Result<Option<TimeRange>, String> {
+        if self.trim_start.is_none() && self.trim_end.is_none() {
+            return Ok(None);
+        }
+        let start = self.trim_start.unwrap_or(0.0).max(0.0);
+        let end = self.trim_end.unwrap_or(f64::INFINITY);
+        if end <= start {
+            return Err("Trim end must be after trim start".to_string());
+        }
+        Ok(Some(TimeRange { start, end }))
+    }
+}
+
+/// Changes made to a browser recording while it's processed
+#[derive(Debug, Default)]
+struct RecordingEdits {
+    /// Burned into the lower third
+    keystrokes: Option<KeystrokeLog>,
+    /// Keep only this part of the recording; `end` is infinite to keep the rest
+    trim: Option<TimeRange>,
+}
+
+fn default_stitch_segments() -> bool {
+    true
+}
+
+fn default_screen_recording_enabled() -> bool {
+    true
+}
+
+fn default_capture_cursor() -> bool {
+    true
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -22,20 +206,157 @@ pub struct RecordingState {
     pub is_recording: bool,
     pub current_settings: RecordingSettings,
     pub output_file: Option<String>,
+    #[serde(default)]
+    pub is_paused: bool,
+    /// Every file the last recording produced, in order (several when segments aren't stitched)
+    #[serde(default)]
+    pub output_files: Vec<String>,
+    /// Webcam files matching output_files when the webcam was saved separately
+    #[serde(default)]
+    pub webcam_files: Vec<String>,
+}
+
+/// An FFmpeg-driven capture. Pausing finishes the current segment and resuming starts a new
+/// one; the segments are joined when recording stops.
+struct NativeSession {
+    /// None while paused
+    child: Option<Child>,
+    stderr_tail: Arc<StdMutex<Vec<String>>>,
+    config: CaptureConfig,
+    segment_dir: PathBuf,
+    segments: Vec<PathBuf>,
+    output_path: PathBuf,
+    /// When the running segment started, for max_segment_duration
+    segment_started: Option<Instant>,
+    stitch_segments: bool,
+    /// Cursor sampler, when cursor events are recorded and the captured area is known
+    cursor: Option<CursorRecorder>,
+    separate_webcam_file: bool,
+    /// Wall-clock start, stamped on the screen and webcam files so they line up
+    started_at: chrono::DateTime<chrono::Utc>,
+    clock: RecordingClock,
+    markers: Vec<RecordingMarker>,
+}
+
+/// Time into a recording, excluding paused time
+struct RecordingClock {
+    started: Instant,
+    paused_at: Option<Instant>,
+    paused_total: Duration,
+}
+
+impl RecordingClock {
+    fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            paused_at: None,
+            paused_total: Duration::ZERO,
+        }
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        match (paused, self.paused_at) {
+            (true, None) => self.paused_at = Some(Instant::now()),
+            (false, Some(paused_at)) => {
+                self.paused_total += paused_at.elapsed();
+                self.paused_at = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn elapsed(&self) -> f64 {
+        let now = self.paused_at.unwrap_or_else(Instant::now);
+        (now.duration_since(self.started).saturating_sub(self.paused_total)).as_secs_f64()
+    }
 }
 
 pub struct RecordingManager {
     pub state: Mutex<RecordingState>,
+    native: Mutex<Option<NativeSession>>,
+    uploads: Mutex<HashMap<String, RecordingUpload>>,
+    replay: Mutex<Option<ReplayBuffer>>,
+    /// The browser recording in progress, between update_recording_state and processing
+    browser: Mutex<Option<BrowserSession>>,
+    voiceover: Mutex<Option<VoiceoverSession>>,
+}
+
+/// Backend-side state of a MediaRecorder recording
+struct BrowserSession {
+    started_at: chrono::DateTime<chrono::Utc>,
+    keystrokes: Option<KeystrokeRecorder>,
+    /// MediaRecorder pauses aren't reported, so the frontend passes marker times itself when paused
+    clock: RecordingClock,
+    markers: Vec<RecordingMarker>,
+}
+
+/// A finished browser recording on its way to being saved
+struct BrowserRecording {
+    webm_path: PathBuf,
+    mp4_path: PathBuf,
+    started_at: chrono::DateTime<chrono::Utc>,
+    markers: Vec<RecordingMarker>,
+}
+
+/// Audio-only narration recorded while the timeline plays
+struct VoiceoverSession {
+    child: Child,
+    stderr_tail: Arc<StdMutex<Vec<String>>>,
+    output_path: PathBuf,
+    /// Timeline position playback starts from
+    timeline_start: f64,
+    /// Input plus output latency to compensate for, in seconds
+    latency: f64,
+    track_index: Option<u32>,
+    /// When start_voiceover returned and the frontend started playback
+    playback_started: Instant,
+}
+
+/// A capture that keeps only the last few minutes, as a ring of short segments on disk
+struct ReplayBuffer {
+    child: Child,
+    stderr_tail: Arc<StdMutex<Vec<String>>>,
+    dir: PathBuf,
+    /// How much history is kept, in seconds
+    length_secs: u64,
+}
+
+/// A browser recording being streamed to disk chunk by chunk
+struct RecordingUpload {
+    file: fs::File,
+    webm_path: PathBuf,
+    mp4_path: PathBuf,
+    bytes_written: u64,
 }
 
 impl Default for RecordingSettings {
     fn default() -> Self {
         Self {
+            screen_recording_enabled: true,
             microphone: None,
             microphone_enabled: false,
             webcam_enabled: false,
             webcam_device: None,
             output_path: None,
+            display: None,
+            capture_target: CaptureTarget::default(),
+            fps: None,
+            quality: RecordingQuality::default(),
+            capture_cursor: true,
+            system_audio_enabled: false,
+            system_audio_device: None,
+            system_audio_mode: SystemAudioMode::default(),
+            processing_mode: ProcessingMode::default(),
+            max_segment_duration: None,
+            max_segment_size_mb: None,
+            stitch_segments: true,
+            low_space_warning_mb: default_low_space_warning_mb(),
+            min_free_space_mb: default_min_free_space_mb(),
+            record_cursor_events: true,
+            record_keystrokes: false,
+            auto_trim: false,
+            auto_trim_options: AnalysisOptions::default(),
+            separate_webcam_file: false,
         }
     }
 }
@@ -47,145 +368,207 @@ impl RecordingManager {
                 is_recording: false,
                 current_settings: RecordingSettings::default(),
                 output_file: None,
+                is_paused: false,
+                output_files: Vec::new(),
+                webcam_files: Vec::new(),
             }),
+            native: Mutex::new(None),
+            uploads: Mutex::new(HashMap::new()),
+            replay: Mutex::new(None),
+            browser: Mutex::new(None),
+            voiceover: Mutex::new(None),
         }
     }
 }
 
-// Get available microphones (simplified - browser handles enumeration)
+// Get available microphones via the platform's FFmpeg audio input backend
 #[tauri::command]
-pub async fn get_available_microphones() -> Result<Vec<String>, String> {
-    // Return empty list - browser's enumerateDevices() handles this
-    Ok(vec![])
+pub async fn get_available_microphones() -> Result<Vec<CaptureDevice>, String> {
+    list_capture_devices(DeviceKind::Audio)
+        .map_err(|e| format!("Failed to list microphones: {}", e))
 }
 
-// Get available webcams (simplified - browser handles enumeration)
+// Get inputs that carry system audio (monitor sources / loopback devices)
 #[tauri::command]
-pub async fn get_available_webcams() -> Result<Vec<String>, String> {
-    // Return empty list - browser's enumerateDevices() handles this
-    Ok(vec![])
+pub async fn get_system_audio_sources() -> Result<Vec<CaptureDevice>, String> {
+    list_system_audio_sources()
+        .map_err(|e| format!("Failed to list system audio sources: {}", e))
 }
 
-// Process recorded WebM data from browser and optionally re-encode to MP4
+// Get screen, microphone and camera permission status so the UI can guide the user first
 #[tauri::command]
-pub async fn process_recording(
-    manager: State<'_, RecordingManager>,
-    data: Vec<u8>,
-) -> Result<String, String> {
-    
-    // Generate output filename
-    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
-    let webm_filename = format!("recording_{}.webm", timestamp);
-    let mp4_filename = format!("recording_{}.mp4", timestamp);
-    
-    // Get the recordings directory
-    let recordings_dir = get_recordings_dir()
-        .map_err(|e| format!("Failed to get recordings directory: {}", e))?;
-    
-    let webm_path = recordings_dir.join(&webm_filename);
-    let mp4_path = recordings_dir.join(&mp4_filename);
-    
-    // Validate data is not empty
-    if data.is_empty() {
-        return Err("Received empty recording data".to_string());
-    }
-    
-    // Write WebM data to temporary file
-    fs::write(&webm_path, &data)
-        .await
-        .map_err(|e| format!("Failed to write WebM file: {}", e))?;
-    
-    
+pub async fn check_permissions() -> Result<Vec<PermissionState>, String> {
+    Ok(PermissionKind::ALL.into_iter().map(check_permission).collect())
+}
+
+// Prompt for (or open the settings page of) the given permissions, all when None
+#[tauri::command]
+pub async fn request_permissions(kinds: Option<Vec<PermissionKind>>) -> Result<Vec<PermissionState>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        kinds
+            .unwrap_or_else(|| PermissionKind::ALL.to_vec())
+            .into_iter()
+            .map(|kind| {
+                request_permission(kind).map_err(|e| format!("Failed to request {:?} permission: {}", kind, e))
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| format!("Permission request failed: {}", e))?
+}
+
+// Get connected monitors with their resolutions and scale factors
+#[tauri::command]
+pub async fn list_displays(app: AppHandle) -> Result<Vec<DisplayInfo>, String> {
+    enumerate_displays(&app).map_err(|e| format!("Failed to list displays: {}", e))
+}
+
+// Get available webcams (with supported modes where the backend reports them)
+#[tauri::command]
+pub async fn get_available_webcams() -> Result<Vec<CaptureDevice>, String> {
+    list_capture_devices(DeviceKind::Video)
+        .map_err(|e| format!("Failed to list webcams: {}", e))
+}
+
+/// Re-encode a finished browser WebM capture to MP4, reporting progress as a percentage. The
+/// WebM is removed on success and kept for debugging on failure.
+///
+/// MediaRecorder output usually has no duration in its header, so the caller can pass the
+/// length it measured; without either, progress only reports completion.
+fn encode_recording(
+    webm_path: &Path,
+    mp4_path: &Path,
+    settings: &RecordingSettings,
+    edits: &RecordingEdits,
+    duration_hint: Option<f64>,
+    cancel: Option<&AtomicBool>,
+    mut on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let webm_str = webm_path.to_str().ok_or("Invalid WebM path")?;
+    let mp4_str = mp4_path.to_str().ok_or("Invalid MP4 path")?;
+    let quality = &settings.quality;
+
     // Verify the WebM file was written correctly
-    let metadata = fs::metadata(&webm_path)
-        .await
+    let metadata = std::fs::metadata(webm_path)
         .map_err(|e| format!("Failed to verify WebM file: {}", e))?;
-    
     if metadata.len() == 0 {
         return Err("WebM file is empty after writing".to_string());
     }
-    
-    
+
+    // Validate with FFprobe first; a failure is only a warning since FFmpeg may still salvage it
+    let probed = match get_video_info(webm_str) {
+        Ok(info) => {
+            eprintln!("[Recording] WebM file validation passed");
+            Some(info)
+        }
+        Err(e) => {
+            eprintln!("[Recording] Warning: FFprobe validation failed: {}", e);
+            eprintln!("[Recording] The WebM file may be corrupted, but attempting to process anyway...");
+            None
+        }
+    };
+    let probed_duration = probed.as_ref().map(|info| info.duration).filter(|d| *d > 0.0);
+    let mut duration = duration_hint.filter(|d| *d > 0.0).or(probed_duration).unwrap_or(0.0);
+    if let Some(trim) = &edits.trim {
+        duration = if trim.end.is_finite() {
+            trim.end - trim.start
+        } else {
+            (duration - trim.start).max(0.0)
+        };
+    }
+
     // Re-encode to MP4 using FFmpeg for better compression and compatibility
-    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
-    
-    
-    // First, try to validate the WebM file with FFprobe
-    let ffprobe_path = get_ffmpeg_path()
-        .ok()
-        .and_then(|p| {
-            let parent = std::path::Path::new(&p).parent()?;
-            let ffprobe = parent.join("ffprobe");
-            if ffprobe.exists() {
-                Some(ffprobe)
-            } else {
-                None
-            }
-        });
-    
-    if let Some(ffprobe) = ffprobe_path {
-        let probe_output = Command::new(ffprobe)
-            .args(&[
-                "-v", "error",
-                "-show_format",
-                "-show_streams",
-                webm_path.to_str().unwrap(),
-            ])
-            .output()
-            .ok();
-        
-        if let Some(probe_output) = probe_output {
-            if !probe_output.status.success() {
-                let stderr = String::from_utf8_lossy(&probe_output.stderr);
-                eprintln!("[Recording] Warning: FFprobe validation failed: {}", stderr);
-                eprintln!("[Recording] The WebM file may be corrupted, but attempting to process anyway...");
-            } else {
-                eprintln!("[Recording] WebM file validation passed");
+    let mut filters = vec![quality.scale_filter()];
+    if let Some(fps) = settings.fps {
+        filters.push(format!("fps={}", fps.clamp(1, 120)));
+    }
+
+    // One drawtext per caption can outgrow the command line, so the key display goes through
+    // a filter script
+    let captions_dir = webm_path.with_extension("captions");
+    let mut filter_script = None;
+    if let Some(log) = edits.keystrokes.as_ref().filter(|log| !log.events.is_empty()) {
+        let height = probed.as_ref().map(|info| info.height).unwrap_or(720);
+        let height = quality.max_height.map(|max| height.min(max)).unwrap_or(height);
+        filters.extend(
+            keystroke_overlay_filters(log, height, &captions_dir)
+                .map_err(|e| format!("Failed to prepare keystroke overlay: {}", e))?,
+        );
+        let script_path = captions_dir.join("filters.txt");
+        std::fs::write(&script_path, filters.join(","))
+            .map_err(|e| format!("Failed to write filter script: {}", e))?;
+        filter_script = Some(script_path.to_string_lossy().to_string());
+    }
+    let video_filter = match &filter_script {
+        Some(path) => ["-filter_script:v".to_string(), path.clone()],
+        None => ["-vf".to_string(), filters.join(",")],
+    };
+
+    let build_args = |quality: &RecordingQuality| -> Result<Vec<String>, String> {
+        let mut args: Vec<String> = [
+            "-err_detect", "ignore_err",  // Try to ignore minor errors
+            "-i", webm_str,
+            &video_filter[0], &video_filter[1],
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        // Output-side seeking keeps the cut frame-accurate and filter times (the key display)
+        // on the source timeline
+        if let Some(trim) = &edits.trim {
+            args.extend(["-ss".to_string(), format!("{:.3}", trim.start)]);
+            if trim.end.is_finite() {
+                args.extend(["-to".to_string(), format!("{:.3}", trim.end)]);
             }
         }
+        // CRF 23 by default: better quality than the browser's recording bitrate
+        args.extend(
+            quality
+                .video_codec_args("fast", 23)
+                .map_err(|e| format!("Failed to configure encoder: {}", e))?,
+        );
+        args.extend(
+            ["-c:a", "aac", "-b:a", "192k", "-movflags", "+faststart", "-y", mp4_str]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+        Ok(args)
+    };
+
+    let mut result = run_ffmpeg_with_progress(&build_args(quality)?, duration, cancel, &mut on_progress);
+    // A hardware encoder can pass detection yet reject this input (size limits, busy GPU)
+    let cancelled = cancel.map(|flag| flag.load(Ordering::SeqCst)).unwrap_or(false);
+    if result.is_err() && !cancelled && quality.hardware_encoding == HardwareEncoding::Auto {
+        eprintln!("[Recording] Hardware encode failed, retrying in software");
+        result = run_ffmpeg_with_progress(&build_args(&quality.software())?, duration, cancel, &mut on_progress);
     }
-    
-    let output = Command::new(&ffmpeg_path)
-        .args(&[
-            "-err_detect", "ignore_err",  // Try to ignore minor errors
-            "-i", webm_path.to_str().unwrap(),
-            "-c:v", "libx264",
-            "-preset", "fast",
-            "-crf", "23",  // Better quality than recording default
-            "-c:a", "aac",
-            "-b:a", "192k",
-            "-movflags", "+faststart",
-            "-y",
-            mp4_path.to_str().unwrap(),
-        ])
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .output()
-        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
-    
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        eprintln!("[Recording] FFmpeg error: {}", stderr);
-        
+    if filter_script.is_some() {
+        let _ = std::fs::remove_dir_all(&captions_dir);
+    }
+
+    if let Err(e) = result {
+        eprintln!("[Recording] FFmpeg error: {}", e);
+        let _ = std::fs::remove_file(mp4_path);
+
         // Keep the WebM file for debugging
         eprintln!("[Recording] WebM file preserved for debugging at: {:?}", webm_path);
-        
+
         return Err(format!(
-            "FFmpeg re-encoding failed. The WebM file may be corrupted. Error: {}", 
-            stderr
+            "FFmpeg re-encoding failed. The WebM file may be corrupted. Error: {}",
+            e
         ));
     }
-    
+
     eprintln!("[Recording] MP4 file created: {:?}", mp4_path);
-    
+
     // Delete the temporary WebM file only on success
-    match fs::remove_file(&webm_path).await {
+    match std::fs::remove_file(webm_path) {
         Ok(_) => eprintln!("[Recording] Temporary WebM file deleted"),
         Err(e) => eprintln!("[Recording] Warning: Failed to delete temporary WebM file: {}", e),
     }
-    
+
     // Verify output file exists and has content
-    match fs::metadata(&mp4_path).await {
+    match std::fs::metadata(mp4_path) {
         Ok(metadata) => {
             eprintln!("[Recording] Output file size: {} bytes", metadata.len());
             if metadata.len() == 0 {
@@ -196,13 +579,465 @@ pub async fn process_recording(
             return Err(format!("Recording failed: output file not found - {}", e));
         }
     }
+
+    Ok(())
+}
+
+/// Turn a finished browser WebM into the saved recording according to `mode` and return its
+/// path. Remux falls back to re-encoding when the codecs can't go into MP4 (e.g. VP8) or the
+/// quality settings require downscaling or a lower frame rate.
+fn process_webm(
+    webm_path: &Path,
+    mp4_path: &Path,
+    settings: &RecordingSettings,
+    edits: &RecordingEdits,
+    duration_hint: Option<f64>,
+    cancel: Option<&AtomicBool>,
+    mut on_progress: impl FnMut(f64),
+) -> Result<PathBuf, String> {
+    let webm_str = webm_path.to_str().ok_or("Invalid WebM path")?;
+
+    match settings.processing_mode {
+        ProcessingMode::KeepOriginal => {
+            if edits.keystrokes.is_some() || edits.trim.is_some() {
+                eprintln!("[Recording] Edits skipped; the original is kept as recorded");
+            }
+            let metadata = std::fs::metadata(webm_path)
+                .map_err(|e| format!("Failed to verify WebM file: {}", e))?;
+            if metadata.len() == 0 {
+                return Err("WebM file is empty after writing".to_string());
+            }
+            on_progress(100.0);
+            return Ok(webm_path.to_path_buf());
+        }
+        ProcessingMode::Remux => {
+            let info = get_video_info(webm_str).map_err(|e| format!("Failed to analyze video: {}", e))?;
+            let too_tall = settings.quality.max_height.map(|max| info.height > max).unwrap_or(false);
+            let too_fast = settings.fps.map(|fps| info.fps > fps as f64 + 0.5).unwrap_or(false);
+            let compatible = if too_tall || too_fast {
+                Err(anyhow::anyhow!("Quality settings need a re-encode"))
+            } else if edits.keystrokes.is_some() {
+                Err(anyhow::anyhow!("Keystroke overlay needs a re-encode"))
+            } else {
+                check_remux_compatibility("mp4", &info.codec, info.audio_codec.as_deref())
+            };
+            match compatible {
+                Ok(()) => {
+                    let mp4_str = mp4_path.to_str().ok_or("Invalid MP4 path")?;
+                    // Rewriting the container also adds the duration and index MediaRecorder leaves out
+                    let (start, end) = match &edits.trim {
+                        Some(trim) => (trim.start, Some(trim.end).filter(|end| end.is_finite())),
+                        None => (0.0, None),
+                    };
+                    match remux_copy_range(webm_str, mp4_str, "mp4", &info.codec, start, end) {
+                        Ok(()) => {
+                            eprintln!("[Recording] Remuxed without re-encoding: {:?}", mp4_path);
+                            let _ = std::fs::remove_file(webm_path);
+                            on_progress(100.0);
+                            return Ok(mp4_path.to_path_buf());
+                        }
+                        Err(e) => eprintln!("[Recording] Remux failed, re-encoding instead: {}", e),
+                    }
+                }
+                Err(e) => eprintln!("[Recording] {}; re-encoding instead", e),
+            }
+        }
+        ProcessingMode::Reencode => {}
+    }
+
+    encode_recording(
+        webm_path,
+        mp4_path,
+        settings,
+        edits,
+        duration_hint,
+        cancel,
+        on_progress,
+    )?;
+    Ok(mp4_path.to_path_buf())
+}
+
+/// End the browser session for a recording saved to `webm_path`/`mp4_path`. Its keystroke log
+/// is moved into `edits` when it should be burned in, and discarded otherwise. Without a
+/// session (update_recording_state never called) the start is estimated from the duration.
+async fn finish_browser_session(
+    manager: &RecordingManager,
+    webm_path: PathBuf,
+    mp4_path: PathBuf,
+    duration: Option<f64>,
+    options: &ProcessOptions,
+    edits: &mut RecordingEdits,
+) -> BrowserRecording {
+    let mut session = manager.browser.lock().await.take();
+    let started_at = session.as_ref().map(|session| session.started_at).unwrap_or_else(|| {
+        let length = duration.unwrap_or(0.0).max(0.0);
+        chrono::Utc::now() - chrono::Duration::milliseconds((length * 1000.0) as i64)
+    });
+    let markers = session
+        .as_mut()
+        .map(|session| std::mem::take(&mut session.markers))
+        .unwrap_or_default();
+
+    // Keys are only ever kept in memory, and only for the overlay; without one they're dropped
+    if let Some(recorder) = session.and_then(|session| session.keystrokes) {
+        if let Ok(log) = tauri::async_runtime::spawn_blocking(move || recorder.finish()).await {
+            if options.keystroke_overlay && !log.events.is_empty() {
+                edits.keystrokes = Some(log);
+            }
+        }
+    }
+
+    BrowserRecording {
+        webm_path,
+        mp4_path,
+        started_at,
+        markers,
+    }
+}
+
+/// Probe a finished recording's files into its metadata sidecar contents. Device fields are
+/// left empty for the caller.
+fn probe_recording_metadata(
+    source: &str,
+    started_at: &chrono::DateTime<chrono::Utc>,
+    files: &[String],
+    webcam_files: &[String],
+) -> Result<RecordingMetadata, String> {
+    let first = files.first().ok_or("Recording has no files")?;
+    let info = get_video_info(first).map_err(|e| format!("Failed to analyze recording: {}", e))?;
+    let duration = info.duration
+        + files[1..]
+            .iter()
+            .filter_map(|file| get_video_info(file).ok())
+            .map(|part| part.duration)
+            .sum::<f64>();
+
+    Ok(RecordingMetadata {
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        source: source.to_string(),
+        started_at: started_at.to_rfc3339(),
+        files: files.to_vec(),
+        webcam_files: webcam_files.to_vec(),
+        microphone: None,
+        webcam: None,
+        system_audio: None,
+        display: None,
+        capture_target: None,
+        width: info.width,
+        height: info.height,
+        fps: info.fps,
+        duration,
+        video_codec: info.codec,
+        audio_codec: info.audio_codec,
+        markers: Vec::new(),
+    })
+}
+
+fn save_recording_metadata(recording: &Path, metadata: &RecordingMetadata) {
+    if let Err(e) = metadata.save(&RecordingMetadata::path_for(recording)) {
+        eprintln!("[Recording] {}", e);
+    }
+}
+
+/// In/out points that drop dead air from the ends of a recording, with the recording's length.
+/// Only stretches that are both black and silent count, so a silent screencast intro or
+/// narration over a black frame is kept; without an audio track black alone is enough.
+/// MediaRecorder files often have no duration in their header, so `duration_hint` stands in.
+fn find_dead_air_trim(
+    webm_path: &Path,
+    options: &AnalysisOptions,
+    duration_hint: Option<f64>,
+) -> Result<Option<(TimeRange, f64)>, String> {
+    let webm_str = webm_path.to_str().ok_or("Invalid WebM path")?;
+    let mut analysis = analyze_dead_air(webm_str, options)?;
+    if analysis.duration <= 0.0 {
+        let Some(duration) = duration_hint.filter(|d| *d > 0.0) else {
+            return Ok(None);
+        };
+        // Silence running to the end was closed at the unknown (zero) duration
+        for range in analysis.silent_ranges.iter_mut().filter(|range| range.end < range.start) {
+            range.end = duration;
+        }
+        analysis.duration = duration;
+    }
+    let dead_air = if analysis.has_audio {
+        intersect_ranges(&analysis.black_ranges, &analysis.silent_ranges)
+    } else {
+        analysis.black_ranges
+    };
+    Ok(suggest_trim(analysis.duration, &dead_air, &[]).map(|trim| (trim, analysis.duration)))
+}
+
+/// Run process_webm as a tracked job, auto-trimming first when the settings ask for it and no
+/// trim was given. The job result is `{ "output_path", "trimmed_seconds" }` (what auto-trim
+/// removed) and the recording state's output_file is set when it completes.
+fn spawn_recording_job(
+    app: &AppHandle,
+    jobs: &JobManager,
+    recording: BrowserRecording,
+    settings: RecordingSettings,
+    mut edits: RecordingEdits,
+    duration_hint: Option<f64>,
+) -> String {
+    let BrowserRecording {
+        webm_path,
+        mp4_path,
+        started_at,
+        mut markers,
+    } = recording;
+    let name = mp4_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (job_id, cancel) = jobs.create(app, "recording", &format!("Save {}", name));
+
+    let worker_app = app.clone();
+    let worker_job = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = worker_app.state::<JobManager>();
+        let mut trimmed_seconds = 0.0;
+        let auto_trim = settings.auto_trim && edits.trim.is_none();
+        if auto_trim && settings.processing_mode != ProcessingMode::KeepOriginal {
+            match find_dead_air_trim(&webm_path, &settings.auto_trim_options, duration_hint) {
+                Ok(Some((trim, duration))) => {
+                    trimmed_seconds = duration - (trim.end - trim.start);
+                    println!(
+                        "[Recording] Auto-trim keeps {:.2}s-{:.2}s, removing {:.2}s",
+                        trim.start, trim.end, trimmed_seconds
+                    );
+                    edits.trim = Some(trim);
+                }
+                Ok(None) => println!("[Recording] Auto-trim found no dead air at the edges"),
+                // Trimming is a nicety; save the whole recording rather than fail
+                Err(e) => eprintln!("[Recording] Auto-trim analysis failed: {}", e),
+            }
+        }
+
+        let result = process_webm(
+            &webm_path,
+            &mp4_path,
+            &settings,
+            &edits,
+            duration_hint,
+            Some(&cancel),
+            |percentage| jobs.set_progress(&worker_app, &worker_job, percentage),
+        );
+
+        match result {
+            Ok(output_path) => {
+                let output = output_path.to_string_lossy().to_string();
+                // Markers were taken against the untrimmed recording; keeping the original skips the trim
+                let trimmed = settings.processing_mode != ProcessingMode::KeepOriginal;
+                if let Some(trim) = edits.trim.as_ref().filter(|_| trimmed) {
+                    markers.retain(|marker| marker.time >= trim.start && marker.time <= trim.end);
+                    for marker in &mut markers {
+                        marker.time -= trim.start;
+                    }
+                }
+                match probe_recording_metadata("browser", &started_at, std::slice::from_ref(&output), &[]) {
+                    Ok(metadata) => {
+                        let metadata = RecordingMetadata {
+                            microphone: settings.microphone.clone().filter(|_| settings.microphone_enabled),
+                            webcam: settings.webcam_device.clone().filter(|_| settings.webcam_enabled),
+                            markers,
+                            ..metadata
+                        };
+                        save_recording_metadata(&output_path, &metadata);
+                    }
+                    Err(e) => eprintln!("[Recording] {}", e),
+                }
+                let manager = worker_app.state::<RecordingManager>();
+                {
+                    let mut state = manager.state.blocking_lock();
+                    state.is_recording = false;
+                    state.output_file = Some(output.clone());
+                    state.output_files = vec![output.clone()];
+                    state.webcam_files.clear();
+                }
+                jobs.complete(
+                    &worker_app,
+                    &worker_job,
+                    Some(serde_json::json!({
+                        "output_path": output,
+                        "trimmed_seconds": trimmed_seconds,
+                    })),
+                );
+            }
+            Err(e) => {
+                eprintln!("[Recording] Processing failed: {}", e);
+                jobs.fail(&worker_app, &worker_job, e);
+            }
+        }
+    });
+
+    job_id
+}
+
+// Save recorded WebM data from the browser and process it in the background according to the
+// settings' processing_mode and the review screen's options (trim points, keystroke overlay).
+// Returns the job id; progress and the output path arrive via `job://update`.
+#[tauri::command]
+pub async fn process_recording(
+    app: AppHandle,
+    manager: State<'_, RecordingManager>,
+    jobs: State<'_, JobManager>,
+    data: Vec<u8>,
+    duration: Option<f64>,
+    options: Option<ProcessOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let trim = options.trim()?;
+    let (webm_path, mp4_path) = recording_output_paths()?;
     
-    // Update state
-    let mut state = manager.state.lock().await;
-    state.is_recording = false;
-    state.output_file = Some(mp4_path.to_string_lossy().to_string());
+    // Validate data is not empty
+    if data.is_empty() {
+        return Err("Received empty recording data".to_string());
+    }
     
-    Ok(mp4_path.to_string_lossy().to_string())
+    // Write WebM data to temporary file
+    fs::write(&webm_path, &data)
+        .await
+        .map_err(|e| format!("Failed to write WebM file: {}", e))?;
+
+    let mut edits = RecordingEdits {
+        keystrokes: None,
+        trim,
+    };
+    let recording = finish_browser_session(&manager, webm_path, mp4_path, duration, &options, &mut edits).await;
+    let settings = manager.state.lock().await.current_settings.clone();
+    Ok(spawn_recording_job(&app, &jobs, recording, settings, edits, duration))
+}
+
+fn recording_output_paths() -> Result<(PathBuf, PathBuf), String> {
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let recordings_dir = get_recordings_dir()
+        .map_err(|e| format!("Failed to get recordings directory: {}", e))?;
+    Ok((
+        recordings_dir.join(format!("recording_{}.webm", timestamp)),
+        recordings_dir.join(format!("recording_{}.mp4", timestamp)),
+    ))
+}
+
+// Start streaming a browser recording to disk; returns the upload id for the chunk calls
+#[tauri::command]
+pub async fn begin_recording_upload(manager: State<'_, RecordingManager>) -> Result<String, String> {
+    let (webm_path, mp4_path) = recording_output_paths()?;
+    let file = fs::File::create(&webm_path)
+        .await
+        .map_err(|e| format!("Failed to create WebM file: {}", e))?;
+
+    let upload_id = uuid::Uuid::new_v4().to_string();
+    println!("[Recording] Upload {} started: {:?}", upload_id, webm_path);
+    manager.uploads.lock().await.insert(
+        upload_id.clone(),
+        RecordingUpload {
+            file,
+            webm_path,
+            mp4_path,
+            bytes_written: 0,
+        },
+    );
+
+    Ok(upload_id)
+}
+
+// Append one MediaRecorder chunk to an upload; returns the total bytes written so far
+#[tauri::command]
+pub async fn append_recording_chunk(
+    manager: State<'_, RecordingManager>,
+    upload_id: String,
+    chunk: Vec<u8>,
+) -> Result<u64, String> {
+    let mut uploads = manager.uploads.lock().await;
+    let upload = uploads
+        .get_mut(&upload_id)
+        .ok_or_else(|| format!("Unknown recording upload: {}", upload_id))?;
+
+    // Refuse chunks before the disk fills so the frontend can stop and finish cleanly
+    let min_free = manager.state.lock().await.current_settings.min_free_space_mb * 1024 * 1024;
+    if min_free > 0 {
+        if let Some(volume) = upload.webm_path.parent() {
+            let free = available_space(volume).unwrap_or(u64::MAX);
+            if free < min_free + chunk.len() as u64 {
+                return Err(format!("Not enough disk space to keep recording ({} bytes free)", free));
+            }
+        }
+    }
+
+    upload
+        .file
+        .write_all(&chunk)
+        .await
+        .map_err(|e| format!("Failed to write recording chunk: {}", e))?;
+    upload.bytes_written += chunk.len() as u64;
+
+    Ok(upload.bytes_written)
+}
+
+// Close an upload and process it in the background, like process_recording.
+// Returns the job id.
+#[tauri::command]
+pub async fn finish_recording(
+    app: AppHandle,
+    manager: State<'_, RecordingManager>,
+    jobs: State<'_, JobManager>,
+    upload_id: String,
+    duration: Option<f64>,
+    options: Option<ProcessOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    let trim = options.trim()?;
+    let mut upload = manager
+        .uploads
+        .lock()
+        .await
+        .remove(&upload_id)
+        .ok_or_else(|| format!("Unknown recording upload: {}", upload_id))?;
+
+    upload
+        .file
+        .flush()
+        .await
+        .map_err(|e| format!("Failed to flush WebM file: {}", e))?;
+    drop(upload.file);
+
+    if upload.bytes_written == 0 {
+        let _ = fs::remove_file(&upload.webm_path).await;
+        return Err("Received empty recording data".to_string());
+    }
+    println!(
+        "[Recording] Upload {} finished: {} bytes",
+        upload_id, upload.bytes_written
+    );
+
+    let mut edits = RecordingEdits {
+        keystrokes: None,
+        trim,
+    };
+    let recording = finish_browser_session(
+        &manager,
+        upload.webm_path,
+        upload.mp4_path,
+        duration,
+        &options,
+        &mut edits,
+    )
+    .await;
+    let settings = manager.state.lock().await.current_settings.clone();
+    Ok(spawn_recording_job(&app, &jobs, recording, settings, edits, duration))
+}
+
+// Abandon an upload and delete the partial file
+#[tauri::command]
+pub async fn cancel_recording_upload(
+    manager: State<'_, RecordingManager>,
+    upload_id: String,
+) -> Result<(), String> {
+    if let Some(upload) = manager.uploads.lock().await.remove(&upload_id) {
+        drop(upload.file);
+        let _ = fs::remove_file(&upload.webm_path).await;
+    }
+    Ok(())
 }
 
 // Get current recording state
@@ -225,10 +1060,29 @@ pub async fn update_recording_state(
     if let Some(settings) = settings {
         state.current_settings = settings;
     }
+
+    // A browser session runs from the start call until the recording is processed
+    let mut browser = manager.browser.lock().await;
+    if let Some(recorder) = browser.take().and_then(|session| session.keystrokes) {
+        tauri::async_runtime::spawn_blocking(move || recorder.finish());
+    }
+    if is_recording {
+        *browser = Some(BrowserSession {
+            started_at: chrono::Utc::now(),
+            keystrokes: state
+                .current_settings
+                .record_keystrokes
+                .then(KeystrokeRecorder::start),
+            clock: RecordingClock::start(),
+            markers: Vec::new(),
+        });
+    }
     
     if !is_recording {
         // Reset output file when starting a new recording
         state.output_file = None;
+        state.output_files.clear();
+        state.webcam_files.clear();
     }
     
     Ok(state.clone())
@@ -288,38 +1142,1344 @@ pub async fn export_recording_to_file(
 // Generate thumbnail for recording
 #[tauri::command]
 pub async fn generate_recording_thumbnail(file_path: String) -> Result<String, String> {
-    use std::fs;
-    
-    // Create thumbnails directory in temp
-    let app_data = std::env::temp_dir().join("zapcut").join("thumbnails");
-    fs::create_dir_all(&app_data)
+    let app_data = get_cache_dir(CacheKind::Thumbnails)
         .map_err(|e| format!("Failed to create thumbnails directory: {}", e))?;
     
     // Generate unique thumbnail name
+    let settings = get_settings().thumbnails;
     let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S_%N");
-    let thumbnail_name = format!("recording_preview_{}.jpg", timestamp);
+    let thumbnail_name = format!("recording_preview_{}.{}", timestamp, settings.format.extension());
     let thumbnail_path = app_data.join(&thumbnail_name);
     
     // Use FFmpeg to generate thumbnail at 1 second mark
-    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
-    let output = Command::new(ffmpeg_path)
-        .args(&[
-            "-ss", "1",
-            "-i", &file_path,
-            "-vframes", "1",
-            "-q:v", "2",
-            "-y",
-            thumbnail_path.to_str().unwrap(),
-        ])
-        .output()
-        .map_err(|e| format!("Failed to execute ffmpeg for thumbnail: {}", e))?;
-    
-    if !output.status.success() {
-        return Err(format!(
-            "FFmpeg thumbnail failed: {}",
-            String::from_utf8_lossy(&output.stderr)
-        ));
-    }
+    generate_thumbnail(&file_path, thumbnail_path.to_str().unwrap(), 1.0, &settings)
+        .map_err(|e| format!("Failed to generate thumbnail: {}", e))?;
     
     Ok(thumbnail_path.to_string_lossy().to_string())
 }
+
+/// Lines of FFmpeg stderr kept for error reporting
+const STDERR_TAIL_LINES: usize = 40;
+
+/// Launch an FFmpeg capture, keeping the last lines of its stderr in `tail`. Fails if FFmpeg
+/// exits right away, which is how device and permission errors show up.
+async fn spawn_capture(args: &[String], tail: &Arc<StdMutex<Vec<String>>>) -> Result<Child, String> {
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
+    let mut child = Command::new(&ffmpeg_path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start FFmpeg capture: {}", e))?;
+
+    // Keep draining stderr so FFmpeg never blocks on a full pipe
+    let stderr = child.stderr.take().ok_or("Failed to capture FFmpeg stderr")?;
+    let stderr_tail = tail.clone();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(|line| line.ok()) {
+            let mut tail = stderr_tail.lock().unwrap();
+            tail.push(line);
+            if tail.len() > STDERR_TAIL_LINES {
+                tail.remove(0);
+            }
+        }
+    });
+
+    // Device and permission errors make FFmpeg exit almost immediately
+    tokio::time::sleep(Duration::from_millis(750)).await;
+    if let Ok(Some(status)) = child.try_wait() {
+        let log = tail.lock().unwrap().join("\n");
+        return Err(format!("FFmpeg capture exited ({}): {}", status, log));
+    }
+
+    Ok(child)
+}
+
+/// Start FFmpeg capturing into the session's next segment file
+async fn start_segment(session: &mut NativeSession) -> Result<(), String> {
+    let segment_path = session
+        .segment_dir
+        .join(format!("segment_{:03}.mp4", session.segments.len()));
+    let args = build_capture_args(&session.config, segment_path.to_str().unwrap())
+        .map_err(|e| format!("Failed to configure capture: {}", e))?;
+
+    let child = spawn_capture(&args, &session.stderr_tail).await?;
+
+    session.segments.push(segment_path);
+    session.child = Some(child);
+    session.segment_started = Some(Instant::now());
+    Ok(())
+}
+
+/// Ask FFmpeg to finish the current segment cleanly ('q' writes the MP4 index). Waits on the
+/// async timer, so callers holding a session lock only make other lockers wait for the
+/// segment to close, without tying up a runtime worker.
+async fn finish_segment(child: &mut Child) {
+    if let Some(stdin) = child.stdin.as_mut() {
+        let _ = stdin.write_all(b"q");
+        let _ = stdin.flush();
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    eprintln!("[Recording] FFmpeg did not stop in time, killing capture");
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Roll the running segment over to a new file whenever it passes a duration or size limit, so
+/// a crash loses at most one segment. Exits once the session identified by `output_path` ends.
+async fn watch_segment_limits(
+    app: AppHandle,
+    output_path: PathBuf,
+    max_duration: Option<Duration>,
+    max_bytes: Option<u64>,
+) {
+    let manager = app.state::<RecordingManager>();
+    loop {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+
+        let mut native = manager.native.lock().await;
+        let session = match native.as_mut() {
+            Some(session) if session.output_path == output_path => session,
+            _ => return,
+        };
+        // Paused: the next segment starts on resume
+        if session.child.is_none() {
+            continue;
+        }
+
+        let elapsed = session.segment_started.map(|started| started.elapsed()).unwrap_or_default();
+        let size = session
+            .segments
+            .last()
+            .and_then(|path| std::fs::metadata(path).ok())
+            .map(|m| m.len())
+            .unwrap_or(0);
+        let over_duration = max_duration.map(|max| elapsed >= max).unwrap_or(false);
+        let over_size = max_bytes.map(|max| size >= max).unwrap_or(false);
+        if !over_duration && !over_size {
+            continue;
+        }
+
+        eprintln!(
+            "[Recording] Rolling over to segment {} ({:.0}s, {} bytes)",
+            session.segments.len(),
+            elapsed.as_secs_f64(),
+            size
+        );
+        // The lock stays held so a stop or pause waits for this segment to be closed instead
+        // of joining it half-written
+        if let Some(mut child) = session.child.take() {
+            finish_segment(&mut child).await;
+        }
+        if let Err(e) = start_segment(session).await {
+            // Leave the session paused so stop still saves what was recorded
+            eprintln!("[Recording] Failed to start next segment: {}", e);
+            session.clock.set_paused(true);
+            drop(native);
+            manager.state.lock().await.is_paused = true;
+            return;
+        }
+    }
+}
+
+fn emit_auto_stopped(app: &AppHandle, reason: &str, result: Result<String, String>) {
+    let _ = app.emit(
+        "recording://auto-stopped",
+        AutoStopped {
+            reason: reason.to_string(),
+            output_path: result.as_ref().ok().cloned(),
+            error: result.err(),
+        },
+    );
+}
+
+/// Stop the running native recording after `duration` unless it has ended by then. Emits
+/// `recording://auto-stopped` with `reason` when it fires.
+pub(crate) async fn stop_native_after(app: &AppHandle, duration: Duration, reason: &'static str) -> Result<(), String> {
+    let manager = app.state::<RecordingManager>();
+    let output_path = manager
+        .native
+        .lock()
+        .await
+        .as_ref()
+        .map(|session| session.output_path.clone())
+        .ok_or("No native recording in progress")?;
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(duration).await;
+        let manager = app.state::<RecordingManager>();
+        match manager.native.lock().await.as_ref() {
+            Some(session) if session.output_path == output_path => {}
+            _ => return,
+        }
+        println!("[Recording] Auto-stopping recording ({})", reason);
+        let result = stop_native_session(&manager).await;
+        emit_auto_stopped(&app, reason, result);
+    });
+    Ok(())
+}
+
+/// Seconds between free-space checks while recording
+const DISK_CHECK_INTERVAL_SECS: u64 = 2;
+
+/// Warn once when the recordings volume runs low and stop the session cleanly before it fills
+/// up, since FFmpeg leaves an unreadable MP4 when a write fails. Exits when the session ends.
+async fn watch_disk_space(app: AppHandle, output_path: PathBuf, volume: PathBuf, warn_bytes: u64, stop_bytes: u64) {
+    let manager = app.state::<RecordingManager>();
+    let mut warned = false;
+    loop {
+        tokio::time::sleep(Duration::from_secs(DISK_CHECK_INTERVAL_SECS)).await;
+
+        match manager.native.lock().await.as_ref() {
+            Some(session) if session.output_path == output_path => {}
+            _ => return,
+        }
+        let free = match available_space(&volume) {
+            Ok(free) => free,
+            Err(e) => {
+                eprintln!("[Recording] Failed to check free disk space: {}", e);
+                continue;
+            }
+        };
+
+        if stop_bytes > 0 && free < stop_bytes {
+            eprintln!("[Recording] Only {} bytes free, stopping recording", free);
+            let result = stop_native_session(&manager).await;
+            emit_auto_stopped(&app, "low_disk_space", result);
+            return;
+        }
+
+        if warn_bytes > 0 && free < warn_bytes && !warned {
+            eprintln!("[Recording] Low disk space: {} bytes free", free);
+            let _ = app.emit(
+                "recording://low-disk-space",
+                LowDiskSpace {
+                    free_bytes: free,
+                    threshold_bytes: warn_bytes,
+                },
+            );
+        }
+        warned = free < warn_bytes;
+    }
+}
+
+/// Resolve recording settings into what FFmpeg should capture
+fn capture_config(app: &AppHandle, settings: &RecordingSettings) -> Result<CaptureConfig, String> {
+    if !settings.screen_recording_enabled && !settings.webcam_enabled {
+        return Err("Enable screen or webcam recording".to_string());
+    }
+    let webcam = match (settings.webcam_enabled, settings.webcam_device.clone()) {
+        (false, _) => None,
+        (true, Some(device)) => Some(device),
+        (true, None) if settings.screen_recording_enabled => None,
+        // A webcam-only recording needs some camera, so fall back to the first one
+        (true, None) => Some(
+            list_capture_devices(DeviceKind::Video)
+                .map_err(|e| format!("Failed to list webcams: {}", e))?
+                .into_iter()
+                .next()
+                .map(|device| device.id)
+                .ok_or("No webcam found")?,
+        ),
+    };
+
+    let system_audio = if settings.system_audio_enabled {
+        match settings.system_audio_device.clone() {
+            Some(device) => Some(device),
+            None => Some(
+                list_system_audio_sources()
+                    .map_err(|e| format!("Failed to list system audio sources: {}", e))?
+                    .into_iter()
+                    .next()
+                    .map(|device| device.id)
+                    .ok_or(
+                        "No system audio source found. Native system audio capture is only \
+                         supported on Linux; on macOS install a loopback device such as \
+                         BlackHole, and on Windows enable Stereo Mix or a virtual audio cable.",
+                    )?,
+            ),
+        }
+    } else {
+        None
+    };
+
+    // Display ids come from list_displays; anything else is passed to FFmpeg as-is
+    let (display, display_bounds) = match &settings.display {
+        Some(id) => match enumerate_displays(app)
+            .map_err(|e| format!("Failed to list displays: {}", e))?
+            .into_iter()
+            .find(|d| &d.id == id)
+        {
+            Some(info) => (display_input(&info), Some(info.bounds)),
+            None => (Some(id.clone()), None),
+        },
+        None => (None, None),
+    };
+
+    Ok(CaptureConfig {
+        screen: settings.screen_recording_enabled,
+        display,
+        display_bounds,
+        target: settings.capture_target.clone(),
+        fps: settings.fps.unwrap_or(30).clamp(1, 120),
+        quality: settings.quality.clone(),
+        capture_cursor: settings.capture_cursor,
+        webcam,
+        microphone: if settings.microphone_enabled {
+            settings.microphone.clone()
+        } else {
+            None
+        },
+        system_audio,
+        system_audio_mode: settings.system_audio_mode,
+    })
+}
+
+/// The captured area in physical desktop pixels, for mapping cursor positions onto the video.
+/// None for window capture, whose position isn't known.
+fn cursor_area(app: &AppHandle, config: &CaptureConfig) -> Option<DisplayBounds> {
+    if matches!(config.target, CaptureTarget::Window { .. }) {
+        return None;
+    }
+    let display = match config.display_bounds {
+        Some(bounds) => bounds,
+        None => {
+            let displays = enumerate_displays(app).ok()?;
+            displays
+                .iter()
+                .find(|d| d.is_primary)
+                .or(displays.first())
+                .map(|d| d.bounds)?
+        }
+    };
+    Some(match config.target.pixel_rect() {
+        Some(rect) => DisplayBounds {
+            x: display.x + rect.x as i32,
+            y: display.y + rect.y as i32,
+            width: rect.width,
+            height: rect.height,
+        },
+        None => display,
+    })
+}
+
+/// Start recording the screen natively with FFmpeg instead of the browser's MediaRecorder
+#[tauri::command]
+pub async fn start_native_recording(
+    app: AppHandle,
+    manager: State<'_, RecordingManager>,
+    settings: Option<RecordingSettings>,
+) -> Result<RecordingState, String> {
+    start_native_session(&app, &manager, settings).await
+}
+
+pub(crate) async fn start_native_session(
+    app: &AppHandle,
+    manager: &RecordingManager,
+    settings: Option<RecordingSettings>,
+) -> Result<RecordingState, String> {
+    let mut native = manager.native.lock().await;
+    if native.is_some() {
+        return Err("A native recording is already in progress".to_string());
+    }
+
+    let mut state = manager.state.lock().await;
+    if let Some(settings) = settings {
+        state.current_settings = settings;
+    }
+    let settings = state.current_settings.clone();
+
+    let recordings_dir = get_recordings_dir()
+        .map_err(|e| format!("Failed to get recordings directory: {}", e))?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let output_path = match &settings.output_path {
+        Some(path) => path.clone(),
+        None => recordings_dir.join(format!("recording_{}.mp4", timestamp)),
+    };
+    let segment_dir = recordings_dir.join(format!(".segments_{}", timestamp));
+    fs::create_dir_all(&segment_dir)
+        .await
+        .map_err(|e| format!("Failed to create segment directory: {}", e))?;
+
+    let mut session = NativeSession {
+        child: None,
+        stderr_tail: Arc::new(StdMutex::new(Vec::new())),
+        config: capture_config(app, &settings)?,
+        segment_dir,
+        segments: Vec::new(),
+        output_path: output_path.clone(),
+        segment_started: None,
+        stitch_segments: settings.stitch_segments,
+        cursor: None,
+        separate_webcam_file: settings.separate_webcam_file,
+        started_at: chrono::Utc::now(),
+        clock: RecordingClock::start(),
+        markers: Vec::new(),
+    };
+
+    let mut started = start_segment(&mut session).await;
+    if started.is_err() && session.config.quality.hardware_encoding == HardwareEncoding::Auto {
+        // Hardware encoders can refuse some capture sizes; later segments stay in software too
+        eprintln!("[Recording] Capture failed to start, retrying with software encoding");
+        session.config.quality = session.config.quality.software();
+        started = start_segment(&mut session).await;
+    }
+    if let Err(e) = started {
+        let _ = fs::remove_dir_all(&session.segment_dir).await;
+        return Err(e);
+    }
+
+    if settings.record_cursor_events && session.config.screen {
+        session.cursor = cursor_area(app, &session.config).map(|area| {
+            CursorRecorder::start(
+                (area.x as f64, area.y as f64),
+                (area.width as f64, area.height as f64),
+            )
+        });
+    }
+
+    eprintln!("[Recording] Native capture started: {:?}", session.output_path);
+    *native = Some(session);
+
+    if settings.low_space_warning_mb > 0 || settings.min_free_space_mb > 0 {
+        tauri::async_runtime::spawn(watch_disk_space(
+            app.clone(),
+            output_path.clone(),
+            recordings_dir.clone(),
+            settings.low_space_warning_mb * 1024 * 1024,
+            settings.min_free_space_mb * 1024 * 1024,
+        ));
+    }
+
+    let max_duration = settings.max_segment_duration.filter(|d| *d > 0).map(Duration::from_secs);
+    let max_bytes = settings.max_segment_size_mb.filter(|mb| *mb > 0).map(|mb| mb * 1024 * 1024);
+    if max_duration.is_some() || max_bytes.is_some() {
+        tauri::async_runtime::spawn(watch_segment_limits(app.clone(), output_path, max_duration, max_bytes));
+    }
+
+    state.is_recording = true;
+    state.is_paused = false;
+    state.output_file = None;
+    state.output_files.clear();
+    state.webcam_files.clear();
+    Ok(state.clone())
+}
+
+/// Pause a native recording by closing the current segment
+#[tauri::command]
+pub async fn pause_native_recording(manager: State<'_, RecordingManager>) -> Result<RecordingState, String> {
+    let mut native = manager.native.lock().await;
+    let session = native.as_mut().ok_or("No native recording in progress")?;
+
+    if let Some(mut child) = session.child.take() {
+        finish_segment(&mut child).await;
+    }
+    if let Some(cursor) = &session.cursor {
+        cursor.set_paused(true);
+    }
+    session.clock.set_paused(true);
+
+    let mut state = manager.state.lock().await;
+    state.is_paused = true;
+    Ok(state.clone())
+}
+
+/// Resume a paused native recording in a new segment
+#[tauri::command]
+pub async fn resume_native_recording(manager: State<'_, RecordingManager>) -> Result<RecordingState, String> {
+    let mut native = manager.native.lock().await;
+    let session = native.as_mut().ok_or("No native recording in progress")?;
+
+    if session.child.is_none() {
+        start_segment(session).await?;
+    }
+    if let Some(cursor) = &session.cursor {
+        cursor.set_paused(false);
+    }
+    session.clock.set_paused(false);
+
+    let mut state = manager.state.lock().await;
+    state.is_paused = false;
+    Ok(state.clone())
+}
+
+/// `recording.mp4` -> `recording_part001.mp4`, `recording_part002.mp4`, ...
+fn segment_part_paths(output_path: &Path, count: usize) -> Vec<PathBuf> {
+    let stem = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    (1..=count)
+        .map(|part| output_path.with_file_name(format!("{}_part{:03}.mp4", stem, part)))
+        .collect()
+}
+
+/// Move segments to their final paths; rename fails across volumes, so fall back to copy
+async fn move_segments(segments: &[PathBuf], destinations: &[PathBuf]) -> Result<(), String> {
+    for (segment, destination) in segments.iter().zip(destinations) {
+        if fs::rename(segment, destination).await.is_err() {
+            fs::copy(segment, destination)
+                .await
+                .map_err(|e| format!("Failed to move recording into place: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Stop a native recording and return the MP4 path. Segments are joined unless the settings
+/// asked to keep them as parts, in which case the first part is returned and the state's
+/// output_files lists all of them.
+#[tauri::command]
+pub async fn stop_native_recording(manager: State<'_, RecordingManager>) -> Result<String, String> {
+    stop_native_session(&manager).await
+}
+
+pub(crate) async fn stop_native_session(manager: &RecordingManager) -> Result<String, String> {
+    let mut session = manager
+        .native
+        .lock()
+        .await
+        .take()
+        .ok_or("No native recording in progress")?;
+
+    if let Some(mut child) = session.child.take() {
+        finish_segment(&mut child).await;
+    }
+    let cursor_track = session.cursor.take().map(CursorRecorder::finish);
+
+    // A segment that failed to write anything is dropped rather than breaking the join
+    let segments: Vec<PathBuf> = session
+        .segments
+        .iter()
+        .filter(|path| std::fs::metadata(path).map(|m| m.len() > 0).unwrap_or(false))
+        .cloned()
+        .collect();
+
+    let mut output_files = vec![session.output_path.clone()];
+    let result = match segments.as_slice() {
+        [] => Err(format!(
+            "Recording produced no video: {}",
+            session.stderr_tail.lock().unwrap().join("\n")
+        )),
+        [single] => move_segments(std::slice::from_ref(single), &output_files).await,
+        many if session.stitch_segments => {
+            let (many, output) = (many.to_vec(), session.output_path.clone());
+            let join = move || concat_files_copy(&many, output.to_str().unwrap());
+            tauri::async_runtime::spawn_blocking(join)
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|result| result)
+                .map_err(|e| format!("Failed to join recording segments: {}", e))
+        }
+        many => {
+            output_files = segment_part_paths(&session.output_path, many.len());
+            move_segments(many, &output_files).await
+        }
+    };
+
+    let mut state = manager.state.lock().await;
+    state.is_recording = false;
+    state.is_paused = false;
+
+    if let Err(e) = result {
+        // Segments are fragmented MP4s, playable as-is, so they are kept for recovery
+        eprintln!("[Recording] Native recording failed, segments kept at {:?}", session.segment_dir);
+        return Err(e);
+    }
+    let _ = fs::remove_dir_all(&session.segment_dir).await;
+
+    // One track covers the whole recording, even when it was kept as parts
+    if let Some(track) = cursor_track.filter(|track| !track.samples.is_empty()) {
+        if let Err(e) = track.save(&CursorTrack::path_for(&session.output_path)) {
+            eprintln!("[Recording] {}", e);
+        }
+    }
+
+    // Splitting and probing run FFmpeg over the whole recording
+    let split_webcam =
+        session.separate_webcam_file && session.config.screen && session.config.webcam.is_some();
+    let started_at = session.started_at;
+    let (output_files, webcam_files, probed) = tauri::async_runtime::spawn_blocking(move || {
+        let mut webcam_files = Vec::new();
+        if split_webcam {
+            for path in &output_files {
+                match split_webcam_stream(path, &started_at) {
+                    Ok(webcam) => webcam_files.push(webcam.to_string_lossy().to_string()),
+                    // The webcam is still in the screen file as a second stream
+                    Err(e) => eprintln!("[Recording] {}", e),
+                }
+            }
+        }
+
+        let output_files: Vec<String> = output_files
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        let probed = probe_recording_metadata("native", &started_at, &output_files, &webcam_files);
+        (output_files, webcam_files, probed)
+    })
+    .await
+    .map_err(|e| format!("Failed to finish recording: {}", e))?;
+    match probed {
+        Ok(metadata) => {
+            let config = &session.config;
+            let metadata = RecordingMetadata {
+                microphone: config.microphone.clone(),
+                webcam: config.webcam.clone(),
+                system_audio: config.system_audio.clone(),
+                display: config.display.clone(),
+                capture_target: Some(config.target.clone()),
+                markers: std::mem::take(&mut session.markers),
+                ..metadata
+            };
+            save_recording_metadata(&session.output_path, &metadata);
+        }
+        Err(e) => eprintln!("[Recording] {}", e),
+    }
+    let output = output_files[0].clone();
+    eprintln!("[Recording] Native recording saved: {}", output_files.join(", "));
+    state.output_file = Some(output.clone());
+    state.output_files = output_files;
+    state.webcam_files = webcam_files;
+    Ok(output)
+}
+
+/// `recording.mp4` -> `recording_webcam.mp4`
+fn webcam_file_path(screen_path: &Path) -> PathBuf {
+    let stem = screen_path.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    screen_path.with_file_name(format!("{}_webcam.mp4", stem))
+}
+
+/// Move the webcam stream of a native recording into its own file. Both files keep the
+/// original timestamps and get the same creation_time, so they stay in sync when placed
+/// side by side. Returns the webcam file.
+fn split_webcam_stream(screen_path: &Path, started_at: &chrono::DateTime<chrono::Utc>) -> Result<PathBuf, String> {
+    let screen = screen_path.to_str().ok_or("Invalid recording path")?;
+    let webcam_path = webcam_file_path(screen_path);
+    let screen_tmp = screen_path.with_extension("split.mp4");
+    let creation_time = format!("creation_time={}", started_at.to_rfc3339());
+
+    let ffmpeg_path = get_ffmpeg_path().map_err(|e| format!("FFmpeg not found: {}", e))?;
+    let output = Command::new(ffmpeg_path)
+        .args(["-hide_banner", "-i", screen])
+        .args(["-map", "0:v:0", "-map", "0:a?", "-c", "copy", "-metadata", &creation_time])
+        .args(["-movflags", "+faststart", "-y"])
+        .arg(&screen_tmp)
+        .args(["-map", "0:v:1", "-c", "copy", "-metadata", &creation_time])
+        .args(["-movflags", "+faststart", "-y"])
+        .arg(&webcam_path)
+        .output()
+        .map_err(|e| format!("Failed to execute FFmpeg: {}", e))?;
+
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&screen_tmp);
+        let _ = std::fs::remove_file(&webcam_path);
+        return Err(format!(
+            "Failed to separate webcam: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    std::fs::rename(&screen_tmp, screen_path)
+        .map_err(|e| format!("Failed to replace recording: {}", e))?;
+    Ok(webcam_path)
+}
+
+/// Import a recording into the library. A separately saved webcam file (`webcam_path`, or
+/// `<name>_webcam.mp4` next to the recording) is imported too, and the two items are linked.
+#[tauri::command]
+pub async fn import_recording_to_library(
+    library: State<'_, LibraryManager>,
+    file_path: String,
+    webcam_path: Option<String>,
+) -> Result<Vec<MediaItem>, String> {
+    let webcam_path = webcam_path.or_else(|| {
+        let default = webcam_file_path(Path::new(&file_path));
+        default.exists().then(|| default.to_string_lossy().to_string())
+    });
+
+    let mut items = vec![import_media_file(&file_path)?];
+    items[0].markers = recording_markers_for(Path::new(&file_path), items[0].duration);
+    if let Some(webcam) = webcam_path {
+        items.push(import_media_file(&webcam)?);
+        let (screen_id, webcam_id) = (items[0].id.clone(), items[1].id.clone());
+        items[0].linked_items.push(webcam_id);
+        items[1].linked_items.push(screen_id);
+    }
+
+    library.add_items(items.clone()).await?;
+    println!("[Recording] Imported {} item(s) from {}", items.len(), file_path);
+    Ok(items)
+}
+
+/// Markers from a recording's sidecar that fall within `path`, relative to its start. A part of
+/// a recording kept in several files gets the markers of its stretch of the whole.
+fn recording_markers_for(path: &Path, duration: f64) -> Vec<RecordingMarker> {
+    let Ok(metadata) = RecordingMetadata::load(&RecordingMetadata::path_for(path)) else {
+        return Vec::new();
+    };
+    let file = path.to_string_lossy();
+    let Some(index) = metadata.files.iter().position(|f| *f == file) else {
+        return Vec::new();
+    };
+    let offset: f64 = metadata.files[..index]
+        .iter()
+        .filter_map(|f| get_video_info(f).ok())
+        .map(|info| info.duration)
+        .sum();
+
+    metadata
+        .markers
+        .into_iter()
+        .filter(|marker| marker.time >= offset && marker.time <= offset + duration)
+        .map(|marker| RecordingMarker {
+            time: marker.time - offset,
+            ..marker
+        })
+        .collect()
+}
+
+/// Composite a webcam over a screen recording as picture-in-picture. The webcam is the second
+/// video stream of a native recording, or a separate file from the browser recorder.
+fn composite_pip(
+    screen_path: &str,
+    webcam_path: Option<&str>,
+    layout: &PipLayout,
+    output_path: &str,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let info = get_video_info(screen_path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+
+    let mut args: Vec<String> = vec!["-i".to_string(), screen_path.to_string()];
+    let webcam = match webcam_path {
+        Some(path) => {
+            args.extend(["-i".to_string(), path.to_string()]);
+            "1:v:0"
+        }
+        None => "0:v:1",
+    };
+    args.extend([
+        "-filter_complex".to_string(),
+        pip_filter(layout, "0:v:0", webcam, info.width),
+        "-map".to_string(),
+        "[vout]".to_string(),
+        "-map".to_string(),
+        "0:a?".to_string(),
+    ]);
+    args.extend(
+        [
+            "-c:v", "libx264", "-preset", "fast", "-crf", "20",
+            "-c:a", "copy",
+            "-movflags", "+faststart",
+            "-y", output_path,
+        ]
+        .iter()
+        .map(|s| s.to_string()),
+    );
+
+    run_ffmpeg_with_progress(&args, info.duration, Some(cancel), on_progress)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to composite webcam: {}", e))
+}
+
+/// Render a recording with the webcam placed as picture-in-picture, in the background.
+/// Returns the job id; the job result is `{ "output_path": ... }`.
+#[tauri::command]
+pub async fn composite_webcam_pip(
+    app: AppHandle,
+    jobs: State<'_, JobManager>,
+    screen_path: String,
+    webcam_path: Option<String>,
+    layout: PipLayout,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    let screen = Path::new(&screen_path);
+    if !screen.exists() {
+        return Err(format!("File does not exist at path: {}", screen_path));
+    }
+    if webcam_path.is_none() {
+        let info = get_video_info(&screen_path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+        if info.video_stream_count < 2 {
+            return Err("Recording has no webcam stream; pass webcam_path".to_string());
+        }
+    }
+
+    let output_path = output_path.unwrap_or_else(|| {
+        let stem = screen.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+        screen
+            .with_file_name(format!("{}_pip.mp4", stem))
+            .to_string_lossy()
+            .to_string()
+    });
+    let name = Path::new(&output_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (job_id, cancel) = jobs.create(&app, "pip", &format!("Composite {}", name));
+
+    let worker_app = app.clone();
+    let worker_job = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = worker_app.state::<JobManager>();
+        let result = composite_pip(
+            &screen_path,
+            webcam_path.as_deref(),
+            &layout,
+            &output_path,
+            &cancel,
+            |percentage| jobs.set_progress(&worker_app, &worker_job, percentage),
+        );
+
+        match result {
+            Ok(()) => {
+                println!("[Recording] Webcam composited: {}", output_path);
+                jobs.complete(
+                    &worker_app,
+                    &worker_job,
+                    Some(serde_json::json!({ "output_path": output_path })),
+                );
+            }
+            Err(e) => {
+                eprintln!("[Recording] {}", e);
+                let _ = std::fs::remove_file(&output_path);
+                jobs.fail(&worker_app, &worker_job, e);
+            }
+        }
+    });
+
+    Ok(job_id)
+}
+
+fn render_cursor(
+    path: &str,
+    track: &CursorTrack,
+    effects: &CursorEffects,
+    output_path: &str,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let info = get_video_info(path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    let commands_path = Path::new(output_path).with_extension("cursor.cmd");
+    let filter = cursor_effects_filter(track, effects, info.width, info.height, &commands_path)
+        .map_err(|e| format!("Failed to build cursor effects: {}", e))?;
+
+    let args: Vec<String> = [
+        "-i", path,
+        "-filter_complex", &filter,
+        "-map", "[vout]",
+        "-map", "0:a?",
+        "-c:v", "libx264", "-preset", "fast", "-crf", "20",
+        "-c:a", "copy",
+        "-movflags", "+faststart",
+        "-y", output_path,
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    let result = run_ffmpeg_with_progress(&args, info.duration, Some(cancel), on_progress)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to render cursor effects: {}", e));
+    let _ = std::fs::remove_file(&commands_path);
+    result
+}
+
+/// Render cursor highlights and click rings into a native recording from the cursor track
+/// saved next to it, in the background. Returns the job id; the job result is
+/// `{ "output_path": ... }`.
+#[tauri::command]
+pub async fn render_cursor_effects(
+    app: AppHandle,
+    jobs: State<'_, JobManager>,
+    path: String,
+    effects: Option<CursorEffects>,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    let source = Path::new(&path);
+    if !source.exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+    let track_path = CursorTrack::path_for(source);
+    if !track_path.exists() {
+        return Err("No cursor data was recorded for this file".to_string());
+    }
+    let track = CursorTrack::load(&track_path).map_err(|e| e.to_string())?;
+    let effects = effects.unwrap_or_default();
+    if !effects.highlight && !effects.click_ripple {
+        return Err("No cursor effects enabled".to_string());
+    }
+
+    let output_path = output_path.unwrap_or_else(|| {
+        let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+        source
+            .with_file_name(format!("{}_cursor.mp4", stem))
+            .to_string_lossy()
+            .to_string()
+    });
+    let name = Path::new(&output_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (job_id, cancel) = jobs.create(&app, "cursor_effects", &format!("Cursor effects {}", name));
+
+    let worker_app = app.clone();
+    let worker_job = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = worker_app.state::<JobManager>();
+        let result = render_cursor(
+            &path,
+            &track,
+            &effects,
+            &output_path,
+            &cancel,
+            |percentage| jobs.set_progress(&worker_app, &worker_job, percentage),
+        );
+
+        match result {
+            Ok(()) => {
+                println!("[Recording] Cursor effects rendered: {}", output_path);
+                jobs.complete(
+                    &worker_app,
+                    &worker_job,
+                    Some(serde_json::json!({ "output_path": output_path })),
+                );
+            }
+            Err(e) => {
+                eprintln!("[Recording] {}", e);
+                let _ = std::fs::remove_file(&output_path);
+                jobs.fail(&worker_app, &worker_job, e);
+            }
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Longest clip recording_to_gif accepts; GIFs get huge quickly
+const MAX_GIF_SECS: f64 = 60.0;
+
+/// Encode `range` of a recording as a GIF using a palette generated from the clip itself
+fn render_gif(
+    path: &str,
+    range: &TimeRange,
+    width: u32,
+    fps: u32,
+    output_path: &str,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let filter = format!(
+        "[0:v]fps={},scale={}:-1:flags=lanczos,split[a][b];[a]palettegen=stats_mode=diff[p];\
+         [b][p]paletteuse=dither=bayer:bayer_scale=5:diff_mode=rectangle",
+        fps, width
+    );
+    let args: Vec<String> = [
+        "-ss", &format!("{:.3}", range.start),
+        "-t", &format!("{:.3}", range.end - range.start),
+        "-i", path,
+        "-filter_complex", &filter,
+        "-loop", "0",
+        "-y", output_path,
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect();
+
+    run_ffmpeg_with_progress(&args, range.end - range.start, Some(cancel), on_progress)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to create GIF: {}", e))
+}
+
+/// Turn a short range of a recording into a shareable GIF next to it, in the background (width
+/// defaults to 480 px, fps to 15). Returns the job id; the job result is `{ "output_path": ... }`.
+#[tauri::command]
+pub async fn recording_to_gif(
+    app: AppHandle,
+    jobs: State<'_, JobManager>,
+    path: String,
+    start: f64,
+    end: f64,
+    width: Option<u32>,
+    fps: Option<u32>,
+) -> Result<String, String> {
+    let source = Path::new(&path);
+    if !source.exists() {
+        return Err(format!("File does not exist at path: {}", path));
+    }
+    let info = get_video_info(&path).map_err(|e| format!("Failed to analyze video: {}", e))?;
+    let start = start.max(0.0);
+    let end = if info.duration > 0.0 { end.min(info.duration) } else { end };
+    if end <= start {
+        return Err("End must be after start".to_string());
+    }
+    if end - start > MAX_GIF_SECS {
+        return Err(format!("GIFs are limited to {} seconds", MAX_GIF_SECS));
+    }
+    let width = width.unwrap_or(480).clamp(16, info.width.max(16)) / 2 * 2;
+    let fps = fps.unwrap_or(15).clamp(1, 50);
+
+    let stem = source.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+    let output_path = source
+        .with_file_name(format!("{}_{:.0}s.gif", stem, start))
+        .to_string_lossy()
+        .to_string();
+    let range = TimeRange { start, end };
+    let name = Path::new(&output_path)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (job_id, cancel) = jobs.create(&app, "gif", &format!("GIF {}", name));
+
+    let worker_app = app.clone();
+    let worker_job = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let jobs = worker_app.state::<JobManager>();
+        let result = render_gif(
+            &path,
+            &range,
+            width,
+            fps,
+            &output_path,
+            &cancel,
+            |percentage| jobs.set_progress(&worker_app, &worker_job, percentage),
+        );
+
+        match result {
+            Ok(()) => {
+                println!("[Recording] GIF created: {}", output_path);
+                jobs.complete(
+                    &worker_app,
+                    &worker_job,
+                    Some(serde_json::json!({ "output_path": output_path })),
+                );
+            }
+            Err(e) => {
+                eprintln!("[Recording] {}", e);
+                let _ = std::fs::remove_file(&output_path);
+                jobs.fail(&worker_app, &worker_job, e);
+            }
+        }
+    });
+
+    Ok(job_id)
+}
+
+/// Default replay buffer length
+const DEFAULT_REPLAY_SECS: u64 = 120;
+
+async fn spawn_replay(
+    config: &CaptureConfig,
+    dir: &Path,
+    segment_count: u64,
+    stderr_tail: &Arc<StdMutex<Vec<String>>>,
+) -> Result<Child, String> {
+    let args = build_replay_args(config, dir.to_str().unwrap(), segment_count)
+        .map_err(|e| format!("Failed to configure capture: {}", e))?;
+    spawn_capture(&args, stderr_tail).await
+}
+
+/// Start keeping a rolling buffer of the last `length_secs` (default 2 minutes) of capture, so
+/// moments can be saved after they happen with save_replay
+#[tauri::command]
+pub async fn start_replay_buffer(
+    app: AppHandle,
+    manager: State<'_, RecordingManager>,
+    settings: Option<RecordingSettings>,
+    length_secs: Option<u64>,
+) -> Result<(), String> {
+    let mut replay = manager.replay.lock().await;
+    if replay.is_some() {
+        return Err("The replay buffer is already running".to_string());
+    }
+
+    let settings = match settings {
+        Some(settings) => settings,
+        None => manager.state.lock().await.current_settings.clone(),
+    };
+    let length_secs = length_secs.unwrap_or(DEFAULT_REPLAY_SECS).clamp(REPLAY_SEGMENT_SECS, 3600);
+    // One extra segment is being written and one may be mid-overwrite at any time
+    let segment_count = length_secs.div_ceil(REPLAY_SEGMENT_SECS) + 2;
+
+    let dir = get_recordings_dir()
+        .map_err(|e| format!("Failed to get recordings directory: {}", e))?
+        .join(format!(".replay_{}", chrono::Utc::now().format("%Y%m%d_%H%M%S")));
+    fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| format!("Failed to create replay directory: {}", e))?;
+
+    let mut config = capture_config(&app, &settings)?;
+    let stderr_tail = Arc::new(StdMutex::new(Vec::new()));
+    let mut child = spawn_replay(&config, &dir, segment_count, &stderr_tail).await;
+    if child.is_err() && config.quality.hardware_encoding == HardwareEncoding::Auto {
+        eprintln!("[Recording] Replay buffer failed to start, retrying with software encoding");
+        config.quality = config.quality.software();
+        child = spawn_replay(&config, &dir, segment_count, &stderr_tail).await;
+    }
+    let child = match child {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = fs::remove_dir_all(&dir).await;
+            return Err(e);
+        }
+    };
+
+    println!("[Recording] Replay buffer started ({}s)", length_secs);
+    *replay = Some(ReplayBuffer {
+        child,
+        stderr_tail,
+        dir,
+        length_secs,
+    });
+    Ok(())
+}
+
+/// Stop the replay buffer and discard what it held
+#[tauri::command]
+pub async fn stop_replay_buffer(manager: State<'_, RecordingManager>) -> Result<(), String> {
+    let Some(mut replay) = manager.replay.lock().await.take() else {
+        return Ok(());
+    };
+    finish_segment(&mut replay.child).await;
+    let _ = fs::remove_dir_all(&replay.dir).await;
+    println!("[Recording] Replay buffer stopped");
+    Ok(())
+}
+
+/// Save the last `seconds` of the replay buffer (all of it by default) to an MP4 and return
+/// its path. The buffer keeps running.
+#[tauri::command]
+pub async fn save_replay(
+    manager: State<'_, RecordingManager>,
+    seconds: Option<u64>,
+    output_path: Option<String>,
+) -> Result<String, String> {
+    let mut replay = manager.replay.lock().await;
+    let buffer = replay.as_mut().ok_or("The replay buffer is not running")?;
+    if let Ok(Some(status)) = buffer.child.try_wait() {
+        let log = buffer.stderr_tail.lock().unwrap().join("\n");
+        return Err(format!("Replay capture stopped ({}): {}", status, log));
+    }
+
+    // Segment names wrap around, so order by modification time, newest last
+    let mut segments: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(&buffer.dir)
+        .map_err(|e| format!("Failed to read replay buffer: {}", e))?
+        .flatten()
+        .filter(|entry| entry.path().extension().map(|e| e == "ts").unwrap_or(false))
+        .filter_map(|entry| {
+            let metadata = entry.metadata().ok()?;
+            (metadata.len() > 0).then(|| (metadata.modified().ok(), entry.path()))
+        })
+        .filter_map(|(modified, path)| Some((modified?, path)))
+        .collect();
+    segments.sort();
+
+    let wanted = seconds.unwrap_or(buffer.length_secs).min(buffer.length_secs);
+    let count = (wanted.div_ceil(REPLAY_SEGMENT_SECS) as usize + 1).min(segments.len());
+    if count == 0 {
+        return Err("The replay buffer is empty".to_string());
+    }
+
+    // Copy first so FFmpeg can't overwrite the oldest segment while it's being joined
+    let staging = buffer.dir.join(format!("save_{}", chrono::Utc::now().timestamp_millis()));
+    fs::create_dir_all(&staging)
+        .await
+        .map_err(|e| format!("Failed to stage replay: {}", e))?;
+    let mut staged = Vec::with_capacity(count);
+    for (index, (_, path)) in segments[segments.len() - count..].iter().enumerate() {
+        let copy = staging.join(format!("{:03}.ts", index));
+        fs::copy(path, &copy)
+            .await
+            .map_err(|e| format!("Failed to stage replay: {}", e))?;
+        staged.push(copy);
+    }
+    drop(replay);
+
+    let output = match output_path {
+        Some(path) => path,
+        None => get_recordings_dir()
+            .map_err(|e| format!("Failed to get recordings directory: {}", e))?
+            .join(format!("replay_{}.mp4", chrono::Utc::now().format("%Y%m%d_%H%M%S")))
+            .to_string_lossy()
+            .to_string(),
+    };
+    let join_output = output.clone();
+    let join = move || concat_files_copy(&staged, &join_output);
+    let result = tauri::async_runtime::spawn_blocking(join)
+        .await
+        .map_err(anyhow::Error::from)
+        .and_then(|result| result)
+        .map_err(|e| format!("Failed to save replay: {}", e));
+    let _ = fs::remove_dir_all(&staging).await;
+    result?;
+
+    println!("[Recording] Replay saved: {}", output);
+    Ok(output)
+}
+
+/// Start recording narration from `microphone` (the current settings' microphone, or the first
+/// one found, when None). Timeline playback from `timeline_position` should start as soon as
+/// this returns; `latency_ms` is the round-trip audio latency to compensate for.
+#[tauri::command]
+pub async fn start_voiceover(
+    manager: State<'_, RecordingManager>,
+    timeline_position: f64,
+    microphone: Option<String>,
+    latency_ms: Option<f64>,
+    track_index: Option<u32>,
+) -> Result<String, String> {
+    let mut voiceover = manager.voiceover.lock().await;
+    if voiceover.is_some() {
+        return Err("A voiceover is already being recorded".to_string());
+    }
+
+    let microphone = match microphone.or(manager.state.lock().await.current_settings.microphone.clone()) {
+        Some(device) => device,
+        None => list_capture_devices(DeviceKind::Audio)
+            .map_err(|e| format!("Failed to list microphones: {}", e))?
+            .into_iter()
+            .next()
+            .map(|device| device.id)
+            .ok_or("No microphone found")?,
+    };
+
+    let recordings_dir = get_recordings_dir()
+        .map_err(|e| format!("Failed to get recordings directory: {}", e))?;
+    let timestamp = chrono::Utc::now().format("%Y%m%d_%H%M%S");
+    let output_path = recordings_dir.join(format!("voiceover_{}.m4a", timestamp));
+
+    let mut args: Vec<String> = vec!["-hide_banner".to_string()];
+    args.extend(audio_input_args(&microphone));
+    args.extend(
+        // Fragmented like native captures so a crash keeps everything up to the last second
+        [
+            "-c:a", "aac", "-b:a", "192k",
+            "-frag_duration", "1000000",
+            "-movflags", "+empty_moov+default_base_moof",
+            "-y", output_path.to_str().ok_or("Invalid output path")?,
+        ]
+            .iter()
+            .map(|s| s.to_string()),
+    );
+
+    let stderr_tail = Arc::new(StdMutex::new(Vec::new()));
+    let child = spawn_capture(&args, &stderr_tail).await?;
+
+    println!("[Recording] Voiceover started at timeline {:.3}s: {:?}", timeline_position, output_path);
+    *voiceover = Some(VoiceoverSession {
+        child,
+        stderr_tail,
+        output_path: output_path.clone(),
+        timeline_start: timeline_position.max(0.0),
+        latency: latency_ms.unwrap_or(0.0).max(0.0) / 1000.0,
+        track_index,
+        playback_started: Instant::now(),
+    });
+
+    Ok(output_path.to_string_lossy().to_string())
+}
+
+/// Stop the voiceover and return a clip placed at the timeline position it was started from,
+/// trimmed so its audio lines up with what was playing
+#[tauri::command]
+pub async fn stop_voiceover(manager: State<'_, RecordingManager>) -> Result<Clip, String> {
+    let mut session = manager
+        .voiceover
+        .lock()
+        .await
+        .take()
+        .ok_or("No voiceover in progress")?;
+
+    let stopped = Instant::now();
+    finish_segment(&mut session.child).await;
+
+    let output = session.output_path.to_string_lossy().to_string();
+    let info = get_video_info(&output).map_err(|e| {
+        format!(
+            "Voiceover produced no audio: {} ({})",
+            e,
+            session.stderr_tail.lock().unwrap().join("\n")
+        )
+    })?;
+
+    // The file ends when capture stopped, so its start can be placed on the wall clock without
+    // knowing how long the device took to open; playback began `played` seconds before the end
+    let played = (stopped - session.playback_started).as_secs_f64();
+    let trim_start = ((info.duration - played).max(0.0) + session.latency).min(info.duration);
+    let clip = Clip {
+        id: uuid::Uuid::new_v4().to_string(),
+        file_path: output,
+        start_time: session.timeline_start,
+        trim_start,
+        trim_end: info.duration,
+        duration: info.duration - trim_start,
+        speed: 1.0,
+        track_index: session.track_index,
+        crop: None,
+        audio_track: None,
+        gain_db: 0.0,
+        pan: 0.0,
+        fade_in_sec: 0.0,
+        fade_out_sec: 0.0,
+        fade_video: false,
+        denoise_audio: None,
+        audio_effects: vec![],
+        normalize_lufs: None,
+        audio_override: None,
+    };
+
+    println!(
+        "[Recording] Voiceover saved: {} ({:.2}s at timeline {:.3}s)",
+        clip.file_path, clip.duration, clip.start_time
+    );
+    Ok(clip)
+}
+
+/// Read the sidecar describing how a recording was made (devices, display, resolution, fps,
+/// duration, markers, app version)
+#[tauri::command]
+pub async fn get_recording_metadata(path: String) -> Result<RecordingMetadata, String> {
+    let sidecar = RecordingMetadata::path_for(Path::new(&path));
+    if !sidecar.exists() {
+        return Err(format!("No recording metadata found for {}", path));
+    }
+    RecordingMetadata::load(&sidecar).map_err(|e| e.to_string())
+}
+
+/// Mark the current moment of the recording in progress (from a hotkey or the UI). The time is
+/// seconds into the recording excluding pauses; browser recordings may pass `time` from the
+/// MediaRecorder instead. Markers are written to the recording's sidecar and copied to the
+/// library item on import.
+#[tauri::command]
+pub async fn add_recording_marker(
+    manager: State<'_, RecordingManager>,
+    label: Option<String>,
+    time: Option<f64>,
+) -> Result<RecordingMarker, String> {
+    let label = label.map(|label| label.trim().to_string()).filter(|label| !label.is_empty());
+    if let Some(time) = time.filter(|time| !time.is_finite() || *time < 0.0) {
+        return Err(format!("Invalid marker time: {}", time));
+    }
+
+    let mut native = manager.native.lock().await;
+    let mut browser = manager.browser.lock().await;
+    let (clock, markers) = match (native.as_mut(), browser.as_mut()) {
+        (Some(session), _) => (&session.clock, &mut session.markers),
+        (None, Some(session)) => (&session.clock, &mut session.markers),
+        (None, None) => return Err("No recording in progress".to_string()),
+    };
+
+    let marker = RecordingMarker {
+        time: time.unwrap_or_else(|| clock.elapsed()),
+        label,
+    };
+    markers.push(marker.clone());
+    println!("[Recording] Marker at {:.2}s", marker.time);
+    Ok(marker)
+}
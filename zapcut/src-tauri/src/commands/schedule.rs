@@ -0,0 +1,200 @@
+use crate::commands::recording::{
+    start_native_session, stop_native_after, RecordingManager, RecordingSettings,
+};
+use crate::utils::app_init::initialize_app_directories;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+/// How often due schedules are checked
+const SCHEDULER_INTERVAL_SECS: u64 = 5;
+/// A schedule whose start passed longer ago than this (app closed, machine asleep) is
+/// reported as missed instead of starting late
+const MISSED_GRACE_SECS: i64 = 300;
+
+/// A native recording set to start at a given time and optionally stop itself
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingSchedule {
+    pub id: String,
+    pub label: Option<String>,
+    /// RFC 3339 start time
+    pub start_at: String,
+    pub stop_after_secs: Option<u64>,
+    /// Settings to record with; the current recording settings when None
+    pub settings: Option<RecordingSettings>,
+    pub created_at: String,
+}
+
+/// Payload of `recording://schedule`
+#[derive(Debug, Serialize, Clone)]
+pub struct ScheduleEvent {
+    pub schedule_id: String,
+    /// "started", "failed" or "missed"
+    pub action: String,
+    pub error: Option<String>,
+}
+
+/// Pending recording schedules, persisted as recording_schedules.json in the Zapcut directory
+pub struct RecordingScheduler {
+    schedules: Mutex<Vec<RecordingSchedule>>,
+}
+
+impl RecordingScheduler {
+    pub fn new() -> Self {
+        let schedules = Self::schedules_path()
+            .ok()
+            .and_then(|path| std::fs::read_to_string(path).ok())
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Self {
+            schedules: Mutex::new(schedules),
+        }
+    }
+
+    fn schedules_path() -> anyhow::Result<PathBuf> {
+        Ok(initialize_app_directories()?.join("recording_schedules.json"))
+    }
+
+    fn save(schedules: &[RecordingSchedule]) -> Result<(), String> {
+        let path = Self::schedules_path()
+            .map_err(|e| format!("Failed to locate schedules file: {}", e))?;
+        let json = serde_json::to_string_pretty(schedules)
+            .map_err(|e| format!("Failed to serialize schedules: {}", e))?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write schedules: {}", e))
+    }
+
+    /// Remove and return every schedule whose start time has come
+    fn take_due(&self, now: DateTime<Utc>) -> Vec<RecordingSchedule> {
+        let mut schedules = self.schedules.lock().unwrap();
+        let (due, pending): (Vec<_>, Vec<_>) = schedules
+            .drain(..)
+            .partition(|schedule| parse_time(&schedule.start_at).map(|t| t <= now).unwrap_or(true));
+        *schedules = pending;
+        if !due.is_empty() {
+            if let Err(e) = Self::save(&schedules) {
+                eprintln!("[Recording] {}", e);
+            }
+        }
+        due
+    }
+}
+
+fn parse_time(value: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid time {}: {}", value, e))
+}
+
+fn emit_schedule_event(app: &AppHandle, schedule_id: &str, action: &str, error: Option<String>) {
+    let _ = app.emit(
+        "recording://schedule",
+        ScheduleEvent {
+            schedule_id: schedule_id.to_string(),
+            action: action.to_string(),
+            error,
+        },
+    );
+}
+
+async fn trigger_schedule(app: &AppHandle, schedule: RecordingSchedule, now: DateTime<Utc>) {
+    let late_by = parse_time(&schedule.start_at)
+        .map(|start| (now - start).num_seconds())
+        .unwrap_or(i64::MAX);
+    if late_by > MISSED_GRACE_SECS {
+        eprintln!("[Recording] Schedule {} missed by {}s", schedule.id, late_by);
+        emit_schedule_event(app, &schedule.id, "missed", None);
+        return;
+    }
+
+    let manager = app.state::<RecordingManager>();
+    if let Err(e) = start_native_session(app, &manager, schedule.settings.clone()).await {
+        eprintln!("[Recording] Scheduled recording {} failed to start: {}", schedule.id, e);
+        emit_schedule_event(app, &schedule.id, "failed", Some(e));
+        return;
+    }
+    println!("[Recording] Scheduled recording {} started", schedule.id);
+
+    let error = match schedule.stop_after_secs {
+        Some(secs) => stop_native_after(app, Duration::from_secs(secs), "schedule").await.err(),
+        None => None,
+    };
+    emit_schedule_event(app, &schedule.id, "started", error);
+}
+
+/// Background loop that starts schedules when they come due; spawned once at startup
+pub async fn run_recording_scheduler(app: AppHandle) {
+    loop {
+        tokio::time::sleep(Duration::from_secs(SCHEDULER_INTERVAL_SECS)).await;
+        let now = Utc::now();
+        let due = app.state::<RecordingScheduler>().take_due(now);
+        for schedule in due {
+            trigger_schedule(&app, schedule, now).await;
+        }
+    }
+}
+
+/// Schedule a native recording to start at `start_at` (RFC 3339) and optionally stop itself
+#[tauri::command]
+pub fn schedule_recording(
+    scheduler: State<'_, RecordingScheduler>,
+    start_at: String,
+    stop_after_secs: Option<u64>,
+    settings: Option<RecordingSettings>,
+    label: Option<String>,
+) -> Result<RecordingSchedule, String> {
+    let start = parse_time(&start_at)?;
+    if start < Utc::now() {
+        return Err("Start time is in the past".to_string());
+    }
+    if stop_after_secs == Some(0) {
+        return Err("Stop duration must be positive".to_string());
+    }
+
+    let schedule = RecordingSchedule {
+        id: uuid::Uuid::new_v4().to_string(),
+        label,
+        start_at: start.to_rfc3339(),
+        stop_after_secs,
+        settings,
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    let mut schedules = scheduler.schedules.lock().unwrap();
+    schedules.push(schedule.clone());
+    schedules.sort_by(|a, b| a.start_at.cmp(&b.start_at));
+    RecordingScheduler::save(&schedules)?;
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub fn list_recording_schedules(scheduler: State<'_, RecordingScheduler>) -> Vec<RecordingSchedule> {
+    scheduler.schedules.lock().unwrap().clone()
+}
+
+/// Remove a pending schedule; returns false if it already ran or never existed
+#[tauri::command]
+pub fn cancel_recording_schedule(
+    scheduler: State<'_, RecordingScheduler>,
+    schedule_id: String,
+) -> Result<bool, String> {
+    let mut schedules = scheduler.schedules.lock().unwrap();
+    let before = schedules.len();
+    schedules.retain(|schedule| schedule.id != schedule_id);
+    if schedules.len() == before {
+        return Ok(false);
+    }
+    RecordingScheduler::save(&schedules)?;
+    Ok(true)
+}
+
+/// Stop the running native recording after the given number of seconds
+#[tauri::command]
+pub async fn set_recording_auto_stop(app: AppHandle, stop_after_secs: u64) -> Result<(), String> {
+    if stop_after_secs == 0 {
+        return Err("Stop duration must be positive".to_string());
+    }
+    stop_native_after(&app, Duration::from_secs(stop_after_secs), "timer").await
+}
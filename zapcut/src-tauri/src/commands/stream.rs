@@ -0,0 +1,1015 @@
+use crate::commands::clip_stream::ClipStreams;
+use crate::commands::library::LibraryManager;
+use crate::commands::media::{
+    ensure_frame_thumbnail, ensure_subtitle_srt, ensure_waveform, generate_filmstrip,
+    FRAME_THUMBNAIL_STEP_MS,
+};
+use crate::commands::playback::{PlaybackEngine, PLAYBACK_FRAME_PATH};
+use crate::commands::stream_metrics::{StreamMetrics, StreamRequestRecord};
+use crate::utils::app_init::initialize_app_directories;
+use crate::utils::ffmpeg::{probe_format, transcode_window_fmp4, ProbedFormat};
+use crate::utils::mp4::init_segment;
+use crate::utils::prerender_cache::{hls_dir, segment_path};
+use crate::utils::subtitles::cached_vtt;
+use http::header::*;
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tauri::{command, AppHandle, Manager};
+
+/// Audio and video files up to this size are sent whole when the webview doesn't ask for a range
+const MAX_FULL_RESPONSE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Most bytes returned for an open-ended range such as `bytes=1000-`; the webview asks for
+/// the rest as it needs it
+const STREAM_CHUNK_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Probed MIME types remembered before the map is cleared
+const MAX_PROBED_CONTENT_TYPES: usize = 512;
+
+lazy_static::lazy_static! {
+    /// MIME types of files without a recognised extension, by path
+    static ref PROBED_CONTENT_TYPES: Mutex<HashMap<String, &'static str>> =
+        Mutex::new(HashMap::new());
+    /// Secret the webview must pass as `?token=` on every stream request; new each launch so
+    /// URLs from a previous session (or guessed by other content) are refused
+    static ref STREAM_TOKEN: String = uuid::Uuid::new_v4().simple().to_string();
+    static ref READ_LIMITER: ReadLimiter = ReadLimiter::new();
+    /// Assets being generated, by asset key; see generation_guard
+    static ref GENERATION_LOCKS: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>> =
+        Mutex::new(HashMap::new());
+    /// Recently transcoded windows by (path, start, duration, height), most recent last
+    static ref TRANSCODE_CACHE: Mutex<VecDeque<(String, Arc<Vec<u8>>)>> =
+        Mutex::new(VecDeque::new());
+}
+
+/// Reads of any files running at once
+const MAX_CONCURRENT_READS: usize = 4;
+/// Reads of one file running at once; the webview may legitimately want two ranges of a file
+/// (e.g. the moov at the end while playing from the start)
+const MAX_CONCURRENT_FILE_READS: usize = 2;
+/// Files whose read state is kept before idle ones are forgotten
+const MAX_TRACKED_FILES: usize = 64;
+
+struct FileReads {
+    permits: Arc<Semaphore>,
+    /// Bumped by every request for the file, per reader
+    latest: HashMap<String, Arc<AtomicU64>>,
+}
+
+/// Limits concurrent disk reads globally and per file. A request that has to wait is dropped
+/// if a newer request for the same file from the same reader arrives meanwhile: while
+/// scrubbing, each seek supersedes the ones before it, and serving those would only delay the
+/// one that matters. Readers (e.g. the two video elements of the player) identify themselves
+/// with `reader=` or READER_HEADER, so one never starves another reading the same file;
+/// requests that don't are never dropped.
+struct ReadLimiter {
+    global: Arc<Semaphore>,
+    files: Mutex<HashMap<String, FileReads>>,
+}
+
+/// Held for the duration of a read
+struct ReadPermit {
+    _file: OwnedSemaphorePermit,
+    _global: OwnedSemaphorePermit,
+}
+
+impl ReadLimiter {
+    fn new() -> Self {
+        Self {
+            global: Arc::new(Semaphore::new(MAX_CONCURRENT_READS)),
+            files: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Wait for a turn to read `file_path`. None when a newer request for the file from the
+    /// same `reader` arrived while waiting.
+    async fn acquire(&self, file_path: &str, reader: Option<&str>) -> Option<ReadPermit> {
+        let (permits, latest) = {
+            let mut files = self.files.lock().unwrap();
+            if files.len() >= MAX_TRACKED_FILES {
+                files.retain(|_, reads| Arc::strong_count(&reads.permits) > 1);
+            }
+            let reads = files.entry(file_path.to_string()).or_insert_with(|| FileReads {
+                permits: Arc::new(Semaphore::new(MAX_CONCURRENT_FILE_READS)),
+                latest: HashMap::new(),
+            });
+            let latest = reader.map(|reader| {
+                let latest = reads.latest.entry(reader.to_string()).or_default().clone();
+                let ticket = latest.fetch_add(1, Ordering::SeqCst) + 1;
+                (latest, ticket)
+            });
+            (reads.permits.clone(), latest)
+        };
+        let superseded = || {
+            latest
+                .as_ref()
+                .is_some_and(|(latest, ticket)| latest.load(Ordering::SeqCst) != *ticket)
+        };
+
+        let file = permits.acquire_owned().await.ok()?;
+        if superseded() {
+            return None;
+        }
+        let global = self.global.clone().acquire_owned().await.ok()?;
+        if superseded() {
+            return None;
+        }
+        Some(ReadPermit { _file: file, _global: global })
+    }
+}
+
+/// Token to append to stream:// URLs for this session
+#[command]
+pub fn get_stream_token() -> String {
+    STREAM_TOKEN.clone()
+}
+
+/// Marks responses served from an in-memory cache, for StreamMetrics
+const CACHE_HIT_HEADER: &str = "x-zapcut-cache";
+
+/// Header that, like the `proxy=1` query parameter, asks for a file's proxy when it has one
+const PROXY_HEADER: &str = "x-zapcut-proxy";
+
+/// Header that, like the `reader=` query parameter, names the element or stream a request
+/// comes from; see ReadLimiter
+const READER_HEADER: &str = "x-zapcut-reader";
+
+fn query_param<'a>(uri: &'a http::Uri, name: &str) -> Option<&'a str> {
+    uri.query()
+        .unwrap_or_default()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Route of the HLS preview playlists and chunks under the prerender cache's hls directory
+const HLS_ROUTE: &str = "/__hls/";
+/// Route of fragmented prerenders split for Media Source Extensions:
+/// `/__fmp4/<cache key>/init.mp4` and `/__fmp4/<cache key>/media.m4s`
+const FMP4_ROUTE: &str = "/__fmp4/";
+
+/// Route of live transcodes of sources the webview can't play:
+/// `/__transcode/<URL-encoded path>?start=<seconds>&duration=<seconds>&height=<pixels>`
+const TRANSCODE_ROUTE: &str = "/__transcode/";
+/// Seconds transcoded per request unless asked otherwise, and the most allowed
+const TRANSCODE_WINDOW_SECONDS: f64 = 10.0;
+const MAX_TRANSCODE_WINDOW_SECONDS: f64 = 30.0;
+/// Default and largest height of live transcodes; they're for previewing only
+const TRANSCODE_HEIGHT: u32 = 720;
+const MAX_TRANSCODE_HEIGHT: u32 = 1080;
+/// Transcoded windows kept in memory for replays and seeks back
+const MAX_CACHED_TRANSCODES: usize = 6;
+
+/// Route of gapless clip streams opened with open_clip_stream: `/__clips/<id>`
+const CLIPS_ROUTE: &str = "/__clips/";
+/// Longest a read of a clip stream waits for FFmpeg to write the bytes it asks for
+const CLIP_STREAM_WAIT: Duration = Duration::from_secs(30);
+
+/// Authorized stream:// URL of an open clip stream
+pub(crate) fn clip_stream_url(id: &str) -> String {
+    format!("stream://localhost{}{}?token={}", CLIPS_ROUTE, id, STREAM_TOKEN.as_str())
+}
+
+/// Authorized stream:// URL of a file in the HLS directory, given relative to it
+pub(crate) fn hls_url(relative: &Path) -> String {
+    let encoded: Vec<String> = relative
+        .components()
+        .map(|component| urlencoding::encode(&component.as_os_str().to_string_lossy()).into_owned())
+        .collect();
+    format!(
+        "stream://localhost{}{}?token={}",
+        HLS_ROUTE,
+        encoded.join("/"),
+        STREAM_TOKEN.as_str()
+    )
+}
+
+fn parsed_param<T: std::str::FromStr>(uri: &http::Uri, name: &str) -> Option<T> {
+    query_param(uri, name).and_then(|value| value.parse().ok())
+}
+
+/// Who is reading, for ReadLimiter's supersession
+fn reader_id(request: &http::Request<Vec<u8>>) -> Option<&str> {
+    query_param(request.uri(), "reader")
+        .or_else(|| request.headers().get(READER_HEADER).and_then(|value| value.to_str().ok()))
+        .filter(|reader| !reader.is_empty())
+}
+
+fn has_valid_token(uri: &http::Uri) -> bool {
+    query_param(uri, "token") == Some(STREAM_TOKEN.as_str())
+}
+
+fn wants_proxy(request: &http::Request<Vec<u8>>) -> bool {
+    let enabled = |value: &str| matches!(value, "1" | "true");
+    query_param(request.uri(), "proxy").is_some_and(enabled)
+        || request
+            .headers()
+            .get(PROXY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(enabled)
+}
+
+/// The proxy of a library file, when one has been generated and is still on disk
+async fn proxy_for(app: &AppHandle, file_path: &str) -> Option<String> {
+    let library = app.state::<LibraryManager>();
+    let library = library.library.lock().await;
+    library
+        .items
+        .iter()
+        .filter(|item| item.file_path == file_path)
+        .find_map(|item| item.proxy_path.clone())
+        .filter(|proxy| Path::new(proxy).is_file())
+}
+
+/// Directories whose files may always be streamed: the Zapcut folder (recordings, exports,
+/// and every cache, prerenders and proxies included)
+fn stream_roots() -> Vec<PathBuf> {
+    initialize_app_directories()
+        .ok()
+        .into_iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .collect()
+}
+
+/// Whether `file_path` may be served: inside one of the stream roots, or a file (or its proxy,
+/// transcode or thumbnail) in the media library
+pub(crate) async fn is_in_scope(app: &AppHandle, file_path: &str) -> bool {
+    if let Ok(canonical) = Path::new(file_path).canonicalize() {
+        if stream_roots().iter().any(|root| canonical.starts_with(root)) {
+            return true;
+        }
+    }
+
+    let library = app.state::<LibraryManager>();
+    let library = library.library.lock().await;
+    library.items.iter().any(|item| {
+        item.file_path == file_path
+            || item.proxy_path.as_deref() == Some(file_path)
+            || item.thumbnail_path.as_deref() == Some(file_path)
+            || item.transcode.as_ref().is_some_and(|transcode| transcode.path == file_path)
+    })
+}
+
+/// Read `length` bytes from `offset` of a file through a buffer, without loading the rest
+pub(crate) fn read_file_range(path: &Path, offset: u64, length: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut data = Vec::with_capacity(length as usize);
+    BufReader::new(file).take(length).read_to_end(&mut data)?;
+    Ok(data)
+}
+
+/// MIME type for a file extension (lowercase), None when the extension isn't known
+fn extension_content_type(extension: &str) -> Option<&'static str> {
+    Some(match extension {
+        // Video
+        "mp4" | "m4v" => "video/mp4",
+        "mov" | "qt" => "video/quicktime",
+        "webm" => "video/webm",
+        "avi" => "video/x-msvideo",
+        "mkv" => "video/x-matroska",
+        "ts" | "mts" | "m2ts" => "video/mp2t",
+        "mpg" | "mpeg" => "video/mpeg",
+        "3gp" => "video/3gpp",
+        "ogv" => "video/ogg",
+        "flv" => "video/x-flv",
+        "wmv" => "video/x-ms-wmv",
+        "m3u8" => "application/vnd.apple.mpegurl",
+        "m4s" => "video/iso.segment",
+        // Audio
+        "mp3" => "audio/mpeg",
+        "m4a" | "m4b" => "audio/mp4",
+        "aac" => "audio/aac",
+        "wav" | "wave" => "audio/wav",
+        "flac" => "audio/flac",
+        "ogg" | "oga" => "audio/ogg",
+        "opus" => "audio/opus",
+        "weba" => "audio/webm",
+        "aif" | "aiff" | "aifc" => "audio/aiff",
+        "mka" => "audio/x-matroska",
+        "caf" => "audio/x-caf",
+        // Images
+        "jpg" | "jpeg" | "jfif" => "image/jpeg",
+        "png" => "image/png",
+        "webp" => "image/webp",
+        "gif" => "image/gif",
+        "bmp" => "image/bmp",
+        "svg" => "image/svg+xml",
+        "avif" => "image/avif",
+        "heic" | "heif" => "image/heic",
+        "tif" | "tiff" => "image/tiff",
+        "ico" => "image/x-icon",
+        // Data
+        "json" => "application/json",
+        "vtt" => "text/vtt",
+        "srt" => "application/x-subrip",
+        _ => return None,
+    })
+}
+
+/// MIME type for an ffprobe container name and the kinds of streams found in it
+fn probed_content_type(probed: &ProbedFormat) -> &'static str {
+    let demuxers: Vec<&str> = probed.format_name.split(',').collect();
+    let has = |name: &str| demuxers.contains(&name);
+    let video = probed.has_video;
+    if has("mp4") || has("mov") {
+        if video { "video/mp4" } else { "audio/mp4" }
+    } else if has("webm") || has("matroska") {
+        if video { "video/webm" } else { "audio/webm" }
+    } else if has("mpegts") {
+        "video/mp2t"
+    } else if has("ogg") {
+        if video { "video/ogg" } else { "audio/ogg" }
+    } else if has("avi") {
+        "video/x-msvideo"
+    } else if has("mp3") {
+        "audio/mpeg"
+    } else if has("wav") {
+        "audio/wav"
+    } else if has("flac") {
+        "audio/flac"
+    } else if has("aac") {
+        "audio/aac"
+    } else if has("png_pipe") {
+        "image/png"
+    } else if has("webp_pipe") {
+        "image/webp"
+    } else if has("jpeg_pipe") || has("image2") {
+        "image/jpeg"
+    } else if video {
+        "video/mp4"
+    } else if probed.has_audio {
+        "audio/mpeg"
+    } else {
+        "application/octet-stream"
+    }
+}
+
+/// MIME type of a file: from its extension when known, otherwise by probing it (remembered per
+/// path so a file is only probed once)
+fn content_type(file_path: &str) -> &'static str {
+    let extension = Path::new(file_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+        .unwrap_or_default();
+    if let Some(content_type) = extension_content_type(&extension) {
+        return content_type;
+    }
+
+    if let Some(content_type) = PROBED_CONTENT_TYPES.lock().unwrap().get(file_path) {
+        return content_type;
+    }
+    let content_type = match probe_format(file_path) {
+        Ok(probed) => probed_content_type(&probed),
+        Err(e) => {
+            eprintln!("[Stream] Failed to probe {}: {}", file_path, e);
+            "application/octet-stream"
+        }
+    };
+    let mut probed = PROBED_CONTENT_TYPES.lock().unwrap();
+    if probed.len() >= MAX_PROBED_CONTENT_TYPES {
+        probed.clear();
+    }
+    probed.insert(file_path.to_string(), content_type);
+    content_type
+}
+
+/// Parse a single `bytes=start-end` range (end inclusive, either side optional) against a
+/// file of `size` bytes. None when it can't be satisfied.
+fn parse_range(header: &str, size: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    // Only the first of several ranges is served
+    let (start, end) = spec.split(',').next()?.trim().split_once('-')?;
+    let (start, end) = match (start.trim(), end.trim()) {
+        // A suffix range: the last N bytes
+        ("", suffix) => {
+            let suffix: u64 = suffix.parse().ok()?;
+            (size.saturating_sub(suffix), size.checked_sub(1)?)
+        }
+        (start, "") => {
+            let start: u64 = start.parse().ok()?;
+            (start, (start + STREAM_CHUNK_BYTES).min(size).checked_sub(1)?)
+        }
+        (start, end) => {
+            let start: u64 = start.parse().ok()?;
+            let end: u64 = end.parse().ok()?;
+            (start, end.min(size.checked_sub(1)?))
+        }
+    };
+    (start <= end && start < size).then_some((start, end))
+}
+
+fn status(code: u16) -> http::Response<Vec<u8>> {
+    http::Response::builder().status(code).body(Vec::new()).unwrap()
+}
+
+/// Answer to a read dropped for a newer request of the same file; the webview has normally
+/// abandoned it already
+fn superseded() -> http::Response<Vec<u8>> {
+    http::Response::builder()
+        .header(RETRY_AFTER, "0")
+        .status(503)
+        .body(Vec::new())
+        .unwrap()
+}
+
+/// Library thumbnails can be regenerated in place, so they're only cached briefly
+const THUMBNAIL_CACHE_CONTROL: &str = "private, max-age=300";
+/// Waveforms and filmstrips are keyed by media id and size and never change once generated
+const GENERATED_CACHE_CONTROL: &str = "private, max-age=86400";
+
+/// Frame thumbnails of `/__thumb/<id>?t=` are this wide unless `width` says otherwise
+const FRAME_THUMBNAIL_WIDTH: u32 = 320;
+
+/// Wait for exclusive use of `key` while generating the asset it names. Concurrent requests for
+/// an uncached asset queue here behind the first one, then find it cached instead of each
+/// running FFmpeg.
+async fn generation_guard(key: String) -> tokio::sync::OwnedMutexGuard<()> {
+    let lock = {
+        let mut locks = GENERATION_LOCKS.lock().unwrap();
+        // Nobody is waiting on these
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(key).or_default().clone()
+    };
+    lock.lock_owned().await
+}
+
+/// Generated assets of library items, by media id:
+/// - `/__thumb/<id>`: the library thumbnail, or with `t` (seconds) the frame at that time,
+///   `width` pixels wide
+/// - `/__waveform/<id>`: waveform peaks as JSON (WaveformData)
+/// - `/__filmstrip/<id>/sprite.jpg` and `/__filmstrip/<id>/index.json`, sized by the `count` and
+///   `width` query parameters
+/// - `/__captions/<id>/<stream index>.vtt`: an embedded subtitle track as WebVTT for <track>
+///
+/// Frames, waveforms, filmstrips and captions are generated the first time they're asked for,
+/// once however many requests arrive meanwhile. None when the path isn't one of these routes.
+async fn serve_media_asset(
+    app: &AppHandle,
+    request: &http::Request<Vec<u8>>,
+    path: &str,
+) -> Option<http::Response<Vec<u8>>> {
+    let (kind, rest) = path.strip_prefix("/__")?.split_once('/')?;
+    if !matches!(kind, "thumb" | "waveform" | "filmstrip" | "captions") {
+        return None;
+    }
+    let (media_id, file) = rest.split_once('/').unwrap_or((rest, ""));
+
+    let item = {
+        let library = app.state::<LibraryManager>();
+        let library = library.library.lock().await;
+        library.items.iter().find(|item| item.id == media_id).cloned()
+    };
+    let Some(item) = item else {
+        return Some(status(404));
+    };
+
+    let response = match kind {
+        "thumb" => match parsed_param::<f64>(request.uri(), "t") {
+            Some(timestamp) => {
+                let width = parsed_param(request.uri(), "width").unwrap_or(FRAME_THUMBNAIL_WIDTH);
+                let step = FRAME_THUMBNAIL_STEP_MS as f64 / 1000.0;
+                let rounded = (timestamp.max(0.0) / step).round() as u64;
+                let key = format!("frame:{}:{}:{}", item.id, rounded, width);
+                let _guard = generation_guard(key).await;
+                let source = item.file_path.clone();
+                let frame = tauri::async_runtime::spawn_blocking(move || {
+                    ensure_frame_thumbnail(&source, timestamp, width)
+                })
+                .await
+                .map_err(|e| e.to_string())
+                .and_then(|result| result);
+                match frame {
+                    Ok((frame_path, _)) => serve_file(
+                        request,
+                        &frame_path.to_string_lossy(),
+                        Some(GENERATED_CACHE_CONTROL),
+                    ),
+                    Err(e) => {
+                        eprintln!(
+                            "[Stream] Frame of {} at {:.2}s failed: {}",
+                            item.id, timestamp, e
+                        );
+                        status(500)
+                    }
+                }
+            }
+            None => match &item.thumbnail_path {
+                Some(thumbnail) => serve_file(request, thumbnail, Some(THUMBNAIL_CACHE_CONTROL)),
+                None => status(404),
+            },
+        },
+        "waveform" => {
+            let _guard = generation_guard(format!("waveform:{}", item.id)).await;
+            let (media_id, source) = (item.id.clone(), item.file_path.clone());
+            let waveform = tauri::async_runtime::spawn_blocking(move || {
+                ensure_waveform(&media_id, &source)
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+            match waveform {
+                Ok(waveform_path) => serve_file(
+                    request,
+                    &waveform_path.to_string_lossy(),
+                    Some(GENERATED_CACHE_CONTROL),
+                ),
+                Err(e) => {
+                    eprintln!("[Stream] Waveform for {} failed: {}", item.id, e);
+                    status(500)
+                }
+            }
+        }
+        "captions" => {
+            let Some(stream_index) = file.strip_suffix(".vtt").and_then(|index| index.parse().ok())
+            else {
+                return Some(status(404));
+            };
+            let _guard = generation_guard(format!("captions:{}:{}", item.id, stream_index)).await;
+            let source = item.file_path.clone();
+            let captions = tauri::async_runtime::spawn_blocking(move || {
+                let srt_path = ensure_subtitle_srt(&source, stream_index)?;
+                cached_vtt(&srt_path).map_err(|e| e.to_string())
+            })
+            .await
+            .map_err(|e| e.to_string())
+            .and_then(|result| result);
+            match captions {
+                // Tracks can be extracted again, so always revalidate
+                Ok(vtt_path) => serve_file(request, &vtt_path.to_string_lossy(), Some("no-cache")),
+                Err(e) => {
+                    eprintln!("[Stream] Captions for {} failed: {}", item.id, e);
+                    status(500)
+                }
+            }
+        }
+        _ => {
+            let count = parsed_param(request.uri(), "count").unwrap_or(10);
+            let width = parsed_param(request.uri(), "width").unwrap_or(160);
+            let key = format!("filmstrip:{}:{}:{}", item.id, count, width);
+            let _guard = generation_guard(key).await;
+            match generate_filmstrip(item.id.clone(), item.file_path.clone(), count, width).await {
+                Ok(index) => {
+                    let sprite = PathBuf::from(&index.sprite_path);
+                    let served = match file {
+                        "index.json" => sprite.with_extension("json"),
+                        _ => sprite,
+                    };
+                    serve_file(request, &served.to_string_lossy(), Some(GENERATED_CACHE_CONTROL))
+                }
+                Err(e) => {
+                    eprintln!("[Stream] Filmstrip for {} failed: {}", item.id, e);
+                    status(500)
+                }
+            }
+        }
+    };
+    Some(response)
+}
+
+/// Playlists and chunks of the HLS preview. Playlists of a timeline still being prerendered
+/// are rewritten as segments finish, so they're always revalidated; chunks never change.
+fn serve_hls(request: &http::Request<Vec<u8>>, relative: &str) -> http::Response<Vec<u8>> {
+    let relative = urlencoding::decode(relative).unwrap_or_default().to_string();
+    let relative = Path::new(&relative);
+    // Only plain names below the HLS directory
+    if !relative.components().all(|component| matches!(component, Component::Normal(_))) {
+        return status(403);
+    }
+    let Ok(dir) = hls_dir() else {
+        return status(500);
+    };
+    let file_path = dir.join(relative).to_string_lossy().to_string();
+    let cache_control = if file_path.ends_with(".m3u8") {
+        "no-cache"
+    } else {
+        GENERATED_CACHE_CONTROL
+    };
+    serve_file(request, &file_path, Some(cache_control))
+}
+
+/// The init segment or the media fragments of a cached fMP4 prerender, so MSE can fetch them
+/// directly instead of going through get_init_segment
+fn serve_fmp4(request: &http::Request<Vec<u8>>, rest: &str) -> http::Response<Vec<u8>> {
+    let Some((key, part)) = rest.split_once('/') else {
+        return status(404);
+    };
+    if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return status(403);
+    }
+    let Ok(path) = segment_path(key, "mp4") else {
+        return status(500);
+    };
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        return status(404);
+    };
+    let (etag, last_modified) = validators(&metadata);
+    let etag = format!("\"{}-{}", part, etag.trim_start_matches('"'));
+    if is_not_modified(request, &etag, &metadata) {
+        return http::Response::builder().header(ETAG, etag).status(304).body(Vec::new()).unwrap();
+    }
+
+    let data = match std::fs::read(&path) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("[Stream] Failed to read {}: {}", path.display(), e);
+            return status(500);
+        }
+    };
+    let Some(init_length) = init_segment(&data).map(<[u8]>::len) else {
+        return status(415);
+    };
+    let (body, content_type) = match part {
+        "init.mp4" => (data[..init_length].to_vec(), "video/mp4"),
+        "media.m4s" => (data[init_length..].to_vec(), "video/iso.segment"),
+        _ => return status(404),
+    };
+    let mut response = http::Response::builder()
+        .header(CONTENT_TYPE, content_type)
+        .header(CONTENT_LENGTH, body.len())
+        .header(CACHE_CONTROL, GENERATED_CACHE_CONTROL)
+        .header(ETAG, etag);
+    if let Some(last_modified) = last_modified {
+        response = response.header(LAST_MODIFIED, last_modified);
+    }
+    response.status(200).body(body).unwrap()
+}
+
+/// A window of a source transcoded on the fly to fragmented MP4/H.264, for HEVC, AV1 and other
+/// sources the webview can't decode, so they can be previewed before a proxy exists. Each
+/// window is a self-contained fMP4 (init segment included) keeping the source's timestamps,
+/// to be appended to a Media Source Extensions buffer in order.
+async fn serve_transcode(
+    app: &AppHandle,
+    request: &http::Request<Vec<u8>>,
+    encoded_path: &str,
+) -> http::Response<Vec<u8>> {
+    let file_path = urlencoding::decode(encoded_path).unwrap_or_default().to_string();
+    if !is_in_scope(app, &file_path).await {
+        eprintln!("[Stream] Refused transcode outside the allowed scope: {}", file_path);
+        return status(403);
+    }
+    let uri = request.uri();
+    let start = parsed_param(uri, "start").unwrap_or(0.0f64).max(0.0);
+    let duration = parsed_param(uri, "duration")
+        .unwrap_or(TRANSCODE_WINDOW_SECONDS)
+        .clamp(1.0, MAX_TRANSCODE_WINDOW_SECONDS);
+    let height = parsed_param(uri, "height")
+        .unwrap_or(TRANSCODE_HEIGHT)
+        .clamp(144, MAX_TRANSCODE_HEIGHT);
+    let key = format!("{}|{:.3}|{:.3}|{}", file_path, start, duration, height);
+
+    let cached = {
+        let mut cache = TRANSCODE_CACHE.lock().unwrap();
+        let index = cache.iter().position(|(cached_key, _)| *cached_key == key);
+        index.and_then(|index| cache.remove(index)).inspect(|entry| cache.push_back(entry.clone()))
+    };
+    let cache_hit = cached.is_some();
+    let data = match cached {
+        Some((_, data)) => data,
+        None => {
+            // Scrubbing supersedes the windows still waiting to be transcoded
+            let reads = format!("transcode:{}", file_path);
+            let Some(_permit) = READ_LIMITER.acquire(&reads, reader_id(request)).await else {
+                return superseded();
+            };
+            let source = file_path.clone();
+            let transcoded = tauri::async_runtime::spawn_blocking(move || {
+                transcode_window_fmp4(&source, start, duration, height)
+            })
+            .await;
+            match transcoded {
+                Ok(Ok(data)) => {
+                    let data = Arc::new(data);
+                    let mut cache = TRANSCODE_CACHE.lock().unwrap();
+                    if cache.len() >= MAX_CACHED_TRANSCODES {
+                        cache.pop_front();
+                    }
+                    cache.push_back((key, data.clone()));
+                    data
+                }
+                Ok(Err(e)) => {
+                    eprintln!("[Stream] Transcode of {} failed: {}", file_path, e);
+                    return status(500);
+                }
+                Err(e) => {
+                    eprintln!("[Stream] Transcode task failed: {}", e);
+                    return status(500);
+                }
+            }
+        }
+    };
+
+    let mut response = http::Response::builder()
+        .header(CONTENT_TYPE, "video/mp4")
+        .header(CONTENT_LENGTH, data.len())
+        .header(CACHE_CONTROL, "private, max-age=300");
+    if cache_hit {
+        response = response.header(CACHE_HIT_HEADER, "hit");
+    }
+    response.status(200).body(data.as_ref().clone()).unwrap()
+}
+
+/// A clip stream, readable while FFmpeg is still writing it. Until it's finished its size
+/// isn't known, so every response is partial with an unknown total (`bytes a-b/*`), and reads
+/// past what's been written wait for it.
+async fn serve_clip_stream(
+    app: &AppHandle,
+    request: &http::Request<Vec<u8>>,
+    id: &str,
+) -> http::Response<Vec<u8>> {
+    let Some(stream) = app.state::<ClipStreams>().get(id) else {
+        return status(404);
+    };
+
+    let range = request.headers().get(RANGE).and_then(|value| value.to_str().ok());
+    // Wait for the start of the requested range (or the end) to be written
+    let start = range
+        .and_then(|range| range.trim().strip_prefix("bytes="))
+        .and_then(|spec| spec.split_once('-'))
+        .and_then(|(start, _)| start.trim().parse::<u64>().ok())
+        .unwrap_or(0);
+    let waiting_since = Instant::now();
+    while !stream.finished.load(Ordering::SeqCst)
+        && stream.written.load(Ordering::SeqCst) <= start
+        && waiting_since.elapsed() < CLIP_STREAM_WAIT
+    {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    if stream.finished.load(Ordering::SeqCst) {
+        if stream.written.load(Ordering::SeqCst) == 0 {
+            return status(500);
+        }
+        return serve_file(request, &stream.path.to_string_lossy(), Some("no-store"));
+    }
+
+    let written = stream.written.load(Ordering::SeqCst);
+    if written <= start {
+        return status(504);
+    }
+    let requested_end = range
+        .and_then(|range| range.split_once('-'))
+        .and_then(|(_, end)| end.split(',').next())
+        .and_then(|end| end.trim().parse::<u64>().ok())
+        .unwrap_or(u64::MAX);
+    let end = requested_end.min(written - 1).min(start + STREAM_CHUNK_BYTES - 1);
+    let data = match read_file_range(&stream.path, start, end - start + 1) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("[Stream] Failed to read clip stream {}: {}", id, e);
+            return status(500);
+        }
+    };
+    let last = (start + data.len() as u64).saturating_sub(1);
+    http::Response::builder()
+        .header(CONTENT_TYPE, "video/mp4")
+        .header(ACCEPT_RANGES, "bytes")
+        .header(CONTENT_LENGTH, data.len())
+        .header(CONTENT_RANGE, format!("bytes {}-{}/*", start, last))
+        .header(CACHE_CONTROL, "no-store")
+        .status(206)
+        .body(data)
+        .unwrap()
+}
+
+/// Validators of a file's current version: an ETag from its size and modification time, and
+/// the modification time as an HTTP date
+fn validators(metadata: &std::fs::Metadata) -> (String, Option<String>) {
+    let modified = metadata.modified().ok();
+    let nanos = modified
+        .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|since_epoch| since_epoch.as_nanos())
+        .unwrap_or(0);
+    let etag = format!("\"{:x}-{:x}\"", metadata.len(), nanos);
+    let last_modified = modified.map(|modified| {
+        chrono::DateTime::<chrono::Utc>::from(modified)
+            .format("%a, %d %b %Y %H:%M:%S GMT")
+            .to_string()
+    });
+    (etag, last_modified)
+}
+
+/// Whether the webview's cached copy is still current. If-None-Match wins over
+/// If-Modified-Since when both are sent.
+fn is_not_modified(
+    request: &http::Request<Vec<u8>>,
+    etag: &str,
+    metadata: &std::fs::Metadata,
+) -> bool {
+    let header = |name: HeaderName| {
+        request.headers().get(name).and_then(|value| value.to_str().ok())
+    };
+    if let Some(if_none_match) = header(IF_NONE_MATCH) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+    let since = header(IF_MODIFIED_SINCE)
+        .and_then(|since| chrono::DateTime::parse_from_rfc2822(since).ok());
+    match (since, metadata.modified().ok()) {
+        (Some(since), Some(modified)) => {
+            // HTTP dates only have whole seconds
+            chrono::DateTime::<chrono::Utc>::from(modified).timestamp() <= since.timestamp()
+        }
+        _ => false,
+    }
+}
+
+/// Serve a file from disk, honouring the request's Range and conditional headers
+fn serve_file(
+    request: &http::Request<Vec<u8>>,
+    file_path: &str,
+    cache_control: Option<&str>,
+) -> http::Response<Vec<u8>> {
+    let Ok(metadata) = std::fs::metadata(file_path) else {
+        return status(404);
+    };
+    if !metadata.is_file() {
+        return status(404);
+    }
+    let size = metadata.len();
+
+    let (etag, last_modified) = validators(&metadata);
+    let with_validators = |mut response: http::response::Builder| {
+        response = response.header(ETAG, &etag);
+        if let Some(last_modified) = &last_modified {
+            response = response.header(LAST_MODIFIED, last_modified);
+        }
+        if let Some(cache_control) = cache_control {
+            response = response.header(CACHE_CONTROL, cache_control);
+        }
+        response
+    };
+    if is_not_modified(request, &etag, &metadata) {
+        return with_validators(http::Response::builder()).status(304).body(Vec::new()).unwrap();
+    }
+
+    let content_type = content_type(file_path);
+    // <img> and fetch() of small assets don't cope with an unrequested partial response, so
+    // only audio and video are split into chunks
+    let chunked = content_type.starts_with("video/") || content_type.starts_with("audio/");
+
+    let range = request.headers().get(RANGE).and_then(|value| value.to_str().ok());
+    let (start, end, partial) = match range {
+        Some(range) => match parse_range(range, size) {
+            Some((start, end)) => (start, end, true),
+            None => {
+                return http::Response::builder()
+                    .header(CONTENT_RANGE, format!("bytes */{}", size))
+                    .status(416)
+                    .body(Vec::new())
+                    .unwrap();
+            }
+        },
+        None if size <= MAX_FULL_RESPONSE_BYTES || !chunked => (0, size.saturating_sub(1), false),
+        None => (0, STREAM_CHUNK_BYTES - 1, true),
+    };
+
+    let length = if size == 0 { 0 } else { end - start + 1 };
+    let data = match read_file_range(Path::new(file_path), start, length) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("[Stream] Failed to read {}: {}", file_path, e);
+            return status(500);
+        }
+    };
+
+    let mut response = with_validators(http::Response::builder())
+        .header(CONTENT_TYPE, content_type)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(CONTENT_LENGTH, data.len());
+    if partial {
+        let last = (start + data.len() as u64).saturating_sub(1);
+        response = response
+            .header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, last, size))
+            .status(206);
+    } else {
+        response = response.status(200);
+    }
+    response.body(data).unwrap()
+}
+
+/// Serve a request to the `stream` protocol: the native preview engine's latest frame, a
+/// library item's generated assets (see serve_media_asset), the HLS preview (HLS_ROUTE),
+/// fragmented prerenders split for MSE (FMP4_ROUTE), live transcodes (TRANSCODE_ROUTE),
+/// gapless clip streams (CLIPS_ROUTE), or a file given by its URL-encoded path.
+///
+/// Files are read in ranges so long recordings are never loaded whole; a request without a
+/// Range header for a large file gets its first chunk as a partial response. Responses carry an
+/// ETag and Last-Modified, and conditional requests for unchanged files get 304. With `proxy=1`
+/// (or an `X-Zapcut-Proxy: 1` header) a library file's proxy is served in its place when one
+/// exists, and an SRT file with `vtt=1` is converted to WebVTT. Reads are limited per file and
+/// overall, and a waiting read is dropped with 503 when a newer request for the same file from
+/// the same reader (`reader=` or `X-Zapcut-Reader`) supersedes it. Requests without this
+/// session's token, or for files outside the library and Zapcut's own directories, are refused
+/// with 403.
+///
+/// Requests are counted in StreamMetrics when it's enabled.
+pub async fn handle_stream_request(
+    app: &AppHandle,
+    request: &http::Request<Vec<u8>>,
+) -> http::Response<Vec<u8>> {
+    let started = Instant::now();
+    let response = route_stream_request(app, request).await;
+
+    let path = urlencoding::decode(request.uri().path()).unwrap_or_default();
+    let cache_hit = response.status() == http::StatusCode::NOT_MODIFIED
+        || response.headers().get(CACHE_HIT_HEADER).is_some();
+    app.state::<StreamMetrics>().record(StreamRequestRecord {
+        path: &path,
+        status: response.status().as_u16(),
+        bytes: response.body().len() as u64,
+        cache_hit,
+        latency: started.elapsed(),
+    });
+    response
+}
+
+async fn route_stream_request(
+    app: &AppHandle,
+    request: &http::Request<Vec<u8>>,
+) -> http::Response<Vec<u8>> {
+    if !has_valid_token(request.uri()) {
+        return status(403);
+    }
+    let path = request.uri().path();
+
+    // Latest frame of the native preview engine
+    if path == PLAYBACK_FRAME_PATH {
+        return match app.state::<PlaybackEngine>().latest_frame() {
+            Some(frame) => http::Response::builder()
+                .header(CONTENT_TYPE, "image/jpeg")
+                .header(CACHE_CONTROL, "no-store")
+                .status(200)
+                .body(frame.as_ref().clone())
+                .unwrap(),
+            None => status(404),
+        };
+    }
+
+    if let Some(relative) = path.strip_prefix(HLS_ROUTE) {
+        let Some(_permit) = READ_LIMITER.acquire(path, reader_id(request)).await else {
+            return superseded();
+        };
+        return serve_hls(request, relative);
+    }
+    if let Some(rest) = path.strip_prefix(FMP4_ROUTE) {
+        return serve_fmp4(request, rest);
+    }
+    if let Some(id) = path.strip_prefix(CLIPS_ROUTE) {
+        return serve_clip_stream(app, request, id).await;
+    }
+    if let Some(encoded_path) = path.strip_prefix(TRANSCODE_ROUTE) {
+        return serve_transcode(app, request, encoded_path).await;
+    }
+    if let Some(response) = serve_media_asset(app, request, path).await {
+        return response;
+    }
+
+    // Remove leading '/' to get actual file path
+    let mut file_path = urlencoding::decode(&path[1..]).unwrap_or_default().to_string();
+    if !is_in_scope(app, &file_path).await {
+        eprintln!("[Stream] Refused file outside the allowed scope: {}", file_path);
+        return status(403);
+    }
+    let mut from_proxy = false;
+    if wants_proxy(request) {
+        if let Some(proxy) = proxy_for(app, &file_path).await {
+            file_path = proxy;
+            from_proxy = true;
+        }
+    }
+
+    // Caption files such as extracted or generated SRTs, converted for <track>
+    let is_srt = file_path.to_lowercase().ends_with(".srt");
+    if is_srt && query_param(request.uri(), "vtt") == Some("1") {
+        return match cached_vtt(Path::new(&file_path)) {
+            Ok(vtt_path) => serve_file(request, &vtt_path.to_string_lossy(), Some("no-cache")),
+            Err(e) => {
+                eprintln!("[Stream] Failed to convert {} to WebVTT: {}", file_path, e);
+                status(404)
+            }
+        };
+    }
+
+    let Some(_permit) = READ_LIMITER.acquire(&file_path, reader_id(request)).await else {
+        return superseded();
+    };
+    // Always revalidated, so a re-recorded or replaced file is never served stale
+    let mut response = serve_file(request, &file_path, Some("no-cache"));
+    if response.status().is_success() {
+        let source = if from_proxy { "proxy" } else { "original" };
+        response.headers_mut().insert("x-zapcut-source", HeaderValue::from_static(source));
+    }
+    response
+}
@@ -0,0 +1,160 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{command, State};
+
+/// Files with their own counters; the least requested is dropped to make room for a new one
+const MAX_TRACKED_FILES: usize = 200;
+
+/// Counters for one requested path
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct FileStreamMetrics {
+    pub path: String,
+    pub requests: u64,
+    pub bytes_served: u64,
+    pub cache_hits: u64,
+    /// Milliseconds
+    pub total_latency_ms: f64,
+    pub max_latency_ms: f64,
+}
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct StreamMetricsSnapshot {
+    pub enabled: bool,
+    pub requests: u64,
+    pub bytes_served: u64,
+    /// 304s and responses served from an in-memory cache
+    pub cache_hits: u64,
+    /// Partial (206) responses
+    pub range_requests: u64,
+    /// Responses with a 4xx or 5xx status, 503s for superseded reads included
+    pub errors: u64,
+    pub average_latency_ms: f64,
+    pub max_latency_ms: f64,
+    /// Requests by status code
+    pub statuses: HashMap<u16, u64>,
+    /// Busiest paths first
+    pub files: Vec<FileStreamMetrics>,
+}
+
+#[derive(Default)]
+struct MetricsState {
+    enabled: bool,
+    /// Also print a line per request
+    log_requests: bool,
+    totals: StreamMetricsSnapshot,
+    total_latency_ms: f64,
+    files: HashMap<String, FileStreamMetrics>,
+}
+
+/// What a stream protocol request and its response are recorded as
+pub struct StreamRequestRecord<'a> {
+    pub path: &'a str,
+    pub status: u16,
+    pub bytes: u64,
+    pub cache_hit: bool,
+    pub latency: Duration,
+}
+
+/// Optional counters of stream protocol traffic for diagnosing playback stalls. Off by default;
+/// nothing is recorded until enabled.
+pub struct StreamMetrics {
+    state: Mutex<MetricsState>,
+}
+
+impl StreamMetrics {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(MetricsState::default()),
+        }
+    }
+
+    pub fn record(&self, record: StreamRequestRecord) {
+        let mut state = self.state.lock().unwrap();
+        if !state.enabled {
+            return;
+        }
+        let latency_ms = record.latency.as_secs_f64() * 1000.0;
+        if state.log_requests {
+            println!(
+                "[Stream] {} {} {} bytes in {:.1}ms{}",
+                record.status,
+                record.path,
+                record.bytes,
+                latency_ms,
+                if record.cache_hit { " (cached)" } else { "" }
+            );
+        }
+
+        let totals = &mut state.totals;
+        totals.requests += 1;
+        totals.bytes_served += record.bytes;
+        totals.cache_hits += record.cache_hit as u64;
+        totals.range_requests += (record.status == 206) as u64;
+        totals.errors += (record.status >= 400) as u64;
+        totals.max_latency_ms = totals.max_latency_ms.max(latency_ms);
+        *totals.statuses.entry(record.status).or_default() += 1;
+        state.total_latency_ms += latency_ms;
+
+        if !state.files.contains_key(record.path) && state.files.len() >= MAX_TRACKED_FILES {
+            let quietest = state
+                .files
+                .values()
+                .min_by_key(|file| file.requests)
+                .map(|file| file.path.clone());
+            if let Some(quietest) = quietest {
+                state.files.remove(&quietest);
+            }
+        }
+        let file = state
+            .files
+            .entry(record.path.to_string())
+            .or_insert_with(|| FileStreamMetrics {
+                path: record.path.to_string(),
+                ..Default::default()
+            });
+        file.requests += 1;
+        file.bytes_served += record.bytes;
+        file.cache_hits += record.cache_hit as u64;
+        file.total_latency_ms += latency_ms;
+        file.max_latency_ms = file.max_latency_ms.max(latency_ms);
+    }
+
+    fn snapshot(&self) -> StreamMetricsSnapshot {
+        let state = self.state.lock().unwrap();
+        let mut snapshot = state.totals.clone();
+        snapshot.enabled = state.enabled;
+        if snapshot.requests > 0 {
+            snapshot.average_latency_ms = state.total_latency_ms / snapshot.requests as f64;
+        }
+        snapshot.files = state.files.values().cloned().collect();
+        snapshot.files.sort_by_key(|file| std::cmp::Reverse(file.bytes_served));
+        snapshot
+    }
+}
+
+/// Turn stream protocol metrics on or off, optionally printing every request as well.
+/// Turning them on starts from zero.
+#[command]
+pub fn set_stream_metrics_enabled(
+    metrics: State<'_, StreamMetrics>,
+    enabled: bool,
+    log_requests: Option<bool>,
+) -> StreamMetricsSnapshot {
+    {
+        let mut state = metrics.state.lock().unwrap();
+        if enabled && !state.enabled {
+            *state = MetricsState::default();
+        }
+        state.enabled = enabled;
+        state.log_requests = enabled && log_requests.unwrap_or(false);
+    }
+    metrics.snapshot()
+}
+
+/// Stream protocol counters since metrics were enabled, for the debug panel
+#[command]
+pub fn get_stream_metrics(metrics: State<'_, StreamMetrics>) -> StreamMetricsSnapshot {
+    metrics.snapshot()
+}
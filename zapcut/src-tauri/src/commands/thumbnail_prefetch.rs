@@ -0,0 +1,198 @@
+use crate::commands::media::{
+    ensure_frame_thumbnail, frame_thumbnail_data_url, FRAME_THUMBNAIL_STEP_MS,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Condvar, Mutex};
+use tauri::{command, AppHandle, Emitter, Manager, State};
+
+/// Most thumbnails fetched on each side of the playhead
+const MAX_PREFETCH_RADIUS: u32 = 60;
+
+/// Sources whose prefetched thumbnails are remembered for lookup
+const MAX_PREFETCH_SOURCES: usize = 8;
+
+/// Payload of `thumbnails://prefetched`
+#[derive(Debug, Serialize, Clone)]
+pub struct PrefetchedThumbnail {
+    pub path: String,
+    pub width: u32,
+    /// Seconds
+    pub timestamp: f64,
+}
+
+#[derive(Clone)]
+struct PrefetchRequest {
+    path: String,
+    playhead: f64,
+    /// Timeline seconds between neighbouring thumbnails at the current zoom
+    interval: f64,
+    radius: u32,
+    width: u32,
+    duration: Option<f64>,
+}
+
+impl PrefetchRequest {
+    /// Timestamps to fetch, nearest the playhead first
+    fn timestamps(&self) -> Vec<f64> {
+        let end = self.duration.unwrap_or(f64::MAX);
+        let mut timestamps = vec![self.playhead.clamp(0.0, end)];
+        for step in 1..=self.radius {
+            let offset = step as f64 * self.interval;
+            for timestamp in [self.playhead + offset, self.playhead - offset] {
+                if (0.0..=end).contains(&timestamp) {
+                    timestamps.push(timestamp);
+                }
+            }
+        }
+        timestamps
+    }
+}
+
+#[derive(Default)]
+struct PrefetchState {
+    request: Option<PrefetchRequest>,
+    /// Bumped by every request so the worker drops outdated work between frames
+    generation: u64,
+    /// Thumbnails fetched so far by (source, width), keyed by rounded milliseconds
+    fetched: HashMap<(String, u32), BTreeMap<u64, PathBuf>>,
+}
+
+/// Extracts frame thumbnails around the playhead in the background so scrubbing finds them
+/// already cached. Only the latest request is worked on.
+pub struct ThumbnailPrefetcher {
+    state: Mutex<PrefetchState>,
+    wake: Condvar,
+}
+
+impl ThumbnailPrefetcher {
+    pub fn new() -> Self {
+        Self {
+            state: Mutex::new(PrefetchState::default()),
+            wake: Condvar::new(),
+        }
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.state.lock().unwrap().generation == generation
+    }
+}
+
+/// Background worker for the thumbnail prefetcher; runs for the life of the app
+pub fn run_thumbnail_prefetcher(app: AppHandle) {
+    let prefetcher = app.state::<ThumbnailPrefetcher>();
+    loop {
+        let (request, generation) = {
+            let mut state = prefetcher.state.lock().unwrap();
+            loop {
+                if let Some(request) = state.request.take() {
+                    break (request, state.generation);
+                }
+                state = prefetcher.wake.wait(state).unwrap();
+            }
+        };
+
+        let key = (request.path.clone(), request.width);
+        for timestamp in request.timestamps() {
+            if !prefetcher.is_current(generation) {
+                break;
+            }
+            match ensure_frame_thumbnail(&request.path, timestamp, request.width) {
+                Ok((frame_path, rounded_ms)) => {
+                    let mut state = prefetcher.state.lock().unwrap();
+                    let new_source = !state.fetched.contains_key(&key);
+                    if new_source && state.fetched.len() >= MAX_PREFETCH_SOURCES {
+                        state.fetched.clear();
+                    }
+                    let fetched = state.fetched.entry(key.clone()).or_default();
+                    if fetched.insert(rounded_ms, frame_path).is_some() {
+                        continue;
+                    }
+                    drop(state);
+                    let prefetched = PrefetchedThumbnail {
+                        path: request.path.clone(),
+                        width: request.width,
+                        timestamp: rounded_ms as f64 / 1000.0,
+                    };
+                    if let Err(e) = app.emit("thumbnails://prefetched", prefetched) {
+                        eprintln!("[Thumbnails] Failed to emit prefetch: {}", e);
+                    }
+                }
+                Err(e) => {
+                    eprintln!(
+                        "[Thumbnails] Prefetch of {} at {:.2}s failed: {}",
+                        request.path, timestamp, e
+                    );
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Prefetch thumbnails of `path` around `playhead`, spaced `interval` seconds apart (the
+/// timeline's zoom), `radius` on each side. Replaces any earlier request. Results can be looked
+/// up with lookup_prefetched_thumbnail and are announced as `thumbnails://prefetched`.
+#[command]
+pub fn prefetch_thumbnails(
+    prefetcher: State<'_, ThumbnailPrefetcher>,
+    path: String,
+    playhead: f64,
+    interval: f64,
+    width: u32,
+    radius: Option<u32>,
+    duration: Option<f64>,
+) -> Result<(), String> {
+    if !interval.is_finite() || interval <= 0.0 {
+        return Err("Thumbnail interval must be positive".to_string());
+    }
+    let mut state = prefetcher.state.lock().unwrap();
+    state.generation += 1;
+    state.request = Some(PrefetchRequest {
+        path,
+        playhead: playhead.max(0.0),
+        // Closer than the cache's rounding would only fetch the same frame again
+        interval: interval.max(FRAME_THUMBNAIL_STEP_MS as f64 / 1000.0),
+        radius: radius.unwrap_or(20).min(MAX_PREFETCH_RADIUS),
+        width,
+        duration,
+    });
+    prefetcher.wake.notify_all();
+    Ok(())
+}
+
+/// The prefetched thumbnail of `path` nearest `timestamp`, as a base64 data URL, when one is
+/// within `tolerance` seconds (0.5 by default). None when nothing close has been fetched yet.
+#[command]
+pub fn lookup_prefetched_thumbnail(
+    prefetcher: State<'_, ThumbnailPrefetcher>,
+    path: String,
+    timestamp: f64,
+    width: u32,
+    tolerance: Option<f64>,
+) -> Result<Option<String>, String> {
+    let target = (timestamp.max(0.0) * 1000.0).round() as u64;
+    let tolerance = (tolerance.unwrap_or(0.5).max(0.0) * 1000.0) as u64;
+
+    let nearest = {
+        let state = prefetcher.state.lock().unwrap();
+        let Some(fetched) = state.fetched.get(&(path, width)) else {
+            return Ok(None);
+        };
+        let before = fetched.range(..=target).next_back();
+        let after = fetched.range(target..).next();
+        before
+            .into_iter()
+            .chain(after)
+            .min_by_key(|(ms, _)| ms.abs_diff(target))
+            .filter(|(ms, _)| ms.abs_diff(target) <= tolerance)
+            .map(|(_, frame_path)| frame_path.clone())
+    };
+
+    match nearest {
+        // The frame cache may have evicted it since
+        Some(frame_path) if frame_path.exists() => frame_thumbnail_data_url(&frame_path).map(Some),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,275 @@
+use crate::commands::library::LibraryManager;
+use crate::commands::media::MediaItem;
+use crate::utils::app_init::get_media_dir;
+use crate::utils::ffmpeg::run_ffmpeg_with_progress;
+use crate::utils::jobs::JobManager;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, State};
+
+/// Editing-friendly intermediate formats
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscodePreset {
+    /// Short-GOP, high-quality H.264 without B-frames, widely decodable
+    H264High,
+    /// ProRes 422 Proxy, cheap to decode and scrub
+    ProresProxy,
+}
+
+impl TranscodePreset {
+    fn extension(&self) -> &'static str {
+        match self {
+            TranscodePreset::H264High => "mp4",
+            TranscodePreset::ProresProxy => "mov",
+        }
+    }
+
+    fn suffix(&self) -> &'static str {
+        match self {
+            TranscodePreset::H264High => "h264",
+            TranscodePreset::ProresProxy => "prores_proxy",
+        }
+    }
+
+    fn codec_args(&self, fps: f64) -> Vec<String> {
+        match self {
+            TranscodePreset::H264High => {
+                // Short GOP keeps seeking cheap; roughly half a second between keyframes
+                let gop = (fps / 2.0).round().max(1.0) as u32;
+                vec![
+                    "-c:v".to_string(),
+                    "libx264".to_string(),
+                    "-preset".to_string(),
+                    "fast".to_string(),
+                    "-crf".to_string(),
+                    "16".to_string(),
+                    "-g".to_string(),
+                    gop.to_string(),
+                    "-bf".to_string(),
+                    "0".to_string(),
+                    "-pix_fmt".to_string(),
+                    "yuv420p".to_string(),
+                    "-c:a".to_string(),
+                    "aac".to_string(),
+                    "-b:a".to_string(),
+                    "320k".to_string(),
+                    "-movflags".to_string(),
+                    "+faststart".to_string(),
+                ]
+            }
+            TranscodePreset::ProresProxy => vec![
+                "-c:v".to_string(),
+                "prores_ks".to_string(),
+                "-profile:v".to_string(),
+                "0".to_string(),
+                "-pix_fmt".to_string(),
+                "yuv422p10le".to_string(),
+                "-c:a".to_string(),
+                "pcm_s16le".to_string(),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TranscodeInfo {
+    pub path: String,
+    pub preset: TranscodePreset,
+    pub created_at: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QueuedTranscode {
+    pub media_id: String,
+    pub job_id: String,
+}
+
+fn transcodes_dir() -> Result<PathBuf, String> {
+    let dir = get_media_dir()
+        .map_err(|e| format!("Failed to get media directory: {}", e))?
+        .join("transcodes");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create transcodes directory: {}", e))?;
+    Ok(dir)
+}
+
+fn transcode_item(
+    item: &MediaItem,
+    preset: TranscodePreset,
+    output_path: &Path,
+    cancel: &AtomicBool,
+    on_progress: impl FnMut(f64),
+) -> Result<(), String> {
+    let mut args = vec!["-i".to_string(), item.file_path.clone()];
+    args.extend(preset.codec_args(item.fps));
+    // Conform variable frame rate sources so the mezzanine stays in sync with its audio
+    if item.is_vfr {
+        args.extend(["-fps_mode", "cfr", "-r"].map(String::from));
+        args.push(item.fps.to_string());
+    }
+    args.push("-y".to_string());
+    args.push(output_path.to_string_lossy().to_string());
+
+    run_ffmpeg_with_progress(&args, item.duration, Some(cancel), on_progress)
+        .map(|_| ())
+        .map_err(|e| {
+            let _ = std::fs::remove_file(output_path);
+            format!("Transcode failed: {}", e)
+        })
+}
+
+/// Queue library items for conversion to an editing-friendly mezzanine.
+/// Items are processed one at a time in the background; each gets a job reported via
+/// `job://update`. On success the item's `transcode` is set and `use_transcode` enabled.
+#[tauri::command]
+pub async fn transcode_media(
+    app: AppHandle,
+    library: State<'_, LibraryManager>,
+    jobs: State<'_, JobManager>,
+    media_ids: Vec<String>,
+    preset: TranscodePreset,
+) -> Result<Vec<QueuedTranscode>, String> {
+    let output_dir = transcodes_dir()?;
+
+    let items: Vec<MediaItem> = {
+        let library = library.library.lock().await;
+        media_ids
+            .iter()
+            .map(|id| {
+                library
+                    .items
+                    .iter()
+                    .find(|item| &item.id == id)
+                    .cloned()
+                    .ok_or_else(|| format!("Media item not found: {}", id))
+            })
+            .collect::<Result<_, _>>()?
+    };
+
+    let mut queue: Vec<(MediaItem, String, Arc<AtomicBool>)> = Vec::new();
+    let mut queued = Vec::new();
+    for item in items {
+        let (job_id, cancel) = jobs.create(&app, "transcode", &format!("Transcode {}", item.name));
+        queued.push(QueuedTranscode {
+            media_id: item.id.clone(),
+            job_id: job_id.clone(),
+        });
+        queue.push((item, job_id, cancel));
+    }
+
+    let worker_app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        for (item, job_id, cancel) in queue {
+            // Cancelled while still waiting in the queue
+            if cancel.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            let output_path = output_dir.join(format!(
+                "{}_{}.{}",
+                item.id,
+                preset.suffix(),
+                preset.extension()
+            ));
+            println!("[Transcode] {} -> {:?}", item.file_path, output_path);
+
+            let progress_app = worker_app.clone();
+            let progress_job = job_id.clone();
+            let job_output = output_path.clone();
+            let job_item = item.clone();
+            let result = tauri::async_runtime::spawn_blocking(move || {
+                let jobs = progress_app.state::<JobManager>();
+                transcode_item(&job_item, preset, &job_output, &cancel, |percentage| {
+                    jobs.set_progress(&progress_app, &progress_job, percentage)
+                })
+            })
+            .await
+            .map_err(|e| format!("Transcode task panicked: {}", e))
+            .and_then(|result| result);
+
+            let jobs = worker_app.state::<JobManager>();
+            match result {
+                Ok(()) => {
+                    let info = TranscodeInfo {
+                        path: output_path.to_string_lossy().to_string(),
+                        preset,
+                        created_at: chrono::Utc::now().to_rfc3339(),
+                    };
+                    let library = worker_app.state::<LibraryManager>();
+                    let mut library = library.library.lock().await;
+                    let saved = match library.get_mut(&item.id) {
+                        Some(entry) => {
+                            // Replace any earlier transcode made with a different preset
+                            if let Some(previous) = entry.transcode.take() {
+                                if previous.path != info.path {
+                                    let _ = std::fs::remove_file(&previous.path);
+                                }
+                            }
+                            entry.transcode = Some(info.clone());
+                            entry.use_transcode = true;
+                            library.save()
+                        }
+                        None => Err(format!("Media item was removed: {}", item.id)),
+                    };
+                    match saved {
+                        Ok(()) => jobs.complete(&worker_app, &job_id, serde_json::to_value(&info).ok()),
+                        Err(e) => jobs.fail(&worker_app, &job_id, e),
+                    }
+                }
+                Err(e) => {
+                    eprintln!("[Transcode] {}: {}", item.name, e);
+                    jobs.fail(&worker_app, &job_id, e);
+                }
+            }
+        }
+    });
+
+    Ok(queued)
+}
+
+/// Switch an item between its transcode and the original for editing
+#[tauri::command]
+pub async fn set_use_transcode(
+    library: State<'_, LibraryManager>,
+    media_id: String,
+    use_transcode: bool,
+) -> Result<MediaItem, String> {
+    let mut library = library.library.lock().await;
+    let item = library
+        .get_mut(&media_id)
+        .ok_or_else(|| format!("Media item not found: {}", media_id))?;
+
+    if use_transcode && item.transcode.is_none() {
+        return Err(format!("Media item has no transcode: {}", media_id));
+    }
+    item.use_transcode = use_transcode;
+
+    let updated = item.clone();
+    library.save()?;
+    Ok(updated)
+}
+
+/// Delete an item's transcode and go back to the original
+#[tauri::command]
+pub async fn remove_transcode(
+    library: State<'_, LibraryManager>,
+    media_id: String,
+) -> Result<MediaItem, String> {
+    let mut library = library.library.lock().await;
+    let item = library
+        .get_mut(&media_id)
+        .ok_or_else(|| format!("Media item not found: {}", media_id))?;
+
+    if let Some(transcode) = item.transcode.take() {
+        if let Err(e) = std::fs::remove_file(&transcode.path) {
+            eprintln!("[Transcode] Failed to delete {}: {}", transcode.path, e);
+        }
+    }
+    item.use_transcode = false;
+
+    let updated = item.clone();
+    library.save()?;
+    Ok(updated)
+}
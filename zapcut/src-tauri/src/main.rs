@@ -4,21 +4,91 @@
 mod commands;
 mod utils;
 
-use commands::media::{import_video, import_videos, validate_video_file, get_thumbnail_base64, read_video_file, read_binary_file};
+use commands::media::{import_video, import_videos, validate_video_file, read_video_file, read_binary_file, extract_subtitles, generate_filmstrip, get_frame_thumbnail, generate_contact_sheet, regenerate_thumbnails, remux_file, render_waveform_image, extract_audio};
 use commands::export::{export_timeline, export_timeline_optimized, get_export_progress};
 use commands::recording::{
     RecordingManager,
-    get_available_microphones, get_available_webcams,
-    process_recording, update_recording_state,
-    get_recording_state, import_recording_to_gallery, export_recording_to_file,
+    get_available_microphones, get_available_webcams, get_system_audio_sources, list_displays,
+    composite_webcam_pip, render_cursor_effects, recording_to_gif, check_permissions, request_permissions,
+    start_replay_buffer, stop_replay_buffer, save_replay, start_voiceover, stop_voiceover,
+    process_recording, update_recording_state, begin_recording_upload, append_recording_chunk,
+    finish_recording, cancel_recording_upload,
+    get_recording_state, get_recording_metadata, add_recording_marker, import_recording_to_gallery, import_recording_to_library, export_recording_to_file,
     generate_recording_thumbnail,
+    start_native_recording, pause_native_recording, resume_native_recording, stop_native_recording,
+};
+use commands::library::{
+    LibraryManager,
+    get_library_items, remove_library_item, relink_media, find_missing_media,
+    update_media_tags, set_media_rating, get_library_tags,
+    get_collections, create_collection, rename_collection, delete_collection,
+    update_collection_items, get_collection_items, search_media,
+};
+use commands::ingest::{import_from_url, ingest_card, import_folder};
+use commands::app::{init_app, get_thumbnail_settings, set_thumbnail_settings, get_cache_stats, clear_media_cache, delete_orphaned_media_caches, get_storage_stats};
+use commands::transcode::{transcode_media, set_use_transcode, remove_transcode};
+use commands::integrity::{repair_media, verify_media};
+use commands::analysis::{
+    detect_scenes, analyze_media, detect_crop, analyze_loudness, detect_silences,
+};
+use commands::jobs::{list_jobs, get_job, cancel_job, clear_finished_jobs};
+use utils::jobs::JobManager;
+use tauri::Manager;
+use commands::prerender::{
+    prerender_segment, prerender_audio_segment, get_prerender_cache_dir, get_prerender_cache_stats,
+    set_prerender_cache_limit, clear_prerender_cache, PrerenderQueue, run_prerender_queue, enqueue_prerender, cancel_prerender,
+    reprioritize_prerender, get_prerender_queue, invalidate_prerender_for_clips, set_prerender_concurrency, get_init_segment,
+    render_preview_range,
+};
+use commands::presets::{
+    RecordingPresets,
+    list_recording_presets, save_recording_preset, delete_recording_preset, apply_recording_preset,
+};
+use commands::audio_engine::{
+    AudioEngine,
+    load_audio_playback, play_audio_playback, pause_audio_playback, seek_audio_playback, get_audio_clock,
+};
+use commands::stream::{handle_stream_request, get_stream_token};
+use commands::clip_stream::{ClipStreams, open_clip_stream, close_clip_stream};
+use commands::stream_metrics::{StreamMetrics, set_stream_metrics_enabled, get_stream_metrics};
+use commands::playback::{
+    PlaybackEngine,
+    set_native_preview, load_playback, play_playback, pause_playback, seek_playback, get_playback_status,
+};
+use commands::thumbnail_prefetch::{
+    ThumbnailPrefetcher, run_thumbnail_prefetcher, prefetch_thumbnails, lookup_prefetched_thumbnail,
+};
+use commands::preview::{get_frame_at, step_frame, build_preview_playlist, get_frame_scopes, preview_filter_on_frame};
+use commands::schedule::{
+    RecordingScheduler, run_recording_scheduler,
+    schedule_recording, list_recording_schedules, cancel_recording_schedule, set_recording_auto_stop,
 };
-use commands::app::init_app;
-use commands::prerender::{prerender_segment, get_prerender_cache_dir, clear_prerender_cache};
 
 fn main() {
     tauri::Builder::default()
         .manage(RecordingManager::new())
+        .manage(LibraryManager::new())
+        .manage(JobManager::new())
+        .manage(RecordingScheduler::new())
+        .manage(RecordingPresets::new())
+        .manage(PrerenderQueue::new())
+        .manage(PlaybackEngine::new())
+        .manage(AudioEngine::new())
+        .manage(ThumbnailPrefetcher::new())
+        .manage(StreamMetrics::new())
+        .manage(ClipStreams::new())
+        .setup(|app| {
+            match app.path().resource_dir() {
+                Ok(dir) => utils::ffmpeg::set_resource_dir(dir),
+                Err(e) => eprintln!("[Audio] Failed to resolve resource directory: {}", e),
+            }
+            tauri::async_runtime::spawn(run_recording_scheduler(app.handle().clone()));
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_prerender_queue(handle));
+            let handle = app.handle().clone();
+            std::thread::spawn(move || run_thumbnail_prefetcher(handle));
+            Ok(())
+        })
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_shell::init())
@@ -26,70 +96,138 @@ fn main() {
             import_video,
             import_videos,
             validate_video_file,
-            get_thumbnail_base64,
             read_video_file,
             read_binary_file,
+            extract_subtitles,
+            render_waveform_image,
+            extract_audio,
+            generate_filmstrip,
+            get_frame_thumbnail,
+            generate_contact_sheet,
+            regenerate_thumbnails,
+            remux_file,
             export_timeline,
             export_timeline_optimized,
             get_export_progress,
             get_available_microphones,
             get_available_webcams,
+            get_system_audio_sources,
+            composite_webcam_pip,
+            render_cursor_effects,
+            recording_to_gif,
+            check_permissions,
+            request_permissions,
+            start_replay_buffer,
+            stop_replay_buffer,
+            save_replay,
+            start_voiceover,
+            stop_voiceover,
+            list_displays,
             process_recording,
+            begin_recording_upload,
+            append_recording_chunk,
+            finish_recording,
+            cancel_recording_upload,
             update_recording_state,
             get_recording_state,
+            get_recording_metadata,
+            add_recording_marker,
             import_recording_to_gallery,
+            import_recording_to_library,
             export_recording_to_file,
             generate_recording_thumbnail,
+            start_native_recording,
+            pause_native_recording,
+            resume_native_recording,
+            stop_native_recording,
+            get_library_items,
+            remove_library_item,
+            relink_media,
+            find_missing_media,
+            update_media_tags,
+            set_media_rating,
+            get_library_tags,
+            get_collections,
+            create_collection,
+            rename_collection,
+            delete_collection,
+            update_collection_items,
+            get_collection_items,
+            search_media,
+            import_from_url,
+            ingest_card,
+            import_folder,
             init_app,
+            get_thumbnail_settings,
+            set_thumbnail_settings,
+            get_cache_stats,
+            clear_media_cache,
+            delete_orphaned_media_caches,
+            get_storage_stats,
+            transcode_media,
+            set_use_transcode,
+            remove_transcode,
+            list_jobs,
+            get_job,
+            cancel_job,
+            clear_finished_jobs,
+            repair_media,
+            verify_media,
+            detect_scenes,
+            analyze_media,
+            detect_crop,
+            analyze_loudness,
+            detect_silences,
             prerender_segment,
+            prerender_audio_segment,
             get_prerender_cache_dir,
+            get_prerender_cache_stats,
+            set_prerender_cache_limit,
             clear_prerender_cache,
+            enqueue_prerender,
+            cancel_prerender,
+            reprioritize_prerender,
+            get_prerender_queue,
+            invalidate_prerender_for_clips,
+            set_prerender_concurrency,
+            get_init_segment,
+            render_preview_range,
+            schedule_recording,
+            list_recording_schedules,
+            cancel_recording_schedule,
+            set_recording_auto_stop,
+            list_recording_presets,
+            save_recording_preset,
+            delete_recording_preset,
+            apply_recording_preset,
+            get_frame_at,
+            step_frame,
+            build_preview_playlist,
+            get_frame_scopes,
+            preview_filter_on_frame,
+            prefetch_thumbnails,
+            lookup_prefetched_thumbnail,
+            set_native_preview,
+            load_playback,
+            play_playback,
+            pause_playback,
+            seek_playback,
+            get_playback_status,
+            load_audio_playback,
+            play_audio_playback,
+            pause_audio_playback,
+            seek_audio_playback,
+            get_audio_clock,
+            get_stream_token,
+            set_stream_metrics_enabled,
+            get_stream_metrics,
+            open_clip_stream,
+            close_clip_stream,
         ])
-        .register_asynchronous_uri_scheme_protocol("stream", |_app, request, responder| {
-            use std::fs;
-            use http::header::*;
-            
+        .register_asynchronous_uri_scheme_protocol("stream", |ctx, request, responder| {
+            let app = ctx.app_handle().clone();
             tauri::async_runtime::spawn(async move {
-                // Extract file path from the URL
-                let path = request.uri().path();
-                // Remove leading '/' to get actual file path
-                let file_path = urlencoding::decode(&path[1..]).unwrap_or_default().to_string();
-                
-                match fs::read(&file_path) {
-                    Ok(data) => {
-                        // Detect content type from file extension
-                        let content_type = if file_path.ends_with(".mp4") {
-                            "video/mp4"
-                        } else if file_path.ends_with(".mov") {
-                            "video/quicktime"
-                        } else if file_path.ends_with(".webm") {
-                            "video/webm"
-                        } else if file_path.ends_with(".avi") {
-                            "video/x-msvideo"
-                        } else if file_path.ends_with(".mkv") {
-                            "video/x-matroska"
-                        } else {
-                            "application/octet-stream"
-                        };
-                        
-                        let response = http::Response::builder()
-                            .header(CONTENT_TYPE, content_type)
-                            .header(ACCEPT_RANGES, "bytes")
-                            .header(CONTENT_LENGTH, data.len())
-                            .status(200)
-                            .body(data)
-                            .unwrap();
-                        
-                        responder.respond(response);
-                    }
-                    Err(_e) => {
-                        let response = http::Response::builder()
-                            .status(404)
-                            .body(Vec::new())
-                            .unwrap();
-                        responder.respond(response);
-                    }
-                }
+                responder.respond(handle_stream_request(&app, &request).await);
             });
         })
         .run(tauri::generate_context!())
@@ -15,6 +15,7 @@ pub fn initialize_app_directories() -> Result<PathBuf> {
     std::fs::create_dir_all(zapcut_dir.join("exports"))?;
     std::fs::create_dir_all(zapcut_dir.join("thumbnails"))?;
     std::fs::create_dir_all(zapcut_dir.join("projects"))?;
+    std::fs::create_dir_all(zapcut_dir.join("media"))?;
 
     Ok(zapcut_dir)
 }
@@ -32,6 +33,18 @@ pub fn get_exports_dir() -> Result<PathBuf> {
     Ok(zapcut_dir.join("exports"))
 }
 
+/// Gets the projects directory path
+pub fn get_projects_dir() -> Result<PathBuf> {
+    let zapcut_dir = initialize_app_directories()?;
+    Ok(zapcut_dir.join("projects"))
+}
+
+/// Gets the directory where downloaded and ingested media is stored
+pub fn get_media_dir() -> Result<PathBuf> {
+    let zapcut_dir = initialize_app_directories()?;
+    Ok(zapcut_dir.join("media"))
+}
+
 /// Gets the user's Documents directory
 #[cfg(target_os = "macos")]
 fn get_documents_dir() -> Result<PathBuf> {
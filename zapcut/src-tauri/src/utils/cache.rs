@@ -0,0 +1,268 @@
+use crate::utils::app_init::initialize_app_directories;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Categories of generated files kept in managed app storage
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheKind {
+    Thumbnails,
+    Proxies,
+    Waveforms,
+    Filmstrips,
+    Frames,
+    ContactSheets,
+    /// Prerendered timeline segments; see prerender_cache
+    Prerender,
+}
+
+impl CacheKind {
+    pub const ALL: [CacheKind; 7] = [
+        CacheKind::Thumbnails,
+        CacheKind::Proxies,
+        CacheKind::Waveforms,
+        CacheKind::Filmstrips,
+        CacheKind::Frames,
+        CacheKind::ContactSheets,
+        CacheKind::Prerender,
+    ];
+
+    pub fn dir_name(&self) -> &'static str {
+        match self {
+            CacheKind::Thumbnails => "thumbnails",
+            CacheKind::Proxies => "proxies",
+            CacheKind::Waveforms => "waveforms",
+            CacheKind::Filmstrips => "filmstrips",
+            CacheKind::Frames => "frames",
+            CacheKind::ContactSheets => "contact_sheets",
+            CacheKind::Prerender => "prerender",
+        }
+    }
+
+    /// Directory name previously used under the OS temp dir, if any
+    fn legacy_temp_dir_name(&self) -> &'static str {
+        match self {
+            CacheKind::Frames => "frame_cache",
+            CacheKind::Prerender => "prerender_cache",
+            other => other.dir_name(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheEntry {
+    pub kind: CacheKind,
+    pub media_id: Option<String>,
+    pub source_path: Option<String>,
+    pub created_at: String,
+}
+
+/// Manifest of generated files, keyed by path relative to the cache root
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CacheManifest {
+    pub entries: HashMap<String, CacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CacheInfo {
+    pub kind: CacheKind,
+    pub path: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref MANIFEST_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Root of all managed caches: <Zapcut>/cache
+pub fn get_cache_root() -> Result<PathBuf> {
+    let root = initialize_app_directories()?.join("cache");
+    std::fs::create_dir_all(&root)?;
+    Ok(root)
+}
+
+/// Get (and create) the directory for a cache category
+pub fn get_cache_dir(kind: CacheKind) -> Result<PathBuf> {
+    let dir = get_cache_root()?.join(kind.dir_name());
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create {} cache directory", kind.dir_name()))?;
+    Ok(dir)
+}
+
+fn manifest_path() -> Result<PathBuf> {
+    Ok(get_cache_root()?.join("manifest.json"))
+}
+
+fn load_manifest() -> CacheManifest {
+    manifest_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_manifest(manifest: &CacheManifest) -> Result<()> {
+    let json = serde_json::to_string_pretty(manifest).context("Failed to serialize cache manifest")?;
+    std::fs::write(manifest_path()?, json).context("Failed to write cache manifest")?;
+    Ok(())
+}
+
+fn manifest_key(path: &Path) -> Option<String> {
+    let root = get_cache_root().ok()?;
+    path.strip_prefix(&root)
+        .ok()
+        .map(|relative| relative.to_string_lossy().replace('\\', "/"))
+}
+
+/// Record a generated file in the manifest so it can be attributed and cleaned up later
+pub fn register_cache_entry(
+    kind: CacheKind,
+    path: &Path,
+    media_id: Option<&str>,
+    source_path: Option<&str>,
+) -> Result<()> {
+    let key = manifest_key(path).context("Cache file is outside the cache root")?;
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = load_manifest();
+    manifest.entries.insert(
+        key,
+        CacheEntry {
+            kind,
+            media_id: media_id.map(|s| s.to_string()),
+            source_path: source_path.map(|s| s.to_string()),
+            created_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    save_manifest(&manifest)
+}
+
+/// Recursive (file count, total bytes) of a directory; missing directories count as empty
+pub fn dir_usage(dir: &Path) -> (u64, u64) {
+    let mut file_count = 0;
+    let mut total_bytes = 0;
+    if let Ok(entries) = std::fs::read_dir(dir) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_file() {
+                    file_count += 1;
+                    total_bytes += metadata.len();
+                } else if metadata.is_dir() {
+                    let (count, bytes) = dir_usage(&entry.path());
+                    file_count += count;
+                    total_bytes += bytes;
+                }
+            }
+        }
+    }
+    (file_count, total_bytes)
+}
+
+/// Disk usage for a single cache category
+pub fn get_cache_info(kind: CacheKind) -> Result<CacheInfo> {
+    let dir = get_cache_dir(kind)?;
+    let (file_count, total_bytes) = dir_usage(&dir);
+    Ok(CacheInfo {
+        kind,
+        path: dir.to_string_lossy().to_string(),
+        file_count,
+        total_bytes,
+    })
+}
+
+/// Delete every file in a cache category and drop its manifest entries
+pub fn clear_cache(kind: CacheKind) -> Result<()> {
+    let dir = get_cache_dir(kind)?;
+    std::fs::remove_dir_all(&dir).context("Failed to clear cache directory")?;
+    std::fs::create_dir_all(&dir).context("Failed to recreate cache directory")?;
+
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = load_manifest();
+    manifest.entries.retain(|_, entry| entry.kind != kind);
+    save_manifest(&manifest)
+}
+
+/// Move caches left in the OS temp dir by older versions into managed storage. Returns the
+/// new path of each moved file by its old path, so stored references can be rewritten.
+pub fn migrate_legacy_caches() -> Result<HashMap<String, String>> {
+    let legacy_root = std::env::temp_dir().join("zapcut");
+    let mut migrated = HashMap::new();
+
+    for kind in CacheKind::ALL {
+        let legacy_dir = legacy_root.join(kind.legacy_temp_dir_name());
+        let Ok(entries) = std::fs::read_dir(&legacy_dir) else {
+            continue;
+        };
+
+        let target_dir = get_cache_dir(kind)?;
+        for entry in entries.flatten() {
+            let source = entry.path();
+            if !source.is_file() {
+                continue;
+            }
+            let destination = target_dir.join(entry.file_name());
+            // rename fails across volumes, so fall back to copy + delete
+            let moved = destination.exists()
+                || std::fs::rename(&source, &destination).is_ok()
+                || std::fs::copy(&source, &destination).is_ok();
+            if moved {
+                let _ = std::fs::remove_file(&source);
+                migrated.insert(
+                    source.to_string_lossy().to_string(),
+                    destination.to_string_lossy().to_string(),
+                );
+            }
+        }
+
+        let _ = std::fs::remove_dir(&legacy_dir);
+    }
+
+    Ok(migrated)
+}
+
+/// Remove manifest entries whose file is gone. Files are never deleted here: a source that
+/// looks missing may just be on a drive that isn't mounted right now.
+pub fn cleanup_caches() -> Result<u64> {
+    let root = get_cache_root()?;
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = load_manifest();
+    let before = manifest.entries.len();
+    manifest.entries.retain(|key, _| root.join(key).exists());
+    let pruned = (before - manifest.entries.len()) as u64;
+    save_manifest(&manifest)?;
+    Ok(pruned)
+}
+
+/// Delete cached files whose source media no longer exists. Only run on request, since
+/// media on an unplugged drive counts as missing too.
+pub fn delete_orphaned_caches() -> Result<u64> {
+    let root = get_cache_root()?;
+    let _guard = MANIFEST_LOCK.lock().unwrap();
+    let mut manifest = load_manifest();
+    let mut removed = 0;
+
+    manifest.entries.retain(|key, entry| {
+        let path = root.join(key);
+        if !path.exists() {
+            return false;
+        }
+        let source_missing = entry
+            .source_path
+            .as_ref()
+            .map(|source| !Path::new(source).exists())
+            .unwrap_or(false);
+        if source_missing {
+            if std::fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+            return false;
+        }
+        true
+    });
+
+    save_manifest(&manifest)?;
+    Ok(removed)
+}
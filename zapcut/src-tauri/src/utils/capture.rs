@@ -0,0 +1,650 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Runtime};
+use crate::utils::ffmpeg::hardware_encoder;
+
+/// Display used when the caller doesn't pick one
+#[cfg(target_os = "macos")]
+pub const DEFAULT_DISPLAY: &str = "Capture screen 0";
+#[cfg(target_os = "windows")]
+pub const DEFAULT_DISPLAY: &str = "desktop";
+#[cfg(target_os = "linux")]
+pub const DEFAULT_DISPLAY: &str = ":0.0";
+
+/// How system audio is combined with the microphone
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SystemAudioMode {
+    /// Mix microphone and system audio into one track
+    #[default]
+    Mix,
+    /// Keep them as separate audio tracks in the recording
+    SeparateTrack,
+}
+
+/// Part of the screen a native capture records. Window and region coordinates are in logical
+/// points, as the UI sees them; `scale_factor` converts them to physical pixels on HiDPI screens.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CaptureTarget {
+    /// The whole display
+    #[default]
+    Display,
+    /// A single window: its title on Windows, its X11 window id on Linux
+    Window { id: String },
+    /// A rectangle on the display
+    Region {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+        #[serde(default)]
+        scale_factor: Option<f64>,
+    },
+}
+
+/// A region in physical pixels, with even dimensions as yuv420p requires
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PixelRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl CaptureTarget {
+    /// The region in physical pixels, if this target is one
+    pub fn pixel_rect(&self) -> Option<PixelRect> {
+        match self {
+            CaptureTarget::Region { x, y, width, height, scale_factor } => {
+                let scale = scale_factor.filter(|s| *s > 0.0).unwrap_or(1.0);
+                let to_px = |v: u32| (v as f64 * scale).round() as u32;
+                Some(PixelRect {
+                    x: to_px(*x),
+                    y: to_px(*y),
+                    width: (to_px(*width) / 2 * 2).max(2),
+                    height: (to_px(*height) / 2 * 2).max(2),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Position and size of a monitor on the virtual desktop, in physical pixels
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct DisplayBounds {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A monitor that native capture can record
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DisplayInfo {
+    /// Pass as RecordingSettings.display
+    pub id: String,
+    pub name: String,
+    pub bounds: DisplayBounds,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+/// Monitors in the order the OS reports them; the id is the index into this list, which on
+/// macOS matches AVFoundation's "Capture screen N" numbering
+pub fn enumerate_displays<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<DisplayInfo>> {
+    let primary = app
+        .primary_monitor()
+        .map_err(|e| anyhow::anyhow!("Failed to query primary display: {}", e))?
+        .map(|m| *m.position());
+    let monitors = app
+        .available_monitors()
+        .map_err(|e| anyhow::anyhow!("Failed to list displays: {}", e))?;
+
+    Ok(monitors
+        .iter()
+        .enumerate()
+        .map(|(index, monitor)| {
+            let position = *monitor.position();
+            let size = *monitor.size();
+            DisplayInfo {
+                id: index.to_string(),
+                name: monitor
+                    .name()
+                    .cloned()
+                    .unwrap_or_else(|| format!("Display {}", index + 1)),
+                bounds: DisplayBounds {
+                    x: position.x,
+                    y: position.y,
+                    width: size.width,
+                    height: size.height,
+                },
+                scale_factor: monitor.scale_factor(),
+                is_primary: primary == Some(position),
+            }
+        })
+        .collect())
+}
+
+/// FFmpeg screen input for a display from list_displays
+#[cfg(target_os = "macos")]
+pub fn display_input(display: &DisplayInfo) -> Option<String> {
+    Some(format!("Capture screen {}", display.id))
+}
+
+/// FFmpeg screen input for a display from list_displays. None here: gdigrab and x11grab grab
+/// the whole desktop and the display's bounds select the monitor.
+#[cfg(not(target_os = "macos"))]
+pub fn display_input(_display: &DisplayInfo) -> Option<String> {
+    None
+}
+
+/// Video codec for recordings
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum VideoEncoder {
+    #[default]
+    H264,
+    /// Smaller files at the same quality, heavier to encode
+    Hevc,
+}
+
+/// Whether recordings use the GPU's video encoder
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum HardwareEncoding {
+    /// VideoToolbox/NVENC/QSV when one works, software otherwise
+    #[default]
+    Auto,
+    /// Fail rather than fall back to software
+    Hardware,
+    Software,
+}
+
+/// Encoding knobs shared by native capture and browser recording processing
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RecordingQuality {
+    /// Downscale anything taller than this, e.g. 1080 to record a 4K display at 1080p
+    #[serde(default)]
+    pub max_height: Option<u32>,
+    /// Constant quality (lower is better); ignored when bitrate_kbps is set
+    #[serde(default)]
+    pub crf: Option<u32>,
+    /// Target video bitrate instead of constant quality
+    #[serde(default)]
+    pub bitrate_kbps: Option<u32>,
+    #[serde(default)]
+    pub encoder: VideoEncoder,
+    /// x264/x265 preset; faster presets drop fewer frames while capturing
+    #[serde(default)]
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub hardware_encoding: HardwareEncoding,
+}
+
+impl RecordingQuality {
+    /// Scale filter applying max_height; always yields the even dimensions yuv420p requires
+    pub fn scale_filter(&self) -> String {
+        match self.max_height {
+            Some(max) => format!("scale=-2:'trunc(min(ih,{})/2)*2'", max.max(2)),
+            None => "scale=trunc(iw/2)*2:trunc(ih/2)*2".to_string(),
+        }
+    }
+
+    /// Encoder arguments, falling back to the caller's preset and CRF. Errors only when
+    /// hardware encoding is forced and none is available.
+    pub fn video_codec_args(&self, default_preset: &str, default_crf: u32) -> Result<Vec<String>> {
+        let codec = match self.encoder {
+            VideoEncoder::H264 => "h264",
+            VideoEncoder::Hevc => "hevc",
+        };
+        let crf = self.crf.unwrap_or(default_crf).min(51);
+        let bitrate = self.bitrate_kbps.filter(|kbps| *kbps > 0).map(|kbps| {
+            to_args(&[
+                "-b:v", &format!("{}k", kbps),
+                "-maxrate", &format!("{}k", kbps),
+                "-bufsize", &format!("{}k", kbps * 2),
+            ])
+        });
+
+        let hardware = match self.hardware_encoding {
+            HardwareEncoding::Software => None,
+            HardwareEncoding::Auto => hardware_encoder(codec),
+            HardwareEncoding::Hardware => Some(hardware_encoder(codec).ok_or_else(|| {
+                anyhow::anyhow!("No working hardware {} encoder found", codec)
+            })?),
+        };
+
+        let mut args = match hardware {
+            Some(encoder) => {
+                let mut args = to_args(&["-c:v", &encoder]);
+                match bitrate {
+                    Some(bitrate) => args.extend(bitrate),
+                    None => args.extend(hardware_quality_args(&encoder, crf)),
+                }
+                // Hardware encoders take NV12 natively
+                args.extend(to_args(&["-pix_fmt", "nv12"]));
+                args
+            }
+            None => {
+                let encoder = if codec == "hevc" { "libx265" } else { "libx264" };
+                let preset = self.preset.as_deref().unwrap_or(default_preset);
+                let mut args = to_args(&["-c:v", encoder, "-preset", preset]);
+                args.extend(bitrate.unwrap_or_else(|| to_args(&["-crf", &crf.to_string()])));
+                args.extend(to_args(&["-pix_fmt", "yuv420p"]));
+                args
+            }
+        };
+
+        if self.encoder == VideoEncoder::Hevc {
+            // QuickTime and Safari only play HEVC in MP4 when tagged hvc1
+            args.extend(to_args(&["-tag:v", "hvc1"]));
+        }
+        Ok(args)
+    }
+
+    /// The same settings with software encoding, for retrying after a hardware failure
+    pub fn software(&self) -> Self {
+        Self {
+            hardware_encoding: HardwareEncoding::Software,
+            ..self.clone()
+        }
+    }
+}
+
+/// Constant-quality flags for a hardware encoder, mapped from an x264-style CRF
+fn hardware_quality_args(encoder: &str, crf: u32) -> Vec<String> {
+    let q = crf.to_string();
+    if encoder.ends_with("_nvenc") {
+        to_args(&["-preset", "p4", "-rc", "vbr", "-cq", &q, "-b:v", "0"])
+    } else if encoder.ends_with("_qsv") {
+        to_args(&["-preset", "veryfast", "-global_quality", &q])
+    } else if encoder.ends_with("_amf") {
+        to_args(&["-rc", "cqp", "-qp_i", &q, "-qp_p", &q])
+    } else {
+        // VideoToolbox quality runs 1-100, higher is better; CRF 23 lands near 60
+        let quality = (100.0 - crf as f64 * 1.75).clamp(1.0, 100.0);
+        to_args(&["-q:v", &format!("{:.0}", quality)])
+    }
+}
+
+/// What a native capture session records
+#[derive(Debug, Clone)]
+pub struct CaptureConfig {
+    /// Whether the screen is captured; when false the webcam is the only video stream
+    pub screen: bool,
+    /// FFmpeg input for the screen (avfoundation device name/index, gdigrab target, X11 display)
+    pub display: Option<String>,
+    /// Bounds of the chosen monitor, for backends that grab the whole virtual desktop
+    pub display_bounds: Option<DisplayBounds>,
+    /// Display, window or region to capture
+    pub target: CaptureTarget,
+    pub fps: u32,
+    pub quality: RecordingQuality,
+    pub capture_cursor: bool,
+    /// Webcam device id as returned by get_available_webcams, recorded as a second video stream
+    /// (the only one when the screen isn't captured)
+    pub webcam: Option<String>,
+    /// Microphone device id as returned by get_available_microphones
+    pub microphone: Option<String>,
+    /// Loopback device id as returned by get_system_audio_sources
+    pub system_audio: Option<String>,
+    pub system_audio_mode: SystemAudioMode,
+}
+
+fn to_args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|s| s.to_string()).collect()
+}
+
+/// FFmpeg input arguments for grabbing the screen
+#[cfg(target_os = "macos")]
+pub fn screen_input_args(config: &CaptureConfig) -> Result<Vec<String>> {
+    let display = config.display.clone().unwrap_or_else(|| DEFAULT_DISPLAY.to_string());
+    let fps = config.fps.to_string();
+    let cursor = if config.capture_cursor { "1" } else { "0" };
+    if let CaptureTarget::Window { .. } = config.target {
+        anyhow::bail!("Window capture isn't available through AVFoundation; capture a region instead");
+    }
+    // "<video>:none" keeps audio out of the screen input; audio devices are separate inputs
+    let input = format!("{}:none", display);
+    Ok(to_args(&[
+        "-f", "avfoundation",
+        "-capture_cursor", cursor,
+        "-framerate", &fps,
+        "-pixel_format", "uyvy422",
+        "-i", &input,
+    ]))
+}
+
+/// Area of the virtual desktop to grab: the region offset into the chosen monitor, or the
+/// monitor itself. None grabs everything.
+#[cfg(not(target_os = "macos"))]
+fn grab_area(config: &CaptureConfig) -> Option<DisplayBounds> {
+    let origin = config.display_bounds.map(|b| (b.x, b.y)).unwrap_or((0, 0));
+    match config.target.pixel_rect() {
+        Some(rect) => Some(DisplayBounds {
+            x: origin.0 + rect.x as i32,
+            y: origin.1 + rect.y as i32,
+            width: rect.width,
+            height: rect.height,
+        }),
+        None => config.display_bounds.map(|b| DisplayBounds {
+            width: b.width / 2 * 2,
+            height: b.height / 2 * 2,
+            ..b
+        }),
+    }
+}
+
+/// FFmpeg input arguments for grabbing the screen
+#[cfg(target_os = "windows")]
+pub fn screen_input_args(config: &CaptureConfig) -> Result<Vec<String>> {
+    let fps = config.fps.to_string();
+    let cursor = if config.capture_cursor { "1" } else { "0" };
+    let mut args = to_args(&["-f", "gdigrab", "-draw_mouse", cursor, "-framerate", &fps]);
+
+    // gdigrab works in physical pixels since the app is DPI aware. A window is grabbed whole,
+    // and its offsets would be relative to the window, not the desktop.
+    let input = match &config.target {
+        CaptureTarget::Window { id } => format!("title={}", id),
+        _ => {
+            if let Some(area) = grab_area(config) {
+                args.extend(to_args(&[
+                    "-offset_x", &area.x.to_string(),
+                    "-offset_y", &area.y.to_string(),
+                    "-video_size", &format!("{}x{}", area.width, area.height),
+                ]));
+            }
+            config.display.clone().unwrap_or_else(|| DEFAULT_DISPLAY.to_string())
+        }
+    };
+    args.extend(to_args(&["-i", &input]));
+    Ok(args)
+}
+
+/// FFmpeg input arguments for grabbing the screen
+#[cfg(target_os = "linux")]
+pub fn screen_input_args(config: &CaptureConfig) -> Result<Vec<String>> {
+    // x11grab can't see Wayland windows; XWayland only exposes X11 clients
+    let x_display = std::env::var("DISPLAY").ok();
+    if x_display.is_none() && std::env::var("WAYLAND_DISPLAY").is_ok() {
+        anyhow::bail!("Native screen capture needs an X11 session; use browser capture on Wayland");
+    }
+
+    let display = config
+        .display
+        .clone()
+        .or(x_display)
+        .unwrap_or_else(|| DEFAULT_DISPLAY.to_string());
+    let fps = config.fps.to_string();
+    let cursor = if config.capture_cursor { "1" } else { "0" };
+    let mut args = to_args(&[
+        "-f", "x11grab",
+        "-draw_mouse", cursor,
+        "-framerate", &fps,
+        "-thread_queue_size", "512",
+    ]);
+
+    // X11 coordinates are physical pixels; the region offset is part of the input name
+    let mut input = display;
+    if let CaptureTarget::Window { id } = &config.target {
+        args.extend(to_args(&["-window_id", id]));
+    } else if let Some(area) = grab_area(config) {
+        args.extend(to_args(&["-video_size", &format!("{}x{}", area.width, area.height)]));
+        input = format!("{}+{},{}", input, area.x, area.y);
+    }
+    args.extend(to_args(&["-i", &input]));
+    Ok(args)
+}
+
+/// FFmpeg input arguments for an audio capture device
+pub fn audio_input_args(device_id: &str) -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    let args = to_args(&["-f", "avfoundation", "-i", &format!(":{}", device_id)]);
+    #[cfg(target_os = "windows")]
+    let args = to_args(&["-f", "dshow", "-i", &format!("audio={}", device_id)]);
+    #[cfg(target_os = "linux")]
+    let args = to_args(&["-f", "pulse", "-i", device_id]);
+
+    let mut with_queue = to_args(&["-thread_queue_size", "1024"]);
+    with_queue.extend(args);
+    with_queue
+}
+
+/// AVFoundation always grabs the whole display, so regions are cropped after capture
+#[cfg(target_os = "macos")]
+fn region_crop_filter(config: &CaptureConfig) -> Option<String> {
+    config
+        .target
+        .pixel_rect()
+        .map(|rect| format!("crop={}:{}:{}:{}", rect.width, rect.height, rect.x, rect.y))
+}
+
+/// gdigrab and x11grab capture regions at the input
+#[cfg(not(target_os = "macos"))]
+fn region_crop_filter(_config: &CaptureConfig) -> Option<String> {
+    None
+}
+
+/// FFmpeg input arguments for a webcam, as returned by get_available_webcams
+pub fn webcam_input_args(device_id: &str) -> Vec<String> {
+    #[cfg(target_os = "macos")]
+    let args = to_args(&["-f", "avfoundation", "-framerate", "30", "-i", &format!("{}:none", device_id)]);
+    #[cfg(target_os = "windows")]
+    let args = to_args(&["-f", "dshow", "-i", &format!("video={}", device_id)]);
+    #[cfg(target_os = "linux")]
+    let args = to_args(&["-f", "v4l2", "-i", device_id]);
+
+    let mut with_queue = to_args(&["-thread_queue_size", "512"]);
+    with_queue.extend(args);
+    with_queue
+}
+
+/// Keyframe (and so fragment) interval of native recordings; a crash loses at most this much
+pub const FRAGMENT_SECS: u64 = 2;
+
+/// Full FFmpeg argument list for recording `config` into an MP4 at `output_path`. The webcam,
+/// when enabled, is kept as a second video stream so its layout can be chosen afterwards.
+///
+/// The MP4 is fragmented with the header written up front, so a file cut short by a crash or
+/// kill is still playable up to its last fragment and needs no remux afterwards.
+pub fn build_capture_args(config: &CaptureConfig, output_path: &str) -> Result<Vec<String>> {
+    let mut args = capture_encode_args(config)?;
+    args.extend(to_args(&[
+        "-force_key_frames", &format!("expr:gte(t,n_forced*{})", FRAGMENT_SECS),
+        "-movflags", "+frag_keyframe+empty_moov+default_base_moof",
+        output_path,
+    ]));
+    Ok(args)
+}
+
+/// Length of each replay buffer segment; saved replays start on one of these boundaries
+pub const REPLAY_SEGMENT_SECS: u64 = 2;
+
+/// FFmpeg arguments for a replay buffer: capture into a ring of `segment_count` short MPEG-TS
+/// files in `dir`, overwriting the oldest. TS segments can be read while FFmpeg writes and join
+/// without re-encoding.
+pub fn build_replay_args(config: &CaptureConfig, dir: &str, segment_count: u64) -> Result<Vec<String>> {
+    let mut args = capture_encode_args(config)?;
+    let pattern = std::path::Path::new(dir).join("replay_%03d.ts");
+    args.extend(to_args(&[
+        // Keyframes on segment boundaries so every segment starts cleanly
+        "-force_key_frames", &format!("expr:gte(t,n_forced*{})", REPLAY_SEGMENT_SECS),
+        "-f", "segment",
+        "-segment_time", &REPLAY_SEGMENT_SECS.to_string(),
+        "-segment_wrap", &segment_count.to_string(),
+        "-reset_timestamps", "1",
+        "-segment_format", "mpegts",
+        &pattern.to_string_lossy(),
+    ]));
+    Ok(args)
+}
+
+/// Inputs, mapping and encoder arguments shared by every capture output
+fn capture_encode_args(config: &CaptureConfig) -> Result<Vec<String>> {
+    let mut args = to_args(&["-hide_banner", "-y"]);
+    let screen_inputs = if config.screen {
+        args.extend(screen_input_args(config)?);
+        1
+    } else {
+        0
+    };
+
+    // Webcam and audio inputs follow the screen input (index 0), each audio with a track title
+    let webcam_input = config.webcam.as_deref().map(|device_id| {
+        args.extend(webcam_input_args(device_id));
+        screen_inputs
+    });
+    if screen_inputs == 0 && webcam_input.is_none() {
+        anyhow::bail!("Nothing to capture: the screen and the webcam are both disabled");
+    }
+    let first_audio_input = screen_inputs + webcam_input.map_or(0, |_| 1);
+    let audio_sources: Vec<(&str, &str)> = [
+        config.microphone.as_deref().map(|id| (id, "Microphone")),
+        config.system_audio.as_deref().map(|id| (id, "System Audio")),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    for (device_id, _) in &audio_sources {
+        args.extend(audio_input_args(device_id));
+    }
+
+    if config.screen {
+        args.extend(to_args(&["-map", "0:v"]));
+    }
+    if let Some(input) = webcam_input {
+        args.extend(to_args(&[
+            "-map",
+            &format!("{}:v", input),
+            &format!("-metadata:s:v:{}", screen_inputs),
+            "title=Webcam",
+        ]));
+    }
+    let mix = audio_sources.len() == 2 && config.system_audio_mode == SystemAudioMode::Mix;
+    if mix {
+        // Summed at full level so the mic isn't quieter than in single-source takes; the limiter
+        // keeps loud moments on both sources from clipping
+        args.extend(to_args(&[
+            "-filter_complex",
+            &format!(
+                "[{}:a][{}:a]amix=inputs=2:duration=longest:dropout_transition=0:normalize=0,\
+                 alimiter=limit=0.97[aout]",
+                first_audio_input,
+                first_audio_input + 1
+            ),
+            "-map",
+            "[aout]",
+        ]));
+    } else {
+        for (track, (_, title)) in audio_sources.iter().enumerate() {
+            args.extend(to_args(&[
+                "-map",
+                &format!("{}:a", first_audio_input + track),
+                &format!("-metadata:s:a:{}", track),
+                &format!("title={}", title),
+            ]));
+        }
+    }
+
+    // Screen content compresses well; veryfast keeps up with high-res displays in real time
+    args.extend(config.quality.video_codec_args("veryfast", 23)?);
+    // Even dimensions are required by yuv420p; some displays and webcams have odd sizes
+    let even = "scale=trunc(iw/2)*2:trunc(ih/2)*2";
+    if config.screen {
+        let mut filters = Vec::new();
+        if let Some(crop) = region_crop_filter(config) {
+            filters.push(crop);
+        }
+        filters.push(config.quality.scale_filter());
+        args.extend(to_args(&["-filter:v:0", &filters.join(",")]));
+        if webcam_input.is_some() {
+            args.extend(to_args(&["-filter:v:1", even]));
+        }
+    } else {
+        // On its own the webcam is the recording, so it gets the quality's resolution cap
+        args.extend(to_args(&["-filter:v:0", &config.quality.scale_filter()]));
+    }
+    if !audio_sources.is_empty() {
+        args.extend(to_args(&["-c:a", "aac", "-b:a", "192k"]));
+    }
+
+    Ok(args)
+}
+
+/// Corner of the screen the webcam is placed in
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum PipCorner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+/// Picture-in-picture layout for compositing a webcam over a screen recording
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PipLayout {
+    #[serde(default)]
+    pub corner: PipCorner,
+    /// Webcam width as a fraction of the screen width
+    #[serde(default = "default_pip_size")]
+    pub size: f64,
+    /// Distance from the screen edges in pixels
+    #[serde(default = "default_pip_margin")]
+    pub margin: u32,
+    /// Corner radius as a fraction of the webcam's shorter side; 0.5 gives a circle on square
+    /// input, 0 a plain rectangle
+    #[serde(default)]
+    pub corner_radius: f64,
+}
+
+fn default_pip_size() -> f64 {
+    0.25
+}
+
+fn default_pip_margin() -> u32 {
+    24
+}
+
+/// filter_complex graph compositing `webcam` (e.g. "1:v" or "0:v:1") over `screen` into [vout]
+pub fn pip_filter(layout: &PipLayout, screen: &str, webcam: &str, screen_width: u32) -> String {
+    let width = ((screen_width as f64 * layout.size.clamp(0.05, 1.0)) as u32 / 2 * 2).max(2);
+    let mut webcam_chain = format!("scale={}:-2", width);
+
+    let radius = layout.corner_radius.clamp(0.0, 0.5);
+    if radius > 0.0 {
+        // Alpha is 0 outside a rounded rectangle: only pixels in a corner square farther than R
+        // from that square's inner corner are cut away
+        let r = format!("(min(W,H)*{:.3})", radius);
+        webcam_chain.push_str(&format!(
+            ",format=yuva420p,geq=lum='p(X,Y)':cb='cb(X,Y)':cr='cr(X,Y)':\
+             a='if(gt(abs(X-W/2),W/2-{r})*gt(abs(Y-H/2),H/2-{r}),\
+             if(lte(hypot(abs(X-W/2)-(W/2-{r}),abs(Y-H/2)-(H/2-{r})),{r}),255,0),255)'",
+            r = r
+        ));
+    }
+
+    let m = layout.margin;
+    let (x, y) = match layout.corner {
+        PipCorner::TopLeft => (format!("{}", m), format!("{}", m)),
+        PipCorner::TopRight => (format!("main_w-overlay_w-{}", m), format!("{}", m)),
+        PipCorner::BottomLeft => (format!("{}", m), format!("main_h-overlay_h-{}", m)),
+        PipCorner::BottomRight => (
+            format!("main_w-overlay_w-{}", m),
+            format!("main_h-overlay_h-{}", m),
+        ),
+    };
+
+    // eof_action=pass keeps the screen going if the webcam stream ends first
+    format!(
+        "[{webcam}]{chain}[pip];[{screen}][pip]overlay=x={x}:y={y}:eof_action=pass,format=yuv420p[vout]",
+        webcam = webcam,
+        chain = webcam_chain,
+        screen = screen,
+        x = x,
+        y = y
+    )
+}
@@ -0,0 +1,460 @@
+use crate::utils::ffmpeg::escape_filter_path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Cursor samples per second; enough for smooth highlight motion without huge tracks
+const SAMPLE_RATE: u64 = 30;
+
+/// Cursor position in physical screen pixels at a point in the recording
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct CursorSample {
+    /// Seconds into the recording, excluding paused time
+    pub t: f64,
+    pub x: f64,
+    pub y: f64,
+    /// Primary button held down
+    pub pressed: bool,
+}
+
+/// Cursor movement recorded alongside a capture, stored as `<recording>.cursor.json`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CursorTrack {
+    /// Captured area on the virtual desktop, in physical pixels
+    pub origin_x: f64,
+    pub origin_y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub samples: Vec<CursorSample>,
+}
+
+impl CursorTrack {
+    /// Sidecar path for a recording
+    pub fn path_for(recording: &Path) -> std::path::PathBuf {
+        let stem = recording.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+        recording.with_file_name(format!("{}.cursor.json", stem))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read cursor track {:?}", path))?;
+        serde_json::from_str(&contents).context("Failed to parse cursor track")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write cursor track {:?}", path))
+    }
+
+    /// Times and positions where the primary button went down
+    pub fn clicks(&self) -> Vec<CursorSample> {
+        self.samples
+            .windows(2)
+            .filter(|pair| pair[1].pressed && !pair[0].pressed)
+            .map(|pair| pair[1])
+            .collect()
+    }
+}
+
+/// Samples the cursor on a background thread while a capture runs
+pub struct CursorRecorder {
+    stop: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+    samples: Arc<Mutex<Vec<CursorSample>>>,
+    thread: Option<JoinHandle<()>>,
+    origin: (f64, f64),
+    size: (f64, f64),
+}
+
+impl CursorRecorder {
+    /// Start sampling. `origin` and `size` describe the captured area in physical pixels.
+    pub fn start(origin: (f64, f64), size: (f64, f64)) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let paused = Arc::new(AtomicBool::new(false));
+        let samples = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = {
+            let stop = stop.clone();
+            let paused = paused.clone();
+            let samples = samples.clone();
+            std::thread::spawn(move || {
+                let Some(pointer) = platform::Pointer::open() else {
+                    eprintln!("[Recording] Cursor tracking is unavailable on this system");
+                    return;
+                };
+                let interval = Duration::from_millis(1000 / SAMPLE_RATE);
+                let mut recorded = Duration::ZERO;
+                let mut last_tick = Instant::now();
+                while !stop.load(Ordering::SeqCst) {
+                    std::thread::sleep(interval);
+                    let now = Instant::now();
+                    // Paused time is cut from the recording, so it isn't counted here either
+                    if !paused.load(Ordering::SeqCst) {
+                        recorded += now - last_tick;
+                        if let Some((x, y, pressed)) = pointer.query() {
+                            samples.lock().unwrap().push(CursorSample {
+                                t: recorded.as_secs_f64(),
+                                x,
+                                y,
+                                pressed,
+                            });
+                        }
+                    }
+                    last_tick = now;
+                }
+            })
+        };
+
+        Self {
+            stop,
+            paused,
+            samples,
+            thread: Some(thread),
+            origin,
+            size,
+        }
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    /// Stop sampling and return the track
+    pub fn finish(mut self) -> CursorTrack {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        CursorTrack {
+            origin_x: self.origin.0,
+            origin_y: self.origin.1,
+            width: self.size.0,
+            height: self.size.1,
+            samples: std::mem::take(&mut *self.samples.lock().unwrap()),
+        }
+    }
+}
+
+/// Which cursor effects to render into a recording
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CursorEffects {
+    /// Translucent circle following the cursor
+    #[serde(default = "default_true")]
+    pub highlight: bool,
+    /// Ring flashed where the primary button is pressed
+    #[serde(default = "default_true")]
+    pub click_ripple: bool,
+    /// Highlight diameter in output pixels
+    #[serde(default = "default_highlight_size")]
+    pub size: u32,
+    /// RGB hex color such as "ffd400"
+    #[serde(default = "default_highlight_color")]
+    pub color: String,
+}
+
+impl Default for CursorEffects {
+    fn default() -> Self {
+        Self {
+            highlight: true,
+            click_ripple: true,
+            size: default_highlight_size(),
+            color: default_highlight_color(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_highlight_size() -> u32 {
+    56
+}
+
+fn default_highlight_color() -> String {
+    "ffd400".to_string()
+}
+
+/// How long a click ring stays visible
+const CLICK_RIPPLE_SECS: f64 = 0.35;
+/// Overlay position that puts an effect off screen
+const HIDDEN: i64 = -10_000;
+
+/// Build a filter_complex graph drawing `effects` over `[0:v]` into `[vout]`. Positions are
+/// driven by a sendcmd script written to `commands_path`, so the graph stays the same size
+/// however long the recording is.
+pub fn cursor_effects_filter(
+    track: &CursorTrack,
+    effects: &CursorEffects,
+    video_width: u32,
+    video_height: u32,
+    commands_path: &Path,
+) -> Result<String> {
+    let scale_x = video_width as f64 / track.width.max(1.0);
+    let scale_y = video_height as f64 / track.height.max(1.0);
+    let size = effects.size.max(8) as f64;
+    let to_video = |sample: &CursorSample| {
+        (
+            ((sample.x - track.origin_x) * scale_x - size / 2.0).round() as i64,
+            ((sample.y - track.origin_y) * scale_y - size / 2.0).round() as i64,
+        )
+    };
+
+    let (r, g, b) = parse_hex_color(&effects.color).unwrap_or((255, 212, 0));
+    let mut commands = Vec::new();
+    let mut graph = vec![format!("[0:v]sendcmd=f='{}'[base]", escape_filter_path(commands_path))];
+    let mut last = "base".to_string();
+
+    if effects.highlight {
+        for sample in &track.samples {
+            let (x, y) = to_video(sample);
+            commands.push(format!("{:.3} overlay@cursor x {}, overlay@cursor y {};", sample.t, x, y));
+        }
+        graph.push(format!(
+            "color=c=black@0:s={s}x{s},format=rgba,geq=r={r}:g={g}:b={b}:a='if(lte(hypot(X-{h},Y-{h}),{h}),110,0)'[hl]",
+            s = size as u32, h = size / 2.0, r = r, g = g, b = b
+        ));
+        graph.push(format!(
+            "[{}][hl]overlay@cursor=x={}:y={}:shortest=1[cur]",
+            last, HIDDEN, HIDDEN
+        ));
+        last = "cur".to_string();
+    }
+
+    if effects.click_ripple {
+        for click in track.clicks() {
+            let (x, y) = to_video(&click);
+            commands.push(format!("{:.3} overlay@click x {}, overlay@click y {};", click.t, x, y));
+            commands.push(format!(
+                "{:.3} overlay@click x {}, overlay@click y {};",
+                click.t + CLICK_RIPPLE_SECS,
+                HIDDEN,
+                HIDDEN
+            ));
+        }
+        graph.push(format!(
+            "color=c=black@0:s={s}x{s},format=rgba,geq=r={r}:g={g}:b={b}:a='if(between(hypot(X-{h},Y-{h}),{h}-5,{h}),230,0)'[ring]",
+            s = size as u32, h = size / 2.0, r = r, g = g, b = b
+        ));
+        graph.push(format!(
+            "[{}][ring]overlay@click=x={}:y={}:shortest=1[clk]",
+            last, HIDDEN, HIDDEN
+        ));
+        last = "clk".to_string();
+    }
+
+    // sendcmd needs commands in time order
+    commands.sort_by(|a, b| {
+        let time = |line: &String| line.split(' ').next().and_then(|t| t.parse::<f64>().ok()).unwrap_or(0.0);
+        time(a).total_cmp(&time(b))
+    });
+    std::fs::write(commands_path, commands.join("\n")).context("Failed to write cursor commands")?;
+
+    graph.push(format!("[{}]format=yuv420p[vout]", last));
+    Ok(graph.join(";"))
+}
+
+fn parse_hex_color(value: &str) -> Option<(u8, u8, u8)> {
+    let hex = value.trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    Some((
+        u8::from_str_radix(&hex[0..2], 16).ok()?,
+        u8::from_str_radix(&hex[2..4], 16).ok()?,
+        u8::from_str_radix(&hex[4..6], 16).ok()?,
+    ))
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::c_void;
+
+    #[repr(C)]
+    struct CGPoint {
+        x: f64,
+        y: f64,
+    }
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventCreate(source: *const c_void) -> *mut c_void;
+        fn CGEventGetLocation(event: *const c_void) -> CGPoint;
+        fn CGEventSourceButtonState(state: i32, button: u32) -> bool;
+        fn CFRelease(object: *const c_void);
+        fn CGMainDisplayID() -> u32;
+        fn CGDisplayPixelsWide(display: u32) -> usize;
+        fn CGDisplayCopyDisplayMode(display: u32) -> *mut c_void;
+        fn CGDisplayModeGetPixelWidth(mode: *const c_void) -> usize;
+    }
+
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+
+    pub struct Pointer {
+        /// CoreGraphics reports points; captures are in pixels
+        scale: f64,
+    }
+
+    impl Pointer {
+        pub fn open() -> Option<Self> {
+            let scale = unsafe {
+                let display = CGMainDisplayID();
+                let mode = CGDisplayCopyDisplayMode(display);
+                if mode.is_null() {
+                    return None;
+                }
+                let pixels = CGDisplayModeGetPixelWidth(mode) as f64;
+                CFRelease(mode);
+                pixels / (CGDisplayPixelsWide(display).max(1) as f64)
+            };
+            Some(Self { scale })
+        }
+
+        pub fn query(&self) -> Option<(f64, f64, bool)> {
+            unsafe {
+                let event = CGEventCreate(std::ptr::null());
+                if event.is_null() {
+                    return None;
+                }
+                let location = CGEventGetLocation(event);
+                CFRelease(event);
+                let pressed = CGEventSourceButtonState(COMBINED_SESSION_STATE, 0);
+                Some((location.x * self.scale, location.y * self.scale, pressed))
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::Foundation::POINT;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_LBUTTON};
+    use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+    /// The app is DPI aware, so cursor coordinates are already physical pixels
+    pub struct Pointer;
+
+    impl Pointer {
+        pub fn open() -> Option<Self> {
+            Some(Self)
+        }
+
+        pub fn query(&self) -> Option<(f64, f64, bool)> {
+            let mut point = POINT::default();
+            unsafe { GetCursorPos(&mut point) }.ok()?;
+            // High bit set means the button is currently down
+            let pressed = unsafe { GetAsyncKeyState(VK_LBUTTON.0 as i32) } < 0;
+            Some((point.x as f64, point.y as f64, pressed))
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::ffi::{c_char, c_int, c_uint, c_ulong, c_void, CString};
+
+    type XOpenDisplay = unsafe extern "C" fn(*const c_char) -> *mut c_void;
+    type XCloseDisplay = unsafe extern "C" fn(*mut c_void) -> c_int;
+    type XDefaultRootWindow = unsafe extern "C" fn(*mut c_void) -> c_ulong;
+    #[allow(clippy::type_complexity)]
+    type XQueryPointer = unsafe extern "C" fn(
+        *mut c_void,
+        c_ulong,
+        *mut c_ulong,
+        *mut c_ulong,
+        *mut c_int,
+        *mut c_int,
+        *mut c_int,
+        *mut c_int,
+        *mut c_uint,
+    ) -> c_int;
+
+    /// Button1Mask from X.h
+    const BUTTON1_MASK: c_uint = 1 << 8;
+
+    /// Xlib loaded at runtime so the app doesn't link against it
+    pub struct Pointer {
+        library: *mut c_void,
+        display: *mut c_void,
+        root: c_ulong,
+        query_pointer: XQueryPointer,
+        close_display: XCloseDisplay,
+    }
+
+    unsafe fn symbol<T: Copy>(library: *mut c_void, name: &str) -> Option<T> {
+        let name = CString::new(name).ok()?;
+        let pointer = libc::dlsym(library, name.as_ptr());
+        if pointer.is_null() {
+            None
+        } else {
+            Some(std::mem::transmute_copy(&pointer))
+        }
+    }
+
+    impl Pointer {
+        pub fn open() -> Option<Self> {
+            unsafe {
+                let name = CString::new("libX11.so.6").ok()?;
+                let library = libc::dlopen(name.as_ptr(), libc::RTLD_NOW);
+                if library.is_null() {
+                    return None;
+                }
+                let loaded = (|| {
+                    let open_display: XOpenDisplay = symbol(library, "XOpenDisplay")?;
+                    let default_root: XDefaultRootWindow = symbol(library, "XDefaultRootWindow")?;
+                    let query_pointer: XQueryPointer = symbol(library, "XQueryPointer")?;
+                    let close_display: XCloseDisplay = symbol(library, "XCloseDisplay")?;
+                    let display = open_display(std::ptr::null());
+                    if display.is_null() {
+                        return None;
+                    }
+                    Some(Self {
+                        library,
+                        display,
+                        root: default_root(display),
+                        query_pointer,
+                        close_display,
+                    })
+                })();
+                if loaded.is_none() {
+                    libc::dlclose(library);
+                }
+                loaded
+            }
+        }
+
+        pub fn query(&self) -> Option<(f64, f64, bool)> {
+            let (mut root, mut child) = (0, 0);
+            let (mut x, mut y, mut win_x, mut win_y) = (0, 0, 0, 0);
+            let mut mask: c_uint = 0;
+            let found = unsafe {
+                (self.query_pointer)(
+                    self.display,
+                    self.root,
+                    &mut root,
+                    &mut child,
+                    &mut x,
+                    &mut y,
+                    &mut win_x,
+                    &mut win_y,
+                    &mut mask,
+                )
+            };
+            (found != 0).then_some((x as f64, y as f64, mask & BUTTON1_MASK != 0))
+        }
+    }
+
+    impl Drop for Pointer {
+        fn drop(&mut self) {
+            unsafe {
+                (self.close_display)(self.display);
+                libc::dlclose(self.library);
+            }
+        }
+    }
+}
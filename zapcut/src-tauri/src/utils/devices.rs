@@ -0,0 +1,375 @@
+use crate::utils::ffmpeg::get_ffmpeg_path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    Video,
+    Audio,
+}
+
+/// A capture mode supported by a video device
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DeviceCapability {
+    pub width: u32,
+    pub height: u32,
+    pub min_fps: Option<f64>,
+    pub max_fps: Option<f64>,
+    /// Pixel format or codec the device delivers (yuyv422, mjpeg, ...)
+    pub pixel_format: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CaptureDevice {
+    /// Value to pass as the FFmpeg input for this device's backend
+    pub id: String,
+    pub label: String,
+    pub kind: DeviceKind,
+    /// FFmpeg input format: avfoundation, dshow, v4l2 or pulse
+    pub backend: String,
+    pub is_default: bool,
+    /// Empty when the backend can't report modes without opening the device
+    pub capabilities: Vec<DeviceCapability>,
+}
+
+/// Run FFmpeg with the given args and return stderr, where device listings are printed.
+/// Listing commands "fail" by design (there is no real input), so the exit status is ignored.
+fn ffmpeg_stderr(args: &[&str]) -> Result<String> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let output = Command::new(ffmpeg_path)
+        .arg("-hide_banner")
+        .args(args)
+        .output()
+        .context("Failed to execute ffmpeg for device listing")?;
+    Ok(String::from_utf8_lossy(&output.stderr).to_string())
+}
+
+/// Strip the "[indev @ 0x...]" prefix FFmpeg puts on log lines
+fn strip_log_prefix(line: &str) -> &str {
+    match (line.find('['), line.find(']')) {
+        (Some(0), Some(end)) => line[end + 1..].trim(),
+        _ => line.trim(),
+    }
+}
+
+/// Parse "1280x720" into (width, height)
+#[cfg(not(target_os = "macos"))]
+fn parse_size(value: &str) -> Option<(u32, u32)> {
+    let (w, h) = value.split_once('x')?;
+    Some((w.trim().parse().ok()?, h.trim().parse().ok()?))
+}
+
+/// List video (webcam) or audio (microphone) capture devices for this platform
+pub fn list_capture_devices(kind: DeviceKind) -> Result<Vec<CaptureDevice>> {
+    platform::list_devices(kind)
+}
+
+/// Virtual devices that expose system audio as an input where FFmpeg has no loopback backend
+/// (macOS needs e.g. BlackHole; Windows needs Stereo Mix or a virtual cable)
+#[cfg(not(target_os = "linux"))]
+const LOOPBACK_DEVICE_NAMES: &[&str] = &[
+    "blackhole",
+    "soundflower",
+    "loopback audio",
+    "stereo mix",
+    "what u hear",
+    "wave out mix",
+    "virtual-audio-capturer",
+    "cable output",
+];
+
+/// Inputs that carry system (desktop) audio. Only Linux has native support, through
+/// PulseAudio/PipeWire monitor sources. macOS (ScreenCaptureKit) and Windows (WASAPI loopback)
+/// capture aren't implemented: there, only third-party loopback devices are found, and the
+/// list is empty on a stock machine. The first entry is the suggested default.
+pub fn list_system_audio_sources() -> Result<Vec<CaptureDevice>> {
+    #[cfg(target_os = "linux")]
+    let mut sources = platform::list_pulse_sources(true)?;
+    #[cfg(not(target_os = "linux"))]
+    let mut sources: Vec<CaptureDevice> = platform::list_devices(DeviceKind::Audio)?
+        .into_iter()
+        .filter(|device| {
+            let label = device.label.to_lowercase();
+            LOOPBACK_DEVICE_NAMES.iter().any(|name| label.contains(name))
+        })
+        .collect();
+
+    sources.sort_by_key(|device| !device.is_default);
+    for (index, device) in sources.iter_mut().enumerate() {
+        device.is_default = index == 0;
+    }
+    Ok(sources)
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+
+    pub fn list_devices(kind: DeviceKind) -> Result<Vec<CaptureDevice>> {
+        let log = ffmpeg_stderr(&["-f", "avfoundation", "-list_devices", "true", "-i", ""])?;
+        Ok(parse_avfoundation(&log, kind))
+    }
+
+    /// Sections look like "AVFoundation video devices:" followed by "[0] FaceTime HD Camera".
+    /// Screens show up as video devices ("Capture screen 0") and are skipped here.
+    /// Capabilities aren't listed: avfoundation only reports modes after opening the camera.
+    pub(super) fn parse_avfoundation(log: &str, kind: DeviceKind) -> Vec<CaptureDevice> {
+        let mut section: Option<DeviceKind> = None;
+        let mut devices = Vec::new();
+
+        for line in log.lines().map(strip_log_prefix) {
+            if line.contains("video devices:") {
+                section = Some(DeviceKind::Video);
+                continue;
+            }
+            if line.contains("audio devices:") {
+                section = Some(DeviceKind::Audio);
+                continue;
+            }
+            if section != Some(kind) {
+                continue;
+            }
+
+            let Some(rest) = line.strip_prefix('[') else {
+                continue;
+            };
+            let Some((index, label)) = rest.split_once(']') else {
+                continue;
+            };
+            let label = label.trim();
+            if index.parse::<u32>().is_err() || label.starts_with("Capture screen") {
+                continue;
+            }
+
+            devices.push(CaptureDevice {
+                id: index.to_string(),
+                label: label.to_string(),
+                kind,
+                backend: "avfoundation".to_string(),
+                is_default: devices.is_empty(),
+                capabilities: Vec::new(),
+            });
+        }
+
+        devices
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+
+    pub fn list_devices(kind: DeviceKind) -> Result<Vec<CaptureDevice>> {
+        let log = ffmpeg_stderr(&["-list_devices", "true", "-f", "dshow", "-i", "dummy"])?;
+        let mut devices = parse_dshow(&log, kind);
+
+        if kind == DeviceKind::Video {
+            for device in &mut devices {
+                let input = format!("video={}", device.id);
+                if let Ok(options) = ffmpeg_stderr(&["-list_options", "true", "-f", "dshow", "-i", &input]) {
+                    device.capabilities = parse_dshow_options(&options);
+                }
+            }
+        }
+
+        Ok(devices)
+    }
+
+    /// Newer FFmpeg prints `"Name" (video)`; older builds group devices under
+    /// "DirectShow video devices" headers. Alternative names (@device_pnp_...) are skipped.
+    fn parse_dshow(log: &str, kind: DeviceKind) -> Vec<CaptureDevice> {
+        let mut section: Option<DeviceKind> = None;
+        let mut devices = Vec::new();
+
+        for line in log.lines().map(strip_log_prefix) {
+            if line.contains("DirectShow video devices") {
+                section = Some(DeviceKind::Video);
+                continue;
+            }
+            if line.contains("DirectShow audio devices") {
+                section = Some(DeviceKind::Audio);
+                continue;
+            }
+            if line.starts_with("Alternative name") || !line.starts_with('"') {
+                continue;
+            }
+
+            let Some(end) = line[1..].find('"') else {
+                continue;
+            };
+            let name = &line[1..end + 1];
+            let suffix = &line[end + 2..];
+            let device_kind = if suffix.contains("(video)") {
+                Some(DeviceKind::Video)
+            } else if suffix.contains("(audio)") {
+                Some(DeviceKind::Audio)
+            } else {
+                section
+            };
+
+            if device_kind == Some(kind) {
+                devices.push(CaptureDevice {
+                    id: name.to_string(),
+                    label: name.to_string(),
+                    kind,
+                    backend: "dshow".to_string(),
+                    is_default: devices.is_empty(),
+                    capabilities: Vec::new(),
+                });
+            }
+        }
+
+        devices
+    }
+
+    /// Lines look like "vcodec=mjpeg  min s=1280x720 fps=5 max s=1280x720 fps=30" or
+    /// "pixel_format=yuyv422  min s=640x480 fps=5 max s=640x480 fps=30"
+    fn parse_dshow_options(log: &str) -> Vec<DeviceCapability> {
+        let mut capabilities: Vec<DeviceCapability> = Vec::new();
+
+        for line in log.lines().map(strip_log_prefix) {
+            let Some(max_index) = line.find(" max ") else {
+                continue;
+            };
+            let (min_part, max_part) = line.split_at(max_index);
+            let format = min_part
+                .split_whitespace()
+                .next()
+                .and_then(|field| field.split_once('='))
+                .map(|(_, value)| value.to_string());
+
+            let field = |part: &str, key: &str| {
+                part.split_whitespace()
+                    .find_map(|f| f.strip_prefix(key))
+                    .map(|v| v.to_string())
+            };
+            let Some((width, height)) = field(max_part, "s=").and_then(|s| parse_size(&s)) else {
+                continue;
+            };
+
+            let capability = DeviceCapability {
+                width,
+                height,
+                min_fps: field(min_part, "fps=").and_then(|v| v.parse().ok()),
+                max_fps: field(max_part, "fps=").and_then(|v| v.parse().ok()),
+                pixel_format: format,
+            };
+            if !capabilities.contains(&capability) {
+                capabilities.push(capability);
+            }
+        }
+
+        capabilities
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    pub fn list_devices(kind: DeviceKind) -> Result<Vec<CaptureDevice>> {
+        match kind {
+            DeviceKind::Video => list_v4l2_devices(),
+            DeviceKind::Audio => list_pulse_sources(false),
+        }
+    }
+
+    fn list_v4l2_devices() -> Result<Vec<CaptureDevice>> {
+        let mut nodes: Vec<String> = std::fs::read_dir("/dev")
+            .context("Failed to read /dev")?
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.starts_with("video"))
+            .collect();
+        nodes.sort();
+
+        let mut devices = Vec::new();
+        for node in nodes {
+            let path = format!("/dev/{}", node);
+            let log = ffmpeg_stderr(&["-f", "v4l2", "-list_formats", "all", "-i", &path])?;
+            let capabilities = parse_v4l2_formats(&log);
+            // Metadata nodes of UVC cameras have no capture formats
+            if capabilities.is_empty() {
+                continue;
+            }
+
+            let label = std::fs::read_to_string(format!("/sys/class/video4linux/{}/name", node))
+                .map(|name| name.trim().to_string())
+                .unwrap_or_else(|_| path.clone());
+
+            devices.push(CaptureDevice {
+                id: path,
+                label,
+                kind: DeviceKind::Video,
+                backend: "v4l2".to_string(),
+                is_default: devices.is_empty(),
+                capabilities,
+            });
+        }
+
+        Ok(devices)
+    }
+
+    /// Lines look like "Raw : yuyv422 : YUYV 4:2:2 : 640x480 1280x720" (sizes may also be
+    /// "{32-4096, 2}x{32-4096, 2}" for stepwise devices, which are skipped)
+    fn parse_v4l2_formats(log: &str) -> Vec<DeviceCapability> {
+        let mut capabilities = Vec::new();
+
+        for line in log.lines().map(strip_log_prefix) {
+            let fields: Vec<&str> = line.split(" : ").map(|f| f.trim()).collect();
+            if fields.len() < 4 || !(fields[0] == "Raw" || fields[0] == "Compressed") {
+                continue;
+            }
+            for size in fields[3].split_whitespace() {
+                if let Some((width, height)) = parse_size(size) {
+                    capabilities.push(DeviceCapability {
+                        width,
+                        height,
+                        min_fps: None,
+                        max_fps: None,
+                        pixel_format: Some(fields[1].to_string()),
+                    });
+                }
+            }
+        }
+
+        capabilities
+    }
+
+    /// PulseAudio/PipeWire sources via `ffmpeg -sources pulse`. Monitor sources capture what
+    /// an output device plays (system audio) and are only returned when `monitors` is set.
+    pub(super) fn list_pulse_sources(monitors: bool) -> Result<Vec<CaptureDevice>> {
+        let ffmpeg_path = get_ffmpeg_path()?;
+        let output = Command::new(ffmpeg_path)
+            .args(["-hide_banner", "-sources", "pulse"])
+            .output()
+            .context("Failed to execute ffmpeg for device listing")?;
+        let listing = String::from_utf8_lossy(&output.stdout);
+
+        // Lines look like "* alsa_input.pci-0000_00_1f.3.analog-stereo [Built-in Audio Analog Stereo]"
+        let devices = listing
+            .lines()
+            .filter(|line| line.starts_with(' ') || line.starts_with('*'))
+            .filter_map(|line| {
+                let is_default = line.starts_with('*');
+                let line = line.trim_start_matches('*').trim();
+                let (id, label) = match line.split_once(" [") {
+                    Some((id, label)) => (id.trim(), label.trim_end_matches(']').trim()),
+                    None => (line, line),
+                };
+                (id.ends_with(".monitor") == monitors).then(|| CaptureDevice {
+                    id: id.to_string(),
+                    label: label.to_string(),
+                    kind: DeviceKind::Audio,
+                    backend: "pulse".to_string(),
+                    is_default,
+                    capabilities: Vec::new(),
+                })
+            })
+            .collect();
+
+        Ok(devices)
+    }
+}
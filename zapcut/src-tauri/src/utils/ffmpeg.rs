@@ -1,7 +1,12 @@
+use crate::utils::settings::{ThumbnailFormat, ThumbnailSettings};
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::process::Command;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct VideoInfo {
@@ -9,10 +14,35 @@ pub struct VideoInfo {
     pub width: u32,
     pub height: u32,
     pub fps: f64,
+    /// Average frame rate (frames / duration); differs from `fps` on variable frame rate sources
+    pub avg_fps: f64,
+    /// HDR transfer function, "pq" (HDR10/Dolby Vision) or "hlg"; None for SDR
+    pub hdr_format: Option<String>,
     pub codec: String,
+    /// Video streams in the file; native recordings keep the webcam as a second one
+    pub video_stream_count: usize,
     pub bitrate: u64,
     pub audio_codec: Option<String>,
+    /// Every audio stream, e.g. separate microphone and system audio tracks of a recording
+    pub audio_streams: Vec<AudioStreamInfo>,
     pub file_size: u64,
+    pub capture: CaptureMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GpsLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
+/// Capture-time metadata parsed from container/stream tags (QuickTime, Android, etc.)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct CaptureMetadata {
+    pub creation_time: Option<String>,
+    pub device_make: Option<String>,
+    pub device_model: Option<String>,
+    pub location: Option<GpsLocation>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -26,17 +56,43 @@ struct FFProbeFormat {
     duration: Option<String>,
     size: Option<String>,
     bit_rate: Option<String>,
+    tags: Option<std::collections::HashMap<String, String>>,
     #[serde(flatten)]
     _extra: std::collections::HashMap<String, serde_json::Value>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AudioStreamInfo {
+    /// Position among the file's audio streams (the N in FFmpeg's `0:a:N`)
+    pub track: u32,
+    pub codec: Option<String>,
+    pub channels: Option<u32>,
+    pub language: Option<String>,
+    /// Track label such as "Microphone" or "System Audio"
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SubtitleStreamInfo {
+    pub index: u32,
+    pub codec: String,
+    /// ISO 639-2 code; tags that aren't one are dropped
+    pub language: Option<String>,
+    pub title: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 struct FFProbeStream {
+    index: Option<u32>,
     codec_type: String,
     codec_name: Option<String>,
     width: Option<u32>,
     height: Option<u32>,
     r_frame_rate: Option<String>,
+    avg_frame_rate: Option<String>,
+    color_transfer: Option<String>,
+    channels: Option<u32>,
+    tags: Option<std::collections::HashMap<String, String>>,
     #[serde(flatten)]
     _extra: std::collections::HashMap<String, serde_json::Value>,
 }
@@ -138,6 +194,37 @@ fn get_sidecar_path(binary_name: &str) -> Result<PathBuf> {
     );
 }
 
+/// RNNoise model used by the arnndn filter, relative to the app's resource directory. Bundled
+/// through `bundle.resources` in tauri.conf.json.
+const RNNOISE_MODEL: &str = "binaries/rnnoise.rnnn";
+
+lazy_static::lazy_static! {
+    /// The app's resource directory, resolved through Tauri at startup
+    static ref RESOURCE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Remember where Tauri put the bundled resources; called once from setup
+pub fn set_resource_dir(dir: PathBuf) {
+    *RESOURCE_DIR.lock().unwrap() = Some(dir);
+}
+
+/// Path to the bundled RNNoise model
+pub fn get_rnnoise_model_path() -> Result<PathBuf> {
+    let resource_dir = RESOURCE_DIR
+        .lock()
+        .unwrap()
+        .clone()
+        .context("App resource directory is not known yet")?;
+    let path = resource_dir.join(RNNOISE_MODEL);
+    if !path.exists() {
+        anyhow::bail!(
+            "RNNoise model not found at {}. Run binaries/download_ffmpeg.sh or reinstall the app.",
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
 pub fn get_video_info(file_path: &str) -> Result<VideoInfo> {
     let ffprobe_path = get_ffprobe_path()?;
     let output = Command::new(ffprobe_path)
@@ -178,6 +265,20 @@ pub fn get_video_info(file_path: &str) -> Result<VideoInfo> {
         .iter()
         .find(|s| s.codec_type == "audio");
 
+    let audio_streams = probe_output
+        .streams
+        .iter()
+        .filter(|s| s.codec_type == "audio")
+        .enumerate()
+        .map(|(track, s)| AudioStreamInfo {
+            track: track as u32,
+            codec: s.codec_name.clone(),
+            channels: s.channels,
+            language: s.tags.as_ref().and_then(|tags| find_tag(tags, &["language"])),
+            title: s.tags.as_ref().and_then(|tags| find_tag(tags, &["title", "handler_name"])),
+        })
+        .collect();
+
     // Parse duration
     let duration = probe_output
         .format
@@ -201,21 +302,165 @@ pub fn get_video_info(file_path: &str) -> Result<VideoInfo> {
 
     // Parse FPS
     let fps = parse_frame_rate(&video_stream.r_frame_rate).unwrap_or(30.0);
+    let avg_fps = parse_frame_rate(&video_stream.avg_frame_rate)
+        .filter(|rate| rate.is_finite() && *rate > 0.0)
+        .unwrap_or(fps);
+
+    // HDR is signalled by the transfer characteristics; primaries alone (bt2020) can still be SDR
+    let hdr_format = match video_stream.color_transfer.as_deref() {
+        Some("smpte2084") => Some("pq".to_string()),
+        Some("arib-std-b67") => Some("hlg".to_string()),
+        _ => None,
+    };
+
+    // Parse capture date, device and location tags
+    let capture = parse_capture_metadata(
+        probe_output.format.tags.as_ref(),
+        video_stream.tags.as_ref(),
+    );
 
     let info = VideoInfo {
         duration,
         width: video_stream.width.unwrap_or(1920),
         height: video_stream.height.unwrap_or(1080),
         fps,
+        avg_fps,
+        hdr_format,
         codec: video_stream.codec_name.clone().unwrap_or_else(|| "unknown".to_string()),
+        video_stream_count: probe_output.streams.iter().filter(|s| s.codec_type == "video").count(),
         bitrate,
         audio_codec: audio_stream.and_then(|s| s.codec_name.clone()),
+        audio_streams,
         file_size,
+        capture,
     };
 
     Ok(info)
 }
 
+/// Look up the first non-empty tag among `keys`, ignoring key case
+fn find_tag(tags: &std::collections::HashMap<String, String>, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| {
+        tags.iter()
+            .find(|(k, v)| k.eq_ignore_ascii_case(key) && !v.trim().is_empty())
+            .map(|(_, v)| v.trim().to_string())
+    })
+}
+
+fn parse_capture_metadata(
+    format_tags: Option<&std::collections::HashMap<String, String>>,
+    stream_tags: Option<&std::collections::HashMap<String, String>>,
+) -> CaptureMetadata {
+    let empty = std::collections::HashMap::new();
+    let format_tags = format_tags.unwrap_or(&empty);
+    let stream_tags = stream_tags.unwrap_or(&empty);
+
+    // QuickTime creationdate keeps the local timezone, so prefer it over the UTC creation_time.
+    // Tags that don't parse as a date are skipped in favor of the next one.
+    let date_tags = [
+        (format_tags, "com.apple.quicktime.creationdate"),
+        (format_tags, "creation_time"),
+        (format_tags, "date"),
+        (stream_tags, "creation_time"),
+    ];
+    let creation_time = date_tags.iter().find_map(|(tags, key)| {
+        find_tag(tags, &[*key]).and_then(|value| normalize_capture_date(&value))
+    });
+
+    let device_make = find_tag(
+        format_tags,
+        &["com.apple.quicktime.make", "com.android.manufacturer", "make"],
+    );
+    let device_model = find_tag(
+        format_tags,
+        &["com.apple.quicktime.model", "com.android.model", "model"],
+    );
+
+    let location = find_tag(
+        format_tags,
+        &["com.apple.quicktime.location.ISO6709", "location", "location-eng"],
+    )
+    .and_then(|value| parse_iso6709(&value));
+
+    CaptureMetadata {
+        creation_time,
+        device_make,
+        device_model,
+        location,
+    }
+}
+
+/// Normalize a capture date tag to RFC 3339, so library sorting and filtering can parse it.
+/// Accepts RFC 3339 (FFmpeg's creation_time), QuickTime's `2023-06-01T12:34:56-0700`, and
+/// EXIF-style `2023:06:01 12:34:56`. Dates without a timezone are taken as UTC.
+fn normalize_capture_date(value: &str) -> Option<String> {
+    use chrono::{DateTime, NaiveDate, NaiveDateTime};
+
+    let value = value.trim();
+    if let Ok(date) = DateTime::parse_from_rfc3339(value) {
+        return Some(date.to_rfc3339());
+    }
+
+    const ZONED: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S%.f%z",
+        "%Y-%m-%d %H:%M:%S%.f%z",
+        "%Y:%m:%d %H:%M:%S%.f%z",
+        "%Y-%m-%dT%H:%M%z",
+    ];
+    let zoned = ZONED.iter().find_map(|format| DateTime::parse_from_str(value, format).ok());
+    if let Some(date) = zoned {
+        return Some(date.to_rfc3339());
+    }
+
+    const NAIVE: [&str; 3] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y:%m:%d %H:%M:%S%.f",
+    ];
+    let naive = NAIVE
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })?;
+    Some(naive.and_utc().to_rfc3339())
+}
+
+/// Parse an ISO 6709 location string such as "+37.7749-122.4194+012.000/"
+fn parse_iso6709(value: &str) -> Option<GpsLocation> {
+    let trimmed = value.trim().trim_end_matches('/');
+
+    // Split into signed components, each starting with '+' or '-'
+    let mut components = Vec::new();
+    let mut current = String::new();
+    for ch in trimmed.chars() {
+        if (ch == '+' || ch == '-') && !current.is_empty() {
+            components.push(current.clone());
+            current.clear();
+        }
+        current.push(ch);
+    }
+    if !current.is_empty() {
+        components.push(current);
+    }
+
+    let latitude = components.first()?.parse::<f64>().ok()?;
+    let longitude = components.get(1)?.parse::<f64>().ok()?;
+    let altitude = components.get(2).and_then(|a| a.parse::<f64>().ok());
+
+    if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+        return None;
+    }
+
+    Some(GpsLocation {
+        latitude,
+        longitude,
+        altitude,
+    })
+}
+
 fn parse_frame_rate(rate_str: &Option<String>) -> Option<f64> {
     rate_str.as_ref().and_then(|s| {
         let parts: Vec<&str> = s.split('/').collect();
@@ -229,22 +474,383 @@ fn parse_frame_rate(rate_str: &Option<String>) -> Option<f64> {
     })
 }
 
-pub fn generate_thumbnail(video_path: &str, output_path: &str, timestamp: f64) -> Result<()> {
+/// EBU R128 measurements from an FFmpeg loudnorm analysis pass
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LoudnessStats {
+    /// Integrated loudness (LUFS)
+    pub integrated_lufs: f64,
+    /// Maximum true peak (dBTP)
+    pub true_peak_db: f64,
+    /// Loudness range (LU)
+    pub loudness_range: f64,
+    /// Gating threshold, needed for a second linear loudnorm pass
+    pub threshold: f64,
+    pub measured_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct LoudnormOutput {
+    input_i: String,
+    input_tp: String,
+    input_lra: String,
+    input_thresh: String,
+}
+
+/// Measure integrated loudness, true peak and loudness range of the first audio stream
+pub fn measure_loudness(file_path: &str) -> Result<LoudnessStats> {
+    measure_loudness_range(file_path, 0, 0.0, None)
+}
+
+/// Measure the loudness of audio track `track` from `start` seconds, for `length` seconds or
+/// to the end
+pub fn measure_loudness_range(
+    file_path: &str,
+    track: u32,
+    start: f64,
+    length: Option<f64>,
+) -> Result<LoudnessStats> {
     let ffmpeg_path = get_ffmpeg_path()?;
+    let mut args = vec!["-hide_banner".to_string(), "-nostats".to_string()];
+    if start > 0.0 {
+        args.extend(["-ss".to_string(), format!("{:.3}", start)]);
+    }
+    if let Some(length) = length {
+        args.extend(["-t".to_string(), format!("{:.3}", length)]);
+    }
+    args.extend(["-i".to_string(), file_path.to_string()]);
+    args.extend([
+        "-map".to_string(),
+        format!("0:a:{}", track),
+        "-af".to_string(),
+        "loudnorm=print_format=json".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]);
     let output = Command::new(ffmpeg_path)
-        .args(&[
-            "-ss",
-            &timestamp.to_string(),
-            "-i",
-            video_path,
-            "-vframes",
-            "1",
-            "-q:v",
-            "2",
-            "-y",
-            output_path,
+        .args(&args)
+        .output()
+        .context("Failed to execute ffmpeg for loudness analysis")?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        anyhow::bail!("FFmpeg loudness analysis failed: {}", stderr);
+    }
+
+    // loudnorm prints its JSON summary as the last block of the log
+    let start = stderr.rfind('{').context("No loudness summary in FFmpeg output")?;
+    let end = stderr.rfind('}').context("No loudness summary in FFmpeg output")?;
+    let summary: LoudnormOutput =
+        serde_json::from_str(&stderr[start..=end]).context("Failed to parse loudness summary")?;
+
+    // Digital silence reports -inf; floor it so the value survives JSON (which has no infinity)
+    let parse = |value: &str| {
+        value
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .filter(|v| v.is_finite())
+            .unwrap_or(-99.0)
+            .max(-99.0)
+    };
+
+    Ok(LoudnessStats {
+        integrated_lufs: parse(&summary.input_i),
+        true_peak_db: parse(&summary.input_tp),
+        loudness_range: parse(&summary.input_lra).max(0.0),
+        threshold: parse(&summary.input_thresh),
+        measured_at: chrono::Utc::now().to_rfc3339(),
+    })
+}
+
+/// Standard rates a variable frame rate source is conformed to
+const STANDARD_FRAME_RATES: &[f64] = &[23.976, 24.0, 25.0, 29.97, 30.0, 50.0, 59.94, 60.0];
+
+/// Snap a measured average frame rate to the nearest standard rate
+pub fn conform_frame_rate(avg_fps: f64) -> f64 {
+    STANDARD_FRAME_RATES
+        .iter()
+        .copied()
+        .min_by(|a, b| (a - avg_fps).abs().partial_cmp(&(b - avg_fps).abs()).unwrap())
+        .unwrap_or(30.0)
+}
+
+/// Detect a variable frame rate video stream.
+/// A mismatch between the nominal (r_frame_rate) and average rate is a strong hint; otherwise the
+/// packet timestamps of the first 10 seconds are checked for uneven frame spacing, which is what
+/// screen recorders produce when they only emit frames on change.
+pub fn detect_variable_frame_rate(file_path: &str, info: &VideoInfo) -> Result<bool> {
+    if info.fps > 0.0 && ((info.fps - info.avg_fps).abs() / info.fps) > 0.01 {
+        return Ok(true);
+    }
+
+    let ffprobe_path = get_ffprobe_path()?;
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "quiet",
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            "%+10",
+            "-show_entries",
+            "packet=pts_time",
+            "-of",
+            "csv=p=0",
+            file_path,
+        ])
+        .output()
+        .context("Failed to execute ffprobe for frame timestamps")?;
+
+    if !output.status.success() {
+        anyhow::bail!("FFprobe failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    // Packets come in decode order, so sort before measuring spacing
+    let mut timestamps: Vec<f64> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+        .collect();
+    timestamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    if timestamps.len() < 3 {
+        return Ok(false);
+    }
+
+    let deltas: Vec<f64> = timestamps
+        .windows(2)
+        .map(|pair| pair[1] - pair[0])
+        .filter(|delta| *delta > 0.0)
+        .collect();
+    if deltas.is_empty() {
+        return Ok(false);
+    }
+
+    // Allow timestamp rounding jitter; a frame held for 1.5x the nominal interval is a dropped/held frame
+    let expected = 1.0 / info.fps.max(1.0);
+    let uneven = deltas
+        .iter()
+        .filter(|delta| **delta > expected * 1.5 || **delta < expected * 0.5)
+        .count();
+
+    Ok(uneven as f64 / deltas.len() as f64 > 0.02)
+}
+
+/// Container of a media file as ffprobe sees it
+#[derive(Debug, Clone)]
+pub struct ProbedFormat {
+    /// ffprobe's comma separated demuxer names, e.g. "mov,mp4,m4a,3gp,3g2,mj2"
+    pub format_name: String,
+    pub has_video: bool,
+    pub has_audio: bool,
+    /// Number of audio streams, the valid N in FFmpeg's `0:a:N` being below it
+    pub audio_streams: usize,
+    /// Seconds; 0 when the container doesn't say
+    pub duration: f64,
+}
+
+/// Quickly identify a file's container and which kinds of streams it has
+pub fn probe_format(file_path: &str) -> Result<ProbedFormat> {
+    let ffprobe_path = get_ffprobe_path()?;
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "quiet",
+            "-show_entries",
+            "format=format_name,duration:stream=codec_type",
+            "-of",
+            "json",
+            file_path,
+        ])
+        .output()
+        .context("Failed to execute ffprobe for container format")?;
+
+    if !output.status.success() {
+        anyhow::bail!("FFprobe failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let format_name = json["format"]["format_name"].as_str().unwrap_or_default().to_string();
+    let count_streams = |kind: &str| {
+        json["streams"].as_array().map_or(0, |streams| {
+            streams.iter().filter(|stream| stream["codec_type"] == kind).count()
+        })
+    };
+    Ok(ProbedFormat {
+        format_name,
+        has_video: count_streams("video") > 0,
+        has_audio: count_streams("audio") > 0,
+        audio_streams: count_streams("audio"),
+        duration: json["format"]["duration"]
+            .as_str()
+            .and_then(|d| d.parse::<f64>().ok())
+            .unwrap_or(0.0),
+    })
+}
+
+/// Fail with a clear message unless `file_path` has audio stream `track` (FFmpeg's `0:a:N`),
+/// rather than letting FFmpeg reject the stream map
+pub fn check_audio_track(file_path: &str, track: u32) -> Result<()> {
+    let audio_streams = probe_format(file_path)?.audio_streams;
+    if track as usize >= audio_streams {
+        anyhow::bail!(
+            "{} has no audio track {} (it has {} audio track{})",
+            file_path,
+            track,
+            audio_streams,
+            if audio_streams == 1 { "" } else { "s" }
+        );
+    }
+    Ok(())
+}
+
+/// Audio formats extract_audio_range writes, with their codec arguments
+pub fn audio_extract_codec(format: &str) -> Option<&'static [&'static str]> {
+    match format {
+        "wav" => Some(&["-c:a", "pcm_s16le"]),
+        "mp3" => Some(&["-c:a", "libmp3lame", "-q:a", "2"]),
+        "flac" => Some(&["-c:a", "flac"]),
+        _ => None,
+    }
+}
+
+/// Write audio stream `track` of `input_path` from `start` to `end` (the end of the file when
+/// None) to `output_path` as `format` ("wav", "mp3" or "flac")
+pub fn extract_audio_range(
+    input_path: &str,
+    output_path: &str,
+    format: &str,
+    track: u32,
+    start: f64,
+    end: Option<f64>,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let codec = audio_extract_codec(format).context("Unsupported audio format")?;
+    check_audio_track(input_path, track)?;
+
+    let start_arg = format!("{:.3}", start);
+    let length_arg = end.map(|end| format!("{:.3}", (end - start).max(0.0)));
+    let map_arg = format!("0:a:{}", track);
+    let mut args = vec!["-hide_banner"];
+    if start > 0.0 {
+        args.extend(["-ss", start_arg.as_str()]);
+    }
+    args.extend(["-i", input_path]);
+    if let Some(length) = &length_arg {
+        args.extend(["-t", length.as_str()]);
+    }
+    args.extend(["-map", map_arg.as_str(), "-vn", "-sn"]);
+    args.extend(codec);
+    args.extend(["-y", output_path]);
+
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute ffmpeg for audio extraction")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg audio extraction failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Presentation times of the video frames around `start`..`end` seconds, relative to the start
+/// of the file and sorted. ffprobe seeks to the keyframe before `start`, so earlier frames may be
+/// included.
+pub fn video_frame_times(file_path: &str, start: f64, end: f64) -> Result<Vec<f64>> {
+    let ffprobe_path = get_ffprobe_path()?;
+
+    // Packet timestamps and read intervals are absolute, while seeking is relative to the start
+    let output = Command::new(&ffprobe_path)
+        .args(["-v", "quiet", "-show_entries", "format=start_time", "-of", "csv=p=0", file_path])
+        .output()
+        .context("Failed to execute ffprobe for start time")?;
+    let start_time = String::from_utf8_lossy(&output.stdout).trim().parse::<f64>().unwrap_or(0.0);
+
+    let interval = format!("{:.6}%{:.6}", (start + start_time).max(0.0), end + start_time);
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "quiet",
+            "-select_streams",
+            "v:0",
+            "-read_intervals",
+            &interval,
+            "-show_entries",
+            "packet=pts_time",
+            "-of",
+            "csv=p=0",
+            file_path,
         ])
         .output()
+        .context("Failed to execute ffprobe for frame timestamps")?;
+
+    if !output.status.success() {
+        anyhow::bail!("FFprobe failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    // Packets come in decode order
+    let mut timestamps: Vec<f64> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.trim().trim_end_matches(',').parse::<f64>().ok())
+        .map(|pts| pts - start_time)
+        .collect();
+    timestamps.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    timestamps.dedup();
+    Ok(timestamps)
+}
+
+pub fn generate_thumbnail(
+    video_path: &str,
+    output_path: &str,
+    timestamp: f64,
+    settings: &ThumbnailSettings,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    // Fit inside max_dimension without upscaling small sources
+    let scale_filter = format!(
+        "scale=w='min(iw,{0})':h='min(ih,{0})':force_original_aspect_ratio=decrease",
+        settings.max_dimension
+    );
+
+    let mut args = vec![
+        "-ss".to_string(),
+        timestamp.to_string(),
+        "-i".to_string(),
+        video_path.to_string(),
+        "-vframes".to_string(),
+        "1".to_string(),
+        "-vf".to_string(),
+        scale_filter,
+    ];
+
+    let quality = settings.quality.clamp(1, 100) as u32;
+    match settings.format {
+        ThumbnailFormat::Jpeg => {
+            // Map 1-100 quality onto FFmpeg's mjpeg qscale (31 = worst, 2 = best)
+            let qscale = 2 + (100 - quality) * 29 / 100;
+            args.extend(["-q:v".to_string(), qscale.to_string()]);
+        }
+        ThumbnailFormat::Webp => {
+            args.extend([
+                "-c:v".to_string(),
+                "libwebp".to_string(),
+                "-quality".to_string(),
+                quality.to_string(),
+            ]);
+        }
+    }
+
+    args.extend(["-y".to_string(), output_path.to_string()]);
+
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
         .context("Failed to execute ffmpeg for thumbnail")?;
 
     if !output.status.success() {
@@ -257,61 +863,908 @@ pub fn generate_thumbnail(video_path: &str, output_path: &str, timestamp: f64) -
     Ok(())
 }
 
-/// Generate a lightweight 720p proxy video for fast preview playback
-/// Uses ultrafast preset and CRF 28 for maximum encoding speed
-pub fn create_proxy(video_path: &str, output_path: &str, target_fps: Option<f64>) -> Result<()> {
+/// Extract a single frame at `timestamp`, optionally scaled to `width` (height keeps aspect ratio)
+pub fn extract_frame(video_path: &str, output_path: &str, timestamp: f64, width: Option<u32>) -> Result<()> {
     let ffmpeg_path = get_ffmpeg_path()?;
-    
+
     let mut args = vec![
+        "-ss".to_string(),
+        format!("{:.3}", timestamp.max(0.0)),
         "-i".to_string(),
         video_path.to_string(),
-        "-vf".to_string(),
-        "scale=-2:720".to_string(), // Scale to 720p height, maintain aspect ratio (divisible by 2)
-        "-c:v".to_string(),
-        "libx264".to_string(),
-        "-preset".to_string(),
-        "ultrafast".to_string(), // Fastest encoding
-        "-crf".to_string(),
-        "28".to_string(), // Lower quality for smaller file size
-        "-maxrate".to_string(),
-        "3M".to_string(), // Cap bitrate at 3 Mbps
-        "-bufsize".to_string(),
-        "6M".to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
     ];
-    
-    // Set FPS if specified (useful for high-fps sources)
-    if let Some(fps) = target_fps {
-        args.push("-r".to_string());
-        args.push(fps.to_string());
+
+    if let Some(width) = width {
+        args.push("-vf".to_string());
+        args.push(format!("scale={}:-2", width));
     }
-    
-    // Audio settings - lower quality for smaller file
-    args.extend(vec![
-        "-c:a".to_string(),
-        "aac".to_string(),
-        "-b:a".to_string(),
-        "128k".to_string(),
-        "-ac".to_string(),
-        "2".to_string(), // Stereo
-    ]);
-    
-    args.extend(vec![
-        "-movflags".to_string(),
-        "+faststart".to_string(), // Enable fast seeking
+
+    args.extend([
+        "-q:v".to_string(),
+        "4".to_string(),
         "-y".to_string(),
         output_path.to_string(),
     ]);
-    
+
     let output = Command::new(ffmpeg_path)
         .args(&args)
         .output()
-        .context("Failed to execute ffmpeg for proxy generation")?;
-    
+        .context("Failed to execute ffmpeg for frame extraction")?;
+
     if !output.status.success() {
-        let error_msg = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("FFmpeg proxy generation failed: {}", error_msg);
+        anyhow::bail!(
+            "FFmpeg frame extraction failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
     }
-    
+
     Ok(())
 }
 
+/// Decode the frame shown at `timestamp` straight to memory as PNG (or JPEG when `png` is
+/// false), run through `filter` when given and then optionally scaled to `width`. Seeking before
+/// the input still decodes up to the exact frame, so this is frame accurate.
+pub fn decode_frame(
+    video_path: &str,
+    timestamp: f64,
+    filter: Option<&str>,
+    width: Option<u32>,
+    png: bool,
+) -> Result<Vec<u8>> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    let mut args = vec![
+        "-ss".to_string(),
+        format!("{:.6}", timestamp.max(0.0)),
+        "-i".to_string(),
+        video_path.to_string(),
+        "-frames:v".to_string(),
+        "1".to_string(),
+    ];
+
+    let mut filters: Vec<String> = filter.map(str::to_string).into_iter().collect();
+    if let Some(width) = width {
+        filters.push(format!("scale={}:-2", width));
+    }
+    if !filters.is_empty() {
+        args.push("-vf".to_string());
+        args.push(filters.join(","));
+    }
+
+    let codec = if png { "png" } else { "mjpeg" };
+    args.extend(["-c:v", codec, "-q:v", "3", "-f", "image2pipe", "-"].map(String::from));
+
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute ffmpeg for frame decoding")?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        anyhow::bail!(
+            "FFmpeg frame decoding failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+/// Decode the frame at `timestamp` to packed RGB24 pixels scaled to `width`.
+/// Returns (width, height, pixels).
+pub fn decode_frame_rgb(video_path: &str, timestamp: f64, width: u32) -> Result<(u32, u32, Vec<u8>)> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let width = width.max(2) / 2 * 2;
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-ss",
+            &format!("{:.6}", timestamp.max(0.0)),
+            "-i",
+            video_path,
+            "-frames:v",
+            "1",
+            "-vf",
+            &format!("scale={}:-2", width),
+            "-pix_fmt",
+            "rgb24",
+            "-f",
+            "rawvideo",
+            "-",
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for frame decoding")?;
+
+    let row = width as usize * 3;
+    if !output.status.success() || output.stdout.len() < row {
+        anyhow::bail!(
+            "FFmpeg frame decoding failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let height = (output.stdout.len() / row) as u32;
+    Ok((width, height, output.stdout))
+}
+
+/// Transcode `duration` seconds of a video from `start` to fragmented MP4 (H.264 and AAC, no
+/// taller than `max_height`) in memory, for playing sources the webview can't decode. Output
+/// timestamps keep the source's time, so consecutive windows can be appended to one Media
+/// Source Extensions buffer.
+pub fn transcode_window_fmp4(
+    video_path: &str,
+    start: f64,
+    duration: f64,
+    max_height: u32,
+) -> Result<Vec<u8>> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let start = format!("{:.6}", start.max(0.0));
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-ss",
+            &start,
+            "-t",
+            &format!("{:.6}", duration),
+            "-i",
+            video_path,
+            "-map",
+            "0:v:0",
+            "-map",
+            "0:a:0?",
+            "-vf",
+            &format!("scale=-2:'min({},ih)'", max_height),
+            "-c:v",
+            "libx264",
+            "-preset",
+            "ultrafast",
+            "-pix_fmt",
+            "yuv420p",
+            "-c:a",
+            "aac",
+            "-b:a",
+            "128k",
+            "-ac",
+            "2",
+            "-output_ts_offset",
+            &start,
+            "-movflags",
+            "+frag_keyframe+empty_moov+default_base_moof",
+            "-f",
+            "mp4",
+            "-",
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for transcoding")?;
+
+    if !output.status.success() || output.stdout.is_empty() {
+        anyhow::bail!("FFmpeg transcoding failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+/// Start FFmpeg joining `ranges` of files, each (path, in point, out point) in seconds, into one
+/// fragmented MP4 written to its stdout. Streams are copied, so the files must share codec
+/// settings and each range starts on the keyframe at or before its in point. The concat list
+/// is written to `list_path`, which must stay until FFmpeg exits.
+pub fn spawn_concat_ranges_fmp4(ranges: &[(String, f64, f64)], list_path: &Path) -> Result<Child> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    let list_content: String = ranges
+        .iter()
+        .map(|(path, in_point, out_point)| {
+            format!(
+                "file '{}'\ninpoint {:.6}\noutpoint {:.6}\n",
+                path.replace('\'', "'\\''"),
+                in_point,
+                out_point
+            )
+        })
+        .collect();
+    std::fs::write(list_path, list_content).context("Failed to write concat list")?;
+
+    Command::new(ffmpeg_path)
+        .args([
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+            list_path.to_str().unwrap(),
+            "-map",
+            "0:v:0",
+            "-map",
+            "0:a:0?",
+            "-c",
+            "copy",
+            "-movflags",
+            "+frag_keyframe+empty_moov+default_base_moof",
+            "-f",
+            "mp4",
+            "-",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute ffmpeg for concatenation")
+}
+
+/// Draw the file's audio as a `width`x`height` PNG waveform strip in `color` on a transparent
+/// background, channels mixed down so the strip has one lane
+pub fn render_waveform_png(
+    file_path: &str,
+    output_path: &str,
+    width: u32,
+    height: u32,
+    color: &str,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let filter = format!(
+        "[0:a:0]aformat=channel_layouts=mono,showwavespic=s={}x{}:colors={}:scale=sqrt",
+        width, height, color
+    );
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            file_path,
+            "-filter_complex",
+            &filter,
+            "-frames:v",
+            "1",
+            "-y",
+            output_path,
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for waveform image")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg waveform image failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Sample rate audio is decoded at for waveform peaks; plenty for drawing
+const PEAK_SAMPLE_RATE: u32 = 8000;
+
+/// Peak amplitude (0..1) of the file's first audio stream, mixed to mono, for each
+/// 1/`peaks_per_second` of a second. Audio is read as it decodes, so long files aren't held
+/// in memory. Empty when the file has no audio.
+pub fn audio_peaks(file_path: &str, peaks_per_second: u32) -> Result<Vec<f32>> {
+    use std::io::Read;
+
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let mut child = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            file_path,
+            "-vn",
+            "-map",
+            "0:a:0?",
+            "-ac",
+            "1",
+            "-ar",
+            &PEAK_SAMPLE_RATE.to_string(),
+            "-f",
+            "f32le",
+            "-",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Failed to execute ffmpeg for waveform peaks")?;
+    let mut stdout = child.stdout.take().context("FFmpeg has no output")?;
+
+    let samples_per_peak = (PEAK_SAMPLE_RATE / peaks_per_second.clamp(1, PEAK_SAMPLE_RATE)) as usize;
+    let mut peaks = Vec::new();
+    let (mut peak, mut counted) = (0.0f32, 0usize);
+    let mut chunk = vec![0u8; 64 * 1024];
+    let mut pending = Vec::new();
+    loop {
+        let read = stdout.read(&mut chunk).context("Failed to read decoded audio")?;
+        if read == 0 {
+            break;
+        }
+        pending.extend_from_slice(&chunk[..read]);
+        let whole = pending.len() / 4 * 4;
+        for bytes in pending[..whole].chunks_exact(4) {
+            let sample = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            peak = peak.max(sample.abs());
+            counted += 1;
+            if counted == samples_per_peak {
+                peaks.push(peak.min(1.0));
+                (peak, counted) = (0.0, 0);
+            }
+        }
+        pending.drain(..whole);
+    }
+    if counted > 0 {
+        peaks.push(peak.min(1.0));
+    }
+
+    let status = child.wait().context("Failed to wait for ffmpeg")?;
+    if !status.success() {
+        anyhow::bail!("FFmpeg audio decoding failed for {}", file_path);
+    }
+    Ok(peaks)
+}
+
+/// Remux a video into MPEG-TS chunks of about `chunk_duration` seconds with an index.m3u8
+/// playlist in `output_dir`. Streams are copied, so chunks split on the nearest keyframes.
+pub fn remux_to_hls(video_path: &str, output_dir: &Path, chunk_duration: f64) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    std::fs::create_dir_all(output_dir).context("Failed to create HLS directory")?;
+    let chunk_pattern = output_dir.join("chunk_%04d.ts");
+    let playlist = output_dir.join("index.m3u8");
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            video_path,
+            "-c",
+            "copy",
+            "-f",
+            "hls",
+            "-hls_time",
+            &format!("{:.3}", chunk_duration),
+            "-hls_playlist_type",
+            "vod",
+            "-hls_segment_type",
+            "mpegts",
+            "-hls_segment_filename",
+            &chunk_pattern.to_string_lossy(),
+            "-y",
+            &playlist.to_string_lossy(),
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for HLS remux")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg HLS remux failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Extract `count` evenly spaced frames into a single tiled sprite-sheet image
+pub fn generate_sprite_sheet(
+    video_path: &str,
+    output_path: &str,
+    duration: f64,
+    count: u32,
+    columns: u32,
+    frame_width: u32,
+    frame_height: u32,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let rows = count.div_ceil(columns);
+    let filter = format!(
+        "fps={}/{:.3},scale={}:{}:force_original_aspect_ratio=decrease,pad={}:{}:(ow-iw)/2:(oh-ih)/2:black,tile={}x{}",
+        count, duration, frame_width, frame_height, frame_width, frame_height, columns, rows
+    );
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            video_path,
+            "-vf",
+            &filter,
+            "-frames:v",
+            "1",
+            "-q:v",
+            "4",
+            "-y",
+            output_path,
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for sprite sheet")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg sprite sheet failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Render a grid of evenly spaced frames with their timestamps burned in
+pub fn render_contact_sheet(
+    video_path: &str,
+    output_path: &str,
+    duration: f64,
+    columns: u32,
+    rows: u32,
+    tile_width: u32,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let count = columns * rows;
+    let font_size = (tile_width / 14).max(10);
+
+    // pts survives the fps filter, so drawtext can print each frame's source timestamp
+    let filter = format!(
+        "fps={}/{:.3},scale={}:-2,drawtext=text='%{{pts\\:hms}}':x=8:y=h-th-8:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.6:boxborderw=4,tile={}x{}:padding=6:margin=6:color=0x0f1419",
+        count, duration, tile_width, font_size, columns, rows
+    );
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            video_path,
+            "-vf",
+            &filter,
+            "-frames:v",
+            "1",
+            "-q:v",
+            "3",
+            "-y",
+            output_path,
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for contact sheet")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg contact sheet failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Escape a path for use inside a quoted filter option (sendcmd, drawtext textfile)
+pub fn escape_filter_path(path: &std::path::Path) -> String {
+    path.to_string_lossy()
+        .replace('\\', "/")
+        .replace(':', "\\:")
+        .replace('\'', "\\'")
+}
+
+/// Join files with identical codec parameters (e.g. spanned camera segments) without re-encoding
+pub fn concat_files_copy(inputs: &[PathBuf], output_path: &str) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    let list_path = std::env::temp_dir().join(format!("zapcut_concat_{}.txt", uuid::Uuid::new_v4()));
+    let list_content: String = inputs
+        .iter()
+        .map(|p| format!("file '{}'\n", p.to_string_lossy().replace('\'', "'\\''")))
+        .collect();
+    std::fs::write(&list_path, list_content).context("Failed to write concat list")?;
+
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+            list_path.to_str().unwrap(),
+            "-c",
+            "copy",
+            "-map",
+            "0",
+            "-y",
+            output_path,
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for concatenation");
+
+    let _ = std::fs::remove_file(&list_path);
+    let output = output?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg concatenation failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// A line FFmpeg wrote to stderr, tagged with the output position (seconds) reached at the time
+#[derive(Debug, Clone)]
+pub struct FfmpegLogLine {
+    pub time: f64,
+    pub message: String,
+}
+
+/// Run FFmpeg with `-progress pipe:1`, reporting percentage complete (0-100) against `duration`.
+/// `args` must not include the binary or the progress flags. When `cancel` is set the process is
+/// killed and an error is returned. On success the stderr log is returned line by line.
+pub fn run_ffmpeg_with_progress<F>(
+    args: &[String],
+    duration: f64,
+    cancel: Option<&AtomicBool>,
+    mut on_progress: F,
+) -> Result<Vec<FfmpegLogLine>>
+where
+    F: FnMut(f64),
+{
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    let mut child = Command::new(ffmpeg_path)
+        .args(["-progress", "pipe:1", "-nostats"])
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to spawn ffmpeg")?;
+
+    // Latest out_time in microseconds, shared with the stderr thread to timestamp log lines
+    let position = Arc::new(AtomicU64::new(0));
+
+    // Drain stderr on its own thread so a chatty encoder can't block on a full pipe
+    let stderr = child.stderr.take().context("Failed to capture ffmpeg stderr")?;
+    let stderr_position = position.clone();
+    let stderr_reader = std::thread::spawn(move || {
+        BufReader::new(stderr)
+            .lines()
+            .map_while(|line| line.ok())
+            .filter(|line| !line.trim().is_empty())
+            .map(|message| FfmpegLogLine {
+                time: stderr_position.load(Ordering::Relaxed) as f64 / 1_000_000.0,
+                message,
+            })
+            .collect::<Vec<_>>()
+    });
+
+    let stdout = child.stdout.take().context("Failed to capture ffmpeg stdout")?;
+    for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+        if cancel.map(|flag| flag.load(Ordering::SeqCst)).unwrap_or(false) {
+            let _ = child.kill();
+            let _ = child.wait();
+            anyhow::bail!("Cancelled");
+        }
+
+        // out_time_us is reported in microseconds despite older builds naming it out_time_ms
+        let value = line
+            .strip_prefix("out_time_us=")
+            .or_else(|| line.strip_prefix("out_time_ms="));
+        if let Some(micros) = value.and_then(|v| v.trim().parse::<u64>().ok()) {
+            position.store(micros, Ordering::Relaxed);
+            if duration > 0.0 {
+                on_progress((micros as f64 / 1_000_000.0 / duration * 100.0).clamp(0.0, 100.0));
+            }
+        }
+    }
+
+    let status = child.wait().context("Failed to wait for ffmpeg")?;
+    let log = stderr_reader.join().unwrap_or_default();
+
+    if cancel.map(|flag| flag.load(Ordering::SeqCst)).unwrap_or(false) {
+        anyhow::bail!("Cancelled");
+    }
+    if !status.success() {
+        let stderr_output: Vec<&str> = log.iter().map(|line| line.message.as_str()).collect();
+        anyhow::bail!("FFmpeg failed: {}", stderr_output.join("\n"));
+    }
+
+    on_progress(100.0);
+    Ok(log)
+}
+
+/// Check that the video/audio codecs can be stream-copied into `container` (mp4, mov, mkv, webm)
+pub fn check_remux_compatibility(container: &str, video_codec: &str, audio_codec: Option<&str>) -> Result<()> {
+    let (video_ok, audio_ok): (&[&str], &[&str]) = match container {
+        "mp4" | "m4v" => (
+            &["h264", "hevc", "av1", "vp9", "mpeg4"],
+            &["aac", "mp3", "ac3", "eac3", "alac", "flac", "opus"],
+        ),
+        "mov" => (
+            &["h264", "hevc", "prores", "mpeg4", "mjpeg"],
+            &["aac", "mp3", "ac3", "alac", "pcm_s16le", "pcm_s24le", "pcm_f32le"],
+        ),
+        "webm" => (&["vp8", "vp9", "av1"], &["vorbis", "opus"]),
+        // Matroska accepts practically anything
+        "mkv" => return Ok(()),
+        other => anyhow::bail!("Unsupported container: {}", other),
+    };
+
+    if !video_ok.contains(&video_codec) {
+        anyhow::bail!("Video codec {} cannot be stored in {} without re-encoding", video_codec, container);
+    }
+    if let Some(audio) = audio_codec {
+        if !audio_ok.contains(&audio) {
+            anyhow::bail!("Audio codec {} cannot be stored in {} without re-encoding", audio, container);
+        }
+    }
+
+    Ok(())
+}
+
+/// FFmpeg muxer for a container accepted by check_remux_compatibility, passed with `-f` so the
+/// output never depends on the file's extension
+fn remux_muxer(container: &str) -> Result<&'static str> {
+    Ok(match container {
+        "mp4" | "m4v" => "mp4",
+        "mov" => "mov",
+        "mkv" => "matroska",
+        "webm" => "webm",
+        other => anyhow::bail!("Unsupported container: {}", other),
+    })
+}
+
+/// Rewrap the video and audio streams into a new container without re-encoding.
+/// Subtitle and data streams are dropped since most targets can't hold them as-is.
+pub fn remux_copy(input_path: &str, output_path: &str, container: &str, video_codec: &str) -> Result<()> {
+    remux_copy_range(input_path, output_path, container, video_codec, 0.0, None)
+}
+
+/// remux_copy keeping only `start..end` (to the end when None). Without re-encoding the cut
+/// snaps back to the keyframe before `start`.
+pub fn remux_copy_range(
+    input_path: &str,
+    output_path: &str,
+    container: &str,
+    video_codec: &str,
+    start: f64,
+    end: Option<f64>,
+) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let muxer = remux_muxer(container)?;
+
+    let start_arg = format!("{:.3}", start);
+    let length_arg = end.map(|end| format!("{:.3}", (end - start).max(0.0)));
+    let mut args = Vec::new();
+    if start > 0.0 {
+        args.extend(["-ss", start_arg.as_str()]);
+    }
+    args.extend(["-i", input_path]);
+    if let Some(length) = &length_arg {
+        args.extend(["-t", length.as_str()]);
+    }
+    args.extend([
+        "-map", "0:v:0",
+        "-map", "0:a?",
+        "-c", "copy",
+    ]);
+    if matches!(container, "mp4" | "m4v" | "mov") {
+        args.extend(["-movflags", "+faststart"]);
+        // QuickTime only plays HEVC tagged as hvc1
+        if video_codec == "hevc" {
+            args.extend(["-tag:v", "hvc1"]);
+        }
+    }
+    args.extend(["-f", muxer, "-y", output_path]);
+
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute ffmpeg for remux")?;
+
+    if !output.status.success() {
+        anyhow::bail!("FFmpeg remux failed: {}", String::from_utf8_lossy(&output.stderr));
+    }
+
+    Ok(())
+}
+
+/// Tone-map HDR (PQ/HLG, BT.2020) to SDR BT.709 so previews look like an SDR export.
+/// Requires an FFmpeg build with zscale (libzimg).
+const HDR_TONEMAP_FILTER: &str = "zscale=t=linear:npl=100,format=gbrpf32le,zscale=p=bt709,\
+tonemap=tonemap=hable:desat=0,zscale=t=bt709:m=bt709:r=tv,format=yuv420p";
+
+/// Generate a lightweight 720p proxy video for fast preview playback
+/// Uses ultrafast preset and CRF 28 for maximum encoding speed
+pub fn create_proxy(video_path: &str, output_path: &str, target_fps: Option<f64>, tone_map: bool) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+
+    // Scale to 720p height, maintain aspect ratio (divisible by 2)
+    let mut video_filter = "scale=-2:720".to_string();
+    if tone_map {
+        video_filter.push(',');
+        video_filter.push_str(HDR_TONEMAP_FILTER);
+    }
+    
+    let mut args = vec![
+        "-i".to_string(),
+        video_path.to_string(),
+        "-vf".to_string(),
+        video_filter,
+        "-c:v".to_string(),
+        "libx264".to_string(),
+        "-preset".to_string(),
+        "ultrafast".to_string(), // Fastest encoding
+        "-crf".to_string(),
+        "28".to_string(), // Lower quality for smaller file size
+        "-maxrate".to_string(),
+        "3M".to_string(), // Cap bitrate at 3 Mbps
+        "-bufsize".to_string(),
+        "6M".to_string(),
+    ];
+    
+    if tone_map {
+        args.extend(
+            ["-color_primaries", "bt709", "-color_trc", "bt709", "-colorspace", "bt709"]
+                .iter()
+                .map(|s| s.to_string()),
+        );
+    }
+
+    // Set FPS if specified (useful for high-fps sources and conforming VFR sources).
+    // -fps_mode cfr duplicates/drops frames so the proxy has evenly spaced timestamps.
+    if let Some(fps) = target_fps {
+        args.push("-fps_mode".to_string());
+        args.push("cfr".to_string());
+        args.push("-r".to_string());
+        args.push(fps.to_string());
+    }
+    
+    // Audio settings - lower quality for smaller file
+    args.extend(vec![
+        "-c:a".to_string(),
+        "aac".to_string(),
+        "-b:a".to_string(),
+        "128k".to_string(),
+        "-ac".to_string(),
+        "2".to_string(), // Stereo
+    ]);
+    
+    args.extend(vec![
+        "-movflags".to_string(),
+        "+faststart".to_string(), // Enable fast seeking
+        "-y".to_string(),
+        output_path.to_string(),
+    ]);
+    
+    let output = Command::new(ffmpeg_path)
+        .args(&args)
+        .output()
+        .context("Failed to execute ffmpeg for proxy generation")?;
+    
+    if !output.status.success() {
+        let error_msg = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("FFmpeg proxy generation failed: {}", error_msg);
+    }
+    
+    Ok(())
+}
+
+
+/// Text subtitle codecs that can be converted to SRT without OCR
+const TEXT_SUBTITLE_CODECS: &[&str] = &["mov_text", "subrip", "srt", "ass", "ssa", "webvtt", "text"];
+
+/// `tag` as an ISO 639-2 language code (three lowercase ASCII letters), or None when it isn't one
+fn iso639_2_language(tag: &str) -> Option<String> {
+    let code = tag.trim().to_ascii_lowercase();
+    if code.len() == 3 && code.bytes().all(|b| b.is_ascii_lowercase()) {
+        Some(code)
+    } else {
+        None
+    }
+}
+
+/// List the embedded text subtitle streams of a media file
+pub fn get_subtitle_streams(file_path: &str) -> Result<Vec<SubtitleStreamInfo>> {
+    let ffprobe_path = get_ffprobe_path()?;
+    let output = Command::new(ffprobe_path)
+        .args([
+            "-v",
+            "quiet",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+            "-select_streams",
+            "s",
+            file_path,
+        ])
+        .output()
+        .context("Failed to execute ffprobe")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFprobe failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    let probe_output: FFProbeOutput =
+        serde_json::from_slice(&output.stdout).context("Failed to parse ffprobe output")?;
+
+    let streams = probe_output
+        .streams
+        .into_iter()
+        .filter(|s| s.codec_type == "subtitle")
+        .filter_map(|s| {
+            let codec = s.codec_name?;
+            if !TEXT_SUBTITLE_CODECS.contains(&codec.as_str()) {
+                return None;
+            }
+            let tags = s.tags.unwrap_or_default();
+            Some(SubtitleStreamInfo {
+                index: s.index?,
+                codec,
+                language: tags.get("language").and_then(|tag| iso639_2_language(tag)),
+                title: tags.get("title").cloned(),
+            })
+        })
+        .collect();
+
+    Ok(streams)
+}
+
+/// Extract a single embedded subtitle stream (absolute stream index) to an SRT file
+pub fn extract_subtitle_stream(video_path: &str, stream_index: u32, output_path: &str) -> Result<()> {
+    let ffmpeg_path = get_ffmpeg_path()?;
+    let output = Command::new(ffmpeg_path)
+        .args([
+            "-i",
+            video_path,
+            "-map",
+            &format!("0:{}", stream_index),
+            "-c:s",
+            "srt",
+            "-y",
+            output_path,
+        ])
+        .output()
+        .context("Failed to execute ffmpeg for subtitle extraction")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "FFmpeg subtitle extraction failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+
+    Ok(())
+}
+
+/// Hardware encoders worth trying per platform, most common first
+#[cfg(target_os = "macos")]
+const HARDWARE_ENCODER_FAMILIES: &[&str] = &["videotoolbox"];
+#[cfg(target_os = "windows")]
+const HARDWARE_ENCODER_FAMILIES: &[&str] = &["nvenc", "qsv", "amf"];
+#[cfg(target_os = "linux")]
+const HARDWARE_ENCODER_FAMILIES: &[&str] = &["nvenc", "qsv"];
+
+lazy_static::lazy_static! {
+    /// codec -> working hardware encoder, probed once per run
+    static ref HARDWARE_ENCODERS: Mutex<HashMap<String, Option<String>>> = Mutex::new(HashMap::new());
+}
+
+/// First hardware encoder for `codec` ("h264" or "hevc") that can actually encode on this
+/// machine. FFmpeg builds list NVENC/QSV even without the GPU, so each candidate is tried on
+/// a short synthetic clip.
+pub fn hardware_encoder(codec: &str) -> Option<String> {
+    if let Some(cached) = HARDWARE_ENCODERS.lock().unwrap().get(codec) {
+        return cached.clone();
+    }
+
+    let ffmpeg_path = get_ffmpeg_path().ok()?;
+    let found = HARDWARE_ENCODER_FAMILIES
+        .iter()
+        .map(|family| format!("{}_{}", codec, family))
+        .find(|encoder| {
+            Command::new(&ffmpeg_path)
+                .args([
+                    "-hide_banner", "-v", "error",
+                    "-f", "lavfi", "-i", "color=black:s=256x256:r=30:d=0.2",
+                    "-c:v", encoder,
+                    "-pix_fmt", "nv12",
+                    "-f", "null", "-",
+                ])
+                .output()
+                .map(|output| output.status.success())
+                .unwrap_or(false)
+        });
+
+    println!("[Encode] Hardware {} encoder: {}", codec, found.as_deref().unwrap_or("none"));
+    HARDWARE_ENCODERS
+        .lock()
+        .unwrap()
+        .insert(codec.to_string(), found.clone());
+    found
+}
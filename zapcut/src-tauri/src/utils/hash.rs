@@ -0,0 +1,48 @@
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Bytes sampled from each end of a file for the quick hash
+const QUICK_HASH_SAMPLE: u64 = 1024 * 1024;
+
+/// Fast fingerprint of a media file: SHA-256 over its size plus the first and last megabyte.
+/// Good enough to recognise a moved/renamed file without reading gigabytes of video.
+pub fn quick_file_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let size = file.metadata()?.len();
+
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    let mut buffer = vec![0u8; QUICK_HASH_SAMPLE.min(size) as usize];
+    file.read_exact(&mut buffer)?;
+    hasher.update(&buffer);
+
+    if size > QUICK_HASH_SAMPLE * 2 {
+        file.seek(SeekFrom::End(-(QUICK_HASH_SAMPLE as i64)))?;
+        file.read_exact(&mut buffer)?;
+        hasher.update(&buffer);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+
+/// Full SHA-256 of a file, streamed in chunks so memory stays flat
+pub fn full_file_hash(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 4 * 1024 * 1024];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
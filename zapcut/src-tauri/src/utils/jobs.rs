@@ -0,0 +1,176 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Complete,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, JobStatus::Complete | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// A long-running backend task (transcode, verification, ...) tracked for the frontend
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct JobInfo {
+    pub id: String,
+    pub kind: String,
+    pub label: String,
+    pub status: JobStatus,
+    /// 0-100
+    pub progress: f64,
+    pub message: Option<String>,
+    pub result: Option<serde_json::Value>,
+    pub created_at: String,
+}
+
+/// Tracks background jobs and their cancellation flags. Every change is emitted to the
+/// frontend as a `job://update` event carrying the full JobInfo.
+pub struct JobManager {
+    jobs: Mutex<HashMap<String, JobInfo>>,
+    cancel_flags: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl JobManager {
+    pub fn new() -> Self {
+        Self {
+            jobs: Mutex::new(HashMap::new()),
+            cancel_flags: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Register a queued job, returning its id and cancellation flag
+    pub fn create(&self, app: &AppHandle, kind: &str, label: &str) -> (String, Arc<AtomicBool>) {
+        let id = uuid::Uuid::new_v4().to_string();
+        let job = JobInfo {
+            id: id.clone(),
+            kind: kind.to_string(),
+            label: label.to_string(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            message: None,
+            result: None,
+            created_at: chrono::Utc::now().to_rfc3339(),
+        };
+        let flag = Arc::new(AtomicBool::new(false));
+
+        self.jobs.lock().unwrap().insert(id.clone(), job.clone());
+        self.cancel_flags.lock().unwrap().insert(id.clone(), flag.clone());
+        emit_job(app, &job);
+
+        (id, flag)
+    }
+
+    /// Mutate a job and emit the updated state
+    pub fn update<F>(&self, app: &AppHandle, id: &str, update: F)
+    where
+        F: FnOnce(&mut JobInfo),
+    {
+        let snapshot = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(job) = jobs.get_mut(id) else {
+                return;
+            };
+            update(job);
+            job.clone()
+        };
+
+        if snapshot.status.is_finished() {
+            self.cancel_flags.lock().unwrap().remove(id);
+        }
+        emit_job(app, &snapshot);
+    }
+
+    /// Like `update`, but leaves a job that already finished (e.g. was cancelled) untouched
+    fn update_unfinished<F>(&self, app: &AppHandle, id: &str, update: F)
+    where
+        F: FnOnce(&mut JobInfo),
+    {
+        let snapshot = {
+            let mut jobs = self.jobs.lock().unwrap();
+            let Some(job) = jobs.get_mut(id) else {
+                return;
+            };
+            if job.status.is_finished() {
+                return;
+            }
+            update(job);
+            job.clone()
+        };
+
+        if snapshot.status.is_finished() {
+            self.cancel_flags.lock().unwrap().remove(id);
+        }
+        emit_job(app, &snapshot);
+    }
+
+    pub fn set_progress(&self, app: &AppHandle, id: &str, progress: f64) {
+        self.update_unfinished(app, id, |job| {
+            job.status = JobStatus::Running;
+            job.progress = progress.clamp(0.0, 100.0);
+        });
+    }
+
+    pub fn complete(&self, app: &AppHandle, id: &str, result: Option<serde_json::Value>) {
+        self.update_unfinished(app, id, |job| {
+            job.status = JobStatus::Complete;
+            job.progress = 100.0;
+            job.result = result;
+        });
+    }
+
+    pub fn fail(&self, app: &AppHandle, id: &str, error: String) {
+        self.update(app, id, |job| {
+            job.status = if job.status == JobStatus::Cancelled {
+                JobStatus::Cancelled
+            } else {
+                JobStatus::Failed
+            };
+            job.message = Some(error);
+        });
+    }
+
+    pub fn get(&self, id: &str) -> Option<JobInfo> {
+        self.jobs.lock().unwrap().get(id).cloned()
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let mut jobs: Vec<JobInfo> = self.jobs.lock().unwrap().values().cloned().collect();
+        jobs.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+        jobs
+    }
+
+    /// Request cancellation; the worker notices the flag and stops its FFmpeg process
+    pub fn cancel(&self, app: &AppHandle, id: &str) -> bool {
+        let flag = self.cancel_flags.lock().unwrap().get(id).cloned();
+        match flag {
+            Some(flag) => {
+                flag.store(true, Ordering::SeqCst);
+                self.update(app, id, |job| job.status = JobStatus::Cancelled);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Drop finished jobs from the list
+    pub fn clear_finished(&self) {
+        self.jobs.lock().unwrap().retain(|_, job| !job.status.is_finished());
+    }
+}
+
+fn emit_job(app: &AppHandle, job: &JobInfo) {
+    if let Err(e) = app.emit("job://update", job.clone()) {
+        eprintln!("[Jobs] Failed to emit job update: {}", e);
+    }
+}
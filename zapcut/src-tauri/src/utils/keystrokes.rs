@@ -0,0 +1,380 @@
+use crate::utils::ffmpeg::escape_filter_path;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// Key polls per second; fast enough to catch quick taps
+const POLL_RATE: u64 = 60;
+/// Typed characters closer together than this share one caption
+const TYPING_GAP_SECS: f64 = 1.0;
+/// How long a caption stays up after its last key
+const CAPTION_HOLD_SECS: f64 = 1.5;
+/// Longest typed run before a new caption starts
+const MAX_CAPTION_CHARS: usize = 32;
+
+/// A key press during a recording
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct KeystrokeEvent {
+    /// Seconds into the recording
+    pub t: f64,
+    /// What to display, e.g. "a", "Enter" or "Ctrl+Shift+S"
+    pub label: String,
+    /// A plain character that joins neighbouring ones into typed text
+    pub typed: bool,
+}
+
+/// Keys pressed during a recording. Only held in memory until burned in; never written to
+/// disk, since it can contain anything typed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct KeystrokeLog {
+    pub events: Vec<KeystrokeEvent>,
+}
+
+impl KeystrokeLog {
+    /// Group events into on-screen captions: typed characters run together, shortcuts and
+    /// special keys get their own
+    pub fn captions(&self) -> Vec<KeystrokeCaption> {
+        let mut captions: Vec<KeystrokeCaption> = Vec::new();
+        let mut last_typed_at: Option<f64> = None;
+
+        for event in &self.events {
+            let joins = event.typed
+                && last_typed_at.map(|t| event.t - t <= TYPING_GAP_SECS).unwrap_or(false)
+                && captions
+                    .last()
+                    .map(|c| c.text.chars().count() < MAX_CAPTION_CHARS)
+                    .unwrap_or(false);
+            match captions.last_mut() {
+                Some(caption) if joins => {
+                    caption.text.push_str(&event.label);
+                    caption.end = event.t + CAPTION_HOLD_SECS;
+                }
+                _ => captions.push(KeystrokeCaption {
+                    start: event.t,
+                    end: event.t + CAPTION_HOLD_SECS,
+                    text: event.label.clone(),
+                }),
+            }
+            last_typed_at = event.typed.then_some(event.t);
+        }
+
+        // Each caption gives way to the next instead of overlapping it
+        for index in 1..captions.len() {
+            let next_start = captions[index].start;
+            let previous = &mut captions[index - 1];
+            previous.end = previous.end.min(next_start);
+        }
+        captions
+    }
+}
+
+/// Text shown in the key display between two times
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeystrokeCaption {
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+}
+
+/// Polls the keyboard on a background thread while a recording runs. Nothing is recorded while
+/// the focused field is a password field.
+pub struct KeystrokeRecorder {
+    stop: Arc<AtomicBool>,
+    events: Arc<Mutex<Vec<KeystrokeEvent>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl KeystrokeRecorder {
+    pub fn start() -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let events = Arc::new(Mutex::new(Vec::new()));
+
+        let thread = {
+            let stop = stop.clone();
+            let events = events.clone();
+            std::thread::spawn(move || {
+                let Some(keyboard) = platform::Keyboard::open() else {
+                    eprintln!("[Recording] Keystroke capture is unavailable on this system");
+                    return;
+                };
+                let interval = Duration::from_millis(1000 / POLL_RATE);
+                let started = Instant::now();
+                let mut held: Vec<u16> = Vec::new();
+                while !stop.load(Ordering::SeqCst) {
+                    std::thread::sleep(interval);
+
+                    if keyboard.secure_input() {
+                        held.clear();
+                        continue;
+                    }
+                    let down = keyboard.held();
+                    for code in down.iter().filter(|code| !held.contains(code)) {
+                        if let Some((label, typed)) = describe(*code, &down) {
+                            events.lock().unwrap().push(KeystrokeEvent {
+                                t: started.elapsed().as_secs_f64(),
+                                label,
+                                typed,
+                            });
+                        }
+                    }
+                    held = down;
+                }
+            })
+        };
+
+        Self {
+            stop,
+            events,
+            thread: Some(thread),
+        }
+    }
+
+    /// Stop polling and return the log
+    pub fn finish(mut self) -> KeystrokeLog {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        KeystrokeLog {
+            events: std::mem::take(&mut *self.events.lock().unwrap()),
+        }
+    }
+}
+
+/// Label for a newly pressed key given everything held with it; None for modifiers on their own
+fn describe(code: u16, down: &[u16]) -> Option<(String, bool)> {
+    if platform::MODIFIERS.iter().any(|(c, _)| *c == code) {
+        return None;
+    }
+    let (_, key) = platform::KEYS.iter().find(|(c, _)| *c == code)?;
+
+    let mut modifiers: Vec<&str> = Vec::new();
+    for (modifier_code, name) in platform::MODIFIERS {
+        if down.contains(modifier_code) && !modifiers.contains(name) {
+            modifiers.push(name);
+        }
+    }
+    let shifted = modifiers.contains(&"Shift");
+    modifiers.retain(|name| *name != "Shift");
+
+    let single_char = key.chars().count() == 1;
+    if modifiers.is_empty() && single_char {
+        let text = if shifted { key.to_uppercase() } else { key.to_string() };
+        return Some((text, true));
+    }
+    if modifiers.is_empty() && *key == "Space" && !shifted {
+        return Some((" ".to_string(), true));
+    }
+
+    if shifted {
+        modifiers.push("Shift");
+    }
+    let key = if single_char { key.to_uppercase() } else { key.to_string() };
+    modifiers.push(key.as_str());
+    Some((modifiers.join("+"), false))
+}
+
+/// drawtext filters that show `log` in the lower third of a `video_height` tall frame. Caption
+/// text goes in files under `text_dir` so nothing typed needs filter escaping.
+pub fn keystroke_overlay_filters(log: &KeystrokeLog, video_height: u32, text_dir: &Path) -> Result<Vec<String>> {
+    std::fs::create_dir_all(text_dir).context("Failed to create caption directory")?;
+    let font_size = (video_height / 18).max(12);
+
+    log.captions()
+        .iter()
+        .enumerate()
+        .map(|(index, caption)| {
+            let text_path = text_dir.join(format!("caption_{:04}.txt", index));
+            std::fs::write(&text_path, &caption.text).context("Failed to write caption")?;
+            Ok(format!(
+                "drawtext=textfile='{}':expansion=none:fontsize={}:fontcolor=white:box=1:boxcolor=black@0.65:boxborderw={}:x=(w-tw)/2:y=h*5/6-th/2:enable='between(t,{:.3},{:.3})'",
+                escape_filter_path(&text_path),
+                font_size,
+                font_size / 2,
+                caption.start,
+                caption.end
+            ))
+        })
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceKeyState(state: i32, key: u16) -> bool;
+    }
+
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        /// True while a password field (or anything else asking for secure input) has focus
+        fn IsSecureEventInputEnabled() -> bool;
+    }
+
+    /// kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+
+    /// Virtual key codes from HIToolbox/Events.h (ANSI layout)
+    pub const KEYS: &[(u16, &str)] = &[
+        (0, "a"), (1, "s"), (2, "d"), (3, "f"), (4, "h"), (5, "g"), (6, "z"), (7, "x"),
+        (8, "c"), (9, "v"), (11, "b"), (12, "q"), (13, "w"), (14, "e"), (15, "r"), (16, "y"),
+        (17, "t"), (18, "1"), (19, "2"), (20, "3"), (21, "4"), (22, "6"), (23, "5"), (24, "="),
+        (25, "9"), (26, "7"), (27, "-"), (28, "8"), (29, "0"), (30, "]"), (31, "o"), (32, "u"),
+        (33, "["), (34, "i"), (35, "p"), (36, "Enter"), (37, "l"), (38, "j"), (39, "'"),
+        (40, "k"), (41, ";"), (42, "\\"), (43, ","), (44, "/"), (45, "n"), (46, "m"), (47, "."),
+        (48, "Tab"), (49, "Space"), (50, "`"), (51, "Backspace"), (53, "Esc"),
+        (96, "F5"), (97, "F6"), (98, "F7"), (99, "F3"), (100, "F8"), (101, "F9"), (103, "F11"),
+        (109, "F10"), (111, "F12"), (115, "Home"), (116, "PageUp"), (117, "Delete"), (118, "F4"),
+        (119, "End"), (120, "F2"), (121, "PageDown"), (122, "F1"), (123, "Left"), (124, "Right"),
+        (125, "Down"), (126, "Up"),
+    ];
+
+    pub const MODIFIERS: &[(u16, &str)] = &[
+        (55, "Cmd"), (54, "Cmd"), (59, "Ctrl"), (62, "Ctrl"), (58, "Option"), (61, "Option"),
+        (56, "Shift"), (60, "Shift"),
+    ];
+
+    pub struct Keyboard;
+
+    impl Keyboard {
+        pub fn open() -> Option<Self> {
+            Some(Self)
+        }
+
+        pub fn held(&self) -> Vec<u16> {
+            KEYS.iter()
+                .chain(MODIFIERS)
+                .map(|(code, _)| *code)
+                .filter(|code| unsafe { CGEventSourceKeyState(COMBINED_SESSION_STATE, *code) })
+                .collect()
+        }
+
+        pub fn secure_input(&self) -> bool {
+            unsafe { IsSecureEventInputEnabled() }
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED,
+    };
+    use windows::Win32::UI::Accessibility::{CUIAutomation, IUIAutomation};
+    use windows::Win32::UI::Input::KeyboardAndMouse::GetAsyncKeyState;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetForegroundWindow, GetGUIThreadInfo, GetWindowLongW, GetWindowThreadProcessId,
+        GUITHREADINFO, GWL_STYLE,
+    };
+
+    /// ES_PASSWORD edit control style
+    const ES_PASSWORD: i32 = 0x0020;
+
+    /// Virtual key codes
+    pub const KEYS: &[(u16, &str)] = &[
+        (0x41, "a"), (0x42, "b"), (0x43, "c"), (0x44, "d"), (0x45, "e"), (0x46, "f"), (0x47, "g"),
+        (0x48, "h"), (0x49, "i"), (0x4A, "j"), (0x4B, "k"), (0x4C, "l"), (0x4D, "m"), (0x4E, "n"),
+        (0x4F, "o"), (0x50, "p"), (0x51, "q"), (0x52, "r"), (0x53, "s"), (0x54, "t"), (0x55, "u"),
+        (0x56, "v"), (0x57, "w"), (0x58, "x"), (0x59, "y"), (0x5A, "z"),
+        (0x30, "0"), (0x31, "1"), (0x32, "2"), (0x33, "3"), (0x34, "4"), (0x35, "5"), (0x36, "6"),
+        (0x37, "7"), (0x38, "8"), (0x39, "9"),
+        (0x08, "Backspace"), (0x09, "Tab"), (0x0D, "Enter"), (0x1B, "Esc"), (0x20, "Space"),
+        (0x21, "PageUp"), (0x22, "PageDown"), (0x23, "End"), (0x24, "Home"), (0x25, "Left"),
+        (0x26, "Up"), (0x27, "Right"), (0x28, "Down"), (0x2E, "Delete"),
+        (0x70, "F1"), (0x71, "F2"), (0x72, "F3"), (0x73, "F4"), (0x74, "F5"), (0x75, "F6"),
+        (0x76, "F7"), (0x77, "F8"), (0x78, "F9"), (0x79, "F10"), (0x7A, "F11"), (0x7B, "F12"),
+        (0xBA, ";"), (0xBB, "="), (0xBC, ","), (0xBD, "-"), (0xBE, "."), (0xBF, "/"), (0xC0, "`"),
+        (0xDB, "["), (0xDC, "\\"), (0xDD, "]"), (0xDE, "'"),
+    ];
+
+    /// Left/right variants only; the generic codes would report each press twice
+    pub const MODIFIERS: &[(u16, &str)] = &[
+        (0xA2, "Ctrl"), (0xA3, "Ctrl"), (0xA4, "Alt"), (0xA5, "Alt"), (0x5B, "Win"), (0x5C, "Win"),
+        (0xA0, "Shift"), (0xA1, "Shift"),
+    ];
+
+    /// UI Automation client, created on the polling thread
+    pub struct Keyboard {
+        automation: IUIAutomation,
+    }
+
+    impl Keyboard {
+        /// None when UI Automation is unavailable, since password fields in browsers and
+        /// custom controls couldn't be detected without it
+        pub fn open() -> Option<Self> {
+            unsafe {
+                CoInitializeEx(None, COINIT_MULTITHREADED).ok()?;
+                let automation: IUIAutomation =
+                    CoCreateInstance(&CUIAutomation, None, CLSCTX_INPROC_SERVER).ok()?;
+                Some(Self { automation })
+            }
+        }
+
+        pub fn held(&self) -> Vec<u16> {
+            KEYS.iter()
+                .chain(MODIFIERS)
+                .map(|(code, _)| *code)
+                // High bit set means the key is currently down
+                .filter(|code| unsafe { GetAsyncKeyState(*code as i32) } < 0)
+                .collect()
+        }
+
+        /// Whether the focused control is a password field: a standard password box, or any
+        /// control UI Automation reports as one (browser and custom-drawn fields). Treated as
+        /// a password field whenever UI Automation can't answer.
+        pub fn secure_input(&self) -> bool {
+            if self.standard_password_box() {
+                return true;
+            }
+            unsafe {
+                self.automation
+                    .GetFocusedElement()
+                    .and_then(|element| element.CurrentIsPassword())
+                    .map(|is_password| is_password.as_bool())
+                    .unwrap_or(true)
+            }
+        }
+
+        fn standard_password_box(&self) -> bool {
+            unsafe {
+                let foreground = GetForegroundWindow();
+                let thread = GetWindowThreadProcessId(foreground, None);
+                let mut info = GUITHREADINFO {
+                    cbSize: std::mem::size_of::<GUITHREADINFO>() as u32,
+                    ..Default::default()
+                };
+                if GetGUIThreadInfo(thread, &mut info).is_err() || info.hwndFocus.0 == 0 {
+                    return false;
+                }
+                GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0
+            }
+        }
+    }
+}
+
+/// X11 has no way to tell when a password field has focus, so keystrokes aren't captured
+#[cfg(target_os = "linux")]
+mod platform {
+    pub const KEYS: &[(u16, &str)] = &[];
+    pub const MODIFIERS: &[(u16, &str)] = &[];
+
+    pub struct Keyboard;
+
+    impl Keyboard {
+        pub fn open() -> Option<Self> {
+            None
+        }
+
+        pub fn held(&self) -> Vec<u16> {
+            Vec::new()
+        }
+
+        pub fn secure_input(&self) -> bool {
+            true
+        }
+    }
+}
@@ -1,3 +1,18 @@
 pub mod ffmpeg;
 pub mod app_init;
 
+pub mod settings;
+pub mod cache;
+pub mod hash;
+pub mod jobs;
+pub mod devices;
+pub mod capture;
+pub mod disk;
+pub mod permissions;
+pub mod cursor;
+pub mod keystrokes;
+pub mod recording_metadata;
+pub mod prerender_cache;
+pub mod mp4;
+pub mod scopes;
+pub mod subtitles;
@@ -0,0 +1,153 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Top-level boxes of an MP4 as (type, whole box including header)
+fn boxes(data: &[u8]) -> Vec<([u8; 4], &[u8])> {
+    let mut found = Vec::new();
+    let mut offset = 0;
+    while offset + 8 <= data.len() {
+        let size32 = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+        let kind: [u8; 4] = data[offset + 4..offset + 8].try_into().unwrap();
+        let size = match size32 {
+            // Extends to the end of the data
+            0 => data.len() - offset,
+            // 64-bit size follows the type
+            1 if offset + 16 <= data.len() => {
+                u64::from_be_bytes(data[offset + 8..offset + 16].try_into().unwrap()) as usize
+            }
+            size => size,
+        };
+        if size < 8 || offset + size > data.len() {
+            break;
+        }
+        found.push((kind, &data[offset..offset + size]));
+        offset += size;
+    }
+    found
+}
+
+/// Children of a container box, skipping `skip` bytes of fields after the header
+fn children(container: &[u8], skip: usize) -> Vec<([u8; 4], &[u8])> {
+    container.get(8 + skip..).map(boxes).unwrap_or_default()
+}
+
+fn child<'a>(container: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    children(container, 0).into_iter().find(|(found, _)| found == kind).map(|(_, data)| data)
+}
+
+/// The initialization segment (ftyp + moov) at the start of a fragmented MP4, which Media
+/// Source Extensions need before any media segment. None when the file has no moov or isn't
+/// fragmented.
+pub fn init_segment(data: &[u8]) -> Option<&[u8]> {
+    let mut end = 0;
+    let mut has_moov = false;
+    for (kind, found) in boxes(data) {
+        match &kind {
+            b"moof" => return has_moov.then(|| &data[..end]),
+            b"moov" => has_moov = true,
+            _ => {}
+        }
+        end += found.len();
+    }
+    None
+}
+
+/// Largest ftyp or moov box read_init_segment accepts; real ones are a few kilobytes
+const MAX_INIT_BOX_BYTES: u64 = 16 * 1024 * 1024;
+
+/// Read just the ftyp and moov boxes at the start of a fragmented MP4 file, stopping at the
+/// first moof, so the media itself is never read. None when the file isn't fragmented (media
+/// data comes before any moof) or has no moov.
+pub fn read_init_segment(path: &Path) -> std::io::Result<Option<Vec<u8>>> {
+    let mut file = File::open(path)?;
+    let mut init = Vec::new();
+    let mut has_moov = false;
+    loop {
+        let mut header = [0u8; 8];
+        if file.read_exact(&mut header).is_err() {
+            return Ok(None);
+        }
+        let kind: [u8; 4] = header[4..8].try_into().unwrap();
+        let (size, header_len) = match u32::from_be_bytes(header[..4].try_into().unwrap()) {
+            // Extends to the end of the file, so no moof can follow
+            0 => return Ok(None),
+            1 => {
+                let mut large = [0u8; 8];
+                file.read_exact(&mut large)?;
+                (u64::from_be_bytes(large), 16)
+            }
+            size => (size as u64, 8),
+        };
+        if size < header_len {
+            return Ok(None);
+        }
+
+        match &kind {
+            b"moof" => return Ok(has_moov.then_some(init)),
+            b"mdat" => return Ok(None),
+            b"ftyp" | b"moov" if size <= MAX_INIT_BOX_BYTES => {
+                let start = init.len();
+                init.extend_from_slice(&header);
+                if header_len == 16 {
+                    init.extend_from_slice(&size.to_be_bytes());
+                }
+                init.resize(start + size as usize, 0);
+                file.read_exact(&mut init[start + header_len as usize..])?;
+                has_moov |= &kind == b"moov";
+            }
+            b"ftyp" | b"moov" => return Ok(None),
+            _ => {
+                file.seek(SeekFrom::Current((size - header_len) as i64))?;
+            }
+        }
+    }
+}
+
+/// RFC 6381 codec strings of the tracks described by an init segment, e.g. "avc1.64001f" and
+/// "mp4a.40.2", for MediaSource.isTypeSupported and addSourceBuffer
+pub fn codec_strings(init: &[u8]) -> Vec<String> {
+    let moov = boxes(init).into_iter().find(|(kind, _)| kind == b"moov");
+    let Some((_, moov)) = moov else {
+        return Vec::new();
+    };
+
+    let mut codecs = Vec::new();
+    for (kind, trak) in children(moov, 0) {
+        if &kind != b"trak" {
+            continue;
+        }
+        let stsd = child(trak, b"mdia")
+            .and_then(|mdia| child(mdia, b"minf"))
+            .and_then(|minf| child(minf, b"stbl"))
+            .and_then(|stbl| child(stbl, b"stsd"));
+        let Some(stsd) = stsd else {
+            continue;
+        };
+        // stsd has version/flags and an entry count before its sample entries
+        for (entry, sample) in children(stsd, 8) {
+            match &entry {
+                b"avc1" | b"avc3" => {
+                    // Visual sample entries have 78 bytes of fields before their child boxes
+                    let config = children(sample, 78)
+                        .into_iter()
+                        .find(|(kind, _)| kind == b"avcC")
+                        .and_then(|(_, avcc)| avcc.get(9..12));
+                    if let Some(config) = config {
+                        codecs.push(format!(
+                            "{}.{:02x}{:02x}{:02x}",
+                            String::from_utf8_lossy(&entry),
+                            config[0],
+                            config[1],
+                            config[2]
+                        ));
+                    }
+                }
+                // AAC LC, the only audio prerender encodes
+                b"mp4a" => codecs.push("mp4a.40.2".to_string()),
+                _ => {}
+            }
+        }
+    }
+    codecs
+}
@@ -0,0 +1,247 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::process::Command;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionKind {
+    Screen,
+    Microphone,
+    Camera,
+}
+
+impl PermissionKind {
+    pub const ALL: [PermissionKind; 3] = [
+        PermissionKind::Screen,
+        PermissionKind::Microphone,
+        PermissionKind::Camera,
+    ];
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Granted,
+    Denied,
+    /// The user hasn't been asked yet; requesting shows the OS prompt
+    NotDetermined,
+    /// Blocked by policy (parental controls, MDM); the user can't change it
+    Restricted,
+    /// The platform doesn't gate this capability
+    NotRequired,
+    /// Native capture can't do this on the current platform/session
+    Unsupported,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PermissionState {
+    pub kind: PermissionKind,
+    pub status: PermissionStatus,
+    /// Where the user can change the permission, for "open settings" buttons
+    pub settings_url: Option<String>,
+}
+
+/// Current status of a capture permission
+pub fn check_permission(kind: PermissionKind) -> PermissionState {
+    PermissionState {
+        kind,
+        status: platform::status(kind),
+        settings_url: platform::settings_url(kind).map(|url| url.to_string()),
+    }
+}
+
+/// Ask for a capture permission. Shows the OS prompt when the user hasn't decided yet and
+/// opens the system settings page when it was denied, since apps can't re-prompt.
+pub fn request_permission(kind: PermissionKind) -> Result<PermissionState> {
+    let current = platform::status(kind);
+    match current {
+        PermissionStatus::NotDetermined => platform::prompt(kind)?,
+        PermissionStatus::Denied => open_settings(kind)?,
+        _ => {}
+    }
+    Ok(check_permission(kind))
+}
+
+fn open_settings(kind: PermissionKind) -> Result<()> {
+    let Some(url) = platform::settings_url(kind) else {
+        return Ok(());
+    };
+    #[cfg(target_os = "macos")]
+    Command::new("open").arg(url).spawn()?;
+    #[cfg(target_os = "windows")]
+    Command::new("explorer").arg(url).spawn()?;
+    #[cfg(target_os = "linux")]
+    Command::new("xdg-open").arg(url).spawn()?;
+    Ok(())
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use super::*;
+    use crate::utils::ffmpeg::get_ffmpeg_path;
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::NSString;
+    use objc::{class, msg_send, sel, sel_impl};
+
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    #[link(name = "AVFoundation", kind = "framework")]
+    extern "C" {}
+
+    /// AVAuthorizationStatus for AVMediaTypeAudio ("soun") or AVMediaTypeVideo ("vide")
+    fn av_authorization(media_type: &str) -> PermissionStatus {
+        unsafe {
+            let media_type: id = NSString::alloc(nil).init_str(media_type);
+            let status: isize =
+                msg_send![class!(AVCaptureDevice), authorizationStatusForMediaType: media_type];
+            let _: () = msg_send![media_type, release];
+            match status {
+                0 => PermissionStatus::NotDetermined,
+                1 => PermissionStatus::Restricted,
+                2 => PermissionStatus::Denied,
+                _ => PermissionStatus::Granted,
+            }
+        }
+    }
+
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        match kind {
+            // There's no "not asked yet" for screen recording; the first request prompts
+            PermissionKind::Screen => {
+                if unsafe { CGPreflightScreenCaptureAccess() } {
+                    PermissionStatus::Granted
+                } else {
+                    PermissionStatus::NotDetermined
+                }
+            }
+            PermissionKind::Microphone => av_authorization("soun"),
+            PermissionKind::Camera => av_authorization("vide"),
+        }
+    }
+
+    pub fn prompt(kind: PermissionKind) -> Result<()> {
+        match kind {
+            PermissionKind::Screen => {
+                // Only prompts once per install; afterwards the user must use System Settings
+                if !unsafe { CGRequestScreenCaptureAccess() } {
+                    open_settings(kind)?;
+                }
+            }
+            // Opening the device through FFmpeg triggers the TCC prompt for the app, which is
+            // the responsible process, without needing an Objective-C completion block
+            PermissionKind::Microphone | PermissionKind::Camera => {
+                let input = if kind == PermissionKind::Microphone { ":0" } else { "0:none" };
+                Command::new(get_ffmpeg_path()?)
+                    .args(["-hide_banner", "-v", "error", "-f", "avfoundation", "-i", input, "-t", "0.1", "-f", "null", "-"])
+                    .output()?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+        Some(match kind {
+            PermissionKind::Screen => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_ScreenCapture"
+            }
+            PermissionKind::Microphone => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Microphone"
+            }
+            PermissionKind::Camera => {
+                "x-apple.systempreferences:com.apple.preference.security?Privacy_Camera"
+            }
+        })
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use super::*;
+
+    /// Read the per-user consent value ("Allow"/"Deny") from the capability access manager
+    fn consent(capability: &str) -> PermissionStatus {
+        let key = format!(
+            "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\{}",
+            capability
+        );
+        let output = match Command::new("reg").args(["query", &key, "/v", "Value"]).output() {
+            Ok(output) if output.status.success() => output,
+            // No key means the OS never asked; desktop apps are allowed by default
+            _ => return PermissionStatus::Granted,
+        };
+        if String::from_utf8_lossy(&output.stdout).contains("Deny") {
+            PermissionStatus::Denied
+        } else {
+            PermissionStatus::Granted
+        }
+    }
+
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        match kind {
+            PermissionKind::Screen => PermissionStatus::NotRequired,
+            PermissionKind::Microphone => consent("microphone"),
+            PermissionKind::Camera => consent("webcam"),
+        }
+    }
+
+    /// Windows has no runtime prompt for desktop apps; access is only toggled in Settings
+    pub fn prompt(_kind: PermissionKind) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn settings_url(kind: PermissionKind) -> Option<&'static str> {
+        match kind {
+            PermissionKind::Screen => None,
+            PermissionKind::Microphone => Some("ms-settings:privacy-microphone"),
+            PermissionKind::Camera => Some("ms-settings:privacy-webcam"),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use super::*;
+
+    pub fn status(kind: PermissionKind) -> PermissionStatus {
+        match kind {
+            // x11grab can't capture Wayland-only sessions
+            PermissionKind::Screen => {
+                if std::env::var("DISPLAY").is_err() && std::env::var("WAYLAND_DISPLAY").is_ok() {
+                    PermissionStatus::Unsupported
+                } else {
+                    PermissionStatus::NotRequired
+                }
+            }
+            PermissionKind::Microphone => PermissionStatus::NotRequired,
+            // Webcams need read access to /dev/video*, usually via the "video" group
+            PermissionKind::Camera => {
+                let devices: Vec<_> = std::fs::read_dir("/dev")
+                    .map(|entries| {
+                        entries
+                            .flatten()
+                            .filter(|e| e.file_name().to_string_lossy().starts_with("video"))
+                            .map(|e| e.path())
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                if devices.is_empty() || devices.iter().any(|path| std::fs::File::open(path).is_ok()) {
+                    PermissionStatus::NotRequired
+                } else {
+                    PermissionStatus::Denied
+                }
+            }
+        }
+    }
+
+    pub fn prompt(_kind: PermissionKind) -> Result<()> {
+        Ok(())
+    }
+
+    pub fn settings_url(_kind: PermissionKind) -> Option<&'static str> {
+        None
+    }
+}
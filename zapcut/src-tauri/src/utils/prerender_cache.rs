@@ -0,0 +1,234 @@
+use crate::utils::cache::{get_cache_dir, CacheKind};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Bookkeeping for one cached segment, keyed by its cache key
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrerenderCacheEntry {
+    /// Unix milliseconds of the last render or reuse
+    pub last_accessed: i64,
+    /// Timeline clips the segment was rendered from
+    #[serde(default)]
+    pub clip_ids: Vec<String>,
+}
+
+/// index.json in the prerender cache directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct PrerenderIndex {
+    pub entries: HashMap<String, PrerenderCacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrerenderCacheStats {
+    pub path: String,
+    pub segment_count: u64,
+    pub total_bytes: u64,
+    pub max_bytes: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref INDEX_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Get (and create) the directory prerendered segments are cached in
+pub fn prerender_cache_dir() -> Result<PathBuf> {
+    get_cache_dir(CacheKind::Prerender)
+}
+
+/// Extensions of cached renders: video segments and audio-only segments
+const SEGMENT_EXTENSIONS: [&str; 3] = ["mp4", "m4a", "wav"];
+
+/// Cached file for a segment key
+pub fn segment_path(key: &str, extension: &str) -> Result<PathBuf> {
+    Ok(prerender_cache_dir()?.join(format!("{}.{}", key, extension)))
+}
+
+/// HLS chunks remuxed from cached segments, one directory per segment key, next to the
+/// playlists built from them
+pub fn hls_dir() -> Result<PathBuf> {
+    Ok(prerender_cache_dir()?.join("hls"))
+}
+
+/// Delete HLS chunks whose segment is no longer cached. Remuxes in progress write to
+/// `<key>.partial` and are left alone.
+fn prune_hls(present: &HashSet<String>) {
+    let Ok(entries) = hls_dir().and_then(|dir| Ok(std::fs::read_dir(dir)?)) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        let orphaned = !name.ends_with(".partial") && !present.contains(name);
+        if path.is_dir() && orphaned {
+            let _ = std::fs::remove_dir_all(&path);
+        }
+    }
+}
+
+fn index_path() -> Result<PathBuf> {
+    Ok(prerender_cache_dir()?.join("index.json"))
+}
+
+fn load_index() -> PrerenderIndex {
+    index_path()
+        .ok()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_index(index: &PrerenderIndex) -> Result<()> {
+    let json = serde_json::to_string_pretty(index).context("Failed to serialize prerender index")?;
+    std::fs::write(index_path()?, json).context("Failed to write prerender index")
+}
+
+/// Mark a segment as just used so it is evicted last, and note the clips it contains
+pub fn touch_segment(key: &str, clip_ids: &[String]) -> Result<()> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut index = load_index();
+    let entry = index.entries.entry(key.to_string()).or_insert(PrerenderCacheEntry {
+        last_accessed: 0,
+        clip_ids: Vec::new(),
+    });
+    entry.last_accessed = chrono::Utc::now().timestamp_millis();
+    for clip_id in clip_ids {
+        if !entry.clip_ids.contains(clip_id) {
+            entry.clip_ids.push(clip_id.clone());
+        }
+    }
+    save_index(&index)
+}
+
+/// Delete one cached segment, its HLS chunks and its index entry
+pub fn remove_segment(key: &str) -> Result<()> {
+    let _guard = INDEX_LOCK.lock().unwrap();
+    for extension in SEGMENT_EXTENSIONS {
+        let path = segment_path(key, extension)?;
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    let _ = std::fs::remove_dir_all(hls_dir()?.join(key));
+
+    let mut index = load_index();
+    if index.entries.remove(key).is_some() {
+        save_index(&index)?;
+    }
+    Ok(())
+}
+
+/// Delete every cached segment rendered from any of `clip_ids`. Returns how many files were
+/// removed.
+pub fn remove_segments_for_clips(clip_ids: &[String]) -> Result<u64> {
+    let dir = prerender_cache_dir()?;
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut index = load_index();
+
+    let affected: HashSet<String> = index
+        .entries
+        .iter()
+        .filter(|(_, entry)| entry.clip_ids.iter().any(|id| clip_ids.contains(id)))
+        .map(|(key, _)| key.clone())
+        .collect();
+
+    let mut removed = 0;
+    let mut present = HashSet::new();
+    for (key, path, _, _) in cached_segments(&dir, &index) {
+        if !affected.contains(&key) {
+            present.insert(key);
+        } else if std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    index.entries.retain(|key, _| !affected.contains(key));
+    prune_hls(&present);
+    save_index(&index)?;
+    Ok(removed)
+}
+
+/// Cached segments as (key, path, size, last access). Files missing from the index (left by
+/// older versions) fall back to their modification time.
+fn cached_segments(dir: &Path, index: &PrerenderIndex) -> Vec<(String, PathBuf, u64, i64)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = path.file_name()?.to_str()?;
+            // Renders in progress are named <key>.partial.<extension>
+            let (key, extension) = name.split_once('.')?;
+            if !SEGMENT_EXTENSIONS.contains(&extension) {
+                return None;
+            }
+            let key = key.to_string();
+            let metadata = entry.metadata().ok()?;
+            let last_accessed = index.entries.get(&key).map(|e| e.last_accessed).unwrap_or_else(|| {
+                metadata
+                    .modified()
+                    .ok()
+                    .map(|time| chrono::DateTime::<chrono::Utc>::from(time).timestamp_millis())
+                    .unwrap_or(0)
+            });
+            Some((key, path, metadata.len(), last_accessed))
+        })
+        .collect()
+}
+
+/// Delete least recently used segments until the cache fits in `max_bytes`.
+/// Returns (segments removed, bytes freed).
+pub fn evict_to_limit(max_bytes: u64) -> Result<(u64, u64)> {
+    let dir = prerender_cache_dir()?;
+    let _guard = INDEX_LOCK.lock().unwrap();
+    let mut index = load_index();
+    let mut segments = cached_segments(&dir, &index);
+    let mut present: HashSet<String> = segments.iter().map(|(key, _, _, _)| key.clone()).collect();
+
+    let mut total: u64 = segments.iter().map(|(_, _, size, _)| size).sum();
+    segments.sort_by_key(|(_, _, _, last_accessed)| *last_accessed);
+
+    let (mut removed, mut freed) = (0, 0);
+    for (key, path, size, _) in segments {
+        if total <= max_bytes {
+            break;
+        }
+        if std::fs::remove_file(&path).is_ok() {
+            index.entries.remove(&key);
+            present.remove(&key);
+            total -= size;
+            removed += 1;
+            freed += size;
+        }
+    }
+
+    // Drop entries whose file is gone
+    index.entries.retain(|key, _| present.contains(key));
+    prune_hls(&present);
+    save_index(&index)?;
+    Ok((removed, freed))
+}
+
+pub fn cache_stats(max_bytes: u64) -> Result<PrerenderCacheStats> {
+    let dir = prerender_cache_dir()?;
+    let segments = cached_segments(&dir, &load_index());
+    Ok(PrerenderCacheStats {
+        path: dir.to_string_lossy().to_string(),
+        segment_count: segments.len() as u64,
+        total_bytes: segments.iter().map(|(_, _, size, _)| size).sum(),
+        max_bytes,
+    })
+}
+
+/// Delete every cached segment along with the index
+pub fn clear_cache() -> Result<()> {
+    let dir = prerender_cache_dir()?;
+    let _guard = INDEX_LOCK.lock().unwrap();
+    std::fs::remove_dir_all(&dir).context("Failed to clear prerender cache")?;
+    std::fs::create_dir_all(&dir).context("Failed to recreate prerender cache directory")?;
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use crate::utils::capture::CaptureTarget;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// A moment flagged while recording
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingMarker {
+    /// Seconds from the start of the recording
+    pub time: f64,
+    pub label: Option<String>,
+}
+
+/// What a recording was made with, stored as `<recording>.recording.json`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RecordingMetadata {
+    pub app_version: String,
+    /// "native" for FFmpeg capture, "browser" for MediaRecorder
+    pub source: String,
+    pub started_at: String,
+    /// Every file the recording produced, in order
+    pub files: Vec<String>,
+    pub webcam_files: Vec<String>,
+    pub microphone: Option<String>,
+    pub webcam: Option<String>,
+    pub system_audio: Option<String>,
+    /// Display id or FFmpeg screen input; None when the platform default (or the browser) chose
+    pub display: Option<String>,
+    pub capture_target: Option<CaptureTarget>,
+    pub width: u32,
+    pub height: u32,
+    pub fps: f64,
+    pub duration: f64,
+    pub video_codec: String,
+    pub audio_codec: Option<String>,
+    #[serde(default)]
+    pub markers: Vec<RecordingMarker>,
+}
+
+impl RecordingMetadata {
+    /// Sidecar path for a recording. Numbered parts share the sidecar of the whole recording.
+    pub fn path_for(recording: &Path) -> PathBuf {
+        let stem = recording.file_stem().and_then(|s| s.to_str()).unwrap_or("recording");
+        let base = match stem.rsplit_once("_part") {
+            Some((base, part)) if !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()) => base,
+            _ => stem,
+        };
+        recording.with_file_name(format!("{}.recording.json", base))
+    }
+
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read recording metadata {:?}", path))?;
+        serde_json::from_str(&contents).context("Failed to parse recording metadata")
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize recording metadata")?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write recording metadata {:?}", path))
+    }
+}
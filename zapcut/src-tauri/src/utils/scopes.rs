@@ -0,0 +1,72 @@
+use serde::Serialize;
+
+/// Luma levels per waveform column
+pub const WAVEFORM_LEVELS: usize = 128;
+/// Cells along each side of the vectorscope grid
+pub const VECTORSCOPE_SIZE: usize = 64;
+
+/// Scope datasets for one frame. 2D data is flattened row-major.
+#[derive(Debug, Serialize, Clone)]
+pub struct FrameScopes {
+    /// Frame size the scopes were computed at
+    pub width: u32,
+    pub height: u32,
+    /// `width` columns of WAVEFORM_LEVELS counts each, bottom (black) level first: how many
+    /// pixels of that column have that luma
+    pub waveform: Vec<u32>,
+    pub waveform_levels: usize,
+    /// 256 bins per channel
+    pub histogram_red: Vec<u32>,
+    pub histogram_green: Vec<u32>,
+    pub histogram_blue: Vec<u32>,
+    /// VECTORSCOPE_SIZE x VECTORSCOPE_SIZE counts of chroma, Cb along x and Cr along y with
+    /// neutral grey in the centre
+    pub vectorscope: Vec<u32>,
+    pub vectorscope_size: usize,
+}
+
+/// Compute luma waveform, RGB histogram and vectorscope from packed RGB24 pixels, using
+/// BT.709 coefficients
+pub fn compute_scopes(width: u32, height: u32, pixels: &[u8]) -> FrameScopes {
+    let columns = width as usize;
+    let mut waveform = vec![0u32; columns * WAVEFORM_LEVELS];
+    let mut histogram_red = vec![0u32; 256];
+    let mut histogram_green = vec![0u32; 256];
+    let mut histogram_blue = vec![0u32; 256];
+    let mut vectorscope = vec![0u32; VECTORSCOPE_SIZE * VECTORSCOPE_SIZE];
+
+    for (index, pixel) in pixels.chunks_exact(3).enumerate() {
+        let (r, g, b) = (pixel[0], pixel[1], pixel[2]);
+        histogram_red[r as usize] += 1;
+        histogram_green[g as usize] += 1;
+        histogram_blue[b as usize] += 1;
+
+        let (rf, gf, bf) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0);
+        let luma = 0.2126 * rf + 0.7152 * gf + 0.0722 * bf;
+        let column = index % columns;
+        let level = ((luma * (WAVEFORM_LEVELS - 1) as f32).round() as usize).min(WAVEFORM_LEVELS - 1);
+        waveform[column * WAVEFORM_LEVELS + level] += 1;
+
+        // Cb and Cr range over -0.5..0.5
+        let cb = (bf - luma) / 1.8556;
+        let cr = (rf - luma) / 1.5748;
+        let cell = |value: f32| {
+            (((value + 0.5) * VECTORSCOPE_SIZE as f32) as usize).min(VECTORSCOPE_SIZE - 1)
+        };
+        // Rows run top to bottom, so positive Cr is up
+        let row = VECTORSCOPE_SIZE - 1 - cell(cr);
+        vectorscope[row * VECTORSCOPE_SIZE + cell(cb)] += 1;
+    }
+
+    FrameScopes {
+        width,
+        height,
+        waveform,
+        waveform_levels: WAVEFORM_LEVELS,
+        histogram_red,
+        histogram_green,
+        histogram_blue,
+        vectorscope,
+        vectorscope_size: VECTORSCOPE_SIZE,
+    }
+}
@@ -0,0 +1,121 @@
+use crate::utils::app_init::initialize_app_directories;
+use crate::utils::capture::HardwareEncoding;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFormat {
+    Jpeg,
+    Webp,
+}
+
+impl ThumbnailFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ThumbnailFormat::Jpeg => "jpg",
+            ThumbnailFormat::Webp => "webp",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ThumbnailSettings {
+    pub format: ThumbnailFormat,
+    /// Longest edge in pixels; sources smaller than this are never upscaled
+    pub max_dimension: u32,
+    /// 1-100, higher is better
+    pub quality: u8,
+}
+
+impl Default for ThumbnailSettings {
+    fn default() -> Self {
+        Self {
+            format: ThumbnailFormat::Jpeg,
+            max_dimension: 640,
+            quality: 80,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(default)]
+pub struct PrerenderSettings {
+    /// Least recently used segments are evicted once the cache grows past this
+    pub max_cache_bytes: u64,
+    /// Auto falls back to software when the hardware encoder fails on a segment
+    pub hardware_encoding: HardwareEncoding,
+    /// Segments the background queue renders at once
+    pub max_concurrent: usize,
+}
+
+impl Default for PrerenderSettings {
+    fn default() -> Self {
+        Self {
+            max_cache_bytes: 5 * 1024 * 1024 * 1024,
+            hardware_encoding: HardwareEncoding::Auto,
+            // Each FFmpeg process is itself multi-threaded, so a quarter of the cores is plenty
+            max_concurrent: std::thread::available_parallelism()
+                .map(|cores| (cores.get() / 4).clamp(1, 4))
+                .unwrap_or(1),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Default)]
+#[serde(default)]
+pub struct PreviewSettings {
+    /// Composite preview frames in the backend instead of the webview. Off by default.
+    pub native_engine: bool,
+}
+
+/// Backend settings persisted to settings.json in the Zapcut directory
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    pub thumbnails: ThumbnailSettings,
+    pub prerender: PrerenderSettings,
+    pub preview: PreviewSettings,
+}
+
+lazy_static::lazy_static! {
+    static ref SETTINGS: Mutex<Option<AppSettings>> = Mutex::new(None);
+}
+
+fn load_from_disk() -> AppSettings {
+    let path = match initialize_app_directories() {
+        Ok(dir) => dir.join("settings.json"),
+        Err(_) => return AppSettings::default(),
+    };
+
+    std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_to_disk(settings: &AppSettings) -> Result<()> {
+    let path = initialize_app_directories()?.join("settings.json");
+    let json = serde_json::to_string_pretty(settings).context("Failed to serialize settings")?;
+    std::fs::write(&path, json).context("Failed to write settings file")?;
+    Ok(())
+}
+
+/// Get a snapshot of the current settings, loading them from disk on first use
+pub fn get_settings() -> AppSettings {
+    let mut guard = SETTINGS.lock().unwrap();
+    guard.get_or_insert_with(load_from_disk).clone()
+}
+
+/// Apply a change to the settings and persist the result
+pub fn update_settings<F>(update: F) -> Result<AppSettings>
+where
+    F: FnOnce(&mut AppSettings),
+{
+    let mut guard = SETTINGS.lock().unwrap();
+    let settings = guard.get_or_insert_with(load_from_disk);
+    update(settings);
+    save_to_disk(settings)?;
+    Ok(settings.clone())
+}
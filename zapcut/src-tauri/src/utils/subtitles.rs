@@ -0,0 +1,56 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+/// Convert SubRip subtitles to WebVTT for <track> elements. Cue numbers are kept as cue
+/// identifiers; timestamps switch to a decimal point and SRT-only <font> tags are dropped.
+pub fn srt_to_vtt(srt: &str) -> String {
+    let srt = srt.trim_start_matches('\u{feff}').replace("\r\n", "\n").replace('\r', "\n");
+    let mut vtt = String::from("WEBVTT\n\n");
+    for line in srt.lines() {
+        if line.contains("-->") {
+            vtt.push_str(&line.replace(',', "."));
+        } else {
+            vtt.push_str(&strip_font_tags(line));
+        }
+        vtt.push('\n');
+    }
+    vtt
+}
+
+fn strip_font_tags(line: &str) -> String {
+    let mut stripped = String::with_capacity(line.len());
+    let mut rest = line;
+    while let Some(start) = rest.find('<') {
+        stripped.push_str(&rest[..start]);
+        let tag = &rest[start..];
+        let lower = tag.to_ascii_lowercase();
+        match tag.find('>') {
+            Some(end) if lower.starts_with("<font") || lower.starts_with("</font") => {
+                rest = &tag[end + 1..];
+            }
+            _ => {
+                stripped.push('<');
+                rest = &tag[1..];
+            }
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// WebVTT copy of an SRT file, kept beside it and converted again whenever the SRT is newer
+pub fn cached_vtt(srt_path: &Path) -> Result<PathBuf> {
+    let vtt_path = srt_path.with_extension("vtt");
+    let modified =
+        |path: &Path| std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+    let up_to_date = match (modified(&vtt_path), modified(srt_path)) {
+        (Some(vtt), Some(srt)) => vtt >= srt,
+        _ => false,
+    };
+    if !up_to_date {
+        let srt = std::fs::read_to_string(srt_path)
+            .with_context(|| format!("Failed to read {}", srt_path.display()))?;
+        std::fs::write(&vtt_path, srt_to_vtt(&srt)).context("Failed to write WebVTT captions")?;
+    }
+    Ok(vtt_path)
+}
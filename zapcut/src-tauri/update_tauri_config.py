@@ -43,7 +43,12 @@ def update_tauri_config(platform: str, config_path: str = "tauri.conf.json"):
         print(f"Valid platforms: {', '.join(platform_resources.keys())}", file=sys.stderr)
         sys.exit(1)
     
-    resources = platform_resources[platform]
+    # Resources bundled on every platform
+    shared_resources = [
+        "binaries/rnnoise.rnnn"
+    ]
+    
+    resources = platform_resources[platform] + shared_resources
     
     # Read the current config
     config_file = Path(config_path)
@@ -2,7 +2,7 @@ import { useState, useEffect } from 'react';
 import { MediaItem as MediaItemType } from '../../types/media';
 import { useMediaStore } from '../../store/mediaStore';
 import { formatDuration } from '../../utils/formatUtils';
-import { invoke } from '@tauri-apps/api/core';
+import { thumbnailUrl } from '../../utils/streamUrl';
 import { X, AlertCircle } from 'lucide-react';
 import { LoadingOverlay } from './LoadingOverlay';
 
@@ -18,26 +18,10 @@ export function MediaItem({ item }: MediaItemProps) {
 
     const isSelected = selectedIds.includes(item.id);
 
-    // Load thumbnail from backend as base64
+    // Load thumbnail straight from the stream protocol
     useEffect(() => {
-        if (!item.thumbnailPath) {
-            return;
-        }
-
-        const loadThumbnail = async () => {
-            try {
-                const base64Data = await invoke<string>('get_thumbnail_base64', {
-                    thumbnailPath: item.thumbnailPath,
-                });
-                setThumbnailSrc(base64Data);
-            } catch (error) {
-                console.error('MediaItem - Failed to load thumbnail:', error);
-                setThumbnailSrc(null);
-            }
-        };
-
-        loadThumbnail();
-    }, [item.thumbnailPath]);
+        setThumbnailSrc(item.thumbnailPath ? thumbnailUrl(item.id) : null);
+    }, [item.id, item.thumbnailPath]);
 
     const handleClick = (e: React.MouseEvent) => {
         e.stopPropagation(); // Prevent bubbling to MediaLibrary
@@ -5,6 +5,7 @@ import { useMediaStore } from '../../store/mediaStore';
 import { getActiveClipAtTime, getSourceTimeInClip, getTimelineDuration, hasTimelineContent } from '../../utils/timelineUtils';
 import { Plus, Check, ChevronDown } from 'lucide-react';
 import { Clip } from '../../types/media';
+import { toStreamUrl } from '../../utils/streamUrl';
 
 interface VideoPlayerProps {
     src?: string;
@@ -12,16 +13,17 @@ interface VideoPlayerProps {
 }
 
 // Helper to get video URL - prefers proxy, uses direct file access (NO memory loading!)
-function getVideoUrl(clip: Clip): string {
+// `reader` names the video element, so the two elements never supersede each other's reads
+function getVideoUrl(clip: Clip, reader: string): string {
     // Always prefer proxy for better performance
     const filePath = clip.proxyPath || clip.filePath;
     // Use custom stream:// protocol for local file access
-    return `stream://localhost/${encodeURIComponent(filePath)}`;
+    return toStreamUrl(filePath, { reader });
 }
 
 // Helper for fallback src (if provided)
 function getFallbackUrl(src: string): string {
-    return `stream://localhost/${encodeURIComponent(src)}`;
+    return toStreamUrl(src, { reader: 'player-video1' });
 }
 
 export const VideoPlayer = memo(function VideoPlayer({ src, autoPlay = false }: VideoPlayerProps) {
@@ -107,7 +109,8 @@ export const VideoPlayer = memo(function VideoPlayer({ src, autoPlay = false }:
                 const video = videoRef.current;
 
                 // Get streaming URL (uses proxy if available, zero RAM!)
-                const videoUrl = getVideoUrl(clip);
+                const reader = videoRef === video1Ref ? 'player-video1' : 'player-video2';
+                const videoUrl = getVideoUrl(clip, reader);
                 setBlobUrl(videoUrl);
 
                 if (cancelled) {
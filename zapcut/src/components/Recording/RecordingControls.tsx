@@ -1,8 +1,8 @@
 import React, { useState, useEffect, useRef } from 'react';
 import { useRecording, RecordingSettings } from '../../hooks/useRecording';
-import { invoke } from '@tauri-apps/api/core';
 import { save } from '@tauri-apps/plugin-dialog';
 import { useMediaImport } from '../../hooks/useMediaImport';
+import { toStreamUrl } from '../../utils/streamUrl';
 
 interface RecordingControlsProps {
     className?: string;
@@ -16,68 +16,15 @@ const VideoPreview: React.FC<{ filePath: string }> = ({ filePath }) => {
     const [duration, setDuration] = useState(0);
     const [currentTime, setCurrentTime] = useState(0);
     const [error, setError] = useState<string | null>(null);
-    const activeUrlRef = useRef<string | null>(null);
 
     useEffect(() => {
     }, [filePath]);
 
     useEffect(() => {
-        const loadVideo = async () => {
-            try {
-                const trimmedPath = filePath.trim();
-
-                // Retry logic: try up to 5 times with increasing delays to ensure file is ready
-                let lastError: unknown = '';
-                for (let attempt = 0; attempt < 5; attempt++) {
-                    try {
-                        const delay = 200 + (attempt * 300); // 200ms, 500ms, 800ms, 1100ms, 1400ms
-                        await new Promise(resolve => setTimeout(resolve, delay));
-
-                        // Read file as binary via backend command
-                        const data = await invoke<number[]>('read_binary_file', { path: trimmedPath });
-
-                        // Convert array to Uint8Array
-                        const uint8Array = new Uint8Array(data);
-                        // Create a blob URL from the binary data
-                        const blob = new Blob([uint8Array], { type: 'video/mp4' });
-                        const url = URL.createObjectURL(blob);
-
-                        // Revoke the old URL only after we've successfully created the new one
-                        if (activeUrlRef.current && activeUrlRef.current !== url) {
-                            URL.revokeObjectURL(activeUrlRef.current);
-                        }
-
-                        activeUrlRef.current = url;
-                        setVideoUrl(url);
-                        setError(null);
-                        return; // Success, exit
-                    } catch (error) {
-                        lastError = error;
-                        if (attempt === 4) {
-                            throw lastError; // Throw on final attempt
-                        }
-                    }
-                }
-            } catch (error) {
-                const errorMessage = `Failed to load video: ${error}`;
-                setError(errorMessage);
-                setVideoUrl(null);
-            }
-        };
-
-        if (filePath) {
-            setVideoUrl(null); // Reset URL when path changes
-            setError(null);
-            loadVideo();
-        }
-
-        // Cleanup: revoke blob URL when component unmounts
-        return () => {
-            if (activeUrlRef.current) {
-                URL.revokeObjectURL(activeUrlRef.current);
-                activeUrlRef.current = null;
-            }
-        };
+        setError(null);
+        // Stream the recording instead of copying it over IPC; the recordings folder is in
+        // the stream protocol's scope
+        setVideoUrl(filePath ? toStreamUrl(filePath.trim()) : null);
     }, [filePath]);
 
     const handlePlayPause = () => {
@@ -536,6 +483,9 @@ export const RecordingControls: React.FC<RecordingControlsProps> = ({ className
 
                         {/* Info Text */}
                         <div className="mt-2 p-3 bg-gray-700/50 rounded text-xs text-gray-400">
+                            <p className="mb-1">
+                                <strong>System audio:</strong> Native recordings capture desktop audio only on Linux (PulseAudio/PipeWire monitor sources). macOS and Windows have no built-in capture; a loopback device such as BlackHole, Stereo Mix or a virtual cable is used when one is installed.
+                            </p>
                             <p className="mb-1">
                                 <strong>Picture-in-Picture:</strong> When both screen and webcam are enabled, webcam appears in the lower-left corner of the recording.
                             </p>
@@ -1,6 +1,8 @@
 import { useState, useCallback, useRef } from 'react';
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { createCompositedStream, canComposite, CompositingResult } from '../utils/videoCompositing';
+import { toStreamUrl } from '../utils/streamUrl';
 
 export interface RecordingSettings {
     screen_recording_enabled: boolean;
@@ -17,6 +19,38 @@ export interface RecordingState {
     output_file?: string;
 }
 
+interface JobUpdate {
+    id: string;
+    status: 'queued' | 'running' | 'complete' | 'failed' | 'cancelled';
+    message?: string;
+    result?: { output_path?: string };
+}
+
+// Resolve with the MP4 path once the background processing job finishes
+const waitForRecordingJob = async (jobId: string): Promise<string> => {
+    let unlisten: (() => void) | undefined;
+    try {
+        return await new Promise<string>((resolve, reject) => {
+            const settle = (job: JobUpdate) => {
+                if (job.status === 'complete' && job.result?.output_path) {
+                    resolve(job.result.output_path);
+                } else if (job.status === 'failed' || job.status === 'cancelled') {
+                    reject(job.message ?? job.status);
+                }
+            };
+            listen<JobUpdate>('job://update', (event) => {
+                if (event.payload.id === jobId) settle(event.payload);
+            }).then(async (fn) => {
+                unlisten = fn;
+                // The job may have finished before the listener was registered
+                settle(await invoke<JobUpdate>('get_job', { jobId }));
+            }, reject);
+        });
+    } finally {
+        unlisten?.();
+    }
+};
+
 export const useRecording = () => {
     const [recordingState, setRecordingState] = useState<RecordingState>({
         is_recording: false,
@@ -240,7 +274,8 @@ export const useRecording = () => {
 
                 try {
                     // Send to backend for processing
-                    const outputFile = await invoke<string>('process_recording', { data: byteArray });
+                    const jobId = await invoke<string>('process_recording', { data: byteArray });
+                    const outputFile = await waitForRecordingJob(jobId);
 
                     // Update state with output file
                     setRecordingState(prev => ({
@@ -371,12 +406,8 @@ export const useRecording = () => {
                 filePath
             });
 
-            // Convert to base64 for display
-            const base64Thumbnail = await invoke<string>('get_thumbnail_base64', {
-                thumbnailPath
-            });
-
-            return base64Thumbnail;
+            // Served from the thumbnail cache through the stream protocol
+            return toStreamUrl(thumbnailPath);
         } catch (error) {
             console.error('Failed to generate recording thumbnail:', error);
             return null;
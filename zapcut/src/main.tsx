@@ -2,11 +2,14 @@ import React from 'react';
 import ReactDOM from 'react-dom/client';
 import { getCurrentWebviewWindow } from '@tauri-apps/api/webviewWindow';
 import App from './App';
+import { initStreamToken } from './utils/streamUrl';
 import './index.css';
 
-// Ensure the window is ready before mounting React
+// Ensure the window is ready (and media URLs can be authorized) before mounting React
 const appWindow = getCurrentWebviewWindow();
-appWindow.show().then(() => {
+initStreamToken().catch(err => {
+    console.error('Failed to get stream token:', err);
+}).then(() => appWindow.show()).then(() => {
     ReactDOM.createRoot(document.getElementById('root')!).render(
         <React.StrictMode>
             <App />
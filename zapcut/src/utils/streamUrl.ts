@@ -0,0 +1,105 @@
+import { invoke } from '@tauri-apps/api/core';
+
+/**
+ * stream:// URL builder
+ *
+ * The stream protocol refuses requests without this session's token,
+ * so every local media URL must be built here.
+ */
+
+let streamToken = '';
+
+/**
+ * Fetch the session token; must finish before any stream URL is used
+ */
+export async function initStreamToken(): Promise<void> {
+    streamToken = await invoke<string>('get_stream_token');
+}
+
+interface StreamUrlOptions {
+    /** Serve the library file's proxy instead when one exists */
+    proxy?: boolean;
+    /**
+     * Name of the element reading the file. A newer request from the same reader
+     * supersedes its reads still waiting (e.g. while scrubbing); other readers of the
+     * file are unaffected.
+     */
+    reader?: string;
+}
+
+/**
+ * Get the stream:// URL for a local file
+ */
+export function toStreamUrl(filePath: string, options: StreamUrlOptions = {}): string {
+    const proxy = options.proxy ? '&proxy=1' : '';
+    const reader = options.reader ? `&reader=${encodeURIComponent(options.reader)}` : '';
+    return `stream://localhost/${encodeURIComponent(filePath)}?token=${streamToken}${proxy}${reader}`;
+}
+
+/**
+ * Get the stream:// URL of a library item's thumbnail, or of its frame at `time` seconds
+ * (extracted on first request)
+ */
+export function thumbnailUrl(mediaId: string, time?: number, width?: number): string {
+    const frame = time === undefined ? '' : `&t=${time}${width ? `&width=${width}` : ''}`;
+    return `stream://localhost/__thumb/${encodeURIComponent(mediaId)}?token=${streamToken}${frame}`;
+}
+
+/**
+ * Get the stream:// URL of a library item's waveform peaks (JSON)
+ */
+export function waveformUrl(mediaId: string): string {
+    return `stream://localhost/__waveform/${encodeURIComponent(mediaId)}?token=${streamToken}`;
+}
+
+/**
+ * Get the stream:// URL of a library item's filmstrip sprite sheet or its index
+ */
+export function filmstripUrl(
+    mediaId: string,
+    count: number,
+    width: number,
+    file: 'sprite.jpg' | 'index.json' = 'sprite.jpg'
+): string {
+    const id = encodeURIComponent(mediaId);
+    return `stream://localhost/__filmstrip/${id}/${file}?token=${streamToken}&count=${count}&width=${width}`;
+}
+
+/**
+ * Get the stream:// URLs of a fragmented (fmp4) prerender's init segment and media
+ * fragments, for appending to a Media Source Extensions SourceBuffer
+ */
+export function fmp4Urls(prerenderPath: string): { init: string; media: string } {
+    const key = prerenderPath.split(/[\\/]/).pop()!.replace(/\.mp4$/, '');
+    const base = `stream://localhost/__fmp4/${encodeURIComponent(key)}`;
+    return {
+        init: `${base}/init.mp4?token=${streamToken}`,
+        media: `${base}/media.m4s?token=${streamToken}`,
+    };
+}
+
+/**
+ * Get the stream:// URL of a window of a source transcoded on the fly to fragmented
+ * MP4/H.264, for sources the webview can't decode (HEVC, AV1, ...). Windows keep the
+ * source's timestamps, so consecutive ones can be appended to one SourceBuffer. Windows
+ * still waiting for the same `reader` are dropped when a newer one is requested.
+ */
+export function transcodeUrl(
+    filePath: string,
+    start: number,
+    duration = 10,
+    height = 720,
+    reader?: string
+): string {
+    const readerParam = reader ? `&reader=${encodeURIComponent(reader)}` : '';
+    const params = `token=${streamToken}&start=${start}&duration=${duration}&height=${height}${readerParam}`;
+    return `stream://localhost/__transcode/${encodeURIComponent(filePath)}?${params}`;
+}
+
+/**
+ * Get the stream:// URL of an embedded subtitle track of a library item as WebVTT, for
+ * a <track> element
+ */
+export function captionsUrl(mediaId: string, streamIndex: number): string {
+    return `stream://localhost/__captions/${encodeURIComponent(mediaId)}/${streamIndex}.vtt?token=${streamToken}`;
+}
@@ -5,6 +5,8 @@
  * Prevents memory leaks by limiting active URLs to a small number.
  */
 
+import { toStreamUrl } from './streamUrl';
+
 interface URLEntry {
     url: string;
     clipId: string;
@@ -36,7 +38,7 @@ export class VideoURLManager {
         }
 
         // Create streaming URL (no memory loading!)
-        const url = `stream://localhost/${encodeURIComponent(filePath)}`;
+        const url = toStreamUrl(filePath);
 
         this.urls.set(clipId, {
             url,
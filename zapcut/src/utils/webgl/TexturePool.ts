@@ -7,6 +7,8 @@
  * Now uses custom stream:// protocol for zero-memory streaming!
  */
 
+import { toStreamUrl } from '../streamUrl';
+
 export interface TextureEntry {
     texture: WebGLTexture;
     videoElement: HTMLVideoElement;
@@ -84,7 +86,7 @@ export class TexturePool {
         this.textures.set(clipId, entry);
 
         // Load video with streaming URL (async, zero memory!)
-        const url = `stream://localhost/${encodeURIComponent(videoSrc)}`;
+        const url = toStreamUrl(videoSrc);
         console.log('[TexturePool:getTexture] Loading video', { clipId, url });
         video.src = url;
         video.load();